@@ -3,28 +3,105 @@ use compiler::hir::very_concrete_program::VCP;
 use constraint_writers::debug_writer::DebugWriter;
 use constraint_writers::ConstraintExporter;
 use program_structure::program_archive::ProgramArchive;
+use std::path::PathBuf;
 
 pub struct ExecutionConfig {
     pub r1cs: String,
     pub sym: String,
     pub json_constraints: String,
+    pub counts: String,
+    pub wtns_dag: String,
+    pub summary: String,
+    pub exe_cache: String,
+    pub sym_constraints: String,
+    pub callgraph: String,
+    pub components_dot: String,
+    pub r1cs_shards: String,
+    pub symbolic_witness: String,
+    pub intermediates: String,
+    pub sql_constraints: String,
+    pub manifest: String,
     pub no_rounds: usize,
+    pub compare_against: Option<PathBuf>,
+    pub public_signal_pattern: Option<String>,
+    pub symbolic_witness_limit: usize,
+    pub export_chunk_size: usize,
+    pub max_instances: Option<usize>,
+    pub max_non_linear: Option<usize>,
+    pub promoted_signals: Vec<String>,
+    pub shard_size: Option<usize>,
+    pub threads: Option<usize>,
     pub flag_s: bool,
     pub flag_f: bool,
     pub flag_p: bool,
     pub inspect_constraints_flag: bool,
+    pub alloc_stats_flag: bool,
     pub sym_flag: bool,
     pub r1cs_flag: bool,
     pub json_substitution_flag: bool,
     pub json_constraint_flag: bool,
+    pub intern_names_flag: bool,
+    pub stable_headers_flag: bool,
+    pub exe_cache_flag: bool,
+    pub unconstrained_report_flag: bool,
+    pub normalize_flag: bool,
+    pub dedup_constraints_flag: bool,
+    pub components_dot_flag: bool,
+    pub r1cs_spans_flag: bool,
+    pub deterministic_flag: bool,
+    pub summary_flag: bool,
+    pub sym_constraints_flag: bool,
+    pub no_simplification_flag: bool,
+    pub bellman_flag: bool,
+    pub constraints_by_category_flag: bool,
+    pub r1cs_info_flag: bool,
+    pub signal_cycles_flag: bool,
+    pub verify_parallel_flag: bool,
+    pub dof_flag: bool,
+    pub strict_inputs_flag: bool,
+    pub symbolic_witness_flag: bool,
+    pub no_timing_flag: bool,
+    pub manifest_flag: bool,
+    pub checked_division_flag: bool,
+    pub constraints_by_file_flag: bool,
+    pub keep_intermediates_flag: bool,
+    pub mult_depth_flag: bool,
+    pub callgraph_flag: bool,
+    pub check_witness_safety_flag: bool,
+    pub werror_flag: bool,
+    pub template_ranges_flag: bool,
+    pub check_signal_count_flag: bool,
+    pub sql_flag: bool,
+    pub declaration_order_flag: bool,
+    pub bisect_flag: bool,
+    pub wtns_dag_flag: bool,
+    pub constraints_by_template_flag: bool,
+    pub strict_pragmas_flag: bool,
+    pub emit_counts_json_flag: bool,
+    pub trigger_order_flag: bool,
+    pub suggest_flag: bool,
+    pub assert_as_constraint_flag: bool,
+}
+
+// Runs before the type analysis, `generator ** k` has to be a plain power by then
+pub fn resolve_generator_powers(program_archive: &mut ProgramArchive) -> Result<(), ()> {
+    use program_structure::constants::UsefulConstants;
+    use program_structure::error_definition::Report;
+    let constants = UsefulConstants::new();
+    constraint_generation::resolve_generator_powers(program_archive, &constants).map_err(|r| {
+        Report::print_reports(&r, program_archive.get_file_library());
+    })
 }
 
 pub fn execute_project(
     program_archive: ProgramArchive,
     config: ExecutionConfig,
 ) -> Result<VCP, ()> {
-    use constraint_generation::{build_circuit, BuildConfig};
-    let debug = DebugWriter::new(config.json_constraints).unwrap();
+    use constraint_generation::{
+        build_circuit, BuildConfig, DivisionMode, ExporterKind, HeaderNaming, PragmaPolicy,
+        SignalOrder, UnconnectedInputPolicy,
+    };
+    let debug = DebugWriter::new(config.json_constraints, config.export_chunk_size).unwrap();
     let build_config = BuildConfig {
         no_rounds: config.no_rounds,
         flag_json_sub: config.json_substitution_flag,
@@ -32,6 +109,104 @@ pub fn execute_project(
         flag_f: config.flag_f,
         flag_p: config.flag_p,
         inspect_constraints: config.inspect_constraints_flag,
+        flag_alloc_stats: config.alloc_stats_flag,
+        flag_assert_as_constraint: config.assert_as_constraint_flag,
+        flag_suggest: config.suggest_flag,
+        flag_trigger_order: config.trigger_order_flag,
+        flag_emit_counts_json: config.emit_counts_json_flag,
+        counts_json: config.counts,
+        unknown_pragma_policy: if config.strict_pragmas_flag {
+            PragmaPolicy::Error
+        } else {
+            PragmaPolicy::Warn
+        },
+        flag_constraints_by_template: config.constraints_by_template_flag,
+        flag_wtns_dag: config.wtns_dag_flag,
+        wtns_dag: config.wtns_dag,
+        sym_constraints: config.sym_constraints,
+        flag_bisect: config.bisect_flag,
+        signal_order: if config.declaration_order_flag {
+            SignalOrder::Declaration
+        } else {
+            SignalOrder::TypeGrouped
+        },
+        flag_check_signal_count: config.check_signal_count_flag,
+        compare_against: config.compare_against,
+        flag_template_ranges: config.template_ranges_flag,
+        flag_werror: config.werror_flag,
+        public_signal_pattern: config.public_signal_pattern,
+        flag_check_witness_safety: config.check_witness_safety_flag,
+        flag_callgraph: config.callgraph_flag,
+        callgraph: config.callgraph,
+        components_dot: config.components_dot,
+        r1cs_shards: config.r1cs_shards,
+        shard_size: config.shard_size,
+        flag_mult_depth: config.mult_depth_flag,
+        flag_keep_intermediates: config.keep_intermediates_flag,
+        intermediates: config.intermediates,
+        flag_constraints_by_file: config.constraints_by_file_flag,
+        constant_division_mode: if config.checked_division_flag {
+            DivisionMode::IntegerChecked
+        } else {
+            DivisionMode::Field
+        },
+        flag_no_timing: config.no_timing_flag,
+        flag_symbolic_witness: config.symbolic_witness_flag,
+        symbolic_witness: config.symbolic_witness,
+        symbolic_witness_limit: config.symbolic_witness_limit,
+        export_chunk_size: config.export_chunk_size,
+        unconnected_input_policy: if config.strict_inputs_flag {
+            UnconnectedInputPolicy::Error
+        } else {
+            UnconnectedInputPolicy::Warn
+        },
+        flag_dof: config.dof_flag,
+        flag_verify_parallel: config.verify_parallel_flag,
+        flag_signal_cycles: config.signal_cycles_flag,
+        max_instances: config.max_instances,
+        max_non_linear: config.max_non_linear,
+        threads: config.threads,
+        flag_r1cs_info: config.r1cs_info_flag,
+        flag_constraints_by_category: config.constraints_by_category_flag,
+        exporter_kind: if config.bellman_flag {
+            ExporterKind::Bellman
+        } else {
+            ExporterKind::Circom
+        },
+        flag_no_simplification: config.no_simplification_flag,
+        flag_sym_constraints: config.sym_constraints_flag,
+        summary_path: if config.summary_flag {
+            Option::Some(PathBuf::from(config.summary))
+        } else {
+            Option::None
+        },
+        // The wasm and C witness generators are fixed to bn128
+        custom_prime: Option::None,
+        instance_cache: Option::None,
+        reporter: Option::None,
+        constraint_visitor: Option::None,
+        exe_cache: if config.exe_cache_flag {
+            Option::Some(PathBuf::from(config.exe_cache))
+        } else {
+            Option::None
+        },
+        wire_permutation: Option::None,
+        phase_stats: Option::None,
+        promoted_signals: config.promoted_signals,
+        flag_deterministic: config.deterministic_flag,
+        flag_r1cs_spans: config.r1cs_spans_flag,
+        flag_components_dot: config.components_dot_flag,
+        flag_dedup_constraints: config.dedup_constraints_flag,
+        flag_normalize: config.normalize_flag,
+        flag_unconstrained_report: config.unconstrained_report_flag,
+        header_naming: if config.stable_headers_flag {
+            HeaderNaming::ParameterHash
+        } else {
+            HeaderNaming::Sequential
+        },
+        flag_intern_signal_names: config.intern_names_flag,
+        flag_provenance: false,
+        main_override: Option::None,
     };
     let (exporter, vcp) = build_circuit(program_archive, build_config)?;
     if config.r1cs_flag {
@@ -43,6 +218,12 @@ pub fn execute_project(
     if config.json_constraint_flag {
         generate_json_constraints(&debug, exporter.as_ref())?;
     }
+    if config.sql_flag {
+        generate_sql_constraints(&config.sql_constraints, exporter.as_ref())?;
+    }
+    if config.manifest_flag {
+        generate_manifest(&config.manifest, exporter.as_ref())?;
+    }
     Result::Ok(vcp)
 }
 
@@ -78,3 +259,23 @@ fn generate_json_constraints(
         Result::Err(())
     }
 }
+
+fn generate_sql_constraints(file: &str, exporter: &dyn ConstraintExporter) -> Result<(), ()> {
+    if let Result::Ok(()) = exporter.sql_constraints(file) {
+        println!("{} {}", Colour::Green.paint("Constraints table written in:"), file);
+        Result::Ok(())
+    } else {
+        println!("{}", Colour::Red.paint("Could not write the output in the given path"));
+        Result::Err(())
+    }
+}
+
+fn generate_manifest(file: &str, exporter: &dyn ConstraintExporter) -> Result<(), ()> {
+    if let Result::Ok(()) = exporter.manifest(file) {
+        println!("{} {}", Colour::Green.paint("Written successfully:"), file);
+        Result::Ok(())
+    } else {
+        println!("{}", Colour::Red.paint("Could not write the output in the given path"));
+        Result::Err(())
+    }
+}
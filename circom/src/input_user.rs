@@ -13,6 +13,18 @@ pub struct Input {
     pub out_c_code: PathBuf,
     pub out_c_dat: PathBuf,
     pub out_sym: PathBuf,
+    pub out_counts: PathBuf,
+    pub out_sql_constraints: PathBuf,
+    pub out_manifest: PathBuf,
+    pub out_wtns_dag: PathBuf,
+    pub out_sym_constraints: PathBuf,
+    pub out_callgraph: PathBuf,
+    pub out_components_dot: PathBuf,
+    pub out_r1cs_shards: PathBuf,
+    pub out_summary: PathBuf,
+    pub out_exe_cache: PathBuf,
+    pub out_symbolic_witness: PathBuf,
+    pub out_intermediates: PathBuf,
     pub field: &'static str,
     pub c_flag: bool,
     pub wasm_flag: bool,
@@ -27,6 +39,57 @@ pub struct Input {
     pub reduced_simplification_flag: bool,
     pub parallel_simplification_flag: bool,
     pub inspect_constraints_flag: bool,
+    pub alloc_stats_flag: bool,
+    pub assert_as_constraint_flag: bool,
+    pub suggest_flag: bool,
+    pub trigger_order_flag: bool,
+    pub emit_counts_json_flag: bool,
+    pub strict_pragmas_flag: bool,
+    pub constraints_by_template_flag: bool,
+    pub wtns_dag_flag: bool,
+    pub bisect_flag: bool,
+    pub declaration_order_flag: bool,
+    pub sql_flag: bool,
+    pub check_signal_count_flag: bool,
+    pub compare_against: Option<PathBuf>,
+    pub public_signal_pattern: Option<String>,
+    pub symbolic_witness_limit: usize,
+    pub export_chunk_size: usize,
+    pub max_instances: Option<usize>,
+    pub max_non_linear: Option<usize>,
+    pub promoted_signals: Vec<String>,
+    pub shard_size: Option<usize>,
+    pub threads: Option<usize>,
+    pub template_ranges_flag: bool,
+    pub werror_flag: bool,
+    pub check_witness_safety_flag: bool,
+    pub callgraph_flag: bool,
+    pub mult_depth_flag: bool,
+    pub keep_intermediates_flag: bool,
+    pub constraints_by_file_flag: bool,
+    pub checked_division_flag: bool,
+    pub manifest_flag: bool,
+    pub no_timing_flag: bool,
+    pub symbolic_witness_flag: bool,
+    pub strict_inputs_flag: bool,
+    pub dof_flag: bool,
+    pub verify_parallel_flag: bool,
+    pub signal_cycles_flag: bool,
+    pub r1cs_info_flag: bool,
+    pub constraints_by_category_flag: bool,
+    pub bellman_flag: bool,
+    pub no_simplification_flag: bool,
+    pub sym_constraints_flag: bool,
+    pub summary_flag: bool,
+    pub deterministic_flag: bool,
+    pub r1cs_spans_flag: bool,
+    pub components_dot_flag: bool,
+    pub dedup_constraints_flag: bool,
+    pub normalize_flag: bool,
+    pub unconstrained_report_flag: bool,
+    pub exe_cache_flag: bool,
+    pub stable_headers_flag: bool,
+    pub intern_names_flag: bool,
     pub no_rounds: usize,
 }
 
@@ -37,9 +100,13 @@ const WAT: &'static str = "wat";
 const WASM: &'static str = "wasm";
 const CPP: &'static str = "cpp";
 const JS: &'static str = "js";
+const INTERMEDIATES: &'static str = "intermediates";
 const DAT: &'static str = "dat";
 const SYM: &'static str = "sym";
 const JSON: &'static str = "json";
+const DOT: &'static str = "dot";
+const SQL: &'static str = "sql";
+const TXT: &'static str = "txt";
 
 impl Input {
     pub fn new() -> Result<Input, ()> {
@@ -51,6 +118,12 @@ impl Input {
         let output_c_path = Input::build_folder(&output_path, &file_name, CPP);
         let output_js_path = Input::build_folder(&output_path, &file_name, JS);
         let o_style = input_processing::get_simplification_style(&matches)?;
+        let symbolic_witness_limit = input_processing::get_symbolic_witness_limit(&matches)?;
+        let export_chunk_size = input_processing::get_export_chunk_size(&matches)?;
+        let max_instances = input_processing::get_max_instances(&matches)?;
+        let max_non_linear = input_processing::get_max_non_linear(&matches)?;
+        let shard_size = input_processing::get_shard_size(&matches)?;
+        let threads = input_processing::get_threads(&matches)?;
         Result::Ok(Input {
             field: P_0,
             input_program: input,
@@ -64,6 +137,50 @@ impl Input {
             out_c_code: Input::build_output(&output_c_path, &file_name, CPP),
             out_c_dat: Input::build_output(&output_c_path, &file_name, DAT),
             out_sym: Input::build_output(&output_path, &file_name, SYM),
+            out_counts: Input::build_output(
+                &output_path,
+                &format!("{}_counts", file_name),
+                JSON,
+            ),
+            out_wtns_dag: Input::build_output(
+                &output_path,
+                &format!("{}_wtns_dag", file_name),
+                DOT,
+            ),
+            out_sym_constraints: Input::build_output(
+                &output_path,
+                &format!("{}_constraints", file_name),
+                SYM,
+            ),
+            out_summary: output_path.join("circuit_summary.json"),
+            out_exe_cache: Input::build_output(&output_path, &file_name, "exe-cache"),
+            out_callgraph: Input::build_output(
+                &output_path,
+                &format!("{}_callgraph", file_name),
+                DOT,
+            ),
+            out_components_dot: Input::build_output(
+                &output_path,
+                &format!("{}_components", file_name),
+                DOT,
+            ),
+            out_r1cs_shards: output_path.join(format!("{}_shard", file_name)),
+            out_symbolic_witness: Input::build_output(
+                &output_path,
+                &format!("{}_symbolic_witness", file_name),
+                TXT,
+            ),
+            out_intermediates: Input::build_folder(&output_path, &file_name, INTERMEDIATES),
+            out_sql_constraints: Input::build_output(
+                &output_path,
+                &format!("{}_constraints", file_name),
+                SQL,
+            ),
+            out_manifest: Input::build_output(
+                &output_path,
+                &format!("{}_manifest", file_name),
+                JSON,
+            ),
             out_json_constraints: Input::build_output(
                 &output_path,
                 &format!("{}_constraints", file_name),
@@ -83,6 +200,57 @@ impl Input {
             reduced_simplification_flag: o_style == SimplificationStyle::O1,
             parallel_simplification_flag: input_processing::get_parallel_simplification(&matches),
             inspect_constraints_flag: input_processing::get_inspect_constraints(&matches),
+            alloc_stats_flag: input_processing::get_alloc_stats(&matches),
+            assert_as_constraint_flag: input_processing::get_assert_as_constraint(&matches),
+            suggest_flag: input_processing::get_suggest(&matches),
+            trigger_order_flag: input_processing::get_trigger_order(&matches),
+            emit_counts_json_flag: input_processing::get_emit_counts_json(&matches),
+            strict_pragmas_flag: input_processing::get_strict_pragmas(&matches),
+            constraints_by_template_flag: input_processing::get_constraints_by_template(&matches),
+            wtns_dag_flag: input_processing::get_wtns_dag(&matches),
+            bisect_flag: input_processing::get_bisect(&matches),
+            declaration_order_flag: input_processing::get_declaration_order(&matches),
+            sql_flag: input_processing::get_sql(&matches),
+            compare_against: input_processing::get_compare_against(&matches),
+            public_signal_pattern: input_processing::get_public_signal_pattern(&matches),
+            symbolic_witness_limit,
+            export_chunk_size,
+            max_instances,
+            max_non_linear,
+            promoted_signals: input_processing::get_promoted_signals(&matches),
+            shard_size,
+            threads,
+            check_signal_count_flag: input_processing::get_check_signal_count(&matches),
+            template_ranges_flag: input_processing::get_template_ranges(&matches),
+            werror_flag: input_processing::get_werror(&matches),
+            check_witness_safety_flag: input_processing::get_check_witness_safety(&matches),
+            callgraph_flag: input_processing::get_callgraph(&matches),
+            mult_depth_flag: input_processing::get_mult_depth(&matches),
+            keep_intermediates_flag: input_processing::get_keep_intermediates(&matches),
+            constraints_by_file_flag: input_processing::get_constraints_by_file(&matches),
+            checked_division_flag: input_processing::get_checked_division(&matches),
+            manifest_flag: input_processing::get_manifest(&matches),
+            no_timing_flag: input_processing::get_no_timing(&matches),
+            symbolic_witness_flag: input_processing::get_symbolic_witness(&matches),
+            strict_inputs_flag: input_processing::get_strict_inputs(&matches),
+            dof_flag: input_processing::get_dof(&matches),
+            verify_parallel_flag: input_processing::get_verify_parallel(&matches),
+            signal_cycles_flag: input_processing::get_signal_cycles(&matches),
+            r1cs_info_flag: input_processing::get_r1cs_info(&matches),
+            constraints_by_category_flag: input_processing::get_constraints_by_category(&matches),
+            bellman_flag: input_processing::get_bellman(&matches),
+            no_simplification_flag: input_processing::get_no_simplification(&matches),
+            sym_constraints_flag: input_processing::get_sym_constraints(&matches),
+            summary_flag: input_processing::get_summary(&matches),
+            deterministic_flag: input_processing::get_deterministic(&matches),
+            r1cs_spans_flag: input_processing::get_r1cs_spans(&matches),
+            components_dot_flag: input_processing::get_components_dot(&matches),
+            dedup_constraints_flag: input_processing::get_dedup_constraints(&matches),
+            normalize_flag: input_processing::get_normalize(&matches),
+            unconstrained_report_flag: input_processing::get_unconstrained_report(&matches),
+            exe_cache_flag: input_processing::get_exe_cache(&matches),
+            stable_headers_flag: input_processing::get_stable_headers(&matches),
+            intern_names_flag: input_processing::get_intern_names(&matches),
         })
     }
 
@@ -135,6 +303,42 @@ impl Input {
     pub fn dat_file(&self) -> &str {
         self.out_c_dat.to_str().unwrap()
     }
+    pub fn counts_file(&self) -> &str {
+        self.out_counts.to_str().unwrap()
+    }
+    pub fn sql_constraints_file(&self) -> &str {
+        self.out_sql_constraints.to_str().unwrap()
+    }
+    pub fn manifest_file(&self) -> &str {
+        self.out_manifest.to_str().unwrap()
+    }
+    pub fn sym_constraints_file(&self) -> &str {
+        self.out_sym_constraints.to_str().unwrap()
+    }
+    pub fn summary_file(&self) -> &str {
+        self.out_summary.to_str().unwrap()
+    }
+    pub fn exe_cache_file(&self) -> &str {
+        self.out_exe_cache.to_str().unwrap()
+    }
+    pub fn wtns_dag_file(&self) -> &str {
+        self.out_wtns_dag.to_str().unwrap()
+    }
+    pub fn callgraph_file(&self) -> &str {
+        self.out_callgraph.to_str().unwrap()
+    }
+    pub fn components_dot_file(&self) -> &str {
+        self.out_components_dot.to_str().unwrap()
+    }
+    pub fn r1cs_shards_file(&self) -> &str {
+        self.out_r1cs_shards.to_str().unwrap()
+    }
+    pub fn symbolic_witness_file(&self) -> &str {
+        self.out_symbolic_witness.to_str().unwrap()
+    }
+    pub fn intermediates_folder(&self) -> &str {
+        self.out_intermediates.to_str().unwrap()
+    }
     pub fn json_constraints_file(&self) -> &str {
         self.out_json_constraints.to_str().unwrap()
     }
@@ -171,12 +375,165 @@ impl Input {
     pub fn inspect_constraints_flag(&self) -> bool {
         self.inspect_constraints_flag
     }
+    pub fn alloc_stats_flag(&self) -> bool {
+        self.alloc_stats_flag
+    }
     pub fn reduced_simplification_flag(&self) -> bool {
         self.reduced_simplification_flag
     }
     pub fn parallel_simplification_flag(&self) -> bool {
         self.parallel_simplification_flag
     }
+    pub fn assert_as_constraint_flag(&self) -> bool {
+        self.assert_as_constraint_flag
+    }
+    pub fn suggest_flag(&self) -> bool {
+        self.suggest_flag
+    }
+    pub fn trigger_order_flag(&self) -> bool {
+        self.trigger_order_flag
+    }
+    pub fn emit_counts_json_flag(&self) -> bool {
+        self.emit_counts_json_flag
+    }
+    pub fn strict_pragmas_flag(&self) -> bool {
+        self.strict_pragmas_flag
+    }
+    pub fn constraints_by_template_flag(&self) -> bool {
+        self.constraints_by_template_flag
+    }
+    pub fn wtns_dag_flag(&self) -> bool {
+        self.wtns_dag_flag
+    }
+    pub fn bisect_flag(&self) -> bool {
+        self.bisect_flag
+    }
+    pub fn declaration_order_flag(&self) -> bool {
+        self.declaration_order_flag
+    }
+    pub fn sql_flag(&self) -> bool {
+        self.sql_flag
+    }
+    pub fn check_signal_count_flag(&self) -> bool {
+        self.check_signal_count_flag
+    }
+    pub fn compare_against(&self) -> Option<PathBuf> {
+        self.compare_against.clone()
+    }
+    pub fn template_ranges_flag(&self) -> bool {
+        self.template_ranges_flag
+    }
+    pub fn werror_flag(&self) -> bool {
+        self.werror_flag
+    }
+    pub fn public_signal_pattern(&self) -> Option<String> {
+        self.public_signal_pattern.clone()
+    }
+    pub fn symbolic_witness_limit(&self) -> usize {
+        self.symbolic_witness_limit
+    }
+    pub fn export_chunk_size(&self) -> usize {
+        self.export_chunk_size
+    }
+    pub fn max_instances(&self) -> Option<usize> {
+        self.max_instances
+    }
+    pub fn max_non_linear(&self) -> Option<usize> {
+        self.max_non_linear
+    }
+    pub fn shard_size(&self) -> Option<usize> {
+        self.shard_size
+    }
+    pub fn promoted_signals(&self) -> Vec<String> {
+        self.promoted_signals.clone()
+    }
+    pub fn threads(&self) -> Option<usize> {
+        self.threads
+    }
+    pub fn check_witness_safety_flag(&self) -> bool {
+        self.check_witness_safety_flag
+    }
+    pub fn callgraph_flag(&self) -> bool {
+        self.callgraph_flag
+    }
+    pub fn mult_depth_flag(&self) -> bool {
+        self.mult_depth_flag
+    }
+    pub fn keep_intermediates_flag(&self) -> bool {
+        self.keep_intermediates_flag
+    }
+    pub fn constraints_by_file_flag(&self) -> bool {
+        self.constraints_by_file_flag
+    }
+    pub fn checked_division_flag(&self) -> bool {
+        self.checked_division_flag
+    }
+    pub fn manifest_flag(&self) -> bool {
+        self.manifest_flag
+    }
+    pub fn no_timing_flag(&self) -> bool {
+        self.no_timing_flag
+    }
+    pub fn symbolic_witness_flag(&self) -> bool {
+        self.symbolic_witness_flag
+    }
+    pub fn strict_inputs_flag(&self) -> bool {
+        self.strict_inputs_flag
+    }
+    pub fn dof_flag(&self) -> bool {
+        self.dof_flag
+    }
+    pub fn verify_parallel_flag(&self) -> bool {
+        self.verify_parallel_flag
+    }
+    pub fn signal_cycles_flag(&self) -> bool {
+        self.signal_cycles_flag
+    }
+    pub fn r1cs_info_flag(&self) -> bool {
+        self.r1cs_info_flag
+    }
+    pub fn constraints_by_category_flag(&self) -> bool {
+        self.constraints_by_category_flag
+    }
+    pub fn bellman_flag(&self) -> bool {
+        self.bellman_flag
+    }
+    pub fn no_simplification_flag(&self) -> bool {
+        self.no_simplification_flag
+    }
+    pub fn sym_constraints_flag(&self) -> bool {
+        self.sym_constraints_flag
+    }
+    pub fn summary_flag(&self) -> bool {
+        self.summary_flag
+    }
+    pub fn deterministic_flag(&self) -> bool {
+        self.deterministic_flag
+    }
+    pub fn r1cs_spans_flag(&self) -> bool {
+        self.r1cs_spans_flag
+    }
+    pub fn components_dot_flag(&self) -> bool {
+        self.components_dot_flag
+    }
+    pub fn dedup_constraints_flag(&self) -> bool {
+        self.dedup_constraints_flag
+    }
+    pub fn normalize_flag(&self) -> bool {
+        self.normalize_flag
+    }
+    pub fn unconstrained_report_flag(&self) -> bool {
+        self.unconstrained_report_flag
+    }
+    pub fn exe_cache_flag(&self) -> bool {
+        self.exe_cache_flag
+    }
+    pub fn stable_headers_flag(&self) -> bool {
+        self.stable_headers_flag
+    }
+    pub fn intern_names_flag(&self) -> bool {
+        self.intern_names_flag
+    }
     pub fn no_rounds(&self) -> usize {
         self.no_rounds
     }
@@ -269,8 +626,199 @@ mod input_processing {
     pub fn get_inspect_constraints(matches: &ArgMatches) -> bool {
         matches.is_present("inspect_constraints")
     }
+    pub fn get_alloc_stats(matches: &ArgMatches) -> bool {
+        matches.is_present("alloc_stats")
+    }
+    pub fn get_assert_as_constraint(matches: &ArgMatches) -> bool {
+        matches.is_present("assert_as_constraint")
+    }
+    pub fn get_suggest(matches: &ArgMatches) -> bool {
+        matches.is_present("suggest")
+    }
+    pub fn get_trigger_order(matches: &ArgMatches) -> bool {
+        matches.is_present("trigger_order")
+    }
+    pub fn get_emit_counts_json(matches: &ArgMatches) -> bool {
+        matches.is_present("emit_counts_json")
+    }
+    pub fn get_strict_pragmas(matches: &ArgMatches) -> bool {
+        matches.is_present("strict_pragmas")
+    }
+    pub fn get_constraints_by_template(matches: &ArgMatches) -> bool {
+        matches.is_present("constraints_by_template")
+    }
+    pub fn get_compare_against(matches: &ArgMatches) -> Option<PathBuf> {
+        matches.value_of("compare_against").map(|route| Path::new(route).to_path_buf())
+    }
+    pub fn get_public_signal_pattern(matches: &ArgMatches) -> Option<String> {
+        matches.value_of("public_signal_pattern").map(|pattern| pattern.to_string())
+    }
+    pub fn get_symbolic_witness_limit(matches: &ArgMatches) -> Result<usize, ()> {
+        let limit = matches.value_of("symbolic_witness_limit").unwrap();
+        usize::from_str_radix(limit, 10).map_err(|_| {
+            println!("{}", Colour::Red.paint("invalid symbolic witness limit"))
+        })
+    }
+    pub fn get_export_chunk_size(matches: &ArgMatches) -> Result<usize, ()> {
+        let chunk_size = matches.value_of("export_chunk_size").unwrap();
+        usize::from_str_radix(chunk_size, 10)
+            .map_err(|_| println!("{}", Colour::Red.paint("invalid export chunk size")))
+    }
+    pub fn get_max_instances(matches: &ArgMatches) -> Result<Option<usize>, ()> {
+        match matches.value_of("max_instances") {
+            Option::Some(limit) => usize::from_str_radix(limit, 10)
+                .map(Option::Some)
+                .map_err(|_| println!("{}", Colour::Red.paint("invalid max instances"))),
+            Option::None => Result::Ok(Option::None),
+        }
+    }
+    pub fn get_max_non_linear(matches: &ArgMatches) -> Result<Option<usize>, ()> {
+        match matches.value_of("max_non_linear") {
+            Option::Some(limit) => usize::from_str_radix(limit, 10)
+                .map(Option::Some)
+                .map_err(|_| println!("{}", Colour::Red.paint("invalid max non linear"))),
+            Option::None => Result::Ok(Option::None),
+        }
+    }
+    pub fn get_shard_size(matches: &ArgMatches) -> Result<Option<usize>, ()> {
+        match matches.value_of("shard_size") {
+            Option::Some(size) => usize::from_str_radix(size, 10)
+                .ok()
+                .filter(|size| *size > 0)
+                .map(Option::Some)
+                .ok_or_else(|| println!("{}", Colour::Red.paint("invalid shard size"))),
+            Option::None => Result::Ok(Option::None),
+        }
+    }
+    pub fn get_promoted_signals(matches: &ArgMatches) -> Vec<String> {
+        match matches.value_of("promote") {
+            Option::Some(paths) => paths.split(',').map(|p| p.trim().to_string()).collect(),
+            Option::None => Vec::new(),
+        }
+    }
+    pub fn get_threads(matches: &ArgMatches) -> Result<Option<usize>, ()> {
+        match matches.value_of("threads") {
+            Option::Some(threads) => match usize::from_str_radix(threads, 10) {
+                Result::Ok(threads) if threads > 0 => Result::Ok(Option::Some(threads)),
+                _ => {
+                    println!("{}", Colour::Red.paint("the number of threads must be positive"));
+                    Result::Err(())
+                }
+            },
+            Option::None => Result::Ok(Option::None),
+        }
+    }
+    pub fn get_wtns_dag(matches: &ArgMatches) -> bool {
+        matches.is_present("wtns_dag")
+    }
+    pub fn get_bisect(matches: &ArgMatches) -> bool {
+        matches.is_present("bisect")
+    }
+    pub fn get_declaration_order(matches: &ArgMatches) -> bool {
+        matches.is_present("declaration_order")
+    }
+    pub fn get_sql(matches: &ArgMatches) -> bool {
+        matches.is_present("sql")
+    }
+    pub fn get_check_signal_count(matches: &ArgMatches) -> bool {
+        matches.is_present("check_signal_count")
+    }
+    pub fn get_template_ranges(matches: &ArgMatches) -> bool {
+        matches.is_present("template_ranges")
+    }
+    pub fn get_werror(matches: &ArgMatches) -> bool {
+        matches.is_present("werror")
+    }
+    pub fn get_check_witness_safety(matches: &ArgMatches) -> bool {
+        matches.is_present("check_witness_safety")
+    }
+    pub fn get_callgraph(matches: &ArgMatches) -> bool {
+        matches.is_present("callgraph")
+    }
+    pub fn get_mult_depth(matches: &ArgMatches) -> bool {
+        matches.is_present("mult_depth")
+    }
+    pub fn get_keep_intermediates(matches: &ArgMatches) -> bool {
+        matches.is_present("keep_intermediates")
+    }
+    pub fn get_constraints_by_file(matches: &ArgMatches) -> bool {
+        matches.is_present("constraints_by_file")
+    }
+    pub fn get_checked_division(matches: &ArgMatches) -> bool {
+        matches.is_present("checked_division")
+    }
+    pub fn get_manifest(matches: &ArgMatches) -> bool {
+        matches.is_present("manifest")
+    }
+    pub fn get_no_timing(matches: &ArgMatches) -> bool {
+        matches.is_present("no_timing")
+    }
+    pub fn get_symbolic_witness(matches: &ArgMatches) -> bool {
+        matches.is_present("symbolic_witness")
+    }
+    pub fn get_strict_inputs(matches: &ArgMatches) -> bool {
+        matches.is_present("strict_inputs")
+    }
+    pub fn get_dof(matches: &ArgMatches) -> bool {
+        matches.is_present("dof")
+    }
+    pub fn get_verify_parallel(matches: &ArgMatches) -> bool {
+        matches.is_present("verify_parallel")
+    }
+    pub fn get_signal_cycles(matches: &ArgMatches) -> bool {
+        matches.is_present("signal_cycles")
+    }
+    pub fn get_r1cs_info(matches: &ArgMatches) -> bool {
+        matches.is_present("r1cs_info")
+    }
+    pub fn get_constraints_by_category(matches: &ArgMatches) -> bool {
+        matches.is_present("constraints_by_category")
+    }
+    pub fn get_bellman(matches: &ArgMatches) -> bool {
+        matches.is_present("bellman")
+    }
+    pub fn get_no_simplification(matches: &ArgMatches) -> bool {
+        matches.is_present("skip_simplification")
+    }
+    pub fn get_sym_constraints(matches: &ArgMatches) -> bool {
+        matches.is_present("sym_constraints")
+    }
+    pub fn get_summary(matches: &ArgMatches) -> bool {
+        matches.is_present("summary")
+    }
+    pub fn get_deterministic(matches: &ArgMatches) -> bool {
+        matches.is_present("deterministic")
+    }
+    pub fn get_r1cs_spans(matches: &ArgMatches) -> bool {
+        matches.is_present("r1cs_spans")
+    }
+    pub fn get_components_dot(matches: &ArgMatches) -> bool {
+        matches.is_present("components_dot")
+    }
+    pub fn get_dedup_constraints(matches: &ArgMatches) -> bool {
+        matches.is_present("dedup_constraints")
+    }
+    pub fn get_normalize(matches: &ArgMatches) -> bool {
+        matches.is_present("normalize")
+    }
+    pub fn get_unconstrained_report(matches: &ArgMatches) -> bool {
+        matches.is_present("unconstrained_report")
+    }
+    pub fn get_exe_cache(matches: &ArgMatches) -> bool {
+        matches.is_present("exe_cache")
+    }
+    pub fn get_stable_headers(matches: &ArgMatches) -> bool {
+        matches.is_present("stable_headers")
+    }
+    pub fn get_intern_names(matches: &ArgMatches) -> bool {
+        matches.is_present("intern_names")
+    }
 
     pub fn view() -> ArgMatches<'static> {
+        app().get_matches()
+    }
+
+    fn app() -> App<'static, 'static> {
         App::new("circom compiler")
             .version("2.0.0")
             .author("IDEN3")
@@ -375,6 +923,13 @@ mod input_processing {
                     .hidden(true)
                     .help("Runs non-linear simplification in parallel"),
             )
+            .arg(
+                Arg::with_name("alloc_stats")
+                    .long("alloc_stats")
+                    .takes_value(false)
+                    .hidden(true)
+                    .help("Reports allocation counts and peak memory per build phase"),
+            )
             .arg(
                 Arg::with_name("main_inputs_log")
                     .long("inputs")
@@ -382,6 +937,329 @@ mod input_processing {
                     .hidden(true)
                     .help("produces a log_inputs.txt file"),
             )
-            .get_matches()
+            .arg(
+                Arg::with_name("assert_as_constraint")
+                    .long("assert_as_constraint")
+                    .takes_value(false)
+                    .help("Enforces assert(a == b) over signals as the constraint a - b === 0"),
+            )
+            .arg(
+                Arg::with_name("suggest")
+                    .long("suggest")
+                    .takes_value(false)
+                    .help("Reports optimization opportunities found in the templates and constraints"),
+            )
+            .arg(
+                Arg::with_name("trigger_order")
+                    .long("trigger_order")
+                    .takes_value(false)
+                    .help("Prints the order in which components are triggered"),
+            )
+            .arg(
+                Arg::with_name("emit_counts_json")
+                    .long("counts")
+                    .takes_value(false)
+                    .help("outputs the constraint and signal counts in json format"),
+            )
+            .arg(
+                Arg::with_name("strict_pragmas")
+                    .long("strict_pragmas")
+                    .takes_value(false)
+                    .help("Unknown pragma directives are reported as errors instead of warnings"),
+            )
+            .arg(
+                Arg::with_name("constraints_by_template")
+                    .long("constraints_by_template")
+                    .takes_value(false)
+                    .help("Prints the number of constraints produced by each template (only with --O0 or --no_simplification)"),
+            )
+            .arg(
+                Arg::with_name("wtns_dag")
+                    .long("wtns_dag")
+                    .takes_value(false)
+                    .help("Outputs the witness dependency graph in dot format"),
+            )
+            .arg(
+                Arg::with_name("bisect")
+                    .long("bisect")
+                    .takes_value(false)
+                    .help("On a failed instantiation, looks for the innermost template instance that reproduces the failure"),
+            )
+            .arg(
+                Arg::with_name("declaration_order")
+                    .long("declaration_order")
+                    .takes_value(false)
+                    .help("Signals of every non main template are numbered in declaration order"),
+            )
+            .arg(
+                Arg::with_name("sql")
+                    .long("sql")
+                    .takes_value(false)
+                    .help("Outputs the constraints as an sql script that fills a table with one row per non zero coefficient"),
+            )
+            .arg(
+                Arg::with_name("check_signal_count")
+                    .long("check_signal_count")
+                    .takes_value(false)
+                    .hidden(true)
+                    .help("Checks that every template instance gets as many signals as it declares"),
+            )
+            .arg(
+                Arg::with_name("compare_against")
+                    .long("compare_against")
+                    .takes_value(true)
+                    .help("Compares the constraint and signal counts with a counts file of a previous build"),
+            )
+            .arg(
+                Arg::with_name("template_ranges")
+                    .long("template_ranges")
+                    .takes_value(false)
+                    .help("Prints the smallest and largest number of signals among the instances of each template"),
+            )
+            .arg(
+                Arg::with_name("werror")
+                    .long("werror")
+                    .takes_value(false)
+                    .help("Treats the public signal naming warnings as errors"),
+            )
+            .arg(
+                Arg::with_name("public_signal_pattern")
+                    .long("public_signal_pattern")
+                    .takes_value(true)
+                    .help("Regular expression that the names of the public signals of main must match"),
+            )
+            .arg(
+                Arg::with_name("check_witness_safety")
+                    .long("check_witness_safety")
+                    .takes_value(false)
+                    .help("Warns about witness computations that divide by a signal that may be zero"),
+            )
+            .arg(
+                Arg::with_name("callgraph")
+                    .long("callgraph")
+                    .takes_value(false)
+                    .help("Outputs the template call graph in dot and json format"),
+            )
+            .arg(
+                Arg::with_name("mult_depth")
+                    .long("mult_depth")
+                    .takes_value(false)
+                    .help("Prints the multiplicative depth of the simplified constraints"),
+            )
+            .arg(
+                Arg::with_name("keep_intermediates")
+                    .long("keep_intermediates")
+                    .takes_value(false)
+                    .help("Keeps the intermediate artifacts of the compilation in <name>_intermediates"),
+            )
+            .arg(
+                Arg::with_name("constraints_by_file")
+                    .long("constraints_by_file")
+                    .takes_value(false)
+                    .help("Prints the number of constraints produced by the templates of each file"),
+            )
+            .arg(
+                Arg::with_name("checked_division")
+                    .long("checked_division")
+                    .takes_value(false)
+                    .help("Reports the divisions between constants that are not exact in the integers"),
+            )
+            .arg(
+                Arg::with_name("manifest")
+                    .long("manifest")
+                    .takes_value(false)
+                    .help("Outputs the name, type and component of every witness signal in json format"),
+            )
+            .arg(
+                Arg::with_name("no_timing")
+                    .long("no_timing")
+                    .takes_value(false)
+                    .help("Leaves the wall-clock times out of the printed output, so two runs print the same"),
+            )
+            .arg(
+                Arg::with_name("symbolic_witness")
+                    .long("symbolic_witness")
+                    .takes_value(false)
+                    .help("Writes the closed form of every computed signal in terms of the inputs of main"),
+            )
+            .arg(
+                Arg::with_name("symbolic_witness_limit")
+                    .long("symbolic_witness_limit")
+                    .takes_value(true)
+                    .default_value("64")
+                    .help("Largest number of operations and operands of an expression written by --symbolic_witness"),
+            )
+            .arg(
+                Arg::with_name("strict_inputs")
+                    .long("strict_inputs")
+                    .takes_value(false)
+                    .help("Turns the subcomponent inputs that are never assigned into errors"),
+            )
+            .arg(
+                Arg::with_name("dof")
+                    .long("dof")
+                    .takes_value(false)
+                    .help("Prints a lower bound of the witness signals left free by the constraints"),
+            )
+            .arg(
+                Arg::with_name("export_chunk_size")
+                    .long("export_chunk_size")
+                    .takes_value(true)
+                    .default_value("1")
+                    .help("Number of constraints written to the json files before each flush, 0 flushes once at the end"),
+            )
+            .arg(
+                Arg::with_name("max_instances")
+                    .long("max_instances")
+                    .takes_value(true)
+                    .help("Maximum number of template instances, the instantiation fails beyond it"),
+            )
+            .arg(
+                Arg::with_name("max_non_linear")
+                    .long("max_non_linear")
+                    .takes_value(true)
+                    .help("Maximum number of non linear constraints of the final circuit, the build fails beyond it"),
+            )
+            .arg(
+                Arg::with_name("shard_size")
+                    .long("shard_size")
+                    .takes_value(true)
+                    .help("Also writes the r1cs as <name>_shard_<i>.r1cs files of at most this many constraints, described by <name>_shard.json"),
+            )
+            .arg(
+                Arg::with_name("promote")
+                    .long("promote")
+                    .takes_value(true)
+                    .help("Comma separated paths of signals (as main.sub.x) written as public outputs"),
+            )
+            .arg(
+                Arg::with_name("threads")
+                    .long("threads")
+                    .takes_value(true)
+                    .help("Maximum number of threads used by the simplification, all the cores by default"),
+            )
+            .arg(
+                Arg::with_name("verify_parallel")
+                    .long("verify_parallel")
+                    .takes_value(false)
+                    .help("Checks that the parallel and the sequential simplification produce the same constraints"),
+            )
+            .arg(
+                Arg::with_name("signal_cycles")
+                    .long("signal_cycles")
+                    .takes_value(false)
+                    .help("Reports the signals whose witness computations depend on each other as errors"),
+            )
+            .arg(
+                Arg::with_name("r1cs_info")
+                    .long("r1cs_info")
+                    .takes_value(false)
+                    .help("Prints the summary of snarkjs r1cs info for the r1cs of the circuit"),
+            )
+            .arg(
+                Arg::with_name("constraints_by_category")
+                    .long("constraints_by_category")
+                    .takes_value(false)
+                    .help("Prints the number of constraints of each category given with pragma category"),
+            )
+            .arg(
+                Arg::with_name("bellman")
+                    .long("bellman")
+                    .takes_value(false)
+                    .help("Writes the r1cs output as (a, b, c) triplets of little endian field elements, to be loaded into bellman"),
+            )
+            .arg(
+                Arg::with_name("skip_simplification")
+                    .long("no_simplification")
+                    .takes_value(false)
+                    .help("Builds the constraint list from the dag without any simplification"),
+            )
+            .arg(
+                Arg::with_name("sym_constraints")
+                    .long("sym_constraints")
+                    .takes_value(false)
+                    .help("Writes the template instance that produced each constraint to a _constraints.sym file (only with --O0 or --no_simplification)"),
+            )
+            .arg(
+                Arg::with_name("summary")
+                    .long("summary")
+                    .takes_value(false)
+                    .help("Writes circuit_summary.json with the counts and the public signals of the circuit"),
+            )
+            .arg(
+                Arg::with_name("deterministic")
+                    .long("deterministic")
+                    .takes_value(false)
+                    .help("Sorts the signals and components of every instance by name so that builds are reproducible"),
+            )
+            .arg(
+                Arg::with_name("r1cs_spans")
+                    .long("r1cs_spans")
+                    .takes_value(false)
+                    .help("Adds a section to the r1cs with the source location of each constraint, unknown for the ones derived by the simplification"),
+            )
+            .arg(
+                Arg::with_name("components_dot")
+                    .long("components_dot")
+                    .takes_value(false)
+                    .help("Outputs the template instances and their subcomponents as a Graphviz graph in <name>_components.dot"),
+            )
+            .arg(
+                Arg::with_name("dedup_constraints")
+                    .long("dedup_constraints")
+                    .takes_value(false)
+                    .help("Removes the constraints that repeat another one up to a constant factor"),
+            )
+            .arg(
+                Arg::with_name("normalize")
+                    .long("normalize")
+                    .takes_value(false)
+                    .help("Writes every constraint scaled so the leading coefficients of C and A are 1, equivalent circuits get the same r1cs"),
+            )
+            .arg(
+                Arg::with_name("unconstrained_report")
+                    .long("unconstrained_report")
+                    .takes_value(false)
+                    .help("Lists the signals of the simplified circuit that appear in no constraint, grouped by component"),
+            )
+            .arg(
+                Arg::with_name("exe_cache")
+                    .long("exe_cache")
+                    .takes_value(false)
+                    .help("Reuses the executed program of <name>.exe-cache when the source did not change, and writes it otherwise"),
+            )
+            .arg(
+                Arg::with_name("stable_headers")
+                    .long("stable_headers")
+                    .takes_value(false)
+                    .help("Names the code of each template instance with a hash of its parameters instead of its position, so it does not change with the instantiation order"),
+            )
+            .arg(
+                Arg::with_name("intern_names")
+                    .long("intern_names")
+                    .takes_value(false)
+                    .hidden(true)
+                    .help("Builds the signal names of arrays with fewer allocations"),
+            )
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn matches(args: &[&str]) -> ArgMatches<'static> {
+            // Building the app panics when two arguments share a name
+            app().get_matches_from_safe(args).unwrap()
+        }
+
+        #[test]
+        fn o0_and_no_simplification_are_different_flags() {
+            let o0 = matches(&["circom", "--O0"]);
+            assert!(get_simplification_style(&o0) == Result::Ok(SimplificationStyle::O0));
+            assert!(!get_no_simplification(&o0));
+            let skip = matches(&["circom", "--no_simplification"]);
+            assert!(get_simplification_style(&skip) != Result::Ok(SimplificationStyle::O0));
+            assert!(get_no_simplification(&skip));
+        }
     }
 }
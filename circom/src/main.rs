@@ -6,6 +6,12 @@ mod type_analysis_user;
 
 use ansi_term::Colour;
 use input_user::Input;
+
+#[cfg(feature = "alloc_stats")]
+#[global_allocator]
+static GLOBAL: constraint_generation::alloc_stats::CountingAllocator =
+    constraint_generation::alloc_stats::CountingAllocator;
+
 fn main() {
     let result = start();
     if result.is_err() {
@@ -20,14 +26,25 @@ fn start() -> Result<(), ()> {
     use execution_user::ExecutionConfig;
     let user_input = Input::new()?;
     let mut program_archive = parser_user::parse_project(&user_input)?;
+    execution_user::resolve_generator_powers(&mut program_archive)?;
     type_analysis_user::analyse_project(&mut program_archive)?;
 
     let config = ExecutionConfig {
         no_rounds: user_input.no_rounds(),
+        compare_against: user_input.compare_against(),
+        public_signal_pattern: user_input.public_signal_pattern(),
+        symbolic_witness_limit: user_input.symbolic_witness_limit(),
+        export_chunk_size: user_input.export_chunk_size(),
+        max_instances: user_input.max_instances(),
+        max_non_linear: user_input.max_non_linear(),
+        promoted_signals: user_input.promoted_signals(),
+        shard_size: user_input.shard_size(),
+        threads: user_input.threads(),
         flag_p: user_input.parallel_simplification_flag(),
         flag_s: user_input.reduced_simplification_flag(),
         flag_f: user_input.unsimplified_flag(),
         inspect_constraints_flag: user_input.inspect_constraints_flag(),
+        alloc_stats_flag: user_input.alloc_stats_flag(),
         r1cs_flag: user_input.r1cs_flag(),
         json_constraint_flag: user_input.json_constraints_flag(),
         json_substitution_flag: user_input.json_substitutions_flag(),
@@ -35,6 +52,59 @@ fn start() -> Result<(), ()> {
         sym: user_input.sym_file().to_string(),
         r1cs: user_input.r1cs_file().to_string(),
         json_constraints: user_input.json_constraints_file().to_string(),
+        counts: user_input.counts_file().to_string(),
+        sql_constraints: user_input.sql_constraints_file().to_string(),
+        manifest: user_input.manifest_file().to_string(),
+        wtns_dag: user_input.wtns_dag_file().to_string(),
+        summary: user_input.summary_file().to_string(),
+        exe_cache: user_input.exe_cache_file().to_string(),
+        sym_constraints: user_input.sym_constraints_file().to_string(),
+        callgraph: user_input.callgraph_file().to_string(),
+        components_dot: user_input.components_dot_file().to_string(),
+        r1cs_shards: user_input.r1cs_shards_file().to_string(),
+        symbolic_witness: user_input.symbolic_witness_file().to_string(),
+        intermediates: user_input.intermediates_folder().to_string(),
+        assert_as_constraint_flag: user_input.assert_as_constraint_flag(),
+        suggest_flag: user_input.suggest_flag(),
+        trigger_order_flag: user_input.trigger_order_flag(),
+        emit_counts_json_flag: user_input.emit_counts_json_flag(),
+        strict_pragmas_flag: user_input.strict_pragmas_flag(),
+        constraints_by_template_flag: user_input.constraints_by_template_flag(),
+        wtns_dag_flag: user_input.wtns_dag_flag(),
+        bisect_flag: user_input.bisect_flag(),
+        declaration_order_flag: user_input.declaration_order_flag(),
+        sql_flag: user_input.sql_flag(),
+        check_signal_count_flag: user_input.check_signal_count_flag(),
+        template_ranges_flag: user_input.template_ranges_flag(),
+        werror_flag: user_input.werror_flag(),
+        check_witness_safety_flag: user_input.check_witness_safety_flag(),
+        callgraph_flag: user_input.callgraph_flag(),
+        mult_depth_flag: user_input.mult_depth_flag(),
+        keep_intermediates_flag: user_input.keep_intermediates_flag(),
+        constraints_by_file_flag: user_input.constraints_by_file_flag(),
+        checked_division_flag: user_input.checked_division_flag(),
+        manifest_flag: user_input.manifest_flag(),
+        no_timing_flag: user_input.no_timing_flag(),
+        symbolic_witness_flag: user_input.symbolic_witness_flag(),
+        strict_inputs_flag: user_input.strict_inputs_flag(),
+        dof_flag: user_input.dof_flag(),
+        verify_parallel_flag: user_input.verify_parallel_flag(),
+        signal_cycles_flag: user_input.signal_cycles_flag(),
+        r1cs_info_flag: user_input.r1cs_info_flag(),
+        constraints_by_category_flag: user_input.constraints_by_category_flag(),
+        bellman_flag: user_input.bellman_flag(),
+        no_simplification_flag: user_input.no_simplification_flag(),
+        sym_constraints_flag: user_input.sym_constraints_flag(),
+        summary_flag: user_input.summary_flag(),
+        deterministic_flag: user_input.deterministic_flag(),
+        r1cs_spans_flag: user_input.r1cs_spans_flag(),
+        components_dot_flag: user_input.components_dot_flag(),
+        dedup_constraints_flag: user_input.dedup_constraints_flag(),
+        normalize_flag: user_input.normalize_flag(),
+        unconstrained_report_flag: user_input.unconstrained_report_flag(),
+        exe_cache_flag: user_input.exe_cache_flag(),
+        stable_headers_flag: user_input.stable_headers_flag(),
+        intern_names_flag: user_input.intern_names_flag(),
     };
     let circuit = execution_user::execute_project(program_archive, config)?;
     let compilation_config = CompilerConfig {
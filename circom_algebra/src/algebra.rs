@@ -2,10 +2,12 @@ use super::modular_arithmetic;
 pub use super::modular_arithmetic::ArithmeticError;
 use num_bigint::BigInt;
 use num_traits::{ToPrimitive, Zero};
+use serde_derive::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::hash::Hash;
 
+#[derive(Serialize, Deserialize)]
 pub enum ArithmeticExpression<C>
 where
     C: Hash + Eq,
@@ -999,7 +1001,7 @@ impl Substitution<usize> {
     Represents a constraint of the form: A*B - C = 0
     where A,B and C are linear expression.
 */
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Constraint<C>
 where
     C: Hash + Eq,
@@ -1065,6 +1067,20 @@ impl<C: Default + Clone + Display + Hash + Eq> Constraint<C> {
         Substitution { from: signal.clone(), to: raw_expression }
     }
 
+    // Clearing a signal of c divides by its coefficient, the first signal
+    // whose coefficient has no inverse in the field is the error
+    pub fn check_coefficients(&self, field: &BigInt) -> Result<(), (C, ArithmeticError)> {
+        let constant = Constraint::constant_coefficient();
+        let one = BigInt::from(1);
+        for (signal, coefficient) in &self.c {
+            if *signal != constant {
+                modular_arithmetic::div(&one, coefficient, field)
+                    .map_err(|error| (signal.clone(), error))?;
+            }
+        }
+        Result::Ok(())
+    }
+
     pub fn take_cloned_signals(&self) -> HashSet<C> {
         let mut signals = HashSet::new();
         for signal in self.a().keys() {
@@ -1186,6 +1202,81 @@ impl Constraint<usize> {
         let c = apply_vectored_correspondence(&self.c, witness);
         Constraint::new(a, b, c)
     }
+
+    // Two constraints that only differ by a non zero factor (in A, B or
+    // the whole constraint, sign flips included) or by the order of the
+    // product get the same canonical form
+    pub fn canonical_form(&self, field: &BigInt) -> CanonicalConstraint {
+        let mut a = canonical_terms(&self.a, field);
+        let mut b = canonical_terms(&self.b, field);
+        let mut c = canonical_terms(&self.c, field);
+        if a.is_empty() || b.is_empty() {
+            a.clear();
+            b.clear();
+            if let Option::Some(lead) = leading_coefficient(&c) {
+                scale_terms(&mut c, &inverse_of(&lead, field), field);
+            }
+        } else {
+            let lead_a = leading_coefficient(&a).unwrap();
+            let lead_b = leading_coefficient(&b).unwrap();
+            scale_terms(&mut a, &inverse_of(&lead_a, field), field);
+            scale_terms(&mut b, &inverse_of(&lead_b, field), field);
+            let factor = modular_arithmetic::mul(&lead_a, &lead_b, field);
+            scale_terms(&mut c, &inverse_of(&factor, field), field);
+            if b < a {
+                std::mem::swap(&mut a, &mut b);
+            }
+        }
+        CanonicalConstraint { a, b, c }
+    }
+}
+
+// Coefficients reduced to [0, field), without zeros and sorted by signal
+pub type CanonicalTerms = Vec<(usize, BigInt)>;
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct CanonicalConstraint {
+    pub a: CanonicalTerms,
+    pub b: CanonicalTerms,
+    pub c: CanonicalTerms,
+}
+
+impl CanonicalConstraint {
+    pub fn is_linear(&self) -> bool {
+        self.a.is_empty()
+    }
+}
+
+fn canonical_terms(expr: &HashMap<usize, BigInt>, field: &BigInt) -> CanonicalTerms {
+    let zero = BigInt::from(0);
+    let mut terms: CanonicalTerms = expr
+        .iter()
+        .map(|(k, v)| (*k, modular_arithmetic::add(v, &zero, field)))
+        .filter(|(_, v)| !v.is_zero())
+        .collect();
+    terms.sort_by_key(|(k, _)| *k);
+    terms
+}
+
+// The leading coefficient is the one of the first signal,
+// the constant term is only used when there are no signals
+fn leading_coefficient(terms: &CanonicalTerms) -> Option<BigInt> {
+    let constant = Constraint::<usize>::constant_coefficient();
+    let signal = terms.iter().find(|(k, _)| *k != constant);
+    signal.or_else(|| terms.first()).map(|(_, v)| v.clone())
+}
+
+fn scale_terms(terms: &mut CanonicalTerms, factor: &BigInt, field: &BigInt) {
+    for (_, v) in terms.iter_mut() {
+        *v = modular_arithmetic::mul(v, factor, field);
+    }
+}
+
+fn inverse_of(value: &BigInt, field: &BigInt) -> BigInt {
+    match modular_arithmetic::div(&BigInt::from(1), value, field) {
+        Result::Ok(inverse) => inverse,
+        Result::Err(_) => unreachable!("zero coefficients are removed before scaling"),
+    }
 }
 
 // model utils
@@ -1338,12 +1429,33 @@ where
     HashMap::contains_key(expr, &cq) && HashMap::len(expr) == 1
 }
 
-pub fn normalize(c: Constraint<usize>, _field: &BigInt) -> Constraint<usize> {
-    use std::collections::LinkedList;
-    let _a: LinkedList<_> = c.a.iter().clone().collect();
-    let _b: LinkedList<_> = c.b.iter().clone().collect();
-    let _c: LinkedList<_> = c.c.iter().clone().collect();
-    todo!()
+// Representative of the constraints equal up to a factor: the leading
+// coefficient of C is 1 and so is the one of A, B takes the rest of the
+// product. Without C, the leading coefficients of A and B are 1
+pub fn normalize(c: Constraint<usize>, field: &BigInt) -> Constraint<usize> {
+    let mut a = canonical_terms(&c.a, field);
+    let mut b = canonical_terms(&c.b, field);
+    let mut c = canonical_terms(&c.c, field);
+    if a.is_empty() || b.is_empty() {
+        a.clear();
+        b.clear();
+    }
+    if let Option::Some(lead) = leading_coefficient(&c) {
+        let factor = inverse_of(&lead, field);
+        scale_terms(&mut a, &factor, field);
+        scale_terms(&mut c, &factor, field);
+    }
+    if let Option::Some(lead_a) = leading_coefficient(&a) {
+        scale_terms(&mut a, &inverse_of(&lead_a, field), field);
+        scale_terms(&mut b, &lead_a, field);
+    }
+    if c.is_empty() {
+        if let Option::Some(lead_b) = leading_coefficient(&b) {
+            scale_terms(&mut b, &inverse_of(&lead_b, field), field);
+        }
+    }
+    let into_map = |terms: CanonicalTerms| terms.into_iter().collect();
+    Constraint::new(into_map(a), into_map(b), into_map(c))
 }
 
 #[cfg(test)]
@@ -1450,4 +1562,82 @@ mod test {
         assert_eq!(*y_c, expected_y_c);
         assert_eq!(*constant_c, expected_constant_c);
     }
+
+    fn terms(entries: &[(usize, i64)]) -> HashMap<usize, BigInt> {
+        entries.iter().map(|(k, v)| (*k, BigInt::from(*v))).collect()
+    }
+
+    #[test]
+    fn algebra_constraint_canonical_form() {
+        let field = BigInt::parse_bytes(FIELD.as_bytes(), 10)
+            .expect("generating the big int was not possible");
+        let (constant, x, y, z) = (C::constant_coefficient(), 1, 2, 3);
+        // (2x) * (y + 1) - 3z = 0
+        let constraint =
+            C::new(terms(&[(x, 2)]), terms(&[(y, 1), (constant, 1)]), terms(&[(z, 3)]));
+        // the same constraint negated with the product sides swapped
+        let flipped =
+            C::new(terms(&[(y, 1), (constant, 1)]), terms(&[(x, -2)]), terms(&[(z, -3)]));
+        let different =
+            C::new(terms(&[(x, 2)]), terms(&[(y, 1), (constant, 1)]), terms(&[(z, 4)]));
+        let canonical = constraint.canonical_form(&field);
+        assert!(canonical == flipped.canonical_form(&field));
+        assert!(canonical != different.canonical_form(&field));
+        assert_eq!(canonical.a, vec![(constant, BigInt::from(1)), (y, BigInt::from(1))]);
+        assert_eq!(canonical.b, vec![(x, BigInt::from(1))]);
+        assert_eq!(canonical.c, vec![(z, BigInt::from(130))]);
+
+        // 3x + 6 = 0 and -x - 2 = 0
+        let linear = C::new(HashMap::new(), HashMap::new(), terms(&[(x, 3), (constant, 6)]));
+        let scaled = C::new(HashMap::new(), HashMap::new(), terms(&[(x, -1), (constant, -2)]));
+        assert!(linear.canonical_form(&field) == scaled.canonical_form(&field));
+        assert!(linear.canonical_form(&field).is_linear());
+    }
+
+    fn serialized(constraint: &C) -> String {
+        let sorted = |terms: &HashMap<usize, BigInt>| {
+            let mut terms: Vec<_> = terms.iter().map(|(k, v)| format!("{}:{}", k, v)).collect();
+            terms.sort();
+            terms.join(",")
+        };
+        format!("{}|{}|{}", sorted(constraint.a()), sorted(constraint.b()), sorted(constraint.c()))
+    }
+
+    #[test]
+    fn algebra_constraint_normalize() {
+        use crate::algebra::normalize;
+        let field = BigInt::parse_bytes(FIELD.as_bytes(), 10)
+            .expect("generating the big int was not possible");
+        let (constant, x, y, z) = (C::constant_coefficient(), 1, 2, 3);
+        // (2x) * (y + 1) - 3z = 0 and the same constraint times -3
+        let constraint =
+            C::new(terms(&[(x, 2)]), terms(&[(y, 1), (constant, 1)]), terms(&[(z, 3)]));
+        let scaled =
+            C::new(terms(&[(x, -6)]), terms(&[(y, 1), (constant, 1)]), terms(&[(z, -9)]));
+        let normalized = normalize(constraint, &field);
+        assert_eq!(serialized(&normalized), serialized(&normalize(scaled, &field)));
+        // x * (2/3 * y + 2/3) = z, 2/3 is 172 in the field
+        assert_eq!(serialized(&normalized), "1:1|0:172,2:172|3:1");
+
+        // x * y = 0 and (5x) * (3y) = 0
+        let product = C::new(terms(&[(x, 1)]), terms(&[(y, 1)]), HashMap::new());
+        let scaled = C::new(terms(&[(x, 5)]), terms(&[(y, 3)]), HashMap::new());
+        assert_eq!(serialized(&normalize(scaled, &field)), serialized(&product));
+    }
+
+    #[test]
+    fn coefficients_without_inverse_are_found() {
+        let field = BigInt::parse_bytes(FIELD.as_bytes(), 10)
+            .expect("generating the big int was not possible");
+        let terms = |values: &[(usize, i64)]| -> HashMap<_, _> {
+            values.iter().map(|(s, v)| (*s, BigInt::from(*v))).collect()
+        };
+        let valid = C::new(HashMap::new(), HashMap::new(), terms(&[(1, 3), (2, -1)]));
+        assert!(valid.check_coefficients(&field).is_ok());
+        // 257 is zero in the field
+        let invalid = C::new(HashMap::new(), HashMap::new(), terms(&[(1, 3), (2, 257)]));
+        let (signal, error) = invalid.check_coefficients(&field).err().unwrap();
+        assert_eq!(signal, 2);
+        assert!(matches!(error, crate::algebra::ArithmeticError::DivisionByZero));
+    }
 }
@@ -3,4 +3,8 @@ mod component_preprocess;
 mod merger;
 mod sugar_cleaner;
 mod type_inference;
+pub mod symbolic_witness;
 pub mod very_concrete_program;
+pub mod witness_dag;
+pub mod witness_layout;
+mod witness_safety;
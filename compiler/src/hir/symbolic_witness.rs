@@ -0,0 +1,385 @@
+use super::very_concrete_program::{trigger_path, VCP};
+use super::witness_dag::accessed_name;
+use num_bigint_dig::BigInt;
+use program_structure::ast::{
+    Access, Expression, ExpressionInfixOpcode, ExpressionPrefixOpcode, SignalType, Statement,
+};
+use std::collections::{HashMap, HashSet};
+
+// Closed form of every computed signal in terms of the inputs of main, the
+// signals assigned inside conditionals or loops and the positions of arrays
+// have no closed form and are left out
+pub struct SymbolicWitness {
+    // (signal, expression), None when the expression is over the size limit
+    pub entries: Vec<(String, Option<String>)>,
+}
+
+impl SymbolicWitness {
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (signal, expression) in &self.entries {
+            match expression {
+                Option::Some(expression) => {
+                    text.push_str(&format!("{} = {}\n", signal, expression))
+                }
+                Option::None => text.push_str(&format!("{} (expression too large)\n", signal)),
+            }
+        }
+        text
+    }
+}
+
+#[derive(Clone)]
+enum Failure {
+    TooLarge,
+    Open,
+}
+
+// Rendered expression and its number of nodes
+type Symbolic = Result<(String, usize), Failure>;
+
+fn infix_str(op: ExpressionInfixOpcode) -> &'static str {
+    use ExpressionInfixOpcode::*;
+    match op {
+        Mul => "*",
+        Div => "/",
+        Add => "+",
+        Sub => "-",
+        Pow => "**",
+        IntDiv => "\\",
+        Mod => "%",
+        ShiftL => "<<",
+        ShiftR => ">>",
+        LesserEq => "<=",
+        GreaterEq => ">=",
+        Lesser => "<",
+        Greater => ">",
+        Eq => "==",
+        NotEq => "!=",
+        BoolOr => "||",
+        BoolAnd => "&&",
+        BitOr => "|",
+        BitAnd => "&",
+        BitXor => "^",
+    }
+}
+
+fn prefix_str(op: ExpressionPrefixOpcode) -> &'static str {
+    use ExpressionPrefixOpcode::*;
+    match op {
+        Sub => "-",
+        BoolNot => "!",
+        Complement => "~",
+    }
+}
+
+// Straight line definitions of a template: variables are replaced by their
+// value at the point of use, so the stored expressions only read signals
+struct Definitions<'a> {
+    signals: &'a HashSet<String>,
+    values: HashMap<String, Expression>,
+    open: HashSet<String>,
+}
+
+impl<'a> Definitions<'a> {
+    fn new(
+        code: &Statement,
+        arguments: &[(String, BigInt)],
+        signals: &'a HashSet<String>,
+    ) -> Definitions<'a> {
+        let mut definitions = Definitions { signals, values: HashMap::new(), open: HashSet::new() };
+        for (name, value) in arguments {
+            let number = Expression::Number(code.get_meta().clone(), value.clone());
+            definitions.values.insert(name.clone(), number);
+        }
+        definitions.visit(code, true);
+        definitions
+    }
+
+    fn visit(&mut self, stmt: &Statement, straight: bool) {
+        use Statement::*;
+        match stmt {
+            IfThenElse { if_case, else_case, .. } => {
+                self.visit(if_case, false);
+                if let Option::Some(else_case) = else_case {
+                    self.visit(else_case, false);
+                }
+            }
+            While { stmt, .. } => self.visit(stmt, false),
+            InitializationBlock { initializations, .. } => {
+                for init in initializations {
+                    self.visit(init, straight);
+                }
+            }
+            Block { stmts, .. } => {
+                for stmt in stmts {
+                    self.visit(stmt, straight);
+                }
+            }
+            Substitution { var, access, rhe, .. } => {
+                let target = accessed_name(var, access);
+                let scalar = access.iter().all(|acc| matches!(acc, Access::ComponentAccess(_)));
+                if straight && scalar {
+                    let value = self.inline(rhe);
+                    self.open.remove(&target);
+                    self.values.insert(target, value);
+                } else {
+                    self.values.remove(&target);
+                    self.open.insert(target);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Replaces the variables read by their current values, signals are kept
+    fn inline(&self, expr: &Expression) -> Expression {
+        use Expression::*;
+        match expr {
+            Variable { name, access, .. } if access.is_empty() && !self.signals.contains(name) => {
+                self.values.get(name).cloned().unwrap_or_else(|| expr.clone())
+            }
+            InfixOp { meta, lhe, infix_op, rhe } => InfixOp {
+                meta: meta.clone(),
+                lhe: Box::new(self.inline(lhe)),
+                infix_op: *infix_op,
+                rhe: Box::new(self.inline(rhe)),
+            },
+            PrefixOp { meta, prefix_op, rhe } => PrefixOp {
+                meta: meta.clone(),
+                prefix_op: *prefix_op,
+                rhe: Box::new(self.inline(rhe)),
+            },
+            InlineSwitchOp { meta, cond, if_true, if_false } => InlineSwitchOp {
+                meta: meta.clone(),
+                cond: Box::new(self.inline(cond)),
+                if_true: Box::new(self.inline(if_true)),
+                if_false: Box::new(self.inline(if_false)),
+            },
+            Call { meta, id, args } => Call {
+                meta: meta.clone(),
+                id: id.clone(),
+                args: args.iter().map(|arg| self.inline(arg)).collect(),
+            },
+            ArrayInLine { meta, values } => ArrayInLine {
+                meta: meta.clone(),
+                values: values.iter().map(|value| self.inline(value)).collect(),
+            },
+            _ => expr.clone(),
+        }
+    }
+}
+
+struct Component {
+    template_id: usize,
+    // Parent path and name of the component in the parent, only for the
+    // components that are not part of a component array
+    parent: Option<(String, String)>,
+}
+
+struct Resolver<'a> {
+    vcp: &'a VCP,
+    limit: usize,
+    components: HashMap<String, Component>,
+    // template id -> definitions
+    definitions: Vec<HashMap<String, Expression>>,
+    resolved: HashMap<String, Symbolic>,
+    visiting: HashSet<String>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(vcp: &'a VCP, limit: usize) -> Resolver<'a> {
+        let mut components = HashMap::new();
+        components.insert("main".to_string(), Component { template_id: vcp.main_id, parent: None });
+        vcp.visit_components(&mut |path, template_id| {
+            for trigger in &vcp.templates[template_id].triggers {
+                let parent = if trigger.indexed_with.is_empty() {
+                    Option::Some((path.to_string(), trigger.component_name.clone()))
+                } else {
+                    Option::None
+                };
+                let child = Component { template_id: trigger.template_id, parent };
+                components.insert(trigger_path(path, trigger), child);
+            }
+        });
+        let mut definitions = Vec::new();
+        for instance in &vcp.templates {
+            let mut signals: HashSet<_> = instance.signals.iter().map(|s| s.name.clone()).collect();
+            signals.extend(instance.components.iter().map(|c| c.name.clone()));
+            let arguments: Vec<_> = instance
+                .header
+                .iter()
+                .filter(|arg| arg.lengths.is_empty() && arg.values.len() == 1)
+                .map(|arg| (arg.name.clone(), arg.values[0].clone()))
+                .collect();
+            let local = Definitions::new(&instance.code, &arguments, &signals);
+            let mut values = local.values;
+            values.retain(|name, _| signals.contains(name.split('.').next().unwrap_or(name)));
+            definitions.push(values);
+        }
+        Resolver {
+            vcp,
+            limit,
+            components,
+            definitions,
+            resolved: HashMap::new(),
+            visiting: HashSet::new(),
+        }
+    }
+
+    fn is_main_input(&self, name: &str) -> bool {
+        let main = &self.vcp.templates[self.vcp.main_id];
+        main.signals.iter().any(|s| s.name == name && s.xtype == SignalType::Input)
+    }
+
+    fn is_defined(&self, path: &str, name: &str) -> bool {
+        let component = &self.components[path];
+        if self.definitions[component.template_id].contains_key(name) {
+            return true;
+        }
+        match &component.parent {
+            Option::Some((parent, local)) => {
+                let outer = format!("{}.{}", local, name);
+                self.definitions[self.components[parent].template_id].contains_key(&outer)
+            }
+            Option::None => false,
+        }
+    }
+
+    // Signals without a definition (the inputs of main) are atoms
+    fn signal(&mut self, path: &str, name: &str) -> Symbolic {
+        let full_name = format!("{}.{}", path, name);
+        if let Option::Some(symbolic) = self.resolved.get(&full_name) {
+            return symbolic.clone();
+        }
+        if !self.visiting.insert(full_name.clone()) {
+            return Result::Err(Failure::Open);
+        }
+        let component = &self.components[path];
+        let definition = self.definitions[component.template_id].get(name).cloned();
+        let symbolic = match (definition, &component.parent) {
+            (Option::Some(definition), _) => self.expression(path, &definition),
+            (Option::None, Option::Some((parent, local))) => {
+                let outer = format!("{}.{}", local, name);
+                let parent = parent.clone();
+                let parent_id = self.components[&parent].template_id;
+                match self.definitions[parent_id].get(&outer).cloned() {
+                    Option::Some(definition) => self.expression(&parent, &definition),
+                    Option::None => Result::Err(Failure::Open),
+                }
+            }
+            (Option::None, Option::None) if path == "main" && self.is_main_input(name) => {
+                Result::Ok((full_name.clone(), 1))
+            }
+            (Option::None, Option::None) => Result::Err(Failure::Open),
+        };
+        self.visiting.remove(&full_name);
+        self.resolved.insert(full_name, symbolic.clone());
+        symbolic
+    }
+
+    fn bounded(&self, rendered: String, size: usize) -> Symbolic {
+        if size > self.limit {
+            Result::Err(Failure::TooLarge)
+        } else {
+            Result::Ok((rendered, size))
+        }
+    }
+
+    fn expression(&mut self, path: &str, expr: &Expression) -> Symbolic {
+        use Expression::*;
+        match expr {
+            Number(_, value) => Result::Ok((value.to_string(), 1)),
+            Variable { name, access, .. } => match access.as_slice() {
+                [] => self.signal(path, name),
+                [Access::ComponentAccess(signal)] => {
+                    let child = format!("{}.{}", path, name);
+                    if self.components.contains_key(&child) {
+                        self.signal(&child, signal)
+                    } else {
+                        Result::Err(Failure::Open)
+                    }
+                }
+                _ => Result::Err(Failure::Open),
+            },
+            InfixOp { lhe, infix_op, rhe, .. } => {
+                let (lhe, lhe_size) = self.expression(path, lhe)?;
+                let (rhe, rhe_size) = self.expression(path, rhe)?;
+                let rendered = format!("({} {} {})", lhe, infix_str(*infix_op), rhe);
+                self.bounded(rendered, lhe_size + rhe_size + 1)
+            }
+            PrefixOp { prefix_op, rhe, .. } => {
+                let (rhe, size) = self.expression(path, rhe)?;
+                self.bounded(format!("{}{}", prefix_str(*prefix_op), rhe), size + 1)
+            }
+            InlineSwitchOp { cond, if_true, if_false, .. } => {
+                let (cond, cond_size) = self.expression(path, cond)?;
+                let (if_true, true_size) = self.expression(path, if_true)?;
+                let (if_false, false_size) = self.expression(path, if_false)?;
+                let rendered = format!("({} ? {} : {})", cond, if_true, if_false);
+                self.bounded(rendered, cond_size + true_size + false_size + 1)
+            }
+            Call { id, args, .. } => {
+                let mut rendered = Vec::new();
+                let mut size = 1;
+                for arg in args {
+                    let (arg, arg_size) = self.expression(path, arg)?;
+                    rendered.push(arg);
+                    size += arg_size;
+                }
+                self.bounded(format!("{}({})", id, rendered.join(", ")), size)
+            }
+            ArrayInLine { .. } => Result::Err(Failure::Open),
+        }
+    }
+}
+
+pub fn build(vcp: &VCP, limit: usize) -> SymbolicWitness {
+    let mut resolver = Resolver::new(vcp, limit);
+    let mut paths = Vec::new();
+    vcp.visit_components(&mut |path, template_id| paths.push((path.to_string(), template_id)));
+    let mut entries = Vec::new();
+    for (path, template_id) in paths {
+        for signal in &vcp.templates[template_id].signals {
+            let is_input = signal.xtype == SignalType::Input;
+            if !signal.lengths.is_empty() || (path == "main" && is_input) {
+                continue;
+            }
+            if !resolver.is_defined(&path, &signal.name) {
+                continue;
+            }
+            let name = format!("{}.{}", path, signal.name);
+            match resolver.signal(&path, &signal.name) {
+                Result::Ok((expression, _)) => entries.push((name, Option::Some(expression))),
+                Result::Err(Failure::TooLarge) => entries.push((name, Option::None)),
+                Result::Err(Failure::Open) => {}
+            }
+        }
+    }
+    SymbolicWitness { entries }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hir::witness_dag::tests::multiplier_vcp;
+
+    #[test]
+    fn signals_are_expressed_with_the_inputs_of_main() {
+        let t = "((main.x + main.x) * (main.x + main.x))";
+        let out = format!("({} * main.y)", t);
+        let symbolic = multiplier_vcp().symbolic_witness(16);
+        let expected = vec![
+            ("main.out", out.clone()),
+            ("main.t", t.to_string()),
+            ("main.m.c", out),
+            ("main.m.a", t.to_string()),
+            ("main.m.b", "main.y".to_string()),
+        ];
+        let entries: Vec<_> =
+            symbolic.entries.iter().map(|(s, e)| (s.as_str(), e.clone().unwrap())).collect();
+        assert_eq!(entries, expected);
+        // t has 7 nodes and out 9
+        let bounded = multiplier_vcp().symbolic_witness(8).to_text();
+        assert!(bounded.starts_with("main.out (expression too large)\nmain.t = "));
+    }
+}
@@ -1,8 +1,12 @@
+use super::symbolic_witness::SymbolicWitness;
+use super::witness_dag::WitnessDag;
+use super::witness_layout::WitnessEntry;
 use num_bigint_dig::BigInt;
 use program_structure::ast::{SignalType, Statement};
+use program_structure::error_definition::ReportCollection;
 use program_structure::program_archive::ProgramArchive;
 use program_structure::program_library::file_definition::FileLibrary;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Range;
 use std::rc::Rc;
 
@@ -35,6 +39,17 @@ impl Signal {
     pub fn size(&self) -> usize {
         self.lengths.iter().fold(1, |p, c| p * (*c))
     }
+    // The names of the positions of an array in row-major order
+    pub fn element_names(&self) -> Vec<String> {
+        let mut names = vec![self.name.clone()];
+        for length in &self.lengths {
+            let prefixes = std::mem::take(&mut names);
+            for prefix in prefixes {
+                names.extend((0..*length).map(|index| format!("{}[{}]", prefix, index)));
+            }
+        }
+        names
+    }
 }
 
 #[derive(Clone)]
@@ -140,6 +155,10 @@ impl TemplateInstance {
         }
         self.signals.push(signal);
     }
+
+    pub fn number_of_signals(&self) -> usize {
+        self.number_of_inputs + self.number_of_outputs + self.number_of_intermediates
+    }
 }
 
 #[derive(Eq, PartialEq, Clone)]
@@ -171,6 +190,7 @@ pub struct VCPConfig {
     pub file_library: FileLibrary,
     pub templates: Vec<TemplateInstance>,
     pub templates_in_mixed: Vec<usize>,
+    pub public_inputs: Vec<String>,
     pub program: ProgramArchive,
 }
 
@@ -184,6 +204,8 @@ pub struct VCP {
     pub templates: Vec<TemplateInstance>,
     pub quick_knowledge: HashMap<String, VCT>,
     pub templates_in_mixed: Vec<usize>,
+    // Public inputs of main in the order of their signal ids
+    pub public_inputs: Vec<String>,
 }
 impl VCP {
     pub fn new(config: VCPConfig) -> VCP {
@@ -194,6 +216,7 @@ impl VCP {
             file_library: config.file_library,
             templates: config.templates,
             templates_in_mixed: config.templates_in_mixed,
+            public_inputs: config.public_inputs,
             functions: vec![],
             quick_knowledge: HashMap::new(),
         };
@@ -209,13 +232,251 @@ impl VCP {
     pub fn get_main_id(&self) -> usize {
         self.main_id
     }
+    // One name per public input of the r1cs, arrays are expanded to their
+    // elements, in the same order after the outputs
+    pub fn public_inputs(&self) -> Vec<String> {
+        let main = match self.templates.get(self.main_id) {
+            Option::Some(main) => main,
+            Option::None => return Vec::new(),
+        };
+        let mut names = Vec::new();
+        for input in &self.public_inputs {
+            let signal = main.signals.iter().find(|s| &s.name == input);
+            names.extend(signal.into_iter().flat_map(|s| s.element_names()));
+        }
+        names
+    }
     pub fn get_witness_list(&self) -> &Vec<usize> {
         &self.witness_list
     }
+    // Signal of each witness position, as set by add_witness_list
+    pub fn witness_as_vec(&self) -> &[usize] {
+        &self.witness_list
+    }
     pub fn get_stats(&self) -> &Stats {
         &self.stats
     }
     pub fn num_templates(&self) -> usize {
         self.templates.len()
     }
+    // Whether the instance runs in parallel and whether it has parallel
+    // subcomponents, None for an unknown id
+    pub fn parallelism_of(&self, template_id: usize) -> Option<(bool, bool)> {
+        self.templates.get(template_id).map(|t| (t.is_parallel, t.has_parallel_sub_cmp))
+    }
+    // Ids of the instances that run in parallel
+    pub fn parallel_instances(&self) -> Vec<usize> {
+        self.templates.iter().filter(|t| t.is_parallel).map(|t| t.template_id).collect()
+    }
+    pub fn trigger_order(&self) -> Vec<String> {
+        let mut order = Vec::new();
+        self.visit_components(&mut |path, _| order.push(path.to_string()));
+        order
+    }
+    pub fn witness_dependency_graph(&self) -> WitnessDag {
+        super::witness_dag::build(self)
+    }
+    // Errors for the signals whose witness computations depend on each other
+    pub fn check_signal_cycles(&self) -> ReportCollection {
+        super::witness_dag::cycle_reports(&self.witness_dependency_graph())
+    }
+    // Closed form of the computed signals, the expressions with more than
+    // limit nodes are left out
+    pub fn symbolic_witness(&self, limit: usize) -> SymbolicWitness {
+        super::symbolic_witness::build(self, limit)
+    }
+    // Every signal of the circuit with its id, path and type, the same
+    // signals as the .sym file
+    pub fn witness_layout(&self) -> Vec<WitnessEntry> {
+        super::witness_layout::build(self)
+    }
+    // Warnings for the witness computations that divide by a signal
+    // without checking that it is not zero
+    pub fn check_witness_safety(&self) -> ReportCollection {
+        super::witness_safety::check(self)
+    }
+    // Smallest and largest number of signals among the instances of each template
+    pub fn template_signal_ranges(&self) -> HashMap<String, (usize, usize)> {
+        let mut ranges = HashMap::new();
+        for instance in &self.templates {
+            let signals = instance.number_of_signals();
+            let range = ranges.entry(instance.template_name.clone()).or_insert((signals, signals));
+            range.0 = usize::min(range.0, signals);
+            range.1 = usize::max(range.1, signals);
+        }
+        ranges
+    }
+    // Template instances reachable from the given component (itself included).
+    // Every instance is expanded once, however many components use it
+    pub fn subtree_of(&self, component_path: &str) -> Vec<usize> {
+        let mut subtree = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending: Vec<_> = self.component_template(component_path).into_iter().collect();
+        while let Option::Some(template_id) = pending.pop() {
+            if !visited.insert(template_id) {
+                continue;
+            }
+            subtree.push(template_id);
+            for trigger in self.templates[template_id].triggers.iter().rev() {
+                pending.push(trigger.template_id);
+            }
+        }
+        subtree
+    }
+
+    // Template of the component at the path, the triggers are followed from
+    // main along the path instead of visiting every component
+    fn component_template(&self, component_path: &str) -> Option<usize> {
+        if self.main_id >= self.templates.len() {
+            return Option::None;
+        }
+        let mut path = "main".to_string();
+        let mut template_id = self.main_id;
+        while path != component_path {
+            let (child, child_id) =
+                self.templates[template_id].triggers.iter().find_map(|trigger| {
+                    let child = trigger_path(&path, trigger);
+                    let rest = component_path.strip_prefix(&child)?;
+                    if rest.is_empty() || rest.starts_with('.') {
+                        Option::Some((child, trigger.template_id))
+                    } else {
+                        Option::None
+                    }
+                })?;
+            path = child;
+            template_id = child_id;
+        }
+        Option::Some(template_id)
+    }
+
+    // Pre-order walk over the component tree: every component is
+    // visited before the subcomponents it triggers, siblings are
+    // visited in the order of the triggers
+    pub(crate) fn visit_components(&self, visitor: &mut dyn FnMut(&str, usize)) {
+        if self.main_id < self.templates.len() {
+            self.visit_component("main", self.main_id, visitor);
+        }
+    }
+    fn visit_component(
+        &self,
+        path: &str,
+        template_id: usize,
+        visitor: &mut dyn FnMut(&str, usize),
+    ) {
+        visitor(path, template_id);
+        for trigger in &self.templates[template_id].triggers {
+            let component_path = trigger_path(path, trigger);
+            self.visit_component(&component_path, trigger.template_id, visitor);
+        }
+    }
+}
+
+pub(crate) fn trigger_path(parent: &str, trigger: &Trigger) -> String {
+    let mut path = format!("{}.{}", parent, trigger.component_name);
+    for index in &trigger.indexed_with {
+        path.push_str(&format!("[{}]", index));
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use program_structure::ast::Meta;
+
+    // (component name, indexes, template id)
+    type TriggerInfo = (&'static str, Vec<usize>, usize);
+
+    fn instance(id: usize, name: &str, triggers: Vec<TriggerInfo>) -> TemplateInstance {
+        let triggers = triggers
+            .into_iter()
+            .map(|(component_name, indexed_with, template_id)| Trigger {
+                runs: format!("template_{}", template_id),
+                offset: 0,
+                component_offset: 0,
+                template_id,
+                component_name: component_name.to_string(),
+                indexed_with,
+                external_signals: vec![],
+            })
+            .collect();
+        TemplateInstance::new(TemplateConfig {
+            is_parallel: false,
+            has_parallel_sub_cmp: false,
+            name: name.to_string(),
+            header: format!("{}_{}", name, id),
+            id,
+            code: Statement::Block { meta: Meta::new(0, 0), stmts: vec![] },
+            triggers,
+            clusters: vec![],
+            components: vec![],
+            arguments: vec![],
+        })
+    }
+
+    fn nested_vcp() -> VCP {
+        // Main triggers two Pairs (and[0], and[1]) and one Bit (b),
+        // every Pair triggers a Bit (x)
+        let templates = vec![
+            instance(0, "Bit", vec![]),
+            instance(1, "Pair", vec![("x", vec![], 0)]),
+            instance(2, "Main", vec![("and", vec![0], 1), ("and", vec![1], 1), ("b", vec![], 0)]),
+        ];
+        VCP {
+            stats: Stats { all_signals: 0, io_signals: 0, all_components: 0 },
+            main_id: 2,
+            functions: vec![],
+            file_library: FileLibrary::new(),
+            witness_list: Rc::new(vec![]),
+            templates,
+            quick_knowledge: HashMap::new(),
+            templates_in_mixed: vec![],
+            public_inputs: vec![],
+        }
+    }
+
+    #[test]
+    fn trigger_order_places_parents_first() {
+        let order = nested_vcp().trigger_order();
+        assert_eq!(
+            order,
+            vec!["main", "main.and[0]", "main.and[0].x", "main.and[1]", "main.and[1].x", "main.b"]
+        );
+        for (position, path) in order.iter().enumerate() {
+            if let Option::Some((parent, _)) = path.rsplit_once('.') {
+                let parent_position = order.iter().position(|p| p == parent).unwrap();
+                assert!(parent_position < position);
+            }
+        }
+    }
+
+    #[test]
+    fn signal_ranges_cover_every_instance() {
+        let mut vcp = nested_vcp();
+        vcp.templates.push(instance(3, "Bit", vec![]));
+        vcp.templates.push(instance(4, "Bit", vec![]));
+        // the Bit instances have one output and 1, 8 and 3 inputs
+        for (id, n) in [(0, 1), (3, 8), (4, 3)] {
+            for (xtype, lengths) in [(SignalType::Output, vec![]), (SignalType::Input, vec![n])] {
+                let signal =
+                    Signal { name: String::new(), lengths, xtype, local_id: 0, dag_local_id: 0 };
+                vcp.templates[id].add_signal(signal);
+            }
+        }
+        let ranges = vcp.template_signal_ranges();
+        assert_eq!(ranges["Bit"], (2, 9));
+        assert_eq!(ranges["Main"], (0, 0));
+        assert_eq!(ranges.len(), 3);
+    }
+
+    #[test]
+    fn subtree_contains_every_nested_instance() {
+        let vcp = nested_vcp();
+        assert_eq!(vcp.subtree_of("main"), vec![2, 1, 0]);
+        assert_eq!(vcp.subtree_of("main.and[1]"), vec![1, 0]);
+        assert_eq!(vcp.subtree_of("main.and[1].x"), vec![0]);
+        assert!(vcp.subtree_of("main.and[2]").is_empty());
+        assert!(vcp.subtree_of("main.an").is_empty());
+        assert!(vcp.subtree_of("other").is_empty());
+    }
 }
@@ -0,0 +1,600 @@
+use super::very_concrete_program::{trigger_path, TemplateInstance, VCP};
+use program_structure::ast::{Access, AssignOp, Expression, SignalType, Statement};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// Signals are tracked by name: the positions of a signal array (and the
+// components of a component array) are merged into a single node
+pub struct WitnessDag {
+    pub nodes: Vec<String>,
+    // (from, to): the value of `to` is computed using the value of `from`
+    pub edges: Vec<(usize, usize)>,
+    edge_set: HashSet<(usize, usize)>,
+    ids: HashMap<String, usize>,
+    // Names that stand for several signals (arrays, or signals of a component array)
+    merged: HashSet<String>,
+}
+
+impl WitnessDag {
+    fn new() -> WitnessDag {
+        WitnessDag {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            edge_set: HashSet::new(),
+            ids: HashMap::new(),
+            merged: HashSet::new(),
+        }
+    }
+
+    fn node(&mut self, name: String) -> usize {
+        if let Option::Some(id) = self.ids.get(&name) {
+            return *id;
+        }
+        let id = self.nodes.len();
+        self.ids.insert(name.clone(), id);
+        self.nodes.push(name);
+        id
+    }
+
+    fn add_edge(&mut self, from: String, to: String) {
+        let from = self.node(from);
+        let to = self.node(to);
+        if from != to && self.edge_set.insert((from, to)) {
+            self.edges.push((from, to));
+        }
+    }
+
+    pub fn node_id(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).cloned()
+    }
+
+    pub fn has_edge(&self, from: &str, to: &str) -> bool {
+        match (self.node_id(from), self.node_id(to)) {
+            (Option::Some(from), Option::Some(to)) => self.edge_set.contains(&(from, to)),
+            _ => false,
+        }
+    }
+
+    // Number of edges in the longest dependency chain, the nodes of a
+    // cycle (only possible after merging array positions) are counted once
+    pub fn critical_path_length(&self) -> usize {
+        let mut successors = vec![Vec::new(); self.nodes.len()];
+        for (from, to) in &self.edges {
+            successors[*from].push(*to);
+        }
+        let mut longest = vec![Option::None; self.nodes.len()];
+        let mut visiting = vec![false; self.nodes.len()];
+        let mut result = 0;
+        for node in 0..self.nodes.len() {
+            longest_from(node, &successors, &mut longest, &mut visiting);
+            result = usize::max(result, longest[node].unwrap());
+        }
+        result
+    }
+
+    // One cycle per group of signals that depend on each other, as the
+    // names along the cycle. Merged nodes are left out, a cycle through an
+    // array may only be a dependency between its different positions
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let scalar: Vec<_> = self.nodes.iter().map(|n| !self.merged.contains(n)).collect();
+        let edges: Vec<_> = self.edges.iter().filter(|(f, t)| scalar[*f] && scalar[*t]).collect();
+        let mut successors = vec![Vec::new(); self.nodes.len()];
+        let mut predecessors = vec![Vec::new(); self.nodes.len()];
+        for (from, to) in edges {
+            successors[*from].push(*to);
+            predecessors[*to].push(*from);
+        }
+        // Only the nodes that are between two cycles survive the peeling
+        let mut alive = scalar;
+        peel(&mut alive, &successors, &predecessors);
+        peel(&mut alive, &predecessors, &successors);
+        let mut cycles = Vec::new();
+        for start in 0..self.nodes.len() {
+            if !alive[start] {
+                continue;
+            }
+            if let Option::Some(cycle) = shortest_cycle(start, &successors, &alive) {
+                for node in &cycle {
+                    alive[*node] = false;
+                }
+                cycles.push(cycle.into_iter().map(|n| self.nodes[n].clone()).collect());
+            }
+        }
+        cycles
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph witness {\n");
+        for (id, name) in self.nodes.iter().enumerate() {
+            dot.push_str(&format!("    {} [label=\"{}\"];\n", id, name));
+        }
+        for (from, to) in &self.edges {
+            dot.push_str(&format!("    {} -> {};\n", from, to));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// Depth first with an explicit stack, a node is pushed again after its
+// successors so its length is set once all of theirs are known. A
+// successor still being visited closes a cycle and counts as 0
+fn longest_from(
+    start: usize,
+    successors: &[Vec<usize>],
+    longest: &mut [Option<usize>],
+    visiting: &mut [bool],
+) {
+    if longest[start].is_some() {
+        return;
+    }
+    let mut stack = vec![(start, false)];
+    while let Option::Some((node, expanded)) = stack.pop() {
+        if expanded {
+            let mut length = 0;
+            for next in &successors[node] {
+                length = usize::max(length, 1 + longest[*next].unwrap_or(0));
+            }
+            visiting[node] = false;
+            longest[node] = Option::Some(length);
+        } else if longest[node].is_none() && !visiting[node] {
+            visiting[node] = true;
+            stack.push((node, true));
+            for next in &successors[node] {
+                if longest[*next].is_none() && !visiting[*next] {
+                    stack.push((*next, false));
+                }
+            }
+        }
+    }
+}
+
+// Removes the nodes with no live predecessor until none is left
+fn peel(alive: &mut [bool], successors: &[Vec<usize>], predecessors: &[Vec<usize>]) {
+    let mut live_predecessors: Vec<_> = predecessors
+        .iter()
+        .map(|p| p.iter().filter(|n| alive[**n]).count())
+        .collect();
+    let mut pending: VecDeque<_> =
+        (0..alive.len()).filter(|n| alive[*n] && live_predecessors[*n] == 0).collect();
+    while let Option::Some(node) = pending.pop_front() {
+        alive[node] = false;
+        for next in &successors[node] {
+            live_predecessors[*next] -= 1;
+            if alive[*next] && live_predecessors[*next] == 0 {
+                pending.push_back(*next);
+            }
+        }
+    }
+}
+
+// Breadth first search over the live nodes for a path back to start
+fn shortest_cycle(start: usize, successors: &[Vec<usize>], alive: &[bool]) -> Option<Vec<usize>> {
+    let mut parent = HashMap::new();
+    let mut pending = VecDeque::from(vec![start]);
+    while let Option::Some(node) = pending.pop_front() {
+        for next in &successors[node] {
+            if *next == start {
+                let mut cycle = vec![node];
+                while let Option::Some(previous) = parent.get(cycle.last().unwrap()) {
+                    cycle.push(*previous);
+                }
+                cycle.reverse();
+                return Option::Some(cycle);
+            }
+            if alive[*next] && !parent.contains_key(next) {
+                parent.insert(*next, node);
+                pending.push_back(*next);
+            }
+        }
+    }
+    Option::None
+}
+
+// Names read by an expression, a component access is read as "component.signal"
+pub(crate) fn collect_reads(expr: &Expression, reads: &mut HashSet<String>) {
+    use Expression::*;
+    match expr {
+        InfixOp { lhe, rhe, .. } => {
+            collect_reads(lhe, reads);
+            collect_reads(rhe, reads);
+        }
+        PrefixOp { rhe, .. } => collect_reads(rhe, reads),
+        InlineSwitchOp { cond, if_true, if_false, .. } => {
+            collect_reads(cond, reads);
+            collect_reads(if_true, reads);
+            collect_reads(if_false, reads);
+        }
+        Variable { name, access, .. } => {
+            reads.insert(accessed_name(name, access));
+            for acc in access {
+                if let Access::ArrayAccess(index) = acc {
+                    collect_reads(index, reads);
+                }
+            }
+        }
+        Call { args, .. } => {
+            for arg in args {
+                collect_reads(arg, reads);
+            }
+        }
+        ArrayInLine { values, .. } => {
+            for value in values {
+                collect_reads(value, reads);
+            }
+        }
+        Number(..) => {}
+    }
+}
+
+pub(crate) fn accessed_name(name: &str, access: &[Access]) -> String {
+    let signal = access.iter().find_map(|acc| match acc {
+        Access::ComponentAccess(signal) => Option::Some(signal),
+        Access::ArrayAccess(_) => Option::None,
+    });
+    match signal {
+        Option::Some(signal) => format!("{}.{}", name, signal),
+        Option::None => name.to_string(),
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct LocalDependencies {
+    // name -> names read to compute it
+    assigned: HashMap<String, HashSet<String>>,
+}
+
+impl LocalDependencies {
+    pub(crate) fn visit(&mut self, stmt: &Statement, context: &HashSet<String>) {
+        use Statement::*;
+        match stmt {
+            IfThenElse { cond, if_case, else_case, .. } => {
+                let mut inner = context.clone();
+                collect_reads(cond, &mut inner);
+                self.visit(if_case, &inner);
+                if let Option::Some(else_case) = else_case {
+                    self.visit(else_case, &inner);
+                }
+            }
+            While { cond, stmt, .. } => {
+                let mut inner = context.clone();
+                collect_reads(cond, &mut inner);
+                self.visit(stmt, &inner);
+            }
+            InitializationBlock { initializations, .. } => {
+                for init in initializations {
+                    self.visit(init, context);
+                }
+            }
+            Block { stmts, .. } => {
+                for stmt in stmts {
+                    self.visit(stmt, context);
+                }
+            }
+            Substitution { var, access, rhe, .. } => {
+                let mut reads = context.clone();
+                collect_reads(rhe, &mut reads);
+                for acc in access {
+                    if let Access::ArrayAccess(index) = acc {
+                        collect_reads(index, &mut reads);
+                    }
+                }
+                let target = accessed_name(var, access);
+                self.assigned.entry(target).or_default().extend(reads);
+            }
+            _ => {}
+        }
+    }
+
+    // Signals (and component signals) a name depends on, variables are
+    // replaced by the signals used to compute them
+    pub(crate) fn signal_sources(
+        &self,
+        name: &str,
+        is_signal: &dyn Fn(&str) -> bool,
+        seen: &mut HashSet<String>,
+        sources: &mut HashSet<String>,
+    ) {
+        if let Option::Some(reads) = self.assigned.get(name) {
+            for read in reads {
+                if is_signal(read) {
+                    sources.insert(read.clone());
+                } else if seen.insert(read.clone()) {
+                    self.signal_sources(read, is_signal, seen, sources);
+                }
+            }
+        }
+    }
+}
+
+fn signal_targets(stmt: &Statement, targets: &mut Vec<String>) {
+    use Statement::*;
+    match stmt {
+        IfThenElse { if_case, else_case, .. } => {
+            signal_targets(if_case, targets);
+            if let Option::Some(else_case) = else_case {
+                signal_targets(else_case, targets);
+            }
+        }
+        While { stmt, .. } => signal_targets(stmt, targets),
+        InitializationBlock { initializations, .. } => {
+            for init in initializations {
+                signal_targets(init, targets);
+            }
+        }
+        Block { stmts, .. } => {
+            for stmt in stmts {
+                signal_targets(stmt, targets);
+            }
+        }
+        Substitution { var, access, op, .. } if *op != AssignOp::AssignVar => {
+            targets.push(accessed_name(var, access));
+        }
+        _ => {}
+    }
+}
+
+// The (source, target) pairs of a template, by their local names
+fn template_edges(instance: &TemplateInstance) -> Vec<(String, String)> {
+    let signals: HashSet<_> = instance.signals.iter().map(|s| s.name.clone()).collect();
+    let components: HashSet<_> = instance.components.iter().map(|c| c.name.clone()).collect();
+    let is_signal = |name: &str| match name.split_once('.') {
+        Option::Some((component, _)) => components.contains(component),
+        Option::None => signals.contains(name),
+    };
+    let mut edges = Vec::new();
+    let mut local = LocalDependencies::default();
+    local.visit(&instance.code, &HashSet::new());
+    let mut targets = Vec::new();
+    signal_targets(&instance.code, &mut targets);
+    targets.sort();
+    targets.dedup();
+    for target in targets.into_iter().filter(|t| is_signal(t)) {
+        let mut sources = HashSet::new();
+        local.signal_sources(&target, &is_signal, &mut HashSet::new(), &mut sources);
+        let mut sources: Vec<_> = sources.into_iter().collect();
+        sources.sort();
+        for source in sources {
+            edges.push((source, target.clone()));
+        }
+    }
+    edges
+}
+
+pub fn build(vcp: &VCP) -> WitnessDag {
+    let mut dag = WitnessDag::new();
+    let mut local_edges: HashMap<usize, Vec<(String, String)>> = HashMap::new();
+    vcp.visit_components(&mut |path, template_id| {
+        let instance = &vcp.templates[template_id];
+        for signal in instance.signals.iter().filter(|s| !s.lengths.is_empty()) {
+            dag.merged.insert(format!("{}.{}", path, signal.name));
+        }
+        let edges = local_edges.entry(template_id).or_insert_with(|| template_edges(instance));
+        for (source, target) in edges.iter() {
+            dag.add_edge(format!("{}.{}", path, source), format!("{}.{}", path, target));
+        }
+        for trigger in &instance.triggers {
+            let merged = format!("{}.{}", path, trigger.component_name);
+            let child_path = trigger_path(path, trigger);
+            for signal in &vcp.templates[trigger.template_id].signals {
+                let outer = format!("{}.{}", merged, signal.name);
+                let inner = format!("{}.{}", child_path, signal.name);
+                if !trigger.indexed_with.is_empty() {
+                    dag.merged.insert(outer.clone());
+                }
+                match signal.xtype {
+                    SignalType::Input => dag.add_edge(outer, inner),
+                    SignalType::Output => dag.add_edge(inner, outer),
+                    SignalType::Intermediate => {}
+                }
+            }
+        }
+    });
+    dag
+}
+
+// One error per cycle, the notes follow the dependencies along it
+pub fn cycle_reports(dag: &WitnessDag) -> ReportCollection {
+    let mut reports = ReportCollection::new();
+    for cycle in dag.cycles() {
+        let msg = format!("The signals {} depend on each other", cycle.join(", "));
+        let mut report = Report::error(msg, ReportCode::CyclicSignalDependency);
+        for (position, signal) in cycle.iter().enumerate() {
+            let next = &cycle[(position + 1) % cycle.len()];
+            report.add_note(format!("{} is computed using {}", next, signal));
+        }
+        reports.push(report);
+    }
+    reports
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::hir::very_concrete_program::{Component, Signal, Stats, TemplateConfig, Trigger};
+    use program_structure::ast::Meta;
+    use program_structure::expression_builders::{build_infix, build_variable};
+    use program_structure::file_definition::FileLibrary;
+    use program_structure::statement_builders::{build_block, build_substitution};
+    use program_structure::ast::ExpressionInfixOpcode::{Add, Mul};
+    use std::rc::Rc;
+
+    fn read(name: &str) -> Expression {
+        let (name, access) = match name.split_once('.') {
+            Option::Some((component, signal)) => {
+                (component, vec![Access::ComponentAccess(signal.to_string())])
+            }
+            Option::None => (name, vec![]),
+        };
+        build_variable(Meta::new(0, 0), name.to_string(), access)
+    }
+
+    fn assign(target: &str, op: AssignOp, rhe: Expression) -> Statement {
+        let (var, access) = match target.split_once('.') {
+            Option::Some((component, signal)) => {
+                (component, vec![Access::ComponentAccess(signal.to_string())])
+            }
+            Option::None => (target, vec![]),
+        };
+        build_substitution(Meta::new(0, 0), var.to_string(), access, op, rhe)
+    }
+
+    fn template(
+        id: usize,
+        name: &str,
+        signals: Vec<(&str, SignalType)>,
+        code: Vec<Statement>,
+        triggers: Vec<Trigger>,
+    ) -> TemplateInstance {
+        let components = triggers
+            .iter()
+            .map(|t| Component { name: t.component_name.clone(), lengths: vec![] })
+            .collect();
+        let mut instance = TemplateInstance::new(TemplateConfig {
+            is_parallel: false,
+            has_parallel_sub_cmp: false,
+            name: name.to_string(),
+            header: format!("{}_{}", name, id),
+            id,
+            code: build_block(Meta::new(0, 0), code),
+            triggers,
+            clusters: vec![],
+            components,
+            arguments: vec![],
+        });
+        for (local_id, (name, xtype)) in signals.into_iter().enumerate() {
+            let signal = Signal {
+                name: name.to_string(),
+                lengths: vec![],
+                xtype,
+                local_id,
+                dag_local_id: local_id,
+            };
+            instance.add_signal(signal);
+        }
+        instance
+    }
+
+    // Mul: c <== a*b
+    // Main: var v = x + x; t <-- v*v; m.a <== t; m.b <== y; out <== m.c
+    pub fn multiplier_vcp() -> VCP {
+        use SignalType::*;
+        let mul = template(
+            0,
+            "Mul",
+            vec![("c", Output), ("a", Input), ("b", Input)],
+            vec![assign(
+                "c",
+                AssignOp::AssignConstraintSignal,
+                build_infix(Meta::new(0, 0), read("a"), Mul, read("b")),
+            )],
+            vec![],
+        );
+        let trigger = Trigger {
+            runs: "Mul_0_run".to_string(),
+            offset: 0,
+            component_offset: 0,
+            template_id: 0,
+            component_name: "m".to_string(),
+            indexed_with: vec![],
+            external_signals: vec![],
+        };
+        let v = build_infix(Meta::new(0, 0), read("x"), Add, read("x"));
+        let main = template(
+            1,
+            "Main",
+            vec![("out", Output), ("x", Input), ("y", Input), ("t", Intermediate)],
+            vec![
+                assign("v", AssignOp::AssignVar, v),
+                assign(
+                    "t",
+                    AssignOp::AssignSignal,
+                    build_infix(Meta::new(0, 0), read("v"), Mul, read("v")),
+                ),
+                assign("m.a", AssignOp::AssignConstraintSignal, read("t")),
+                assign("m.b", AssignOp::AssignConstraintSignal, read("y")),
+                assign("out", AssignOp::AssignConstraintSignal, read("m.c")),
+            ],
+            vec![trigger],
+        );
+        VCP {
+            stats: Stats { all_signals: 0, io_signals: 0, all_components: 0 },
+            main_id: 1,
+            functions: vec![],
+            file_library: FileLibrary::new(),
+            witness_list: Rc::new(vec![]),
+            templates: vec![mul, main],
+            quick_knowledge: HashMap::new(),
+            templates_in_mixed: vec![],
+            public_inputs: vec![],
+        }
+    }
+
+    #[test]
+    fn edges_follow_the_substitutions() {
+        let dag = multiplier_vcp().witness_dependency_graph();
+        let expected = [
+            ("main.x", "main.t"),
+            ("main.t", "main.m.a"),
+            ("main.y", "main.m.b"),
+            ("main.m.a", "main.m.c"),
+            ("main.m.b", "main.m.c"),
+            ("main.m.c", "main.out"),
+        ];
+        for (from, to) in expected.iter() {
+            assert!(dag.has_edge(from, to), "missing edge {} -> {}", from, to);
+        }
+        assert_eq!(dag.edges.len(), expected.len());
+        assert!(dag.node_id("main.v").is_none());
+        assert_eq!(dag.critical_path_length(), 4);
+        assert!(dag.to_dot().starts_with("digraph witness {"));
+        assert!(dag.cycles().is_empty());
+    }
+
+    #[test]
+    fn cycles_between_signals_are_errors() {
+        use SignalType::*;
+        // a <-- b + x; b <-- a*a; c <-- b, and the array s computed from itself
+        let infix = |l, op, r| build_infix(Meta::new(0, 0), read(l), op, read(r));
+        let code = vec![
+            assign("a", AssignOp::AssignSignal, infix("b", Add, "x")),
+            assign("b", AssignOp::AssignSignal, infix("a", Mul, "a")),
+            assign("c", AssignOp::AssignSignal, read("b")),
+            assign("s", AssignOp::AssignSignal, read("s")),
+            assign("s", AssignOp::AssignSignal, read("c")),
+        ];
+        let signals = vec![
+            ("x", Input),
+            ("a", Intermediate),
+            ("b", Intermediate),
+            ("c", Output),
+            ("s", Output),
+        ];
+        let mut main = template(0, "Main", signals, code, vec![]);
+        main.signals[4].lengths = vec![2];
+        let mut vcp = multiplier_vcp();
+        vcp.templates = vec![main];
+        vcp.main_id = 0;
+        let dag = vcp.witness_dependency_graph();
+        assert_eq!(dag.cycles(), vec![vec!["main.b".to_string(), "main.a".to_string()]]);
+        let reports = cycle_reports(&dag);
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].is_error());
+        assert!(reports[0].get_message().contains("main.b, main.a"));
+    }
+
+    #[test]
+    fn long_chains_do_not_overflow_the_stack() {
+        let mut dag = WitnessDag::new();
+        let length = 100_000;
+        for node in 0..length {
+            dag.add_edge(format!("s{}", node), format!("s{}", node + 1));
+            dag.add_edge(format!("s{}", node), format!("s{}", node + 1));
+        }
+        // The closing edge makes a cycle, every node of it is counted once
+        dag.add_edge(format!("s{}", length), "s0".to_string());
+        assert_eq!(dag.edges.len(), length + 1);
+        assert_eq!(dag.critical_path_length(), length + 1);
+    }
+}
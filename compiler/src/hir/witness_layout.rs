@@ -0,0 +1,65 @@
+use super::very_concrete_program::{trigger_path, VCP};
+use program_structure::ast::SignalType;
+use std::collections::HashMap;
+
+// One signal of the circuit as numbered by the dag, before the simplification
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WitnessEntry {
+    pub id: usize,
+    // Dotted path as in the .sym file, e.g. main.c[1].out
+    pub name: String,
+    pub xtype: SignalType,
+    // The outputs and the public inputs of main
+    pub is_public: bool,
+    // Position in the witness, None when the simplification removed the signal
+    pub witness: Option<usize>,
+}
+
+// The entries sorted by id. A component starts at the offset of its parent
+// plus the offset of its trigger, its signals follow their dag_local_id
+pub fn build(vcp: &VCP) -> Vec<WitnessEntry> {
+    let positions: HashMap<_, _> =
+        vcp.witness_list.iter().enumerate().map(|(position, id)| (*id, position)).collect();
+    let mut layout = Vec::new();
+    if vcp.main_id < vcp.templates.len() {
+        add_component(vcp, &positions, "main", vcp.main_id, 0, &mut layout);
+    }
+    layout.sort_by_key(|entry| entry.id);
+    layout
+}
+
+fn add_component(
+    vcp: &VCP,
+    positions: &HashMap<usize, usize>,
+    path: &str,
+    template_id: usize,
+    offset: usize,
+    layout: &mut Vec<WitnessEntry>,
+) {
+    let instance = &vcp.templates[template_id];
+    let is_main = path == "main";
+    for signal in &instance.signals {
+        let is_public = is_main
+            && match signal.xtype {
+                SignalType::Output => true,
+                SignalType::Input => vcp.public_inputs.contains(&signal.name),
+                SignalType::Intermediate => false,
+            };
+        for (position, name) in signal.element_names().into_iter().enumerate() {
+            let id = offset + signal.dag_local_id + position;
+            let entry = WitnessEntry {
+                id,
+                name: format!("{}.{}", path, name),
+                xtype: signal.xtype,
+                is_public,
+                witness: positions.get(&id).cloned(),
+            };
+            layout.push(entry);
+        }
+    }
+    for trigger in &instance.triggers {
+        let component_path = trigger_path(path, trigger);
+        let offset = offset + trigger.offset;
+        add_component(vcp, positions, &component_path, trigger.template_id, offset, layout);
+    }
+}
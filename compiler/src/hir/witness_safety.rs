@@ -0,0 +1,255 @@
+use super::very_concrete_program::{TemplateInstance, VCP};
+use super::witness_dag::{accessed_name, collect_reads, LocalDependencies};
+use program_structure::ast::{Access, Expression, ExpressionInfixOpcode, Statement};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use std::collections::HashSet;
+
+// Short rendering of the divisor for the diagnostic
+fn render(expr: &Expression) -> String {
+    use Expression::*;
+    use ExpressionInfixOpcode::*;
+    match expr {
+        Number(_, value) => value.to_string(),
+        Variable { name, access, .. } => {
+            let mut rendered = name.clone();
+            for acc in access {
+                match acc {
+                    Access::ComponentAccess(signal) => rendered.push_str(&format!(".{}", signal)),
+                    Access::ArrayAccess(index) => {
+                        rendered.push_str(&format!("[{}]", render(index)))
+                    }
+                }
+            }
+            rendered
+        }
+        InfixOp { lhe, infix_op, rhe, .. } => {
+            let op = match infix_op {
+                Mul => "*",
+                Div => "/",
+                Add => "+",
+                Sub => "-",
+                Pow => "**",
+                IntDiv => "\\",
+                Mod => "%",
+                _ => "?",
+            };
+            format!("({} {} {})", render(lhe), op, render(rhe))
+        }
+        PrefixOp { rhe, .. } => format!("-{}", render(rhe)),
+        _ => "...".to_string(),
+    }
+}
+
+struct DivisionCheck<'a> {
+    template_name: &'a str,
+    is_signal: &'a dyn Fn(&str) -> bool,
+    local: LocalDependencies,
+    reports: ReportCollection,
+}
+
+impl<'a> DivisionCheck<'a> {
+    // Signals the value of the expression depends on
+    fn signals_of(&self, expr: &Expression) -> HashSet<String> {
+        let mut reads = HashSet::new();
+        collect_reads(expr, &mut reads);
+        let mut signals = HashSet::new();
+        for read in reads {
+            if (self.is_signal)(&read) {
+                signals.insert(read);
+            } else {
+                let mut seen = HashSet::new();
+                self.local.signal_sources(&read, self.is_signal, &mut seen, &mut signals);
+            }
+        }
+        signals
+    }
+
+    // A division is taken as safe when its divisor is a constant or when
+    // some enclosing condition looks at a signal of the divisor
+    fn check_expression(&mut self, target: &str, expr: &Expression, guards: &HashSet<String>) {
+        use Expression::*;
+        match expr {
+            InfixOp { meta, lhe, infix_op, rhe } => {
+                self.check_expression(target, lhe, guards);
+                self.check_expression(target, rhe, guards);
+                let divides =
+                    matches!(infix_op, ExpressionInfixOpcode::Div | ExpressionInfixOpcode::IntDiv);
+                let signals = self.signals_of(rhe);
+                if divides && !signals.is_empty() && signals.is_disjoint(guards) {
+                    let msg = format!(
+                        "The witness of \"{}\" in template {} divides by \"{}\", which is not checked to be non zero",
+                        target,
+                        self.template_name,
+                        render(rhe)
+                    );
+                    let mut report = Report::warning(msg, ReportCode::PossibleDivisionByZero);
+                    if let Option::Some(file_id) = meta.file_id {
+                        let label = "this division fails when the divisor is zero".to_string();
+                        report.add_primary(meta.file_location(), file_id, label);
+                    }
+                    self.reports.push(report);
+                }
+            }
+            PrefixOp { rhe, .. } => self.check_expression(target, rhe, guards),
+            InlineSwitchOp { cond, if_true, if_false, .. } => {
+                self.check_expression(target, cond, guards);
+                let mut inner = guards.clone();
+                inner.extend(self.signals_of(cond));
+                self.check_expression(target, if_true, &inner);
+                self.check_expression(target, if_false, &inner);
+            }
+            Call { args, .. } => {
+                for arg in args {
+                    self.check_expression(target, arg, guards);
+                }
+            }
+            ArrayInLine { values, .. } => {
+                for value in values {
+                    self.check_expression(target, value, guards);
+                }
+            }
+            Variable { .. } | Number(..) => {}
+        }
+    }
+
+    fn check_statement(&mut self, stmt: &Statement, guards: &HashSet<String>) {
+        use Statement::*;
+        match stmt {
+            IfThenElse { cond, if_case, else_case, .. } => {
+                let mut inner = guards.clone();
+                inner.extend(self.signals_of(cond));
+                self.check_statement(if_case, &inner);
+                if let Option::Some(else_case) = else_case {
+                    self.check_statement(else_case, &inner);
+                }
+            }
+            While { stmt, .. } => self.check_statement(stmt, guards),
+            InitializationBlock { initializations, .. } => {
+                for init in initializations {
+                    self.check_statement(init, guards);
+                }
+            }
+            Block { stmts, .. } => {
+                for stmt in stmts {
+                    self.check_statement(stmt, guards);
+                }
+            }
+            Substitution { var, access, rhe, .. } => {
+                self.check_expression(&accessed_name(var, access), rhe, guards);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_template(instance: &TemplateInstance) -> ReportCollection {
+    let signals: HashSet<_> = instance.signals.iter().map(|s| s.name.clone()).collect();
+    let components: HashSet<_> = instance.components.iter().map(|c| c.name.clone()).collect();
+    let is_signal = |name: &str| match name.split_once('.') {
+        Option::Some((component, _)) => components.contains(component),
+        Option::None => signals.contains(name),
+    };
+    let mut local = LocalDependencies::default();
+    local.visit(&instance.code, &HashSet::new());
+    let mut check = DivisionCheck {
+        template_name: &instance.template_name,
+        is_signal: &is_signal,
+        local,
+        reports: ReportCollection::new(),
+    };
+    check.check_statement(&instance.code, &HashSet::new());
+    check.reports
+}
+
+// Every template is checked once, its instances share the code
+pub fn check(vcp: &VCP) -> ReportCollection {
+    let mut checked = HashSet::new();
+    let mut reports = ReportCollection::new();
+    for instance in &vcp.templates {
+        if checked.insert(instance.template_name.clone()) {
+            reports.append(&mut check_template(instance));
+        }
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::very_concrete_program::{Signal, TemplateConfig};
+    use num_bigint_dig::BigInt;
+    use program_structure::ast::{AssignOp, Meta, SignalType};
+    use program_structure::expression_builders::{
+        build_infix, build_inline_switch_op, build_number, build_variable,
+    };
+    use program_structure::statement_builders::{build_block, build_substitution};
+    use ExpressionInfixOpcode::{Div, NotEq};
+
+    fn read(name: &str) -> Expression {
+        build_variable(Meta::new(0, 0), name.to_string(), vec![])
+    }
+
+    fn assign(target: &str, op: AssignOp, rhe: Expression) -> Statement {
+        build_substitution(Meta::new(0, 0), target.to_string(), vec![], op, rhe)
+    }
+
+    fn divide(lhe: Expression, rhe: Expression) -> Expression {
+        build_infix(Meta::new(0, 0), lhe, Div, rhe)
+    }
+
+    // IsZero like template: inv <-- in != 0 ? 1/in : 0, plus the unsafe
+    // variants bad <-- 1/in and var d = in; worse <-- 1/d and a safe 1/2
+    fn template() -> TemplateInstance {
+        let one = || build_number(Meta::new(0, 0), BigInt::from(1));
+        let zero = build_number(Meta::new(0, 0), BigInt::from(0));
+        let cond = build_infix(Meta::new(0, 0), read("in"), NotEq, zero.clone());
+        let guarded =
+            build_inline_switch_op(Meta::new(0, 0), cond, divide(one(), read("in")), zero);
+        let code = vec![
+            assign("inv", AssignOp::AssignSignal, guarded),
+            assign("bad", AssignOp::AssignSignal, divide(one(), read("in"))),
+            assign("d", AssignOp::AssignVar, read("in")),
+            assign("worse", AssignOp::AssignSignal, divide(one(), read("d"))),
+            assign(
+                "half",
+                AssignOp::AssignConstraintSignal,
+                divide(read("in"), build_number(Meta::new(0, 0), BigInt::from(2))),
+            ),
+        ];
+        let mut instance = TemplateInstance::new(TemplateConfig {
+            is_parallel: false,
+            has_parallel_sub_cmp: false,
+            name: "IsZero".to_string(),
+            header: "IsZero_0".to_string(),
+            id: 0,
+            code: build_block(Meta::new(0, 0), code),
+            triggers: vec![],
+            clusters: vec![],
+            components: vec![],
+            arguments: vec![],
+        });
+        for name in ["in", "inv", "bad", "worse", "half"] {
+            let xtype = if name == "in" { SignalType::Input } else { SignalType::Intermediate };
+            let signal = Signal {
+                name: name.to_string(),
+                lengths: vec![],
+                xtype,
+                local_id: 0,
+                dag_local_id: 0,
+            };
+            instance.add_signal(signal);
+        }
+        instance
+    }
+
+    #[test]
+    fn unguarded_divisions_by_signals_are_reported() {
+        let reports = check_template(&template());
+        let messages: Vec<_> = reports.iter().map(|r| r.get_message().clone()).collect();
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(messages[0].contains("\"bad\"") && messages[0].contains("\"in\""));
+        assert!(messages[1].contains("\"worse\"") && messages[1].contains("\"d\""));
+        assert!(reports.iter().all(|r| r.is_warning()));
+    }
+}
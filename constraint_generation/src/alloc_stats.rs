@@ -0,0 +1,163 @@
+use crate::{print_line, LineStyle, Reporter};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+// Counters are only updated when CountingAllocator is installed as the
+// global allocator (circom does it under the alloc_stats feature).
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static CURRENT_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+pub struct CountingAllocator;
+
+fn on_alloc(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    let current = CURRENT_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_BYTES.fetch_max(current, Ordering::Relaxed);
+}
+
+fn on_dealloc(size: usize) {
+    CURRENT_BYTES.fetch_sub(size, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            on_alloc(layout.size());
+        }
+        ptr
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        on_dealloc(layout.size());
+    }
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            on_alloc(layout.size());
+        }
+        ptr
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            on_dealloc(layout.size());
+            on_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PhaseStats {
+    pub phase: &'static str,
+    pub allocations: usize,
+    pub peak_bytes: usize,
+    pub elapsed: Duration,
+}
+
+// The phases are always measured, enabled only decides whether they are printed
+pub struct AllocStats {
+    enabled: bool,
+    // Wall-clock times are always measured but only printed when this is set.
+    // Without it the printed stats can be compared between runs
+    print_timing: bool,
+    phases: Vec<PhaseStats>,
+    current: Option<(&'static str, usize, Instant)>,
+}
+
+impl AllocStats {
+    pub fn new(enabled: bool, print_timing: bool) -> AllocStats {
+        AllocStats { enabled, print_timing, phases: Vec::new(), current: None }
+    }
+
+    pub fn is_available() -> bool {
+        cfg!(feature = "alloc_stats")
+    }
+
+    pub fn start_phase(&mut self, phase: &'static str) {
+        self.end_phase();
+        PEAK_BYTES.store(CURRENT_BYTES.load(Ordering::Relaxed), Ordering::Relaxed);
+        self.current = Some((phase, ALLOCATIONS.load(Ordering::Relaxed), Instant::now()));
+    }
+
+    pub fn end_phase(&mut self) {
+        if let Some((phase, allocations_at_start, start)) = self.current.take() {
+            let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_at_start;
+            let peak_bytes = PEAK_BYTES.load(Ordering::Relaxed);
+            let elapsed = start.elapsed();
+            self.phases.push(PhaseStats { phase, allocations, peak_bytes, elapsed });
+        }
+    }
+
+    pub fn phases(&self) -> &[PhaseStats] {
+        &self.phases
+    }
+
+    pub fn into_phases(self) -> Vec<PhaseStats> {
+        self.phases
+    }
+
+    pub fn print(&self, reporter: &mut Option<Reporter>) {
+        if !self.enabled {
+            return;
+        }
+        if !AllocStats::is_available() {
+            let warning = "allocation stats unavailable: circom was built without the \
+                           alloc_stats feature";
+            print_line(reporter, LineStyle::Warning, warning.to_string());
+            // The times do not need the counting allocator
+            if !self.print_timing {
+                return;
+            }
+        }
+        print_line(reporter, LineStyle::Heading, "allocation stats per phase:".to_string());
+        for line in self.phase_lines() {
+            print_line(reporter, LineStyle::Plain, line);
+        }
+    }
+
+    fn phase_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for stats in &self.phases {
+            let mut measures = Vec::new();
+            if AllocStats::is_available() {
+                measures.push(format!(
+                    "{} allocations, peak {} bytes",
+                    stats.allocations, stats.peak_bytes
+                ));
+            }
+            if self.print_timing {
+                measures.push(format!("{} ms", stats.elapsed.as_millis()));
+            }
+            lines.push(format!("  {}: {}", stats.phase, measures.join(", ")));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn run(print_timing: bool, pause: u64) -> Vec<String> {
+        let mut stats = AllocStats::new(true, print_timing);
+        stats.start_phase("instantiation");
+        thread::sleep(Duration::from_millis(pause));
+        stats.start_phase("export");
+        stats.end_phase();
+        stats.phase_lines()
+    }
+
+    #[test]
+    fn phase_lines_only_have_the_times_when_asked() {
+        let first = run(false, 0);
+        assert_eq!(first, run(false, 5));
+        assert_eq!(first.len(), 2);
+        assert!(!first[0].contains(" ms"));
+        assert!(run(true, 5)[0].ends_with(" ms"));
+    }
+}
@@ -0,0 +1,85 @@
+use program_structure::ast::Pragma;
+use std::collections::HashMap;
+
+const CATEGORY_PRAGMA: &str = "pragma category";
+
+// Arguments of a pragma, the strings keep their quotes
+fn arguments(text: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut in_string = false;
+    for c in text.chars() {
+        match c {
+            '"' => {
+                in_string = !in_string;
+                current.push(c);
+            }
+            ' ' if !in_string => {
+                if !current.is_empty() {
+                    arguments.push(std::mem::take(&mut current));
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        arguments.push(current);
+    }
+    arguments
+}
+
+// pragma category "name" Template1 Template2; puts the constraints of the
+// listed templates in the category. Returns the category of each template
+// (the last pragma wins) and the pragmas that are not category pragmas
+pub fn template_categories(pragmas: &[Pragma]) -> (HashMap<String, String>, Vec<Pragma>) {
+    let mut categories = HashMap::new();
+    let mut others = Vec::new();
+    for pragma in pragmas {
+        let text = pragma.text.trim_end_matches(';');
+        let arguments = match text.strip_prefix(CATEGORY_PRAGMA) {
+            Option::Some(rest) if rest.is_empty() || rest.starts_with(' ') => arguments(rest),
+            _ => {
+                others.push(pragma.clone());
+                continue;
+            }
+        };
+        if let Option::Some((category, templates)) = arguments.split_first() {
+            let category = category.trim_matches('"');
+            for template in templates {
+                categories.insert(template.clone(), category.to_string());
+            }
+        } else {
+            others.push(pragma.clone());
+        }
+    }
+    (categories, others)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use program_structure::ast::{build_pragma, Meta};
+
+    fn pragma(name: &str, arguments: &[&str]) -> Pragma {
+        let arguments = arguments.iter().map(|a| a.to_string()).collect();
+        build_pragma(Meta::new(0, 0), name.to_string(), arguments)
+    }
+
+    #[test]
+    fn category_pragmas_tag_their_templates() {
+        let pragmas = vec![
+            pragma("category", &["\"input validation\"", "Num2Bits", "IsZero"]),
+            pragma("custom_templates", &[]),
+            pragma("category", &["hashing", "Poseidon"]),
+            pragma("category", &[]),
+        ];
+        let (categories, others) = template_categories(&pragmas);
+        assert_eq!(categories.len(), 3);
+        assert_eq!(categories["Num2Bits"], "input validation");
+        assert_eq!(categories["IsZero"], "input validation");
+        assert_eq!(categories["Poseidon"], "hashing");
+        // A category without templates is left for the unknown pragma check
+        let others: Vec<_> = others.iter().map(|p| p.text.as_str()).collect();
+        assert_eq!(others, ["pragma custom_templates;", "pragma category;"]);
+    }
+}
@@ -122,6 +122,11 @@ impl ComponentRepresentation {
         }
         Result::Ok(())
     }
+    pub fn unassigned_inputs(&self) -> Vec<&String> {
+        let mut unassigned: Vec<_> = self.unassigned_inputs.keys().collect();
+        unassigned.sort();
+        unassigned
+    }
     pub fn is_initialized(&self) -> bool {
         self.node_pointer.is_some()
     }
@@ -16,9 +16,39 @@ use super::{
     UsefulConstants,
 };
 use circom_algebra::num_bigint::BigInt;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 type AExpr = ArithmeticExpressionGen<String>;
 
+// How a division between two constants is read
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum DivisionMode {
+    // Multiplication by the inverse in the field, 7/2 is (p + 7) / 2
+    #[default]
+    Field,
+    // Divisions that are not exact in the integers are errors, the integer
+    // division has to be written with \
+    IntegerChecked,
+}
+
+// What to do with the inputs of a subcomponent that its parent never assigns
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum UnconnectedInputPolicy {
+    #[default]
+    Warn,
+    Error,
+}
+
+#[derive(Clone, Default)]
+pub struct ExecutionFlags {
+    pub assert_as_constraint: bool,
+    pub constant_division_mode: DivisionMode,
+    pub unconnected_input_policy: UnconnectedInputPolicy,
+    // Template instances that can be created, None for no limit
+    pub max_instances: Option<usize>,
+    // The field, bn128 unless a custom prime is given
+    pub constants: UsefulConstants,
+}
+
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 enum BlockType {
     Known,
@@ -33,8 +63,14 @@ struct RuntimeInformation {
     pub call_trace: Vec<String>,
     pub current_file: FileID,
     pub runtime_errors: ReportCollection,
+    pub runtime_warnings: ReportCollection,
     pub environment: ExecutionEnvironment,
     pub exec_program: ExecutedProgram,
+    pub flags: ExecutionFlags,
+    // Asserts already reported as promoted or as not promoted
+    pub promoted_asserts: HashSet<usize>,
+    // Template instantiations started and not finished yet
+    pub instantiation_stack: Vec<Instantiation>,
 }
 impl RuntimeInformation {
     pub fn new(current_file: FileID, id_max: usize) -> RuntimeInformation {
@@ -46,10 +82,28 @@ impl RuntimeInformation {
             constants: UsefulConstants::new(),
             call_trace: Vec::new(),
             runtime_errors: ReportCollection::new(),
+            runtime_warnings: ReportCollection::new(),
             environment: ExecutionEnvironment::new(),
             exec_program: ExecutedProgram::new(),
+            flags: ExecutionFlags::default(),
+            promoted_asserts: HashSet::new(),
+            instantiation_stack: Vec::new(),
         }
     }
+
+    // The executed program keeps the field of the flags for the export
+    pub fn set_flags(&mut self, flags: ExecutionFlags) {
+        self.constants = flags.constants.clone();
+        self.exec_program.constants = flags.constants.clone();
+        self.flags = flags;
+    }
+}
+
+#[derive(Clone)]
+struct Instantiation {
+    template: String,
+    name: String,
+    parameter_values: Vec<AExpressionSlice>,
 }
 
 struct FoldedValue {
@@ -78,9 +132,11 @@ enum ExecutionError {
 
 pub fn constraint_execution(
     program_archive: &ProgramArchive,
-) -> Result<ExecutedProgram, ReportCollection> {
+    flags: ExecutionFlags,
+) -> Result<(ExecutedProgram, ReportCollection), ReportCollection> {
     let main_file_id = program_archive.get_file_id_main();
     let mut runtime_information = RuntimeInformation::new(*main_file_id, program_archive.id_max);
+    runtime_information.set_flags(flags);
     runtime_information.public_inputs = program_archive.get_public_inputs_main_component().clone();
     let folded_value_result = execute_expression(
         program_archive.get_main_expression(),
@@ -91,11 +147,72 @@ pub fn constraint_execution(
         Result::Err(_) => Result::Err(runtime_information.runtime_errors),
         Result::Ok(folded_value) => {
             debug_assert!(FoldedValue::valid_node_pointer(&folded_value));
-            Result::Ok(runtime_information.exec_program)
+            Result::Ok((runtime_information.exec_program, runtime_information.runtime_warnings))
         }
     }
 }
 
+// Runs the execution and, if it fails (with an error or a panic), bisects the
+// chain of instantiations that were in progress looking for the innermost one
+// that still fails when it is instantiated on its own. Returns its name and
+// whether it fails in isolation or only in the context of its parents
+pub fn bisect_failure(
+    program_archive: &ProgramArchive,
+    flags: ExecutionFlags,
+) -> Option<(String, bool)> {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    let main_file_id = program_archive.get_file_id_main();
+    let mut runtime = RuntimeInformation::new(*main_file_id, program_archive.id_max);
+    runtime.set_flags(flags.clone());
+    runtime.public_inputs = program_archive.get_public_inputs_main_component().clone();
+    let main = program_archive.get_main_expression();
+    let result =
+        catch_unwind(AssertUnwindSafe(|| execute_expression(main, program_archive, &mut runtime)));
+    if let Result::Ok(Result::Ok(_)) = result {
+        return Option::None;
+    }
+    let chain = runtime.instantiation_stack;
+    let innermost = chain.last()?.name.clone();
+    // The ancestors of a failing instantiation fail as well
+    let (mut lo, mut hi, mut found) = (0, chain.len(), Option::None);
+    let panic_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if fails_in_isolation(&chain[mid], program_archive, flags.clone()) {
+            found = Option::Some(mid);
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    std::panic::set_hook(panic_hook);
+    match found {
+        Option::Some(position) => Option::Some((chain[position].name.clone(), true)),
+        Option::None => Option::Some((innermost, false)),
+    }
+}
+
+fn fails_in_isolation(
+    instantiation: &Instantiation,
+    program_archive: &ProgramArchive,
+    flags: ExecutionFlags,
+) -> bool {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    let id = &instantiation.template;
+    let file_id = program_archive.get_template_data(id).get_file_id();
+    let mut runtime = RuntimeInformation::new(file_id, program_archive.id_max);
+    runtime.set_flags(flags);
+    runtime.environment =
+        prepare_environment_for_call(id, &instantiation.parameter_values, program_archive);
+    runtime.call_trace.push(id.clone());
+    let values = &instantiation.parameter_values;
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        execute_template_call(id, values, program_archive, &mut runtime)
+    }));
+    !matches!(result, Result::Ok(Result::Ok(_)))
+}
+
 pub fn execute_constant_expression(
     expression: &Expression,
     program_archive: &ProgramArchive,
@@ -184,8 +301,14 @@ fn execute_statement(
         Substitution { meta, var, access, op, rhe, .. } => {
             let access_information = treat_accessing(meta, access, program_archive, runtime)?;
             let r_folded = execute_expression(rhe, program_archive, runtime)?;
+            let own_signal = ExecutionEnvironment::has_signal(&runtime.environment, var);
             let possible_constraint =
                 perform_assign(meta, var, &access_information, r_folded, actual_node, runtime)?;
+            if let (Option::Some(node), AssignOp::AssignSignal, true, Option::Some(assigned)) =
+                (actual_node.as_mut(), op, own_signal, &possible_constraint)
+            {
+                node.add_witness_assignment(&assigned.left, meta);
+            }
             if let (Option::Some(node), AssignOp::AssignConstraintSignal) = (actual_node, op) {
                 debug_assert!(possible_constraint.is_some());
                 let constrained = possible_constraint.unwrap();
@@ -202,7 +325,7 @@ fn execute_statement(
                     let symbol = AExpr::Signal { symbol: constrained.left };
                     let expr = AExpr::sub(&symbol, &constrained.right, &p);
                     let ctr = AExpr::transform_expression_to_constraint_form(expr, &p).unwrap();
-                    node.add_constraint(ctr);
+                    node.add_constraint(ctr, meta);
                 }
             }
             Option::None
@@ -216,9 +339,17 @@ fn execute_statement(
             let possible_non_quadratic =
                 AExpr::sub(&arith_left, &arith_right, &runtime.constants.get_p());
             if possible_non_quadratic.is_nonquadratic() {
+                // The report points to the side of degree above two when there is one
+                let culprit = if arith_left.is_nonquadratic() {
+                    lhe.get_meta()
+                } else if arith_right.is_nonquadratic() {
+                    rhe.get_meta()
+                } else {
+                    meta
+                };
                 treat_result_with_execution_error(
                     Result::Err(ExecutionError::NonQuadraticConstraint),
-                    meta,
+                    culprit,
                     &mut runtime.runtime_errors,
                     &runtime.call_trace,
                 )?;
@@ -230,7 +361,7 @@ fn execute_statement(
             )
             .unwrap();
             if let Option::Some(node) = actual_node {
-                node.add_constraint(constraint_expression);
+                node.add_constraint(constraint_expression, meta);
             }
             Option::None
         }
@@ -290,9 +421,34 @@ fn execute_statement(
         }
         LogCall { .. } => Option::None,
         Assert { arg, meta, .. } => {
+            let promotable = runtime.flags.assert_as_constraint
+                && runtime.block_type == BlockType::Known;
+            if let (true, Option::Some(node)) = (promotable, actual_node.as_mut()) {
+                if let Expression::InfixOp { lhe, infix_op: ExpressionInfixOpcode::Eq, rhe, .. } =
+                    arg
+                {
+                    return execute_assert_as_constraint(
+                        meta,
+                        lhe,
+                        rhe,
+                        program_archive,
+                        runtime,
+                        node,
+                    );
+                }
+            }
             let f_result = execute_expression(arg, program_archive, runtime)?;
             let arith = safe_unwrap_to_single_arithmetic_expression(f_result, line!());
             let possible_bool = AExpr::get_boolean_equivalence(&arith, runtime.constants.get_p());
+            let over_signals = possible_bool.is_none() && actual_node.is_some();
+            if runtime.flags.assert_as_constraint && over_signals {
+                let reason = if runtime.block_type == BlockType::Known {
+                    "not an equality"
+                } else {
+                    "inside an unknown block"
+                };
+                warn_assert_not_promoted(meta, reason, runtime);
+            }
             let result = match possible_bool {
                 Some(b) if !b => Err(ExecutionError::FalseAssert),
                 _ => Ok(None),
@@ -308,6 +464,64 @@ fn execute_statement(
     Result::Ok(res)
 }
 
+// assert(lhe == rhe) over signals is enforced as the constraint lhe - rhe === 0
+fn execute_assert_as_constraint(
+    meta: &Meta,
+    lhe: &Expression,
+    rhe: &Expression,
+    program_archive: &ProgramArchive,
+    runtime: &mut RuntimeInformation,
+    node: &mut ExecutedTemplate,
+) -> Result<Option<FoldedValue>, ()> {
+    let f_left = execute_expression(lhe, program_archive, runtime)?;
+    let f_right = execute_expression(rhe, program_archive, runtime)?;
+    let arith_left = safe_unwrap_to_single_arithmetic_expression(f_left, line!());
+    let arith_right = safe_unwrap_to_single_arithmetic_expression(f_right, line!());
+    let p = runtime.constants.get_p().clone();
+    let difference = AExpr::sub(&arith_left, &arith_right, &p);
+    if let AExpr::Number { value } = &difference {
+        let result = if *value == BigInt::from(0) {
+            Ok(None)
+        } else {
+            Err(ExecutionError::FalseAssert)
+        };
+        return treat_result_with_execution_error(
+            result,
+            meta,
+            &mut runtime.runtime_errors,
+            &runtime.call_trace,
+        );
+    }
+    if difference.is_nonquadratic() {
+        warn_assert_not_promoted(meta, "non quadratic", runtime);
+        return Result::Ok(Option::None);
+    }
+    let constraint = AExpr::transform_expression_to_constraint_form(difference, &p).unwrap();
+    node.add_constraint(constraint, meta);
+    if runtime.promoted_asserts.insert(meta.elem_id) {
+        let mut report = Report::note(
+            "Assertion promoted to a constraint".to_string(),
+            ReportCode::AssertPromotedToConstraint,
+        );
+        report.add_primary(meta.location.clone(), meta.get_file_id(), "promoted".to_string());
+        runtime.runtime_warnings.push(report);
+    }
+    Result::Ok(Option::None)
+}
+
+// Asserts over signals that can not be promoted are left to the witness calculator
+fn warn_assert_not_promoted(meta: &Meta, reason: &str, runtime: &mut RuntimeInformation) {
+    if runtime.promoted_asserts.insert(meta.elem_id) {
+        let mut report = Report::warning(
+            format!("Assertion not promoted: {}", reason),
+            ReportCode::AssertNotPromoted,
+        );
+        let message = "not enforced by the constraints".to_string();
+        report.add_primary(meta.location.clone(), meta.get_file_id(), message);
+        runtime.runtime_warnings.push(report);
+    }
+}
+
 fn execute_expression(
     expr: &Expression,
     program_archive: &ProgramArchive,
@@ -971,6 +1185,13 @@ fn execute_template_call(
     let node_pointer = if let Option::Some(pointer) = existent_node {
         pointer
     } else {
+        check_instantiation_cycle(&instantiation_name, runtime)?;
+        check_instance_limit(&instantiation_name, runtime)?;
+        runtime.instantiation_stack.push(Instantiation {
+            template: id.to_string(),
+            name: instantiation_name.clone(),
+            parameter_values: parameter_values.to_vec(),
+        });
         let analysis =
             std::mem::replace(&mut runtime.analysis, Analysis::new(program_archive.id_max));
         let code = program_archive.get_template_data(id).get_body().clone();
@@ -990,13 +1211,115 @@ fn execute_template_call(
         )?;
         debug_assert!(ret.is_none());
         let new_node = node_wrap.unwrap();
+        check_unconnected_inputs(&new_node, runtime)?;
+        runtime.runtime_warnings.append(&mut new_node.unconstrained_assignments());
         let analysis = std::mem::replace(&mut runtime.analysis, analysis);
         let node_pointer = runtime.exec_program.add_node_to_scheme(new_node, analysis);
+        runtime.instantiation_stack.pop();
         node_pointer
     };
     Result::Ok(FoldedValue { node_pointer: Option::Some(node_pointer), ..FoldedValue::default() })
 }
 
+// The instances being executed are counted along with the finished ones
+fn check_instance_limit(
+    instantiation_name: &str,
+    runtime: &mut RuntimeInformation,
+) -> Result<(), ()> {
+    let limit = if let Option::Some(limit) = runtime.flags.max_instances {
+        limit
+    } else {
+        return Result::Ok(());
+    };
+    let instances = runtime.exec_program.number_of_nodes() + runtime.instantiation_stack.len();
+    if instances < limit {
+        return Result::Ok(());
+    }
+    let msg = format!(
+        "Instantiating {} exceeds the limit of {} template instances",
+        instantiation_name, limit
+    );
+    let mut report = Report::error(msg, ReportCode::InstanceLimitExceeded);
+    report.add_note(format!("{} template instances were created before it", instances));
+    runtime.runtime_errors.push(report);
+    Result::Err(())
+}
+
+// The same template with the same parameters reappearing while it is still
+// being executed would instantiate itself forever
+fn check_instantiation_cycle(
+    instantiation_name: &str,
+    runtime: &mut RuntimeInformation,
+) -> Result<(), ()> {
+    let stack = &runtime.instantiation_stack;
+    let start = match stack.iter().position(|i| i.name == instantiation_name) {
+        Option::Some(start) => start,
+        Option::None => return Result::Ok(()),
+    };
+    let mut chain: Vec<_> = stack[start..].iter().map(|i| i.name.as_str()).collect();
+    chain.push(instantiation_name);
+    let msg = format!("Cyclic instantiation of {}", instantiation_name);
+    let mut report = Report::error(msg, ReportCode::CyclicInstantiation);
+    report.add_note(format!("The cycle is {}", chain.join(" -> ")));
+    runtime.runtime_errors.push(report);
+    Result::Err(())
+}
+
+// "[i][j]" for the position-th cell of an array with the given dimensions
+fn cell_indexes(route: &[usize], mut position: usize) -> String {
+    let mut indexes = vec![0; route.len()];
+    for (index, length) in indexes.iter_mut().zip(route).rev() {
+        *index = position % length;
+        position /= length;
+    }
+    indexes.iter().map(|i| format!("[{}]", i)).collect()
+}
+
+// Runs once the body of an instance is executed: the witness of a subcomponent
+// with an input that was never assigned can not be computed
+fn check_unconnected_inputs(
+    node: &ExecutedTemplate,
+    runtime: &mut RuntimeInformation,
+) -> Result<(), ()> {
+    let policy = runtime.flags.unconnected_input_policy;
+    let mut reports = ReportCollection::new();
+    for (name, _) in &node.components {
+        let slice = if let Option::Some(slice) = runtime.environment.get_component(name) {
+            slice
+        } else {
+            continue;
+        };
+        for (position, component) in slice.values().iter().enumerate() {
+            if !component.is_initialized() {
+                continue;
+            }
+            let indexes = cell_indexes(slice.route(), position);
+            for input in component.unassigned_inputs() {
+                let msg = format!(
+                    "Input {} of the component {}.{}{} is never assigned",
+                    input, node.report_name, name, indexes
+                );
+                let report = match policy {
+                    UnconnectedInputPolicy::Warn => {
+                        Report::warning(msg, ReportCode::UnconnectedInput)
+                    }
+                    UnconnectedInputPolicy::Error => {
+                        Report::error(msg, ReportCode::UnconnectedInput)
+                    }
+                };
+                reports.push(report);
+            }
+        }
+    }
+    if policy == UnconnectedInputPolicy::Error && !reports.is_empty() {
+        runtime.runtime_errors.append(&mut reports);
+        Result::Err(())
+    } else {
+        runtime.runtime_warnings.append(&mut reports);
+        Result::Ok(())
+    }
+}
+
 fn execute_infix_op(
     meta: &Meta,
     infix: ExpressionInfixOpcode,
@@ -1005,6 +1328,9 @@ fn execute_infix_op(
     runtime: &mut RuntimeInformation,
 ) -> Result<AExpr, ()> {
     use ExpressionInfixOpcode::*;
+    if infix == Div && runtime.flags.constant_division_mode == DivisionMode::IntegerChecked {
+        check_exact_division(meta, l_value, r_value, runtime)?;
+    }
     let field = runtime.constants.get_p();
     let possible_result = match infix {
         Mul => Result::Ok(AExpr::mul(l_value, r_value, field)),
@@ -1036,6 +1362,39 @@ fn execute_infix_op(
     )
 }
 
+// Only divisions between two known numbers are checked, the values above p/2
+// are taken as negative as in the relational operators
+fn check_exact_division(
+    meta: &Meta,
+    l_value: &AExpr,
+    r_value: &AExpr,
+    runtime: &mut RuntimeInformation,
+) -> Result<(), ()> {
+    let (dividend, divisor) = match (l_value, r_value) {
+        (AExpr::Number { value: l }, AExpr::Number { value: r }) => (l, r),
+        _ => return Result::Ok(()),
+    };
+    let field = runtime.constants.get_p();
+    let signed = |value: &BigInt| {
+        if value > &(field / 2) {
+            value - field
+        } else {
+            value.clone()
+        }
+    };
+    let (dividend, divisor) = (signed(dividend), signed(divisor));
+    if divisor == BigInt::from(0) || &dividend % &divisor == BigInt::from(0) {
+        return Result::Ok(());
+    }
+    let msg = format!("The division {} / {} is not exact in the integers", dividend, divisor);
+    let mut report = Report::error(msg, ReportCode::NonExactConstantDivision);
+    report.add_note(
+        "Use \\ for the integer division or multiply by the inverse explicitly".to_string(),
+    );
+    add_report_to_runtime(report, meta, &mut runtime.runtime_errors, &runtime.call_trace);
+    Result::Err(())
+}
+
 fn execute_prefix_op(
     prefix_op: ExpressionPrefixOpcode,
     value: &AExpr,
@@ -1278,7 +1637,7 @@ fn treat_result_with_execution_error<C>(
             let report = match execution_error {
                 NonQuadraticConstraint => Report::error(
                     "Non quadratic constraints are not allowed!".to_string(),
-                    ReportCode::RuntimeError,
+                    ReportCode::NonQuadraticConstraint,
                 ),
                 FalseAssert => {
                     Report::error("False assert reached".to_string(), ReportCode::RuntimeError)
@@ -1309,3 +1668,444 @@ fn add_report_to_runtime(
     report.add_note(trace);
     runtime_errors.push(report);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{archive, typed};
+    use program_structure::expression_builders::*;
+    use program_structure::statement_builders::*;
+
+    fn meta() -> Meta {
+        Meta::new(0, 0)
+    }
+
+    fn component(name: &str, template: &str, args: Vec<u32>) -> Vec<Statement> {
+        let args = args.into_iter().map(|a| build_number(meta(), BigInt::from(a))).collect();
+        let call = build_call(meta(), template.to_string(), args);
+        vec![
+            build_declaration(meta(), VariableType::Component, name.to_string(), vec![]),
+            build_substitution(meta(), name.to_string(), vec![], AssignOp::AssignVar, call),
+        ]
+    }
+
+    // As component, with the declaration in the initialization block the
+    // type analysis expects
+    fn typed_component(name: &str, template: &str, args: Vec<u32>) -> Vec<Statement> {
+        let mut statements = component(name, template, args);
+        let declaration = statements.remove(0);
+        let block = build_initialization_block(meta(), VariableType::Component, vec![declaration]);
+        statements.insert(0, block);
+        statements
+    }
+
+    fn template(name: &str, args: Vec<&str>, body: Vec<Statement>) -> Definition {
+        let args = args.into_iter().map(|a| a.to_string()).collect();
+        build_template(meta(), name.to_string(), args, 0..0, build_block(meta(), body), false)
+    }
+
+    // Fail(n) asserts n < 3, Inner instantiates Fail(3),
+    // Main instantiates Fail(1) and Inner
+    fn program(main: &str) -> ProgramArchive {
+        let n = build_variable(meta(), "n".to_string(), vec![]);
+        let three = build_number(meta(), BigInt::from(3));
+        let check = build_infix(meta(), n, ExpressionInfixOpcode::Lesser, three);
+        let mut main_body = component("a", "Fail", vec![1]);
+        main_body.append(&mut component("b", "Inner", vec![]));
+        let definitions = vec![
+            template("Fail", vec!["n"], vec![build_assert(meta(), check)]),
+            template("Inner", vec![], component("f", "Fail", vec![3])),
+            template("Main", vec![], main_body),
+            template("Valid", vec![], component("a", "Fail", vec![1])),
+        ];
+        let call = build_call(meta(), main.to_string(), vec![]);
+        archive(definitions, call)
+    }
+
+    #[test]
+    fn bisect_finds_the_innermost_failing_instance() {
+        let flags = ExecutionFlags::default();
+        assert!(constraint_execution(&program("Main"), flags.clone()).is_err());
+        let found = bisect_failure(&program("Main"), flags);
+        assert_eq!(found, Option::Some(("Fail(3)".to_string(), true)));
+    }
+
+    // Main has the single statement var x = dividend / divisor
+    fn division(dividend: i32, divisor: i32) -> ProgramArchive {
+        let dividend = build_number(meta(), BigInt::from(dividend));
+        let divisor = build_number(meta(), BigInt::from(divisor));
+        let quotient = build_infix(meta(), dividend, ExpressionInfixOpcode::Div, divisor);
+        let body = vec![
+            build_declaration(meta(), VariableType::Var, "x".to_string(), vec![]),
+            build_substitution(meta(), "x".to_string(), vec![], AssignOp::AssignVar, quotient),
+        ];
+        let call = build_call(meta(), "Main".to_string(), vec![]);
+        archive(vec![template("Main", vec![], body)], call)
+    }
+
+    #[test]
+    fn checked_mode_only_accepts_exact_divisions() {
+        let checked = ExecutionFlags {
+            constant_division_mode: DivisionMode::IntegerChecked,
+            ..ExecutionFlags::default()
+        };
+        assert!(constraint_execution(&division(6, 3), checked.clone()).is_ok());
+        assert!(constraint_execution(&division(-6, 3), checked.clone()).is_ok());
+        let errors = constraint_execution(&division(7, 2), checked).err().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].get_message(), "The division 7 / 2 is not exact in the integers");
+        // The field mode keeps the modular inverse
+        assert!(constraint_execution(&division(7, 2), ExecutionFlags::default()).is_ok());
+    }
+
+    // Sub has the inputs a and b, Main connects a and, when wired, b
+    fn wiring(wired: bool) -> ProgramArchive {
+        let input = VariableType::Signal(SignalType::Input, SignalElementType::FieldElement);
+        let sub_body = vec![
+            build_declaration(meta(), input, "a".to_string(), vec![]),
+            build_declaration(meta(), input, "b".to_string(), vec![]),
+        ];
+        let mut main_body = component("s", "Sub", vec![]);
+        let inputs = if wired { vec!["a", "b"] } else { vec!["a"] };
+        for input in inputs {
+            let access = vec![build_component_access(input.to_string())];
+            let one = build_number(meta(), BigInt::from(1));
+            let op = AssignOp::AssignConstraintSignal;
+            main_body.push(build_substitution(meta(), "s".to_string(), access, op, one));
+        }
+        let definitions =
+            vec![template("Sub", vec![], sub_body), template("Main", vec![], main_body)];
+        let call = build_call(meta(), "Main".to_string(), vec![]);
+        archive(definitions, call)
+    }
+
+    // The wired program of wiring, decorated by the type analysis. Sub is
+    // marked as parallel when asked
+    fn typed_wiring(parallel: bool) -> ProgramArchive {
+        let input = VariableType::Signal(SignalType::Input, SignalElementType::FieldElement);
+        let declarations = vec![
+            build_declaration(meta(), input, "a".to_string(), vec![]),
+            build_declaration(meta(), input, "b".to_string(), vec![]),
+        ];
+        let sub_body =
+            build_block(meta(), vec![build_initialization_block(meta(), input, declarations)]);
+        let mut main_body = typed_component("s", "Sub", vec![]);
+        for input in ["a", "b"].iter() {
+            let access = vec![build_component_access(input.to_string())];
+            let one = build_number(meta(), BigInt::from(1));
+            let op = AssignOp::AssignConstraintSignal;
+            main_body.push(build_substitution(meta(), "s".to_string(), access, op, one));
+        }
+        let sub = build_template(meta(), "Sub".to_string(), vec![], 0..0, sub_body, parallel);
+        let definitions = vec![sub, template("Main", vec![], main_body)];
+        let call = build_call(meta(), "Main".to_string(), vec![]);
+        typed(archive(definitions, call))
+    }
+
+    #[test]
+    fn unconnected_inputs_follow_the_policy() {
+        let strict = ExecutionFlags {
+            unconnected_input_policy: UnconnectedInputPolicy::Error,
+            ..ExecutionFlags::default()
+        };
+        let (_, warnings) = constraint_execution(&wiring(true), strict.clone()).ok().unwrap();
+        assert!(warnings.is_empty());
+        let (_, warnings) =
+            constraint_execution(&wiring(false), ExecutionFlags::default()).ok().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].is_warning());
+        let errors = constraint_execution(&wiring(false), strict).err().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].get_message(), "Input b of the component Main().s is never assigned");
+    }
+
+    // Main has the signals a, b, c and d and the constraint a * b * c === d
+    fn cubic() -> ProgramArchive {
+        let input = VariableType::Signal(SignalType::Input, SignalElementType::FieldElement);
+        let names = ["a", "b", "c", "d"];
+        let declarations =
+            names.iter().map(|n| build_declaration(meta(), input, n.to_string(), vec![])).collect();
+        let var = |name: &str| build_variable(meta(), name.to_string(), vec![]);
+        let mul = ExpressionInfixOpcode::Mul;
+        let product = build_infix(meta(), var("a"), mul, var("b"));
+        let product = build_infix(meta(), product, mul, var("c"));
+        let body = vec![
+            build_initialization_block(meta(), input, declarations),
+            build_constraint_equality(meta(), product, var("d")),
+        ];
+        let call = build_call(meta(), "Main".to_string(), vec![]);
+        archive(vec![template("Main", vec![], body)], call)
+    }
+
+    #[test]
+    fn cubic_constraints_are_rejected() {
+        let errors = constraint_execution(&cubic(), ExecutionFlags::default()).err().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].get_code(), ReportCode::NonQuadraticConstraint));
+        assert_eq!(errors[0].get_message(), "Non quadratic constraints are not allowed!");
+    }
+
+    #[test]
+    fn cached_nodes_match_a_fresh_build() {
+        use crate::execution_data::executed_program::ExportFlags;
+        use crate::execution_data::instance_cache::InstanceCache;
+        let flags = ExportFlags {
+            signal_order: Default::default(),
+            check_signal_count: true,
+            deterministic: false,
+            header_naming: Default::default(),
+            intern_signal_names: false,
+            execution: ExecutionFlags::default(),
+        };
+        let export = |cache: Option<&mut InstanceCache>| {
+            let program = typed_wiring(false);
+            let (exe, _) = constraint_execution(&program, ExecutionFlags::default()).ok().unwrap();
+            let exported = exe.export(program, flags.clone(), cache, &mut Option::None);
+            let (dag, vcp, _) = exported.ok().unwrap();
+            let offsets: Vec<_> = vcp.templates[1]
+                .triggers
+                .iter()
+                .map(|t| (t.offset, t.component_offset))
+                .collect();
+            (dag.export_json().dump(), offsets, vcp.templates[1].has_parallel_sub_cmp)
+        };
+        let fresh = export(Option::None);
+        let mut cache = InstanceCache::new();
+        assert_eq!(export(Option::Some(&mut cache)), fresh);
+        assert_eq!(export(Option::Some(&mut cache)), fresh);
+        assert_eq!(cache.hits(), ["Sub()".to_string(), "Main()".to_string()]);
+    }
+
+    // Main assigns out <-- Hint(in). Hint calls itself in a dead branch and
+    // returns Add(x), which returns x + k
+    fn hinted(k: u32) -> ProgramArchive {
+        let input = VariableType::Signal(SignalType::Input, SignalElementType::FieldElement);
+        let output = VariableType::Signal(SignalType::Output, SignalElementType::FieldElement);
+        let var = |name: &str| build_variable(meta(), name.to_string(), vec![]);
+        let number = |n: u32| build_number(meta(), BigInt::from(n));
+        let call = |id: &str, arg: &str| build_call(meta(), id.to_string(), vec![var(arg)]);
+        let never = build_infix(meta(), number(0), ExpressionInfixOpcode::Eq, number(1));
+        let recursion = build_return(meta(), call("Hint", "x"));
+        let hint = vec![
+            build_conditional_block(meta(), never, recursion, Option::None),
+            build_return(meta(), call("Add", "x")),
+        ];
+        let sum = build_infix(meta(), var("x"), ExpressionInfixOpcode::Add, number(k));
+        let add = vec![build_return(meta(), sum)];
+        let op = AssignOp::AssignSignal;
+        let body = vec![
+            build_declaration(meta(), input, "in".to_string(), vec![]),
+            build_declaration(meta(), output, "out".to_string(), vec![]),
+            build_substitution(meta(), "out".to_string(), vec![], op, call("Hint", "in")),
+            build_constraint_equality(meta(), var("out"), var("in")),
+        ];
+        let function = |name: &str, body| {
+            let args = vec!["x".to_string()];
+            build_function(meta(), name.to_string(), args, 0..0, build_block(meta(), body))
+        };
+        let definitions =
+            vec![function("Hint", hint), function("Add", add), template("Main", vec![], body)];
+        let call = build_call(meta(), "Main".to_string(), vec![]);
+        archive(definitions, call)
+    }
+
+    #[test]
+    fn instance_hashes_follow_the_called_functions() {
+        let hashes = |k| {
+            let program = hinted(k);
+            let (exe, _) = constraint_execution(&program, ExecutionFlags::default()).ok().unwrap();
+            exe.instance_hashes(&program)
+        };
+        assert_eq!(hashes(1), hashes(1));
+        // Only the body of Add changes, Main calls it through Hint
+        assert_ne!(hashes(1), hashes(2));
+    }
+
+    #[test]
+    fn reloaded_programs_export_as_fresh_ones() {
+        use crate::execution_data::exe_cache;
+        use crate::execution_data::executed_program::ExportFlags;
+        let flags = ExportFlags {
+            signal_order: Default::default(),
+            check_signal_count: true,
+            deterministic: false,
+            header_naming: Default::default(),
+            intern_signal_names: false,
+            execution: ExecutionFlags::default(),
+        };
+        let program = typed_wiring(false);
+        let execution = ExecutionFlags::default();
+        let hash = exe_cache::source_hash(&program, &execution);
+        let (exe, _) = constraint_execution(&program, execution.clone()).ok().unwrap();
+        let name = format!("circom_wiring_{}.exe-cache", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        exe_cache::store(&file, hash, &exe, &ReportCollection::new()).unwrap();
+        let export = |exe: ExecutedProgram| {
+            let flags = flags.clone();
+            let exported = exe.export(program.clone(), flags, Option::None, &mut Option::None);
+            let (dag, vcp, _) = exported.ok().unwrap();
+            let matrices = dag.matrices();
+            let triggers: Vec<_> = vcp.templates[1]
+                .triggers
+                .iter()
+                .map(|t| (t.offset, t.component_offset, t.component_name.clone()))
+                .collect();
+            let witness = vcp.symbolic_witness(usize::MAX).to_text();
+            (dag.export_json().dump(), matrices.a, matrices.b, matrices.c, triggers, witness)
+        };
+        let (reloaded, _) = exe_cache::load(&file, hash).unwrap();
+        assert!(exe_cache::load(&file, hash + 1).is_none());
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(export(reloaded), export(exe));
+        // Other flags give another hash
+        let wide = ExecutionFlags { max_instances: Option::Some(10), ..execution };
+        assert_ne!(exe_cache::source_hash(&program, &wide), hash);
+    }
+
+    #[test]
+    fn reloaded_programs_keep_their_warnings() {
+        use crate::execution_data::exe_cache;
+        let program = wiring(false);
+        let execution = ExecutionFlags::default();
+        let hash = exe_cache::source_hash(&program, &execution);
+        let (exe, warnings) = constraint_execution(&program, execution).ok().unwrap();
+        assert!(!warnings.is_empty());
+        let name = format!("circom_warnings_{}.exe-cache", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        exe_cache::store(&file, hash, &exe, &warnings).unwrap();
+        let (_, reloaded) = exe_cache::load(&file, hash).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        let messages = |reports: &ReportCollection| -> Vec<_> {
+            reports.iter().map(|r| (r.is_warning(), r.get_message().clone())).collect()
+        };
+        assert_eq!(messages(&reloaded), messages(&warnings));
+    }
+
+    #[test]
+    fn parallel_instances_reach_the_vcp() {
+        use crate::execution_data::executed_program::ExportFlags;
+        let flags = ExportFlags {
+            signal_order: Default::default(),
+            check_signal_count: true,
+            deterministic: false,
+            header_naming: Default::default(),
+            intern_signal_names: false,
+            execution: ExecutionFlags::default(),
+        };
+        let program = typed_wiring(true);
+        let (exe, _) = constraint_execution(&program, ExecutionFlags::default()).ok().unwrap();
+        let (_, vcp, _) = exe.export(program, flags, Option::None, &mut Option::None).ok().unwrap();
+        assert_eq!(vcp.parallel_instances(), vec![0]);
+        assert_eq!(vcp.parallelism_of(0), Option::Some((true, false)));
+        assert_eq!(vcp.parallelism_of(1), Option::Some((false, true)));
+        assert_eq!(vcp.parallelism_of(2), Option::None);
+    }
+
+    // Main has the input in and the output out = in * in, assigned with op
+    fn square(op: AssignOp) -> ProgramArchive {
+        let input = VariableType::Signal(SignalType::Input, SignalElementType::FieldElement);
+        let output = VariableType::Signal(SignalType::Output, SignalElementType::FieldElement);
+        let x = || build_variable(meta(), "in".to_string(), vec![]);
+        let product = build_infix(meta(), x(), ExpressionInfixOpcode::Mul, x());
+        let body = vec![
+            build_declaration(meta(), input, "in".to_string(), vec![]),
+            build_declaration(meta(), output, "out".to_string(), vec![]),
+            build_substitution(meta(), "out".to_string(), vec![], op, product),
+        ];
+        let call = build_call(meta(), "Main".to_string(), vec![]);
+        archive(vec![template("Main", vec![], body)], call)
+    }
+
+    #[test]
+    fn witness_only_assignments_are_reported() {
+        let flags = ExecutionFlags::default();
+        let witness_only = square(AssignOp::AssignSignal);
+        let (_, warnings) = constraint_execution(&witness_only, flags.clone()).ok().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].is_warning());
+        assert_eq!(
+            warnings[0].get_message(),
+            "Signal out of Main() is assigned with <-- but never constrained"
+        );
+        let constrained = square(AssignOp::AssignConstraintSignal);
+        let (_, warnings) = constraint_execution(&constrained, flags).ok().unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn instances_beyond_the_limit_are_errors() {
+        let limited = |max| ExecutionFlags { max_instances: max, ..ExecutionFlags::default() };
+        // Valid and Fail(1)
+        assert!(constraint_execution(&program("Valid"), limited(Option::Some(2))).is_ok());
+        let errors = constraint_execution(&program("Valid"), limited(Option::Some(1)));
+        let errors = errors.err().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].get_message(),
+            "Instantiating Fail(1) exceeds the limit of 1 template instances"
+        );
+    }
+
+    // Rec(n) instantiates Rec(n + 1) without a base case
+    fn endless_recursion() -> ProgramArchive {
+        let n = build_variable(meta(), "n".to_string(), vec![]);
+        let one = build_number(meta(), BigInt::from(1));
+        let next = build_infix(meta(), n, ExpressionInfixOpcode::Add, one);
+        let call = build_call(meta(), "Rec".to_string(), vec![next]);
+        let xtype = VariableType::Component;
+        let declaration = build_declaration(meta(), xtype, "c".to_string(), vec![]);
+        let body = vec![
+            build_initialization_block(meta(), xtype, vec![declaration]),
+            build_substitution(meta(), "c".to_string(), vec![], AssignOp::AssignVar, call),
+        ];
+        let zero = build_number(meta(), BigInt::from(0));
+        let main = build_call(meta(), "Rec".to_string(), vec![zero]);
+        archive(vec![template("Rec", vec!["n"], body)], main)
+    }
+
+    #[test]
+    fn the_limit_stops_endless_recursion() {
+        let limited =
+            ExecutionFlags { max_instances: Option::Some(50), ..ExecutionFlags::default() };
+        let errors = constraint_execution(&endless_recursion(), limited).err().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].get_code(), ReportCode::InstanceLimitExceeded));
+        assert_eq!(
+            errors[0].get_message(),
+            "Instantiating Rec(50) exceeds the limit of 50 template instances"
+        );
+    }
+
+    // Main instantiates A, A instantiates B(1) and B(1) instantiates A again
+    fn cyclic_instantiation() -> ProgramArchive {
+        let main = build_call(meta(), "Main".to_string(), vec![]);
+        let templates = vec![
+            template("Main", vec![], component("a", "A", vec![])),
+            template("A", vec![], component("b", "B", vec![1])),
+            template("B", vec!["n"], component("a", "A", vec![])),
+        ];
+        archive(templates, main)
+    }
+
+    #[test]
+    fn cyclic_instantiations_are_errors() {
+        let flags = ExecutionFlags::default();
+        let errors = constraint_execution(&cyclic_instantiation(), flags).err().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].get_code(), ReportCode::CyclicInstantiation));
+        assert_eq!(errors[0].get_message(), "Cyclic instantiation of A()");
+        assert_eq!(errors[0].get_notes()[0], "The cycle is A() -> B(1) -> A()");
+        // Recursion that changes its parameters is not a cycle
+        let limited =
+            ExecutionFlags { max_instances: Option::Some(50), ..ExecutionFlags::default() };
+        let errors = constraint_execution(&endless_recursion(), limited).err().unwrap();
+        assert!(matches!(errors[0].get_code(), ReportCode::InstanceLimitExceeded));
+    }
+
+    #[test]
+    fn bisect_is_not_needed_without_failures() {
+        let flags = ExecutionFlags::default();
+        assert!(constraint_execution(&program("Valid"), flags.clone()).is_ok());
+        assert_eq!(bisect_failure(&program("Valid"), flags), Option::None);
+    }
+}
@@ -0,0 +1,70 @@
+use super::ExecutedProgram;
+use crate::execute::ExecutionFlags;
+use lz_fnv::Fnv1a;
+use program_structure::error_definition::ReportCollection;
+use program_structure::program_archive::ProgramArchive;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+pub type SourceHash = u64;
+
+// Changes whenever the layout of the cache file changes
+const CACHE_VERSION: u64 = 1;
+
+// Hash of everything the execution depends on: the templates, the
+// functions, the main component and the flags of the execution. FNV
+// gives the same hash in every build of the compiler
+pub fn source_hash(program: &ProgramArchive, flags: &ExecutionFlags) -> SourceHash {
+    let mut state = Fnv1a::<u64>::new();
+    CACHE_VERSION.hash(&mut state);
+    let mut templates: Vec<_> = program.get_templates().iter().collect();
+    templates.sort_by(|l, r| l.0.cmp(r.0));
+    for (name, data) in templates {
+        name.hash(&mut state);
+        data.get_name_of_params().hash(&mut state);
+        data.is_parallel().hash(&mut state);
+        serde_json::to_string(data.get_body()).unwrap_or_default().hash(&mut state);
+    }
+    let mut functions: Vec<_> = program.get_functions().iter().collect();
+    functions.sort_by(|l, r| l.0.cmp(r.0));
+    for (name, data) in functions {
+        name.hash(&mut state);
+        data.get_name_of_params().hash(&mut state);
+        serde_json::to_string(data.get_body()).unwrap_or_default().hash(&mut state);
+    }
+    program.get_public_inputs_main_component().hash(&mut state);
+    let main = program.get_main_expression();
+    serde_json::to_string(main).unwrap_or_default().hash(&mut state);
+    flags.assert_as_constraint.hash(&mut state);
+    format!("{:?}", flags.constant_division_mode).hash(&mut state);
+    format!("{:?}", flags.unconnected_input_policy).hash(&mut state);
+    flags.max_instances.hash(&mut state);
+    flags.constants.get_p().hash(&mut state);
+    state.finish()
+}
+
+// The file holds the hash of the source, the executed program and the
+// reports of the execution, which are emitted again on a hit
+pub fn store(
+    file: &Path,
+    source_hash: SourceHash,
+    program: &ExecutedProgram,
+    reports: &ReportCollection,
+) -> Result<(), ()> {
+    let writer = BufWriter::new(File::create(file).map_err(|_err| {})?);
+    serde_json::to_writer(writer, &(source_hash, program, reports)).map_err(|_err| {})
+}
+
+// None when there is no cache or it was written for another source
+pub fn load(file: &Path, source_hash: SourceHash) -> Option<(ExecutedProgram, ReportCollection)> {
+    let reader = BufReader::new(File::open(file).ok()?);
+    let (hash, program, reports): (SourceHash, ExecutedProgram, ReportCollection) =
+        serde_json::from_reader(reader).ok()?;
+    if hash == source_hash {
+        Option::Some((program, reports))
+    } else {
+        Option::None
+    }
+}
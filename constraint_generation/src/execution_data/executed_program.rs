@@ -1,18 +1,39 @@
 use super::analysis::Analysis;
-use super::executed_template::ExecutedTemplate;
+use super::executed_template::{check_signal_count, ExecutedTemplate, HeaderNaming, SignalOrder};
+use super::instance_cache::{content_hash, node_hash, InstanceCache, InstanceHash, NodeKey};
 use super::type_definitions::*;
+use crate::execute::ExecutionFlags;
 use compiler::hir::very_concrete_program::{Stats, VCPConfig, VCP};
 use dag::DAG;
+use program_structure::constants::UsefulConstants;
 use program_structure::program_archive::ProgramArchive;
 use program_structure::program_library::error_definition::ReportCollection;
+use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 pub type ExportResult = Result<(DAG, VCP, ReportCollection), ReportCollection>;
+// Instance name, times it is used, its own constraints and the constraints of every use
+pub type TemplateCost = (String, usize, usize, usize);
 
-#[derive(Default)]
+#[derive(Clone)]
+pub struct ExportFlags {
+    pub signal_order: SignalOrder,
+    pub check_signal_count: bool,
+    // Signals and components sorted by name inside each instance
+    pub deterministic: bool,
+    pub header_naming: HeaderNaming,
+    // The names of the array elements are built without temporary strings
+    pub intern_signal_names: bool,
+    // The flags the program was executed with, the cached nodes depend on them
+    pub execution: ExecutionFlags,
+}
+
+#[derive(Default, Serialize, Deserialize)]
 pub struct ExecutedProgram {
     pub model: Vec<ExecutedTemplate>,
     pub template_to_nodes: HashMap<String, Vec<NodePointer>>,
+    // Field of the execution, the dag is exported over it
+    pub constants: UsefulConstants,
 }
 
 impl ExecutedProgram {
@@ -42,6 +63,37 @@ impl ExecutedProgram {
         Option::Some(&self.model[node_pointer])
     }
 
+    // Content hash of every instance, indexed by its node pointer. The
+    // functions called by the instances come from the program
+    pub fn instance_hashes(&self, program: &ProgramArchive) -> Vec<InstanceHash> {
+        self.model.iter().map(|exe| content_hash(exe, program)).collect()
+    }
+
+    // Constraints before the simplification, the heaviest instances first
+    pub fn template_costs(&self) -> Vec<TemplateCost> {
+        // Subcomponents are added to the model before the instances using them
+        let mut uses = vec![0; self.model.len()];
+        if let Option::Some(main) = uses.last_mut() {
+            *main = 1;
+        }
+        for (id, exe) in self.model.iter().enumerate().rev() {
+            for sub in exe.sub_instance_uses() {
+                uses[sub] += uses[id];
+            }
+        }
+        let mut costs: Vec<TemplateCost> = self
+            .model
+            .iter()
+            .zip(uses)
+            .map(|(exe, uses)| {
+                let constraints = exe.constraints.len();
+                (exe.report_name.clone(), uses, constraints, constraints * uses)
+            })
+            .collect();
+        costs.sort_by(|l, r| r.3.cmp(&l.3).then_with(|| l.0.cmp(&r.0)));
+        costs
+    }
+
     pub fn add_node_to_scheme(
         &mut self,
         mut node: ExecutedTemplate,
@@ -49,7 +101,7 @@ impl ExecutedProgram {
     ) -> NodePointer {
         use super::filters::*;
         // Clean code
-        apply_unused(&mut node.code, &analysis);
+        apply_unused(&mut node.code, &analysis, self.constants.get_p());
         apply_computed(&mut node.code, &analysis);
         // Insert template
         let possible_index = self.identify_node(node.template_name(), node.parameter_instances());
@@ -64,7 +116,15 @@ impl ExecutedProgram {
         node_index
     }
 
-    pub fn export(mut self, mut program: ProgramArchive) -> ExportResult {
+    // Instances whose node is in the cache are not inserted again in the dag,
+    // the visitor sees the constraints of the cached node instead
+    pub fn export(
+        mut self,
+        mut program: ProgramArchive,
+        flags: ExportFlags,
+        mut cache: Option<&mut InstanceCache>,
+        visitor: &mut Option<ConstraintVisitor>,
+    ) -> ExportResult {
         use super::executed_template::templates_in_mixed_arrays;
         fn merge_mixed(org: Vec<bool>, new: Vec<bool>) -> Vec<bool> {
             let mut result = Vec::with_capacity(org.len());
@@ -78,6 +138,7 @@ impl ExecutedProgram {
 
         let mut warnings = vec![];
         let mut dag = DAG::new();
+        dag.constants = self.constants.clone();
         let mut temp_instances = Vec::with_capacity(self.model.len());
         let mut mixed_instances = vec![false; self.model.len()];
 
@@ -86,14 +147,63 @@ impl ExecutedProgram {
             mixed_instances = merge_mixed(mixed_instances, mixed);
         }
 
-        for exe in &mut self.model {
-            exe.insert_in_dag(&mut dag);
+        // The public signals of main must keep the r1cs layout
+        let main_id = self.model.len() - 1;
+        let order = flags.signal_order;
+        let order_of = |id: usize| if id == main_id { SignalOrder::TypeGrouped } else { order };
+        if flags.deterministic {
+            for (id, exe) in self.model.iter_mut().enumerate() {
+                exe.sort_by_name(id == main_id);
+            }
+        }
+        let files = &program.file_library;
+        let intern = flags.intern_signal_names;
+        if let Option::Some(cache) = cache.as_mut() {
+            cache.start_build();
+        }
+        let contents = if cache.is_some() { self.instance_hashes(&program) } else { vec![] };
+        let mut hashes: Vec<InstanceHash> = Vec::with_capacity(self.model.len());
+        for (id, exe) in self.model.iter_mut().enumerate() {
+            let cache = match cache.as_mut() {
+                Option::Some(cache) => cache,
+                Option::None => {
+                    exe.insert_in_dag(&mut dag, order_of(id), files, visitor, intern);
+                    continue;
+                }
+            };
+            let subs: Vec<_> = exe.sub_instances().into_iter().map(|s| hashes[s]).collect();
+            let field = dag.constants.get_p();
+            let key = NodeKey { id, order: order_of(id), deterministic: flags.deterministic };
+            let hash = node_hash(exe, contents[id], key, &flags.execution, field, &subs);
+            hashes.push(hash);
+            if let Option::Some((node, edges)) = cache.lookup(&exe.report_name, hash) {
+                if let Option::Some(visitor) = visitor {
+                    node.constraints().iter().for_each(|c| visitor(exe.template_name(), c));
+                }
+                exe.reuse_in_dag(&mut dag, node, edges, files);
+            } else {
+                exe.insert_in_dag(&mut dag, order_of(id), files, visitor, intern);
+                let edges = dag.adjacency[id].clone();
+                cache.store(&exe.report_name, hash, dag.nodes[id].clone(), edges);
+            }
         }
 
-        for exe in self.model {
-            let tmp_instance = exe.export_to_circuit(&temp_instances);
+        let public_inputs = self.model[main_id].ordered_public_inputs();
+        let mut count_errors = vec![];
+        for (id, exe) in self.model.into_iter().enumerate() {
+            let declared = exe.number_of_declared_signals();
+            let naming = flags.header_naming;
+            let tmp_instance = exe.export_to_circuit(&temp_instances, order_of(id), naming);
+            if flags.check_signal_count {
+                if let Result::Err(report) = check_signal_count(declared, &tmp_instance) {
+                    count_errors.push(report);
+                }
+            }
             temp_instances.push(tmp_instance);
         }
+        if !count_errors.is_empty() {
+            return Result::Err(count_errors);
+        }
         let mut w = dag.constraint_analysis()?;
         warnings.append(&mut w);
 
@@ -114,6 +224,7 @@ impl ExecutedProgram {
             file_library: std::mem::take(&mut program.file_library),
             templates: temp_instances,
             templates_in_mixed: mixed,
+            public_inputs,
             program,
         };
         let vcp = VCP::new(config);
@@ -1,11 +1,19 @@
 use super::type_definitions::*;
 use circom_algebra::algebra::ArithmeticExpression;
 use compiler::hir::very_concrete_program::*;
-use dag::DAG;
+use constraint_writers::r1cs_writer::SourceSpan;
+use dag::{Edge, Node, DAG};
+use lz_fnv::Fnv1a;
 use num_bigint::BigInt;
-use program_structure::ast::{SignalType, Statement};
+use program_structure::ast::{Meta, SignalType, Statement};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::{FileID, FileLibrary, FileLocation};
+use serde_derive::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 
+#[derive(Serialize, Deserialize)]
 struct Connexion {
     full_name: String,
     inspect: SubComponentData,
@@ -15,6 +23,29 @@ struct Connexion {
     dag_component_jump: usize,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum SignalOrder {
+    // outputs, public inputs, private inputs and intermediates
+    #[default]
+    TypeGrouped,
+    Declaration,
+}
+
+// How the header of an instance, the name of its code, is chosen
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum HeaderNaming {
+    // template name and position of the instance in the build
+    #[default]
+    Sequential,
+    // template name and a short hash of the parameters, the same in every
+    // build whatever the instantiation order
+    ParameterHash,
+}
+
+// (name, dimensions, type, is public)
+type OrderedSignal = (String, Vec<usize>, SignalType, bool);
+
+#[derive(Serialize, Deserialize)]
 pub struct ExecutedTemplate {
     pub code: Statement,
     pub template_name: String,
@@ -22,6 +53,8 @@ pub struct ExecutedTemplate {
     pub inputs: SignalCollector,
     pub outputs: SignalCollector,
     pub constraints: Vec<Constraint>,
+    // Statement that produced each constraint, by position
+    constraint_origins: Vec<Option<(FileID, FileLocation)>>,
     pub intermediates: SignalCollector,
     pub components: ComponentCollector,
     pub public_inputs: HashSet<String>,
@@ -29,6 +62,9 @@ pub struct ExecutedTemplate {
     pub is_parallel: bool,
     pub has_parallel_sub_cmp: bool,
     connexions: Vec<Connexion>,
+    signal_declarations: Vec<String>,
+    // Own signals given a value with <--, with the assignment
+    witness_assignments: Vec<(String, Meta)>,
 }
 
 impl ExecutedTemplate {
@@ -54,7 +90,10 @@ impl ExecutedTemplate {
             intermediates: SignalCollector::new(),
             components: ComponentCollector::new(),
             constraints: Vec::new(),
+            constraint_origins: Vec::new(),
             connexions: Vec::new(),
+            signal_declarations: Vec::new(),
+            witness_assignments: Vec::new(),
         }
     }
 
@@ -70,22 +109,53 @@ impl ExecutedTemplate {
 
     pub fn add_input(&mut self, input_name: &str, dimensions: &[usize]) {
         self.inputs.push((input_name.to_string(), dimensions.to_vec()));
+        self.signal_declarations.push(input_name.to_string());
     }
 
     pub fn add_output(&mut self, output_name: &str, dimensions: &[usize]) {
         self.outputs.push((output_name.to_string(), dimensions.to_vec()));
+        self.signal_declarations.push(output_name.to_string());
     }
 
     pub fn add_intermediate(&mut self, intermediate_name: &str, dimensions: &[usize]) {
         self.intermediates.push((intermediate_name.to_string(), dimensions.to_vec()));
+        self.signal_declarations.push(intermediate_name.to_string());
     }
 
     pub fn add_component(&mut self, component_name: &str, dimensions: &[usize]) {
         self.components.push((component_name.to_string(), dimensions.to_vec()));
     }
 
-    pub fn add_constraint(&mut self, constraint: Constraint) {
+    pub fn add_constraint(&mut self, constraint: Constraint, meta: &Meta) {
         self.constraints.push(constraint);
+        self.constraint_origins.push(meta.file_id.map(|id| (id, meta.location.clone())));
+    }
+
+    pub fn add_witness_assignment(&mut self, signal: &str, meta: &Meta) {
+        self.witness_assignments.push((signal.to_string(), meta.clone()));
+    }
+
+    // Signals assigned with <-- that no constraint of the instance mentions,
+    // nothing forces the value computed for them
+    pub fn unconstrained_assignments(&self) -> ReportCollection {
+        let constrained: HashSet<String> =
+            self.constraints.iter().flat_map(Constraint::take_cloned_signals).collect();
+        let mut reports = ReportCollection::new();
+        for (signal, meta) in &self.witness_assignments {
+            if constrained.contains(signal) {
+                continue;
+            }
+            let msg = format!(
+                "Signal {} of {} is assigned with <-- but never constrained",
+                signal, self.report_name
+            );
+            let mut report = Report::warning(msg, ReportCode::UnconstrainedAssignment);
+            let label = "assigned here".to_string();
+            report.add_primary(meta.location.clone(), meta.get_file_id(), label);
+            report.add_note(format!("Maybe add a constraint on {} with ===", signal));
+            reports.push(report);
+        }
+        reports
     }
 
     pub fn template_name(&self) -> &String {
@@ -108,40 +178,173 @@ impl ExecutedTemplate {
         &self.intermediates
     }
 
-    pub fn insert_in_dag(&mut self, dag: &mut DAG) {
-        dag.add_node(self.report_name.clone(), self.is_parallel);
-        self.build_signals(dag);
-        self.build_connexions(dag);
-        self.build_constraints(dag);
+    // The signals keep their type grouping, main has the same r1cs layout
+    // The inputs and outputs of main keep their declaration order, it is
+    // the public layout of the r1cs
+    pub fn sort_by_name(&mut self, is_main: bool) {
+        if !is_main {
+            self.inputs.sort_by(|l, r| l.0.cmp(&r.0));
+            self.outputs.sort_by(|l, r| l.0.cmp(&r.0));
+        }
+        self.intermediates.sort_by(|l, r| l.0.cmp(&r.0));
+        self.components.sort_by(|l, r| l.0.cmp(&r.0));
+    }
+
+    // Outputs and public inputs, in the order of the r1cs
+    pub fn public_signals(&self) -> Vec<String> {
+        let outputs = self.outputs().iter().map(|(name, _)| name.clone());
+        outputs.chain(self.ordered_public_inputs()).collect()
+    }
+
+    pub fn ordered_public_inputs(&self) -> Vec<String> {
+        let inputs = self.inputs().iter().map(|(name, _)| name);
+        inputs.filter(|name| self.public_inputs.contains(*name)).cloned().collect()
     }
 
-    fn build_signals(&self, dag: &mut DAG) {
+    // Sum of the sizes of every declared signal
+    pub fn number_of_declared_signals(&self) -> usize {
+        let collectors = [self.inputs(), self.outputs(), self.intermediates()];
+        let dimensions = collectors.iter().flat_map(|c| c.iter().map(|(_, dim)| dim));
+        dimensions.map(|dim| dim.iter().product::<usize>()).sum()
+    }
+
+    // A hash that collides with a previous header falls back to the position.
+    // FNV gives the same header in every build of the compiler
+    fn header(&self, instances: &[TemplateInstance], naming: HeaderNaming) -> String {
+        let sequential = format!("{}_{}", self.template_name, instances.len());
+        if naming == HeaderNaming::Sequential {
+            return sequential;
+        }
+        let mut parameters: Vec<_> = self.parameter_instances.iter().collect();
+        parameters.sort_by(|l, r| l.0.cmp(r.0));
+        let mut state = Fnv1a::<u64>::new();
+        for (name, value) in parameters {
+            name.hash(&mut state);
+            value.to_string().hash(&mut state);
+        }
+        let header = format!("{}_{:08x}", self.template_name, state.finish() as u32);
+        if instances.iter().any(|instance| instance.template_header == header) {
+            sequential
+        } else {
+            header
+        }
+    }
+
+    // The dag and the exported instance must use the same order,
+    // otherwise the witness would not match the constraints
+    fn ordered_signals(&self, order: SignalOrder) -> Vec<OrderedSignal> {
+        use SignalType::*;
+        let mut signals = Vec::new();
         for (name, dim) in self.outputs() {
-            let state = State { name: name.clone(), dim: 0 };
-            let config = SignalConfig { signal_type: 1, dimensions: dim, is_public: false };
-            generate_symbols(dag, state, &config);
+            signals.push((name.clone(), dim.clone(), Output, false));
         }
         for (name, dim) in self.inputs() {
             if self.public_inputs.contains(name) {
-                let state = State { name: name.clone(), dim: 0 };
-                let config = SignalConfig { signal_type: 0, dimensions: dim, is_public: true };
-                generate_symbols(dag, state, &config);
+                signals.push((name.clone(), dim.clone(), Input, true));
             }
         }
         for (name, dim) in self.inputs() {
             if !self.public_inputs.contains(name) {
-                let state = State { name: name.clone(), dim: 0 };
-                let config = SignalConfig { signal_type: 0, dimensions: dim, is_public: false };
-                generate_symbols(dag, state, &config);
+                signals.push((name.clone(), dim.clone(), Input, false));
             }
         }
         for (name, dim) in self.intermediates() {
-            let state = State { name: name.clone(), dim: 0 };
-            let config = SignalConfig { signal_type: 2, dimensions: dim, is_public: false };
+            signals.push((name.clone(), dim.clone(), Intermediate, false));
+        }
+        if order == SignalOrder::Declaration {
+            let position: HashMap<_, _> =
+                self.signal_declarations.iter().enumerate().map(|(i, n)| (n, i)).collect();
+            signals.sort_by_key(|(name, ..)| position[name]);
+        }
+        signals
+    }
+
+    pub fn insert_in_dag(
+        &mut self,
+        dag: &mut DAG,
+        order: SignalOrder,
+        files: &FileLibrary,
+        visitor: &mut Option<ConstraintVisitor>,
+        intern_names: bool,
+    ) {
+        dag.add_node(self.report_name.clone(), self.is_parallel);
+        if intern_names {
+            self.build_interned_signals(dag, order);
+        } else {
+            self.build_signals(dag, order);
+        }
+        self.build_connexions(dag);
+        self.build_constraints(dag, files, visitor);
+    }
+
+    fn build_signals(&self, dag: &mut DAG, order: SignalOrder) {
+        use SignalType::*;
+        for (name, dim, xtype, is_public) in self.ordered_signals(order) {
+            let signal_type = match xtype {
+                Input => 0,
+                Output => 1,
+                Intermediate => 2,
+            };
+            let state = State { name, dim: 0 };
+            let config = SignalConfig { signal_type, dimensions: &dim, is_public };
             generate_symbols(dag, state, &config);
         }
     }
-    fn build_connexions(&mut self, dag: &mut DAG) {
+
+    // Same names as build_signals. The index suffixes are formatted once for
+    // the whole instance and every element name is written in one buffer, so
+    // the only allocation per signal is its final name
+    fn build_interned_signals(&self, dag: &mut DAG, order: SignalOrder) {
+        use SignalType::*;
+        let signals = self.ordered_signals(order);
+        let longest = signals.iter().flat_map(|(_, dim, ..)| dim.iter()).max().cloned();
+        let suffixes: Vec<_> = (0..longest.unwrap_or(0)).map(|i| format!("[{}]", i)).collect();
+        for (mut name, dim, xtype, is_public) in signals {
+            let signal_type = match xtype {
+                Input => 0,
+                Output => 1,
+                Intermediate => 2,
+            };
+            let config = SignalConfig { signal_type, dimensions: &dim, is_public };
+            generate_interned_symbols(dag, &mut name, 0, &config, &suffixes);
+        }
+    }
+    // Same as insert_in_dag with the node built by a previous compilation
+    pub fn reuse_in_dag(
+        &mut self,
+        dag: &mut DAG,
+        node: Node,
+        edges: Vec<Edge>,
+        files: &FileLibrary,
+    ) {
+        self.sort_connexions();
+        let spans = self.constraint_origins.iter().map(|o| span_of(files, o.as_ref()?)).collect();
+        dag.add_cached_node(node, edges, spans);
+        let edges = dag.get_edges(dag.main_id()).unwrap();
+        for (cnn, edge) in self.connexions.iter_mut().zip(edges) {
+            cnn.dag_offset = edge.get_in();
+            // The component count of a node includes the node itself
+            cnn.dag_component_offset = edge.get_in_component() - 1;
+            cnn.dag_jump = edge.get_out() - edge.get_in();
+            cnn.dag_component_jump = edge.get_out_component() - edge.get_in_component();
+        }
+        self.has_parallel_sub_cmp = dag.nodes[dag.main_id()].has_parallel_sub_cmp();
+    }
+
+    // Instances used as subcomponents, without repetitions
+    pub fn sub_instances(&self) -> Vec<NodePointer> {
+        let mut instances: Vec<_> = self.connexions.iter().map(|c| c.inspect.goes_to).collect();
+        instances.sort_unstable();
+        instances.dedup();
+        instances
+    }
+
+    // Instance of each subcomponent, once per component
+    pub fn sub_instance_uses(&self) -> impl Iterator<Item = NodePointer> + '_ {
+        self.connexions.iter().map(|c| c.inspect.goes_to)
+    }
+
+    fn sort_connexions(&mut self) {
         self.connexions.sort_by(|l, r| {
             use std::cmp::Ordering;
             let l_data = &l.inspect;
@@ -153,6 +356,9 @@ impl ExecutedTemplate {
             }
         });
         self.components = filter_used_components(self);
+    }
+    fn build_connexions(&mut self, dag: &mut DAG) {
+        self.sort_connexions();
         for cnn in &mut self.connexions {
             cnn.dag_offset = dag.get_entry().unwrap().get_out();
             cnn.dag_component_offset = dag.get_entry().unwrap().get_out_component();
@@ -162,15 +368,28 @@ impl ExecutedTemplate {
         }
         self.has_parallel_sub_cmp = dag.nodes[dag.main_id()].has_parallel_sub_cmp();
     }
-    fn build_constraints(&self, dag: &mut DAG) {
-        for c in &self.constraints {
+    fn build_constraints(
+        &self,
+        dag: &mut DAG,
+        files: &FileLibrary,
+        visitor: &mut Option<ConstraintVisitor>,
+    ) {
+        for (c, origin) in self.constraints.iter().zip(&self.constraint_origins) {
             let correspondence = dag.get_main().unwrap().correspondence();
             let cc = Constraint::apply_correspondence(c, correspondence);
-            dag.add_constraint(cc);
+            if let Option::Some(visitor) = visitor {
+                visitor(self.template_name(), &cc);
+            }
+            let span = origin.as_ref().and_then(|origin| span_of(files, origin));
+            dag.add_constraint_with_span(cc, span);
         }
     }
-    pub fn export_to_circuit(self, instances: &[TemplateInstance]) -> TemplateInstance {
-        use SignalType::*;
+    pub fn export_to_circuit(
+        self,
+        instances: &[TemplateInstance],
+        order: SignalOrder,
+        naming: HeaderNaming,
+    ) -> TemplateInstance {
         fn build_triggers(
             instances: &[TemplateInstance],
             connexions: Vec<Connexion>,
@@ -210,7 +429,8 @@ impl ExecutedTemplate {
             arguments
         }
 
-        let header = format!("{}_{}", self.template_name, instances.len());
+        let signals = self.ordered_signals(order);
+        let header = self.header(instances, naming);
         let clusters = build_clusters(&self, instances);
         let triggers = build_triggers(instances, self.connexions);
         let components = build_components(self.components);
@@ -230,37 +450,10 @@ impl ExecutedTemplate {
 
         let mut instance = TemplateInstance::new(config);
 
-        let mut public = vec![];
-        let mut not_public = vec![];
-        for s in self.inputs {
-            if self.public_inputs.contains(&s.0) {
-                public.push(s);
-            } else {
-                not_public.push(s);
-            }
-        }
         let mut local_id = 0;
         let mut dag_local_id = 1;
-        for (name, lengths) in self.outputs {
-            let signal = Signal { name, lengths, local_id, dag_local_id, xtype: Output };
-            local_id += signal.size();
-            dag_local_id += signal.size();
-            instance.add_signal(signal);
-        }
-        for (name, lengths) in public {
-            let signal = Signal { name, lengths, local_id, dag_local_id, xtype: Input };
-            local_id += signal.size();
-            dag_local_id += signal.size();
-            instance.add_signal(signal);
-        }
-        for (name, lengths) in not_public {
-            let signal = Signal { name, lengths, local_id, dag_local_id, xtype: Input };
-            local_id += signal.size();
-            dag_local_id += signal.size();
-            instance.add_signal(signal);
-        }
-        for (name, lengths) in self.intermediates {
-            let signal = Signal { name, lengths, local_id, dag_local_id, xtype: Intermediate };
+        for (name, lengths, xtype, _) in signals {
+            let signal = Signal { name, lengths, local_id, dag_local_id, xtype };
             local_id += signal.size();
             dag_local_id += signal.size();
             instance.add_signal(signal);
@@ -269,6 +462,28 @@ impl ExecutedTemplate {
     }
 }
 
+// The signals added to an exported instance must cover its declarations,
+// a mismatch is a bug in the export and not in the circuit
+pub fn check_signal_count(expected: usize, instance: &TemplateInstance) -> Result<(), Report> {
+    let added =
+        instance.number_of_inputs + instance.number_of_outputs + instance.number_of_intermediates;
+    if added == expected {
+        return Result::Ok(());
+    }
+    let msg = format!(
+        "Template instance {} has {} signals but its declarations add up to {}",
+        instance.template_header, added, expected
+    );
+    Result::Err(Report::error(msg, ReportCode::InternalCompilerError))
+}
+
+fn span_of(files: &FileLibrary, origin: &(FileID, FileLocation)) -> Option<SourceSpan> {
+    let (file_id, location) = origin;
+    let start = files.get_line_column(location.start, *file_id)?;
+    let end = files.get_line_column(location.end, *file_id)?;
+    Option::Some(SourceSpan { file_id: *file_id, start, end })
+}
+
 struct SignalConfig<'a> {
     is_public: bool,
     signal_type: usize,
@@ -278,15 +493,18 @@ struct State {
     name: String,
     dim: usize,
 }
+fn add_symbol(dag: &mut DAG, name: String, config: &SignalConfig) {
+    if config.signal_type == 0 {
+        dag.add_input(name, config.is_public);
+    } else if config.signal_type == 1 {
+        dag.add_output(name);
+    } else if config.signal_type == 2 {
+        dag.add_intermediate(name);
+    }
+}
 fn generate_symbols(dag: &mut DAG, state: State, config: &SignalConfig) {
     if state.dim == config.dimensions.len() {
-        if config.signal_type == 0 {
-            dag.add_input(state.name, config.is_public);
-        } else if config.signal_type == 1 {
-            dag.add_output(state.name);
-        } else if config.signal_type == 2 {
-            dag.add_intermediate(state.name);
-        }
+        add_symbol(dag, state.name, config);
     } else {
         let mut index = 0;
         while index < config.dimensions[state.dim] {
@@ -298,6 +516,25 @@ fn generate_symbols(dag: &mut DAG, state: State, config: &SignalConfig) {
     }
 }
 
+fn generate_interned_symbols(
+    dag: &mut DAG,
+    name: &mut String,
+    dim: usize,
+    config: &SignalConfig,
+    suffixes: &[String],
+) {
+    if dim == config.dimensions.len() {
+        add_symbol(dag, name.clone(), config);
+        return;
+    }
+    let base = name.len();
+    for suffix in &suffixes[..config.dimensions[dim]] {
+        name.push_str(suffix);
+        generate_interned_symbols(dag, name, dim + 1, config, suffixes);
+        name.truncate(base);
+    }
+}
+
 fn as_big_int(exprs: Vec<ArithmeticExpression<String>>) -> Vec<BigInt> {
     let mut numbers = Vec::with_capacity(exprs.len());
     for e in exprs {
@@ -430,3 +667,63 @@ pub fn templates_in_mixed_arrays(exec_tmp: &ExecutedTemplate, no_templates: usiz
     }
     mixed
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use program_structure::ast::Meta;
+
+    // signal t; signal input a; signal output c; signal input p (public)
+    fn template() -> ExecutedTemplate {
+        let code = Statement::Block { meta: Meta::new(0, 0), stmts: vec![] };
+        let public = vec!["p".to_string()];
+        let name = "T".to_string();
+        let mut template =
+            ExecutedTemplate::new(public, name.clone(), name, HashMap::new(), code, false);
+        template.add_intermediate("t", &[]);
+        template.add_input("a", &[2]);
+        template.add_output("c", &[]);
+        template.add_input("p", &[]);
+        template
+    }
+
+    fn layout(order: SignalOrder) -> (Vec<(String, usize)>, HashMap<String, usize>) {
+        let mut dag = DAG::new();
+        let mut exe = template();
+        exe.insert_in_dag(&mut dag, order, &FileLibrary::new(), &mut Option::None, false);
+        let instance = exe.export_to_circuit(&[], order, HeaderNaming::Sequential);
+        let signals = instance.signals.into_iter().map(|s| (s.name, s.dag_local_id)).collect();
+        (signals, dag.get_main().unwrap().correspondence().clone())
+    }
+
+    #[test]
+    fn signals_follow_the_chosen_order() {
+        let grouped = vec![("c", 1), ("p", 2), ("a", 3), ("t", 5)];
+        let declared = vec![("t", 1), ("a", 2), ("c", 4), ("p", 5)];
+        for (order, expected) in
+            [(SignalOrder::TypeGrouped, grouped), (SignalOrder::Declaration, declared)]
+        {
+            let (signals, correspondence) = layout(order);
+            let expected: Vec<_> = expected.into_iter().map(|(n, i)| (n.to_string(), i)).collect();
+            assert_eq!(signals, expected);
+            // the dag gives the same ids to the exported signals
+            for (name, id) in &signals {
+                let dag_name = if name == "a" { "a[0]" } else { name.as_str() };
+                assert_eq!(correspondence[dag_name], *id);
+            }
+            assert_eq!(correspondence["a[1]"], correspondence["a[0]"] + 1);
+        }
+    }
+
+    #[test]
+    fn signal_count_matches_the_declarations() {
+        let exe = template();
+        let expected = exe.number_of_declared_signals();
+        assert_eq!(expected, 5);
+        let naming = HeaderNaming::Sequential;
+        let instance = exe.export_to_circuit(&[], SignalOrder::TypeGrouped, naming);
+        assert!(check_signal_count(expected, &instance).is_ok());
+        let report = check_signal_count(expected + 1, &instance).err().unwrap();
+        assert!(report.get_message().contains("T_0"));
+    }
+}
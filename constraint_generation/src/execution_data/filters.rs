@@ -1,28 +1,31 @@
 use super::analysis::Analysis;
+use num_bigint::BigInt;
 use program_structure::ast::*;
 
-pub fn apply_unused(stmt: &mut Statement, analysis: &Analysis) {
-    clean_dead_code(stmt, analysis);
+pub fn apply_unused(stmt: &mut Statement, analysis: &Analysis, field: &BigInt) {
+    clean_dead_code(stmt, analysis, field);
 }
 
-fn clean_dead_code(stmt: &mut Statement, analysis: &Analysis) -> bool {
+fn clean_dead_code(stmt: &mut Statement, analysis: &Analysis, field: &BigInt) -> bool {
     use circom_algebra::modular_arithmetic::as_bool;
     use Statement::*;
     match stmt {
-        While { stmt, .. } => clean_dead_code(stmt, analysis),
+        While { stmt, .. } => clean_dead_code(stmt, analysis, field),
         IfThenElse { if_case, else_case, cond, meta } => {
-            let field = program_structure::constants::UsefulConstants::new().get_p().clone();
             let empty_block = Box::new(Block { meta: meta.clone(), stmts: vec![] });
-            let if_case_empty = clean_dead_code(if_case, analysis);
-            let else_case_empty =
-                if let Some(case) = else_case { clean_dead_code(case, analysis) } else { true };
+            let if_case_empty = clean_dead_code(if_case, analysis, field);
+            let else_case_empty = if let Some(case) = else_case {
+                clean_dead_code(case, analysis, field)
+            } else {
+                true
+            };
             if else_case_empty {
                 *else_case = None;
             }
 
             match Analysis::read_computed(analysis, cond.get_meta().elem_id) {
-                Some(val) if as_bool(&val, &field) => *stmt = *if_case.clone(),
-                Some(val) if !as_bool(&val, &field) => {
+                Some(val) if as_bool(&val, field) => *stmt = *if_case.clone(),
+                Some(val) if !as_bool(&val, field) => {
                     *stmt = *else_case.clone().unwrap_or(empty_block)
                 }
                 _ => {}
@@ -34,7 +37,7 @@ fn clean_dead_code(stmt: &mut Statement, analysis: &Analysis) -> bool {
             for mut w in work {
                 let id = w.get_meta().elem_id;
                 if Analysis::is_reached(analysis, id) {
-                    let empty = clean_dead_code(&mut w, analysis);
+                    let empty = clean_dead_code(&mut w, analysis, field);
                     if !empty {
                         stmts.push(w)
                     }
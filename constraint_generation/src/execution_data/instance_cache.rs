@@ -0,0 +1,205 @@
+use super::executed_template::{ExecutedTemplate, SignalOrder};
+use crate::execute::ExecutionFlags;
+use dag::{Edge, Node};
+use num_bigint::BigInt;
+use program_structure::ast::{Access, Expression, Statement, VariableType};
+use program_structure::program_archive::ProgramArchive;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::mem::discriminant;
+
+pub type InstanceHash = u64;
+
+// Dag nodes of a previous build, indexed by the instance name
+#[derive(Default)]
+pub struct InstanceCache {
+    pub hashes: HashMap<String, InstanceHash>,
+    nodes: HashMap<String, (Node, Vec<Edge>)>,
+    hits: Vec<String>,
+}
+
+impl InstanceCache {
+    pub fn new() -> InstanceCache {
+        InstanceCache::default()
+    }
+
+    // Instances whose dag node was reused by the last build
+    pub fn hits(&self) -> &[String] {
+        &self.hits
+    }
+
+    pub(crate) fn start_build(&mut self) {
+        self.hits.clear();
+    }
+
+    pub(crate) fn lookup(&mut self, name: &str, hash: InstanceHash) -> Option<(Node, Vec<Edge>)> {
+        if self.hashes.get(name) != Option::Some(&hash) {
+            return Option::None;
+        }
+        let cached = self.nodes.get(name).cloned();
+        if cached.is_some() {
+            self.hits.push(name.to_string());
+        }
+        cached
+    }
+
+    pub(crate) fn store(&mut self, name: &str, hash: InstanceHash, node: Node, edges: Vec<Edge>) {
+        self.hashes.insert(name.to_string(), hash);
+        self.nodes.insert(name.to_string(), (node, edges));
+    }
+}
+
+// Hash of the template name, the parameters and the executed code, with
+// the bodies of the functions it calls
+pub fn content_hash(template: &ExecutedTemplate, program: &ProgramArchive) -> InstanceHash {
+    let mut hasher = CodeHasher { state: DefaultHasher::new(), calls: Vec::new() };
+    template.template_name.hash(&mut hasher.state);
+    let mut parameters: Vec<_> = template.parameter_instances.iter().collect();
+    parameters.sort_by(|l, r| l.0.cmp(r.0));
+    for (name, value) in parameters {
+        name.hash(&mut hasher.state);
+        value.to_string().hash(&mut hasher.state);
+    }
+    hash_statement(&template.code, &mut hasher);
+    // Every function is hashed once, recursive calls included
+    let mut visited = HashSet::new();
+    while let Option::Some(function) = hasher.calls.pop() {
+        if program.contains_function(&function) && visited.insert(function.clone()) {
+            function.hash(&mut hasher.state);
+            hash_statement(program.get_function_data(&function).get_body(), &mut hasher);
+        }
+    }
+    hasher.state.finish()
+}
+
+// How the node of an instance is laid out in the dag
+#[derive(Copy, Clone)]
+pub struct NodeKey {
+    pub id: usize,
+    pub order: SignalOrder,
+    // Signals and components sorted by name
+    pub deterministic: bool,
+}
+
+// The node of an instance also depends on its layout, the execution flags
+// that change its constraints, the field and the nodes of its subcomponents
+pub fn node_hash(
+    template: &ExecutedTemplate,
+    content: InstanceHash,
+    key: NodeKey,
+    flags: &ExecutionFlags,
+    field: &BigInt,
+    sub_hashes: &[InstanceHash],
+) -> InstanceHash {
+    let mut state = DefaultHasher::new();
+    content.hash(&mut state);
+    key.id.hash(&mut state);
+    discriminant(&key.order).hash(&mut state);
+    key.deterministic.hash(&mut state);
+    flags.assert_as_constraint.hash(&mut state);
+    discriminant(&flags.constant_division_mode).hash(&mut state);
+    discriminant(&flags.unconnected_input_policy).hash(&mut state);
+    field.hash(&mut state);
+    template.is_parallel.hash(&mut state);
+    let mut public: Vec<_> = template.public_inputs.iter().collect();
+    public.sort();
+    public.hash(&mut state);
+    sub_hashes.hash(&mut state);
+    state.finish()
+}
+
+// The names of the called functions are collected while hashing the code
+struct CodeHasher {
+    state: DefaultHasher,
+    calls: Vec<String>,
+}
+
+fn hash_statement(stmt: &Statement, hasher: &mut CodeHasher) {
+    use Statement::*;
+    discriminant(stmt).hash(&mut hasher.state);
+    match stmt {
+        IfThenElse { cond, if_case, else_case, .. } => {
+            hash_expression(cond, hasher);
+            hash_statement(if_case, hasher);
+            if let Option::Some(case) = else_case {
+                hash_statement(case, hasher);
+            }
+        }
+        While { cond, stmt, .. } => {
+            hash_expression(cond, hasher);
+            hash_statement(stmt, hasher);
+        }
+        Return { value, .. } => hash_expression(value, hasher),
+        InitializationBlock { xtype, initializations, .. } => {
+            hash_type(xtype, hasher);
+            initializations.iter().for_each(|s| hash_statement(s, hasher));
+        }
+        Declaration { xtype, name, dimensions, is_constant, .. } => {
+            hash_type(xtype, hasher);
+            name.hash(&mut hasher.state);
+            dimensions.iter().for_each(|e| hash_expression(e, hasher));
+            is_constant.hash(&mut hasher.state);
+        }
+        Substitution { var, access, op, rhe, .. } => {
+            var.hash(&mut hasher.state);
+            access.iter().for_each(|a| hash_access(a, hasher));
+            discriminant(op).hash(&mut hasher.state);
+            hash_expression(rhe, hasher);
+        }
+        ConstraintEquality { lhe, rhe, .. } => {
+            hash_expression(lhe, hasher);
+            hash_expression(rhe, hasher);
+        }
+        LogCall { arg, .. } | Assert { arg, .. } => hash_expression(arg, hasher),
+        Block { stmts, .. } => stmts.iter().for_each(|s| hash_statement(s, hasher)),
+    }
+}
+
+fn hash_expression(expr: &Expression, hasher: &mut CodeHasher) {
+    use Expression::*;
+    discriminant(expr).hash(&mut hasher.state);
+    match expr {
+        InfixOp { lhe, infix_op, rhe, .. } => {
+            hash_expression(lhe, hasher);
+            discriminant(infix_op).hash(&mut hasher.state);
+            hash_expression(rhe, hasher);
+        }
+        PrefixOp { prefix_op, rhe, .. } => {
+            discriminant(prefix_op).hash(&mut hasher.state);
+            hash_expression(rhe, hasher);
+        }
+        InlineSwitchOp { cond, if_true, if_false, .. } => {
+            hash_expression(cond, hasher);
+            hash_expression(if_true, hasher);
+            hash_expression(if_false, hasher);
+        }
+        Variable { name, access, .. } => {
+            name.hash(&mut hasher.state);
+            access.iter().for_each(|a| hash_access(a, hasher));
+        }
+        Number(_, value) => value.hash(&mut hasher.state),
+        Call { id, args, .. } => {
+            id.hash(&mut hasher.state);
+            hasher.calls.push(id.clone());
+            args.iter().for_each(|e| hash_expression(e, hasher));
+        }
+        ArrayInLine { values, .. } => values.iter().for_each(|e| hash_expression(e, hasher)),
+    }
+}
+
+fn hash_access(access: &Access, hasher: &mut CodeHasher) {
+    discriminant(access).hash(&mut hasher.state);
+    match access {
+        Access::ComponentAccess(name) => name.hash(&mut hasher.state),
+        Access::ArrayAccess(index) => hash_expression(index, hasher),
+    }
+}
+
+fn hash_type(xtype: &VariableType, hasher: &mut CodeHasher) {
+    discriminant(xtype).hash(&mut hasher.state);
+    if let VariableType::Signal(signal_type, element_type) = xtype {
+        discriminant(signal_type).hash(&mut hasher.state);
+        discriminant(element_type).hash(&mut hasher.state);
+    }
+}
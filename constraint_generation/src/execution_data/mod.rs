@@ -5,7 +5,9 @@ pub use executed_template::ExecutedTemplate;
 pub use type_definitions::NodePointer;
 
 pub mod analysis;
+pub mod exe_cache;
 pub mod executed_program;
 pub mod executed_template;
 mod filters;
+pub mod instance_cache;
 pub mod type_definitions;
@@ -1,5 +1,6 @@
 use super::AExpressionSlice;
 use super::Constraint as ConstraintGen;
+use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 pub type NodePointer = usize;
@@ -7,6 +8,10 @@ pub type Constraint = ConstraintGen<String>;
 pub type ParameterContext = HashMap<String, AExpressionSlice>;
 pub type SignalCollector = Vec<(String, Vec<usize>)>;
 pub type ComponentCollector = Vec<(String, Vec<usize>)>;
+// Sees each constraint added to the dag, over the signals of the dag, along
+// with the name of the template that owns it
+pub type ConstraintVisitor = Box<dyn FnMut(&str, &ConstraintGen<usize>)>;
+#[derive(Serialize, Deserialize)]
 pub struct SubComponentData {
     pub name: String,
     pub indexed_with: Vec<usize>,
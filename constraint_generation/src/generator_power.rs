@@ -0,0 +1,145 @@
+use circom_algebra::num_bigint::BigInt;
+use program_structure::ast::{Expression, ExpressionInfixOpcode, Meta, Statement};
+use program_structure::ast_walker::{walk_expression_mut, walk_expressions_mut, walk_statements};
+use program_structure::constants::UsefulConstants;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::program_archive::ProgramArchive;
+
+// `generator ** k` denotes a power of the generator of the multiplicative
+// group of the field, as in roots of unity for FFT based gadgets. The base is
+// replaced by the generator before the analysis, so the rest of the compiler
+// only sees a number. Templates and functions with their own symbol named
+// generator keep it.
+pub const GENERATOR: &str = "generator";
+
+fn is_generator(expr: &Expression) -> bool {
+    match expr {
+        Expression::Variable { name, access, .. } => name == GENERATOR && access.is_empty(),
+        _ => false,
+    }
+}
+
+// Replaces the base of expr when it is a generator power
+fn rewrite(expr: &mut Expression, generator: &Option<BigInt>, found: &mut Vec<Meta>) {
+    if let Expression::InfixOp { lhe, infix_op: ExpressionInfixOpcode::Pow, .. } = expr {
+        if is_generator(lhe) {
+            let meta = lhe.get_meta().clone();
+            if let Option::Some(value) = generator {
+                **lhe = Expression::Number(meta.clone(), value.clone());
+            }
+            found.push(meta);
+        }
+    }
+}
+
+fn declares_generator(stmt: &Statement) -> bool {
+    let mut declares = false;
+    walk_statements(stmt, &mut |stmt| {
+        if let Statement::Declaration { name, .. } = stmt {
+            declares |= name == GENERATOR;
+        }
+    });
+    declares
+}
+
+// Returns the places where the form was found
+fn rewrite_body(body: &mut Statement, params: &[String], generator: &Option<BigInt>) -> Vec<Meta> {
+    let mut found = Vec::new();
+    if !params.iter().any(|p| p == GENERATOR) && !declares_generator(body) {
+        walk_expressions_mut(body, &mut |expr| rewrite(expr, generator, &mut found));
+    }
+    found
+}
+
+fn unknown_generator(meta: &Meta, p: &BigInt) -> Report {
+    let msg = format!("No generator is known for the field of order {}", p);
+    let mut report = Report::error(msg, ReportCode::UnknownFieldGenerator);
+    report.add_primary(
+        meta.file_location(),
+        meta.get_file_id(),
+        "generator powers need a generator for the configured prime".to_string(),
+    );
+    report
+}
+
+pub fn resolve_generator_powers(
+    program: &mut ProgramArchive,
+    constants: &UsefulConstants,
+) -> Result<(), ReportCollection> {
+    let generator = constants.get_generator();
+    let mut found = Vec::new();
+    for template in program.get_mut_templates().values_mut() {
+        let params = template.get_name_of_params().clone();
+        found.append(&mut rewrite_body(template.get_mut_body(), &params, &generator));
+    }
+    for function in program.get_mut_functions().values_mut() {
+        let params = function.get_name_of_params().clone();
+        found.append(&mut rewrite_body(function.get_mut_body(), &params, &generator));
+    }
+    let call = &mut program.initial_template_call;
+    walk_expression_mut(call, &mut |expr| rewrite(expr, &generator, &mut found));
+    if generator.is_none() && !found.is_empty() {
+        Result::Err(found.iter().map(|meta| unknown_generator(meta, constants.get_p())).collect())
+    } else {
+        Result::Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use program_structure::ast::{AssignOp, VariableType};
+    use program_structure::expression_builders::{build_infix, build_number, build_variable};
+    use program_structure::statement_builders::{build_block, build_declaration, build_substitution};
+
+    fn meta() -> Meta {
+        Meta::new(0, 0)
+    }
+
+    // var w; w = generator ** 3;
+    fn root_of_unity() -> Statement {
+        let generator = build_variable(meta(), GENERATOR.to_string(), vec![]);
+        let power = build_infix(
+            meta(),
+            generator,
+            ExpressionInfixOpcode::Pow,
+            build_number(meta(), BigInt::from(3)),
+        );
+        let assignment =
+            build_substitution(meta(), "w".to_string(), vec![], AssignOp::AssignVar, power);
+        let declaration = build_declaration(meta(), VariableType::Var, "w".to_string(), vec![]);
+        build_block(meta(), vec![declaration, assignment])
+    }
+
+    fn assigned_base(body: &Statement) -> Option<BigInt> {
+        if let Statement::Block { stmts, .. } = body {
+            if let Statement::Substitution { rhe: Expression::InfixOp { lhe, .. }, .. } = &stmts[1]
+            {
+                if let Expression::Number(_, value) = &**lhe {
+                    return Option::Some(value.clone());
+                }
+            }
+        }
+        Option::None
+    }
+
+    #[test]
+    fn bn128_generator_powers_are_resolved() {
+        let generator = UsefulConstants::new().get_generator();
+        let mut body = root_of_unity();
+        assert_eq!(rewrite_body(&mut body, &[], &generator).len(), 1);
+        assert_eq!(assigned_base(&body), Option::Some(BigInt::from(5)));
+        // A parameter named generator shadows the field generator
+        let mut shadowed = root_of_unity();
+        assert!(rewrite_body(&mut shadowed, &[GENERATOR.to_string()], &generator).is_empty());
+        assert_eq!(assigned_base(&shadowed), Option::None);
+    }
+
+    #[test]
+    fn unknown_generators_are_found_but_not_replaced() {
+        let mut body = root_of_unity();
+        assert_eq!(rewrite_body(&mut body, &[], &Option::None).len(), 1);
+        assert_eq!(assigned_base(&body), Option::None);
+    }
+}
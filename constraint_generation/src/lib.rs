@@ -1,27 +1,57 @@
 extern crate num_bigint_dig as num_bigint;
 extern crate num_traits;
 
+pub mod alloc_stats;
+mod categories;
 mod compute_constants;
 mod environment_utils;
 mod execute;
 mod execution_data;
+mod generator_power;
+mod main_check;
+mod naming_check;
+mod pragma_check;
+mod unused_parameters;
 
+use alloc_stats::{AllocStats, PhaseStats};
 use ansi_term::Colour;
 use circom_algebra::algebra::{ArithmeticError, ArithmeticExpression};
 use compiler::hir::very_concrete_program::VCP;
-use constraint_list::ConstraintList;
+use constraint_list::{
+    BellmanExporter, ConstraintList, Matrices, SimplificationError,
+};
+use constraint_writers::counts_writer::CircuitCounts;
+use constraint_writers::json_writer::SubstitutionJSON;
+use constraint_writers::manifest_writer::SignalManifest;
+use constraint_writers::summary_writer::CircuitSummary;
 use constraint_writers::ConstraintExporter;
 use dag::DAG;
-use execution_data::executed_program::ExportResult;
+pub use execute::{DivisionMode, UnconnectedInputPolicy};
+use execute::ExecutionFlags;
+use execution_data::executed_program::{ExportFlags, ExportResult};
+pub use execution_data::executed_program::TemplateCost;
+pub use execution_data::instance_cache::{InstanceCache, InstanceHash};
+pub use execution_data::type_definitions::ConstraintVisitor;
+use execution_data::exe_cache;
 use execution_data::ExecutedProgram;
+pub use execution_data::executed_template::{HeaderNaming, SignalOrder};
+pub use generator_power::resolve_generator_powers;
+pub use main_check::{override_main, validate_main};
+pub use pragma_check::PragmaPolicy;
 use program_structure::ast::{self};
-use program_structure::constants::UsefulConstants;
+use num_bigint::BigInt;
+use program_structure::constants::{is_probable_prime, UsefulConstants};
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::{Report, ReportCollection};
-use program_structure::file_definition::FileID;
+use program_structure::file_definition::{FileID, FileLibrary};
 use program_structure::program_archive::ProgramArchive;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::cell::RefCell;
 use std::rc::Rc;
 
+#[derive(Default)]
 pub struct BuildConfig {
     pub no_rounds: usize,
     pub flag_json_sub: bool,
@@ -29,64 +59,2112 @@ pub struct BuildConfig {
     pub flag_f: bool,
     pub flag_p: bool,
     pub inspect_constraints: bool,
+    pub flag_alloc_stats: bool,
+    // The wall-clock times are left out of the printed allocation stats, so
+    // two runs print the same. phase_stats still has them
+    pub flag_no_timing: bool,
+    pub flag_assert_as_constraint: bool,
+    pub flag_suggest: bool,
+    pub flag_trigger_order: bool,
+    pub flag_emit_counts_json: bool,
+    pub counts_json: String,
+    pub unknown_pragma_policy: PragmaPolicy,
+    pub flag_constraints_by_template: bool,
+    pub flag_wtns_dag: bool,
+    pub wtns_dag: String,
+    pub flag_bisect: bool,
+    pub signal_order: SignalOrder,
+    pub flag_check_signal_count: bool,
+    pub compare_against: Option<PathBuf>,
+    pub flag_template_ranges: bool,
+    pub public_signal_pattern: Option<String>,
+    pub flag_werror: bool,
+    pub flag_check_witness_safety: bool,
+    // One warning listing the signals of the simplified circuit that appear
+    // in no constraint, not available with flag_f
+    pub flag_unconstrained_report: bool,
+    pub flag_callgraph: bool,
+    pub callgraph: String,
+    // Graph of the template instances, in the dot format
+    pub flag_components_dot: bool,
+    pub components_dot: String,
+    pub flag_mult_depth: bool,
+    pub flag_keep_intermediates: bool,
+    pub intermediates: String,
+    pub flag_constraints_by_file: bool,
+    pub constant_division_mode: DivisionMode,
+    pub flag_symbolic_witness: bool,
+    pub symbolic_witness: String,
+    pub symbolic_witness_limit: usize,
+    pub unconnected_input_policy: UnconnectedInputPolicy,
+    pub flag_dof: bool,
+    pub export_chunk_size: usize,
+    pub flag_verify_parallel: bool,
+    pub flag_signal_cycles: bool,
+    pub max_instances: Option<usize>,
+    pub flag_r1cs_info: bool,
+    pub flag_constraints_by_category: bool,
+    pub exporter_kind: ExporterKind,
+    pub flag_no_simplification: bool,
+    pub flag_sym_constraints: bool,
+    pub sym_constraints: String,
+    pub threads: Option<usize>,
+    // Where circuit_summary.json is written, no summary when None
+    pub summary_path: Option<PathBuf>,
+    // Field of the circuit instead of bn128, it must be a prime
+    pub custom_prime: Option<BigInt>,
+    // Dag nodes of previous builds, unchanged instances are not rebuilt
+    pub instance_cache: Option<Rc<RefCell<InstanceCache>>>,
+    pub flag_deterministic: bool,
+    // Source locations of the constraints in the r1cs. A constraint derived
+    // by the simplification has the unknown location
+    pub flag_r1cs_spans: bool,
+    // Removes the constraints that repeat another one up to a factor
+    pub flag_dedup_constraints: bool,
+    // The constraints are written scaled to a canonical representative, so
+    // equivalent circuits produce the same r1cs
+    pub flag_normalize: bool,
+    // Wire w of the constraint list is written as wire_permutation[w], not
+    // available with flag_f where the r1cs is written from the dag
+    pub wire_permutation: Option<Vec<usize>>,
+    // Filled with the stats of the main phases of the build, the phases the
+    // build did not run are missing (the simplification is skipped by flag_f)
+    pub phase_stats: Option<Rc<RefCell<Vec<PhaseStats>>>>,
+    // Paths of signals (as main.sub.x) written as public outputs, they are
+    // kept by the simplification. Not available with flag_f
+    pub promoted_signals: Vec<String>,
+    // The r1cs is also written as <r1cs_shards>_<i>.r1cs files of at most
+    // shard_size constraints, with the manifest <r1cs_shards>.json
+    pub shard_size: Option<usize>,
+    pub r1cs_shards: String,
+    // The build fails when the final circuit has more non linear constraints
+    pub max_non_linear: Option<usize>,
+    // Receives the messages and reports of the build in place of stdout
+    pub reporter: Option<Reporter>,
+    // Called with every constraint as it is added to the dag, before the
+    // simplification
+    pub constraint_visitor: Option<ConstraintVisitor>,
+    // The executed program is read from this file when it was written for
+    // the same source and flags, otherwise the execution writes it
+    pub exe_cache: Option<PathBuf>,
+    // Names of the instance headers in the generated witness code
+    pub header_naming: HeaderNaming,
+    // Template and parameters compiled as main in place of the declared one
+    pub main_override: Option<(String, Vec<BigInt>)>,
+    // Fewer allocations when naming the signals of large arrays, the names
+    // and the sym file do not change
+    pub flag_intern_signal_names: bool,
+    // The constraint list records the provenance of its constraints, see
+    // ConstraintList::provenance
+    pub flag_provenance: bool,
+}
+
+// Layout of the file written as the r1cs output
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum ExporterKind {
+    #[default]
+    Circom,
+    Bellman,
 }
 
 pub type ConstraintWriter = Box<dyn ConstraintExporter>;
 type BuildResponse = Result<(ConstraintWriter, VCP), ()>;
 pub fn build_circuit(program: ProgramArchive, config: BuildConfig) -> BuildResponse {
+    build(program, config, &mut Option::None)
+}
+
+// Same build, but the warnings are returned instead of printed. The errors
+// are still printed, the build stops at the first one
+pub fn build_circuit_with_reports(
+    program: ProgramArchive,
+    config: BuildConfig,
+) -> Result<(ConstraintWriter, VCP, ReportCollection), ()> {
+    let mut collected = Option::Some(ReportCollection::new());
+    let (exporter, vcp) = build(program, config, &mut collected)?;
+    Result::Ok((exporter, vcp, collected.unwrap_or_default()))
+}
+
+// The warnings are printed as they are found unless the caller collects them
+fn emit(
+    reports: ReportCollection,
+    files: &FileLibrary,
+    collected: &mut Option<ReportCollection>,
+    reporter: &mut Option<Reporter>,
+) {
+    match collected {
+        Option::Some(collected) => collected.extend(reports),
+        Option::None => notify(reporter, files, ReportEvent::Reports(reports)),
+    }
+}
+
+// Messages of a build, printed to stdout unless the config has a reporter
+pub enum ReportEvent {
+    // Printed as "title: message"
+    Info { title: String, message: String },
+    // A line of the listings asked by the flags, printed as is
+    Line { style: LineStyle, text: String },
+    Reports(ReportCollection),
+}
+pub type Reporter = Box<dyn FnMut(ReportEvent)>;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LineStyle {
+    Plain,
+    Heading,
+    Warning,
+    Failure,
+}
+
+impl LineStyle {
+    fn paint(self, text: String) -> String {
+        match self {
+            LineStyle::Plain => text,
+            LineStyle::Heading => Colour::Green.paint(text).to_string(),
+            LineStyle::Warning => Colour::Yellow.paint(text).to_string(),
+            LineStyle::Failure => Colour::Red.paint(text).to_string(),
+        }
+    }
+}
+
+fn notify(reporter: &mut Option<Reporter>, files: &FileLibrary, event: ReportEvent) {
+    match (reporter, event) {
+        (_, ReportEvent::Reports(reports)) if reports.is_empty() => {}
+        (reporter, ReportEvent::Line { style, text }) => print_line(reporter, style, text),
+        (Option::Some(reporter), event) => reporter(event),
+        (Option::None, ReportEvent::Info { title, message }) => {
+            println!("{}: {}", Colour::Green.paint(title), message)
+        }
+        (Option::None, ReportEvent::Reports(reports)) => Report::print_reports(&reports, files),
+    }
+}
+
+// The lines do not refer to the files, the helpers that print them do not get them
+fn print_line(reporter: &mut Option<Reporter>, style: LineStyle, text: String) {
+    match reporter {
+        Option::Some(reporter) => reporter(ReportEvent::Line { style, text }),
+        Option::None => println!("{}", style.paint(text)),
+    }
+}
+
+fn print_written(reporter: &mut Option<Reporter>, file: &str) {
+    let (title, message) = ("Written successfully".to_string(), file.to_string());
+    match reporter {
+        Option::Some(reporter) => reporter(ReportEvent::Info { title, message }),
+        Option::None => println!("{} {}", Colour::Green.paint(title + ":"), message),
+    }
+}
+
+fn print_write_failure(reporter: &mut Option<Reporter>) {
+    let msg = "Could not write the output in the given path".to_string();
+    print_line(reporter, LineStyle::Failure, msg);
+}
+
+fn build(
+    mut program: ProgramArchive,
+    mut config: BuildConfig,
+    collected: &mut Option<ReportCollection>,
+) -> BuildResponse {
+    if let Option::Some((template, params)) = &config.main_override {
+        override_main(&mut program, template, params);
+    }
     let files = program.file_library.clone();
-    let exe = instantiation(&program).map_err(|r| {
-        Report::print_reports(&r, &files);
+    let mut reporter = config.reporter.take();
+    validate_main(&program).map_err(|r| {
+        notify(&mut reporter, &files, ReportEvent::Reports(vec![r]));
     })?;
-    let (mut dag, mut vcp, warnings) = export(exe, program).map_err(|r| {
-        Report::print_reports(&r, &files);
+    let (template_categories, pragmas) = categories::template_categories(&program.unknown_pragmas);
+    let pragma_reports = pragma_check::check_unknown_pragmas(&pragmas, config.unknown_pragma_policy)
+        .map_err(|r| {
+            notify(&mut reporter, &files, ReportEvent::Reports(r));
+        })?;
+    emit(pragma_reports, &files, collected, &mut reporter);
+    if config.flag_callgraph {
+        write_template_call_graph(&program, &config.callgraph, &mut reporter)?;
+    }
+    let mut alloc_stats = AllocStats::new(config.flag_alloc_stats, !config.flag_no_timing);
+    alloc_stats.start_phase("instantiation");
+    let flags = execution_flags(&config).map_err(|r| {
+        notify(&mut reporter, &files, ReportEvent::Reports(vec![r]));
+    })?;
+    let source_hash = config.exe_cache.as_ref().map(|_| exe_cache::source_hash(&program, &flags));
+    let cached = match (&config.exe_cache, source_hash) {
+        (Option::Some(file), Option::Some(hash)) => exe_cache::load(file, hash),
+        _ => Option::None,
+    };
+    let cache_hit = cached.is_some();
+    let execution = if let Option::Some(cached) = cached {
+        Result::Ok(cached)
+    } else if config.flag_bisect {
+        instantiation_or_bisect(&program, flags.clone(), &mut reporter)
+    } else {
+        instantiation(&program, flags.clone(), &mut reporter)
+    };
+    let (exe, notes) = execution.map_err(|r| {
+        notify(&mut reporter, &files, ReportEvent::Reports(r));
+    })?;
+    if let (Option::Some(file), Option::Some(hash), false) =
+        (&config.exe_cache, source_hash, cache_hit)
+    {
+        exe_cache::store(file, hash, &exe, &notes).map_err(|_| {
+            let msg = "Could not write the executed program cache".to_string();
+            print_line(&mut reporter, LineStyle::Failure, msg);
+        })?;
+    }
+    alloc_stats.end_phase();
+    emit(notes, &files, collected, &mut reporter);
+    if let Option::Some(pattern) = &config.public_signal_pattern {
+        let main = exe.model.last().unwrap();
+        let naming = naming_check::check_public_signal_names(
+            &main.public_signals(),
+            pattern,
+            config.flag_werror,
+        );
+        let reports = naming.map_err(|r| {
+            notify(&mut reporter, &files, ReportEvent::Reports(r));
+        })?;
+        emit(reports, &files, collected, &mut reporter);
+    }
+    let unused_params = if config.flag_suggest {
+        let instantiated: BTreeSet<String> =
+            exe.model.iter().map(|t| t.template_name().clone()).collect();
+        unused_parameters::unused_parameters(&program, &instantiated)
+    } else {
+        ReportCollection::new()
+    };
+    let template_files: HashMap<String, FileID> = if config.flag_constraints_by_file {
+        let templates = program.get_templates().iter();
+        templates.map(|(name, data)| (name.clone(), data.get_file_id())).collect()
+    } else {
+        HashMap::new()
+    };
+    alloc_stats.start_phase("export");
+    let export_flags = ExportFlags {
+        signal_order: config.signal_order,
+        check_signal_count: config.flag_check_signal_count,
+        deterministic: config.flag_deterministic,
+        header_naming: config.header_naming,
+        intern_signal_names: config.flag_intern_signal_names,
+        execution: flags,
+    };
+    let mut cache = config.instance_cache.as_ref().map(|c| c.borrow_mut());
+    // The templates go away with the program in the export, the unused
+    // parameters and the files of the templates are taken before
+    let exported =
+        export(exe, program, export_flags, cache.as_deref_mut(), &mut config.constraint_visitor);
+    let (mut dag, mut vcp, warnings) = exported.map_err(|r| {
+        notify(&mut reporter, &files, ReportEvent::Reports(r));
     })?;
-    if config.inspect_constraints {
-        Report::print_reports(&warnings, &files);
+    alloc_stats.end_phase();
+    // Collected warnings include the ones of the constraints, as with --inspect
+    if config.inspect_constraints || collected.is_some() {
+        emit(warnings, &files, collected, &mut reporter);
+    }
+    if config.flag_suggest {
+        emit(unused_params, &files, collected, &mut reporter);
+        emit(dag.redundant_range_checks(), &files, collected, &mut reporter);
+    }
+    if config.flag_signal_cycles {
+        let cycles = vcp.check_signal_cycles();
+        let cyclic = !cycles.is_empty();
+        notify(&mut reporter, &files, ReportEvent::Reports(cycles));
+        if cyclic {
+            return Result::Err(());
+        }
+    }
+    if config.flag_check_witness_safety {
+        emit(vcp.check_witness_safety(), &files, collected, &mut reporter);
+    }
+    if config.flag_components_dot {
+        write_components_dot(&dag, &config.components_dot, &mut reporter)?;
+    }
+    if config.flag_trigger_order {
+        print_trigger_order(&vcp, &mut reporter);
+    }
+    if config.flag_template_ranges {
+        print_template_signal_ranges(&vcp, &mut reporter);
     }
-    if config.flag_f {
+    if config.flag_wtns_dag {
+        write_witness_dependency_graph(&vcp, &config.wtns_dag, &mut reporter)?;
+    }
+    if config.flag_symbolic_witness {
+        let limit = config.symbolic_witness_limit;
+        write_symbolic_witness(&vcp, &config.symbolic_witness, limit, &mut reporter)?;
+    }
+    // The indices of the dag are the ones of the list only without simplification
+    let unsimplified = config.flag_f || config.flag_no_simplification;
+    if config.flag_constraints_by_template && unsimplified {
+        print_constraints_by_template(&dag, &mut reporter);
+    } else if config.flag_constraints_by_template {
+        let msg = "constraints by template are only listed with --O0 or --no_simplification";
+        let report = Report::warning(msg.to_string(), ReportCode::IgnoredBuildOption);
+        emit(vec![report], &files, collected, &mut reporter);
+    }
+    if config.flag_constraints_by_file {
+        print_constraints_by_file(&dag, &template_files, &files, config.flag_f, &mut reporter);
+    }
+    if config.flag_constraints_by_category {
+        print_constraints_by_category(&dag, &template_categories, config.flag_f, &mut reporter);
+    }
+    if config.flag_sym_constraints && unsimplified {
+        write_constraint_templates(&dag, &config.sym_constraints, &mut reporter)?;
+    } else if config.flag_sym_constraints {
+        let msg = "constraint templates are only written with --O0 or --no_simplification";
+        let report = Report::warning(msg.to_string(), ReportCode::IgnoredBuildOption);
+        emit(vec![report], &files, collected, &mut reporter);
+    }
+    if config.flag_keep_intermediates {
+        let chunk_size = config.export_chunk_size;
+        write_intermediates(&dag, &vcp, &config.intermediates, chunk_size, &mut reporter)?;
+    }
+    dag.r1cs_spans = config.flag_r1cs_spans;
+    let exporter: ConstraintWriter = if config.flag_f {
+        if config.wire_permutation.is_some() || !config.promoted_signals.is_empty() {
+            let msg = "Wire permutations and promoted signals need the constraint list, \
+                       not available with --O0";
+            let report = Report::error(msg.to_string(), ReportCode::InvalidWirePermutation);
+            notify(&mut reporter, &files, ReportEvent::Reports(vec![report]));
+            return Result::Err(());
+        }
+        if let Option::Some(limit) = config.max_non_linear {
+            check_non_linear_limit(&dag.circuit_counts(), limit).map_err(|r| {
+                notify(&mut reporter, &files, ReportEvent::Reports(vec![r]));
+            })?;
+        }
         sync_dag_and_vcp(&mut vcp, &mut dag);
-        Result::Ok((Box::new(dag), vcp))
+        if config.flag_emit_counts_json || config.compare_against.is_some() {
+            let warnings = output_counts(&dag.circuit_counts(), &config, &mut reporter)?;
+            emit(warnings, &files, collected, &mut reporter);
+        }
+        if let Option::Some(summary) = &config.summary_path {
+            let manifest = dag.signal_manifest();
+            write_summary(&dag.circuit_counts(), &manifest, summary, &mut reporter)?;
+        }
+        // Nothing is simplified with --O0, the dag is the circuit that is written
+        if config.flag_normalize {
+            dag.normalize_constraints();
+        }
+        exporter_of_kind(config.exporter_kind, dag, DAG::matrices)
+    } else {
+        if config.flag_verify_parallel {
+            compare_parallelism(&dag, &config, &mut reporter).map_err(|r| {
+                notify(&mut reporter, &files, ReportEvent::Reports(vec![r]));
+            })?;
+        }
+        let substitution_log = if config.flag_keep_intermediates && !config.flag_no_simplification {
+            Option::Some(open_substitution_log(&config.intermediates, &mut reporter)?)
+        } else {
+            Option::None
+        };
+        alloc_stats.start_phase("simplification");
+        let list = simplification_process(&mut vcp, dag, &config, substitution_log);
+        let mut list = list.map_err(|r| {
+            notify(&mut reporter, &files, ReportEvent::Reports(vec![r]));
+        })?;
+        alloc_stats.end_phase();
+        if let Option::Some(limit) = config.max_non_linear {
+            check_non_linear_limit(&list.circuit_counts(), limit).map_err(|r| {
+                notify(&mut reporter, &files, ReportEvent::Reports(vec![r]));
+            })?;
+        }
+        if config.flag_emit_counts_json || config.compare_against.is_some() {
+            let warnings = output_counts(&list.circuit_counts(), &config, &mut reporter)?;
+            emit(warnings, &files, collected, &mut reporter);
+        }
+        if let Option::Some(summary) = &config.summary_path {
+            let manifest = list.signal_manifest();
+            write_summary(&list.circuit_counts(), &manifest, summary, &mut reporter)?;
+        }
+        if config.flag_mult_depth {
+            print_multiplicative_depth(&list, &files, &mut reporter);
+        }
+        if config.flag_dof {
+            print_degrees_of_freedom(&list, &mut reporter);
+        }
+        if config.flag_unconstrained_report {
+            let report = unconstrained_report(&list.unconstrained_signals());
+            emit(report.into_iter().collect(), &files, collected, &mut reporter);
+        }
+        // Once the wires are final, after the simplification and the permutation
+        if config.flag_normalize {
+            list.normalize_constraints();
+        }
+        exporter_of_kind(config.exporter_kind, list, ConstraintList::matrices)
+    };
+    if let Option::Some(shard_size) = config.shard_size {
+        write_r1cs_shards(&*exporter, &config, shard_size, &mut reporter)?;
+    }
+    if config.flag_r1cs_info {
+        for line in exporter.r1cs_header().info_lines() {
+            print_line(&mut reporter, LineStyle::Plain, line);
+        }
+    }
+    alloc_stats.print(&mut reporter);
+    if let Option::Some(phase_stats) = &config.phase_stats {
+        *phase_stats.borrow_mut() = alloc_stats.into_phases();
+    }
+    Result::Ok((exporter, vcp))
+}
+
+// Compiles a program given as a single source, the errors refer to it
+// by the name <source>. The tests always have it
+#[cfg(any(test, feature = "from_source"))]
+pub fn build_from_source(src: &str, config: BuildConfig) -> BuildResponse {
+    let parsed = parser::run_parser_from_source("<source>".to_string(), src.to_string());
+    let mut program = match parsed {
+        Result::Ok((program, warnings)) => {
+            Report::print_reports(&warnings, &program.file_library);
+            program
+        }
+        Result::Err((files, reports)) => {
+            Report::print_reports(&reports, &files);
+            return Result::Err(());
+        }
+    };
+    // The generator powers and the types are resolved before the build, as in the cli
+    let constants = match field_constants(&config.custom_prime) {
+        Result::Ok(constants) => constants,
+        Result::Err(report) => {
+            Report::print_reports(&[report], &program.file_library);
+            return Result::Err(());
+        }
+    };
+    if let Result::Err(reports) = resolve_generator_powers(&mut program, &constants) {
+        Report::print_reports(&reports, &program.file_library);
+        return Result::Err(());
+    }
+    match type_analysis::check_types::check_types(&mut program) {
+        Result::Ok(warnings) => Report::print_reports(&warnings, &program.file_library),
+        Result::Err(errors) => {
+            Report::print_reports(&errors, &program.file_library);
+            return Result::Err(());
+        }
+    }
+    build_circuit(program, config)
+}
+
+fn exporter_of_kind<E: ConstraintExporter + 'static>(
+    kind: ExporterKind,
+    exporter: E,
+    matrices: fn(&E) -> Matrices,
+) -> ConstraintWriter {
+    match kind {
+        ExporterKind::Circom => Box::new(exporter),
+        ExporterKind::Bellman => Box::new(BellmanExporter::new(exporter, matrices)),
+    }
+}
+
+// Returns the warnings of the comparison with the baseline
+fn output_counts(
+    counts: &CircuitCounts,
+    config: &BuildConfig,
+    reporter: &mut Option<Reporter>,
+) -> Result<ReportCollection, ()> {
+    if config.flag_emit_counts_json {
+        write_counts(counts, &config.counts_json, reporter)?;
+    }
+    match &config.compare_against {
+        Option::Some(baseline) => Result::Ok(compare_counts(counts, baseline, reporter)),
+        Option::None => Result::Ok(ReportCollection::new()),
+    }
+}
+
+// A missing or malformed baseline is not an error of the build, only a warning
+fn compare_counts(
+    counts: &CircuitCounts,
+    baseline: &Path,
+    reporter: &mut Option<Reporter>,
+) -> ReportCollection {
+    let baseline = match baseline.to_str().map(CircuitCounts::read) {
+        Option::Some(Result::Ok(baseline)) => baseline,
+        _ => {
+            let msg = format!("could not read the counts baseline {}", baseline.display());
+            return vec![Report::warning(msg, ReportCode::UnreadableCountsBaseline)];
+        }
+    };
+    let title = "counts compared with the baseline:".to_string();
+    print_line(reporter, LineStyle::Heading, title);
+    for delta in counts.diff(&baseline) {
+        let line = format!(
+            "  {}: {} -> {} ({:+})",
+            delta.metric,
+            delta.baseline,
+            delta.current,
+            delta.difference()
+        );
+        let style = if delta.is_regression() { LineStyle::Failure } else { LineStyle::Plain };
+        print_line(reporter, style, line);
+    }
+    ReportCollection::new()
+}
+
+fn write_counts(
+    counts: &CircuitCounts,
+    file: &str,
+    reporter: &mut Option<Reporter>,
+) -> Result<(), ()> {
+    if let Result::Ok(()) = counts.write(file) {
+        print_written(reporter, file);
+        Result::Ok(())
     } else {
-        let list = simplification_process(&mut vcp, dag, &config);
-        Result::Ok((Box::new(list), vcp))
+        print_write_failure(reporter);
+        Result::Err(())
     }
 }
 
-type InstantiationResponse = Result<ExecutedProgram, ReportCollection>;
-fn instantiation(program: &ProgramArchive) -> InstantiationResponse {
-    let execution_result = execute::constraint_execution(&program);
+fn write_summary(
+    counts: &CircuitCounts,
+    manifest: &SignalManifest,
+    file: &Path,
+    reporter: &mut Option<Reporter>,
+) -> Result<(), ()> {
+    let summary = CircuitSummary::new(counts, manifest);
+    if let Option::Some(Result::Ok(())) = file.to_str().map(|f| summary.write(f)) {
+        print_written(reporter, &file.display().to_string());
+        Result::Ok(())
+    } else {
+        print_write_failure(reporter);
+        Result::Err(())
+    }
+}
+
+fn field_constants(custom_prime: &Option<BigInt>) -> Result<UsefulConstants, Report> {
+    match custom_prime {
+        Option::None => Result::Ok(UsefulConstants::new()),
+        Option::Some(p) if is_probable_prime(p) => {
+            Result::Ok(UsefulConstants::with_prime(p.clone()))
+        }
+        Option::Some(p) => {
+            let msg = format!("The custom prime {} is not a prime number", p);
+            Result::Err(Report::error(msg, ReportCode::InvalidCustomPrime))
+        }
+    }
+}
+
+type InstantiationResponse = Result<(ExecutedProgram, ReportCollection), ReportCollection>;
+fn instantiation(
+    program: &ProgramArchive,
+    flags: ExecutionFlags,
+    reporter: &mut Option<Reporter>,
+) -> InstantiationResponse {
+    let execution_result = execute::constraint_execution(&program, flags);
     match execution_result {
-        Ok(program_exe) => {
-            let no_nodes = program_exe.number_of_nodes();
-            let success = Colour::Green.paint("template instances");
-            let nodes_created = format!("{}: {}", success, no_nodes);
-            println!("{}", &nodes_created);
-            InstantiationResponse::Ok(program_exe)
+        Ok((program_exe, notes)) => {
+            let title = "template instances".to_string();
+            let message = program_exe.number_of_nodes().to_string();
+            notify(reporter, &program.file_library, ReportEvent::Info { title, message });
+            InstantiationResponse::Ok((program_exe, notes))
         }
         Err(reports) => InstantiationResponse::Err(reports),
     }
 }
 
-fn export(exe: ExecutedProgram, program: ProgramArchive) -> ExportResult {
-    let exported = exe.export(program);
+fn instantiation_or_bisect(
+    program: &ProgramArchive,
+    flags: ExecutionFlags,
+    reporter: &mut Option<Reporter>,
+) -> InstantiationResponse {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    let attempt = AssertUnwindSafe(|| instantiation(program, flags.clone(), reporter));
+    let reports = match catch_unwind(attempt) {
+        Result::Ok(Result::Ok(response)) => return InstantiationResponse::Ok(response),
+        Result::Ok(Result::Err(reports)) => reports,
+        Result::Err(payload) => vec![panic_report(payload)],
+    };
+    print_line(reporter, LineStyle::Warning, "bisecting the failed instantiation...".to_string());
+    let line = match execute::bisect_failure(program, flags) {
+        Option::Some((instance, true)) => {
+            format!("the failure is reproduced by instantiating {} on its own", instance)
+        }
+        Option::Some((instance, false)) => {
+            format!("{} only fails when it is instantiated from its parents", instance)
+        }
+        Option::None => "the failure does not happen inside a template instantiation".to_string(),
+    };
+    print_line(reporter, LineStyle::Plain, line);
+    InstantiationResponse::Err(reports)
+}
+
+// A panic of the instantiation is reported as any other error
+fn panic_report(payload: Box<dyn std::any::Any + Send>) -> Report {
+    let cause = match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Option::Some(cause), _) => cause.to_string(),
+        (_, Option::Some(cause)) => cause.clone(),
+        _ => "unknown cause".to_string(),
+    };
+    let msg = format!("The instantiation panicked: {}", cause);
+    Report::error(msg, ReportCode::InternalCompilerError)
+}
+
+fn export(
+    exe: ExecutedProgram,
+    program: ProgramArchive,
+    flags: ExportFlags,
+    cache: Option<&mut InstanceCache>,
+    visitor: &mut Option<ConstraintVisitor>,
+) -> ExportResult {
+    let exported = exe.export(program, flags, cache, visitor);
     exported
 }
 
+fn print_trigger_order(vcp: &VCP, reporter: &mut Option<Reporter>) {
+    print_line(reporter, LineStyle::Heading, "component trigger order:".to_string());
+    for path in vcp.trigger_order() {
+        print_line(reporter, LineStyle::Plain, format!("  {}", path));
+    }
+}
+
+fn print_multiplicative_depth(
+    list: &ConstraintList,
+    files: &FileLibrary,
+    reporter: &mut Option<Reporter>,
+) {
+    match list.multiplicative_depth() {
+        Result::Ok(depth) => {
+            print_line(reporter, LineStyle::Plain, format!("multiplicative depth: {}", depth))
+        }
+        Result::Err(report) => notify(reporter, files, ReportEvent::Reports(vec![report])),
+    }
+}
+
+fn print_degrees_of_freedom(list: &ConstraintList, reporter: &mut Option<Reporter>) {
+    let dof = list.degrees_of_freedom();
+    let no_inputs = list.no_public_inputs + list.no_private_inputs;
+    let line = format!("degrees of freedom: {} (inputs: {})", dof, no_inputs);
+    print_line(reporter, LineStyle::Plain, line);
+    if dof > no_inputs {
+        let warning = "more free signals than inputs, the circuit may be underconstrained";
+        print_line(reporter, LineStyle::Warning, warning.to_string());
+    }
+}
+
+fn print_template_signal_ranges(vcp: &VCP, reporter: &mut Option<Reporter>) {
+    print_line(reporter, LineStyle::Heading, "signals per template instance:".to_string());
+    let mut ranges: Vec<_> = vcp.template_signal_ranges().into_iter().collect();
+    ranges.sort_by(|(n0, (_, max0)), (n1, (_, max1))| max1.cmp(max0).then(n0.cmp(n1)));
+    for (template_name, (min, max)) in ranges {
+        let sizes: Vec<_> = vcp
+            .templates
+            .iter()
+            .filter(|t| t.template_name == template_name)
+            .map(|t| t.number_of_signals().to_string())
+            .collect();
+        let line = format!("  {}: min {}, max {} ({})", template_name, min, max, sizes.join(", "));
+        print_line(reporter, LineStyle::Plain, line);
+    }
+}
+
+fn write_witness_dependency_graph(
+    vcp: &VCP,
+    file: &str,
+    reporter: &mut Option<Reporter>,
+) -> Result<(), ()> {
+    let graph = vcp.witness_dependency_graph();
+    if let Result::Ok(()) = std::fs::write(file, graph.to_dot()) {
+        print_written(reporter, file);
+        let line = format!("witness critical path length: {}", graph.critical_path_length());
+        print_line(reporter, LineStyle::Plain, line);
+        Result::Ok(())
+    } else {
+        print_write_failure(reporter);
+        Result::Err(())
+    }
+}
+
+// Only without simplification, the indices are the ones of the dag
+fn write_constraint_templates(
+    dag: &DAG,
+    file: &str,
+    reporter: &mut Option<Reporter>,
+) -> Result<(), ()> {
+    if let Result::Ok(()) = dag.write_constraint_templates(file) {
+        print_written(reporter, file);
+        Result::Ok(())
+    } else {
+        print_write_failure(reporter);
+        Result::Err(())
+    }
+}
+
+// The dot file goes to the given path and the json one next to it
+fn write_template_call_graph(
+    program: &ProgramArchive,
+    file: &str,
+    reporter: &mut Option<Reporter>,
+) -> Result<(), ()> {
+    let graph = program.template_call_graph();
+    let json_file = Path::new(file).with_extension("json");
+    let written = std::fs::write(file, graph.to_dot())
+        .and_then(|_| std::fs::write(&json_file, graph.to_json().dump()));
+    if let Result::Ok(()) = written {
+        print_written(reporter, file);
+        print_written(reporter, &json_file.display().to_string());
+        Result::Ok(())
+    } else {
+        print_write_failure(reporter);
+        Result::Err(())
+    }
+}
+
+// None when every signal of the circuit is in some constraint
+fn unconstrained_report(unconstrained: &[(String, Vec<String>)]) -> Option<Report> {
+    let total: usize = unconstrained.iter().map(|(_, signals)| signals.len()).sum();
+    if total == 0 {
+        return Option::None;
+    }
+    let msg = format!("{} signals of the simplified circuit appear in no constraint", total);
+    let mut report = Report::warning(msg, ReportCode::UnconstrainedInCircuit);
+    for (instance, signals) in unconstrained {
+        report.add_note(format!("{}: {}", instance, signals.join(", ")));
+    }
+    Option::Some(report)
+}
+
+fn write_r1cs_shards(
+    exporter: &dyn ConstraintExporter,
+    config: &BuildConfig,
+    shard_size: usize,
+    reporter: &mut Option<Reporter>,
+) -> Result<(), ()> {
+    if config.exporter_kind != ExporterKind::Circom || shard_size == 0 {
+        let msg = "The shards need a positive size and the circom r1cs layout";
+        print_line(reporter, LineStyle::Failure, msg.to_string());
+        return Result::Err(());
+    }
+    let manifest = format!("{}.json", config.r1cs_shards);
+    if let Result::Ok(()) = exporter.r1cs_shards(&config.r1cs_shards, shard_size) {
+        print_written(reporter, &manifest);
+        Result::Ok(())
+    } else {
+        print_write_failure(reporter);
+        Result::Err(())
+    }
+}
+
+fn write_components_dot(dag: &DAG, file: &str, reporter: &mut Option<Reporter>) -> Result<(), ()> {
+    if let Result::Ok(()) = std::fs::write(file, dag.to_dot()) {
+        print_written(reporter, file);
+        Result::Ok(())
+    } else {
+        print_write_failure(reporter);
+        Result::Err(())
+    }
+}
+
+fn write_symbolic_witness(
+    vcp: &VCP,
+    file: &str,
+    limit: usize,
+    reporter: &mut Option<Reporter>,
+) -> Result<(), ()> {
+    if let Result::Ok(()) = std::fs::write(file, vcp.symbolic_witness(limit).to_text()) {
+        print_written(reporter, file);
+        Result::Ok(())
+    } else {
+        print_write_failure(reporter);
+        Result::Err(())
+    }
+}
+
+// Layout of the intermediates folder:
+//   dag_constraints.json  constraints before the simplification, in the json format of --json
+//   vcp.txt               template instances of the VCP with their signals and components
+//   substitutions.json    substitutions applied by the simplification, in order
+//   round_<n>.json        constraints left after the n-th linear simplification round
+// The last two are only produced when the constraints are simplified
+fn write_intermediates(
+    dag: &DAG,
+    vcp: &VCP,
+    folder: &str,
+    chunk_size: usize,
+    reporter: &mut Option<Reporter>,
+) -> Result<(), ()> {
+    use constraint_writers::debug_writer::DebugWriter;
+    let written = std::fs::create_dir_all(folder)
+        .and_then(|_| std::fs::write(Path::new(folder).join("vcp.txt"), vcp_dump(vcp)));
+    let dag_constraints = Path::new(folder).join("dag_constraints.json");
+    let debug = DebugWriter::new(dag_constraints.to_str().unwrap().to_string(), chunk_size)?;
+    if written.is_ok() && dag.json_constraints(&debug).is_ok() {
+        print_written(reporter, folder);
+        Result::Ok(())
+    } else {
+        print_write_failure(reporter);
+        Result::Err(())
+    }
+}
+
+fn open_substitution_log(
+    folder: &str,
+    reporter: &mut Option<Reporter>,
+) -> Result<SubstitutionJSON, ()> {
+    let path = Path::new(folder).join("substitutions.json");
+    SubstitutionJSON::new(path.to_str().unwrap()).map_err(|_| print_write_failure(reporter))
+}
+
+fn vcp_dump(vcp: &VCP) -> String {
+    use program_structure::ast::SignalType;
+    let mut dump = String::new();
+    for instance in &vcp.templates {
+        let main = if instance.template_id == vcp.main_id { " (main)" } else { "" };
+        dump.push_str(&format!("{} {}{}\n", instance.template_id, instance.template_header, main));
+        for signal in &instance.signals {
+            let xtype = match signal.xtype {
+                SignalType::Output => "output",
+                SignalType::Input => "input",
+                SignalType::Intermediate => "intermediate",
+            };
+            dump.push_str(&format!(
+                "  signal {} {}{:?} at {}\n",
+                xtype, signal.name, signal.lengths, signal.dag_local_id
+            ));
+        }
+        for component in &instance.components {
+            dump.push_str(&format!("  component {}{:?}\n", component.name, component.lengths));
+        }
+    }
+    dump
+}
+
+// One line per group, the groups with more constraints first. The counts
+// come from the dag, so they are the ones before simplification unless --O0
+fn print_constraint_counts(
+    grouping: &str,
+    counts: HashMap<String, usize>,
+    flag_f: bool,
+    reporter: &mut Option<Reporter>,
+) {
+    let title = if flag_f {
+        format!("constraints by {}:", grouping)
+    } else {
+        format!("constraints by {} (before simplification):", grouping)
+    };
+    print_line(reporter, LineStyle::Heading, title);
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|(n0, c0), (n1, c1)| c1.cmp(c0).then(n0.cmp(n1)));
+    for (group, constraints) in counts {
+        print_line(reporter, LineStyle::Plain, format!("  {}: {}", group, constraints));
+    }
+}
+
+fn print_constraints_by_template(dag: &DAG, reporter: &mut Option<Reporter>) {
+    let counts = dag.constraints_grouped_by(|template| Option::Some(template.to_string()));
+    // Only listed when the constraints are not simplified
+    print_constraint_counts("template", counts, true, reporter);
+}
+
+fn print_constraints_by_file(
+    dag: &DAG,
+    template_files: &HashMap<String, FileID>,
+    files: &FileLibrary,
+    flag_f: bool,
+    reporter: &mut Option<Reporter>,
+) {
+    let counts = dag.constraints_grouped_by(|template| {
+        let file_id = *template_files.get(template)?;
+        Option::Some(files.get_file_name(file_id).unwrap_or("<unknown file>").to_string())
+    });
+    print_constraint_counts("file", counts, flag_f, reporter);
+}
+
+fn print_constraints_by_category(
+    dag: &DAG,
+    template_categories: &HashMap<String, String>,
+    flag_f: bool,
+    reporter: &mut Option<Reporter>,
+) {
+    // Templates without a category are left out
+    let counts = dag.constraints_grouped_by(|template| template_categories.get(template).cloned());
+    print_constraint_counts("category", counts, flag_f, reporter);
+}
+
 fn sync_dag_and_vcp(vcp: &mut VCP, dag: &mut DAG) {
     let witness = Rc::new(DAG::produce_witness(dag));
     VCP::add_witness_list(vcp, Rc::clone(&witness));
 }
 
-fn simplification_process(vcp: &mut VCP, dag: DAG, config: &BuildConfig) -> ConstraintList {
+// Builds the program and checks that the parallel and the sequential
+// simplification end with the same constraints
+pub fn build_and_compare_parallelism(
+    program: &ProgramArchive,
+    config: &BuildConfig,
+) -> Result<(), ReportCollection> {
+    let flags = execution_flags(config).map_err(|r| vec![r])?;
+    let (exe, _) = instantiation(program, flags.clone(), &mut Option::None)?;
+    let export_flags = ExportFlags {
+        signal_order: config.signal_order,
+        check_signal_count: config.flag_check_signal_count,
+        deterministic: config.flag_deterministic,
+        header_naming: config.header_naming,
+        intern_signal_names: config.flag_intern_signal_names,
+        execution: flags,
+    };
+    let (dag, _, _) = export(exe, program.clone(), export_flags, Option::None, &mut Option::None)?;
+    compare_parallelism(&dag, config, &mut Option::None).map_err(|r| vec![r])
+}
+
+// Constraints of each template instance before the simplification, the
+// heaviest ones first
+pub fn template_costs(
+    program: &ProgramArchive,
+    config: &BuildConfig,
+) -> Result<Vec<TemplateCost>, ReportCollection> {
+    let flags = execution_flags(config).map_err(|r| vec![r])?;
+    let program = with_main_override(program, config);
+    let (exe, _) = instantiation(&program, flags, &mut Option::None)?;
+    Result::Ok(exe.template_costs())
+}
+
+fn with_main_override<'a>(
+    program: &'a ProgramArchive,
+    config: &BuildConfig,
+) -> Cow<'a, ProgramArchive> {
+    match &config.main_override {
+        Option::Some((template, params)) => {
+            let mut program = program.clone();
+            override_main(&mut program, template, params);
+            Cow::Owned(program)
+        }
+        Option::None => Cow::Borrowed(program),
+    }
+}
+
+// Dry run: the program is instantiated but no constraints are exported,
+// simplified or written
+pub fn validate_circuit(
+    program: &ProgramArchive,
+    config: &BuildConfig,
+) -> Result<(), ReportCollection> {
+    let program = &*with_main_override(program, config);
+    validate_main(program).map_err(|r| vec![r])?;
+    let (_, pragmas) = categories::template_categories(&program.unknown_pragmas);
+    pragma_check::check_unknown_pragmas(&pragmas, config.unknown_pragma_policy)?;
+    let flags = execution_flags(config).map_err(|r| vec![r])?;
+    instantiation(program, flags, &mut Option::None)?;
+    Result::Ok(())
+}
+
+fn execution_flags(config: &BuildConfig) -> Result<ExecutionFlags, Report> {
+    Result::Ok(ExecutionFlags {
+        assert_as_constraint: config.flag_assert_as_constraint,
+        constant_division_mode: config.constant_division_mode,
+        unconnected_input_policy: config.unconnected_input_policy,
+        max_instances: config.max_instances,
+        constants: field_constants(&config.custom_prime)?,
+    })
+}
+
+fn check_non_linear_limit(counts: &CircuitCounts, limit: usize) -> Result<(), Report> {
+    if counts.no_non_linear > limit {
+        let msg = format!(
+            "The circuit has {} non linear constraints, the limit is {}",
+            counts.no_non_linear, limit
+        );
+        Result::Err(Report::error(msg, ReportCode::NonLinearLimitExceeded))
+    } else {
+        Result::Ok(())
+    }
+}
+
+fn compare_parallelism(
+    dag: &DAG,
+    config: &BuildConfig,
+    reporter: &mut Option<Reporter>,
+) -> Result<(), Report> {
+    match dag.parallel_simplification_difference(config.flag_s, config.no_rounds) {
+        Option::None => {
+            let line = "parallel and sequential simplification agree".to_string();
+            print_line(reporter, LineStyle::Heading, line);
+            Result::Ok(())
+        }
+        Option::Some(difference) => {
+            let msg = "The parallel simplification does not match the sequential one".to_string();
+            let mut report = Report::error(msg, ReportCode::ParallelSimplificationMismatch);
+            report.add_note(format!("First difference: {}", difference));
+            Result::Err(report)
+        }
+    }
+}
+
+fn simplification_report(error: SimplificationError, field: &BigInt) -> Report {
+    let operation = match error.error {
+        ArithmeticError::DivisionByZero => "Division by zero",
+        ArithmeticError::BitOverFlowInShift => "Shifting caused bit overflow",
+    };
+    let constraint = dag::pretty_constraint(&error.constraint, &HashMap::new(), field);
+    let msg = format!(
+        "{} in the simplification: the signal #{} can not be cleared from {}",
+        operation, error.signal, constraint
+    );
+    Report::error(msg, ReportCode::SimplificationArithmeticError)
+}
+
+fn simplification_process(
+    vcp: &mut VCP,
+    mut dag: DAG,
+    config: &BuildConfig,
+    substitution_log: Option<SubstitutionJSON>,
+) -> Result<ConstraintList, Report> {
     use dag::SimplificationFlags;
+    let field = dag.constants.get_p().clone();
+    let mut promoted = Vec::new();
+    for path in &config.promoted_signals {
+        let signal = dag.signal_id(path)?;
+        dag.keep_signal(signal);
+        promoted.push(signal);
+    }
     let flags = SimplificationFlags {
         flag_s: config.flag_s,
         parallel_flag: config.flag_p,
+        threads: config.threads,
         port_substitution: config.flag_json_sub,
         no_rounds: config.no_rounds,
+        intermediates: if config.flag_keep_intermediates {
+            Option::Some(config.intermediates.clone())
+        } else {
+            Option::None
+        },
+        substitution_log,
+        progress: Option::None,
+        dedup_constraints: config.flag_dedup_constraints,
+        provenance: config.flag_provenance,
+    };
+    let mut list = if config.flag_no_simplification {
+        DAG::map_to_list_unsimplified(dag)
+    } else {
+        DAG::try_map_to_list(dag, flags).map_err(|e| simplification_report(e, &field))?
     };
-    let list = DAG::map_to_list(dag, flags);
+    if !promoted.is_empty() {
+        list.promote_to_outputs(&promoted)?;
+    }
+    if let Option::Some(permutation) = &config.wire_permutation {
+        list.permute_wires(permutation)?;
+    }
     VCP::add_witness_list(vcp, Rc::new(list.get_witness_as_vec()));
-    list
+    Result::Ok(list)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use program_structure::ast::*;
+    use program_structure::expression_builders::build_call;
+    use program_structure::statement_builders::*;
+
+    const MULTIPLIER: &str = "
+        pragma circom 2.0.0;
+        template Multiplier() {
+            signal input a;
+            signal input b;
+            signal output c;
+            c <== a*b;
+        }
+        component main = Multiplier();
+    ";
+
+    fn config() -> BuildConfig {
+        BuildConfig { no_rounds: usize::MAX, ..BuildConfig::default() }
+    }
+
+    // Program of the definitions in an empty main.circom, call is its main
+    pub fn archive(definitions: Vec<Definition>, call: Expression) -> ProgramArchive {
+        archive_with_public(definitions, call, vec![])
+    }
+
+    pub fn archive_with_public(
+        definitions: Vec<Definition>,
+        call: Expression,
+        public: Vec<String>,
+    ) -> ProgramArchive {
+        let mut file_library = FileLibrary::new();
+        let file_id = file_library.add_file("main.circom".to_string(), String::new());
+        let contents = vec![(file_id, definitions)];
+        ProgramArchive::new(file_library, file_id, (public, call), contents).ok().unwrap()
+    }
+
+    // The program decorated by the type analysis
+    pub fn typed(mut program: ProgramArchive) -> ProgramArchive {
+        type_analysis::check_types::check_types(&mut program).ok().unwrap();
+        program
+    }
+
+    // Main has the input a and nothing else
+    fn unconstrained_input() -> ProgramArchive {
+        let input = VariableType::Signal(SignalType::Input, SignalElementType::FieldElement);
+        let a = build_declaration(Meta::new(0, 0), input, "a".to_string(), vec![]);
+        let init = build_initialization_block(Meta::new(0, 0), input, vec![a]);
+        let body = build_block(Meta::new(0, 0), vec![init]);
+        let main = build_template(Meta::new(0, 0), "Main".to_string(), vec![], 0..0, body, false);
+        let call = build_call(Meta::new(0, 0), "Main".to_string(), vec![]);
+        archive(vec![main], call)
+    }
+
+    // Main has the inputs b and a, in that order, and the output c <== a * b
+    fn product() -> ProgramArchive {
+        product_with_public(vec![])
+    }
+
+    fn product_with_public(public: Vec<&str>) -> ProgramArchive {
+        use program_structure::expression_builders::{build_infix, build_variable};
+        let signal = |xtype, name: &str| {
+            let xtype = VariableType::Signal(xtype, SignalElementType::FieldElement);
+            let declaration = build_declaration(Meta::new(0, 0), xtype, name.to_string(), vec![]);
+            build_initialization_block(Meta::new(0, 0), xtype, vec![declaration])
+        };
+        let var = |name: &str| build_variable(Meta::new(0, 0), name.to_string(), vec![]);
+        let mul = build_infix(Meta::new(0, 0), var("a"), ExpressionInfixOpcode::Mul, var("b"));
+        let op = AssignOp::AssignConstraintSignal;
+        let body = vec![
+            signal(SignalType::Input, "b"),
+            signal(SignalType::Input, "a"),
+            signal(SignalType::Output, "c"),
+            build_substitution(Meta::new(0, 0), "c".to_string(), vec![], op, mul),
+        ];
+        let body = build_block(Meta::new(0, 0), body);
+        let main = build_template(Meta::new(0, 0), "Main".to_string(), vec![], 0..0, body, false);
+        let call = build_call(Meta::new(0, 0), "Main".to_string(), vec![]);
+        let public = public.into_iter().map(|s| s.to_string()).collect();
+        typed(archive_with_public(vec![main], call, public))
+    }
+
+    // Small has one constraint and Big three, Main uses Small twice and Big once
+    fn two_templates() -> ProgramArchive {
+        use program_structure::expression_builders::{build_infix, build_variable};
+        let input = VariableType::Signal(SignalType::Input, SignalElementType::FieldElement);
+        let a = || build_variable(Meta::new(0, 0), "a".to_string(), vec![]);
+        let square = || build_infix(Meta::new(0, 0), a(), ExpressionInfixOpcode::Mul, a());
+        let template = |name: &str, constraints: usize| {
+            let declaration = build_declaration(Meta::new(0, 0), input, "a".to_string(), vec![]);
+            let init = build_initialization_block(Meta::new(0, 0), input, vec![declaration]);
+            let mut body = vec![init];
+            for _ in 0..constraints {
+                body.push(build_constraint_equality(Meta::new(0, 0), square(), a()));
+            }
+            let body = build_block(Meta::new(0, 0), body);
+            build_template(Meta::new(0, 0), name.to_string(), vec![], 0..0, body, false)
+        };
+        let mut main_body = vec![];
+        for (name, template) in [("s0", "Small"), ("s1", "Small"), ("b", "Big")].iter() {
+            let xtype = VariableType::Component;
+            let declaration = build_declaration(Meta::new(0, 0), xtype, name.to_string(), vec![]);
+            let call = build_call(Meta::new(0, 0), template.to_string(), vec![]);
+            let op = AssignOp::AssignVar;
+            main_body.push(build_initialization_block(Meta::new(0, 0), xtype, vec![declaration]));
+            main_body.push(build_substitution(Meta::new(0, 0), name.to_string(), vec![], op, call));
+        }
+        let body = build_block(Meta::new(0, 0), main_body);
+        let main = build_template(Meta::new(0, 0), "Main".to_string(), vec![], 0..0, body, false);
+        let definitions = vec![template("Small", 1), template("Big", 3), main];
+        let call = build_call(Meta::new(0, 0), "Main".to_string(), vec![]);
+        archive(definitions, call)
+    }
+
+    // Main uses Param(n) once for each of the values, in that order
+    fn parameterized(values: &[i64]) -> ProgramArchive {
+        use program_structure::expression_builders::build_number;
+        let input = VariableType::Signal(SignalType::Input, SignalElementType::FieldElement);
+        let declaration = build_declaration(Meta::new(0, 0), input, "a".to_string(), vec![]);
+        let init = build_initialization_block(Meta::new(0, 0), input, vec![declaration]);
+        let body = build_block(Meta::new(0, 0), vec![init]);
+        let args = vec!["n".to_string()];
+        let param = build_template(Meta::new(0, 0), "Param".to_string(), args, 0..0, body, false);
+        let mut main_body = vec![];
+        for (index, value) in values.iter().enumerate() {
+            let name = format!("c{}", index);
+            let xtype = VariableType::Component;
+            let declaration = build_declaration(Meta::new(0, 0), xtype, name.clone(), vec![]);
+            let n = build_number(Meta::new(0, 0), BigInt::from(*value));
+            let call = build_call(Meta::new(0, 0), "Param".to_string(), vec![n]);
+            let op = AssignOp::AssignVar;
+            main_body.push(build_initialization_block(Meta::new(0, 0), xtype, vec![declaration]));
+            main_body.push(build_substitution(Meta::new(0, 0), name, vec![], op, call));
+        }
+        let body = build_block(Meta::new(0, 0), main_body);
+        let main = build_template(Meta::new(0, 0), "Main".to_string(), vec![], 0..0, body, false);
+        let call = build_call(Meta::new(0, 0), "Main".to_string(), vec![]);
+        typed(archive(vec![param, main], call))
+    }
+
+    #[test]
+    fn main_can_be_overridden_by_a_leaf_template() {
+        let leaf = |params: Vec<i64>| {
+            let params = params.into_iter().map(BigInt::from).collect();
+            BuildConfig { main_override: Option::Some(("Param".to_string(), params)), ..config() }
+        };
+        let (_, vcp) = build_circuit(parameterized(&[1, 2]), leaf(vec![5])).ok().unwrap();
+        let main = &vcp.templates[vcp.main_id];
+        assert_eq!(main.template_name, "Param");
+        assert_eq!(main.header[0].values, vec![BigInt::from(5)]);
+        assert!(main.triggers.is_empty());
+        let layout = vcp.witness_layout();
+        let names: Vec<_> = layout.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["main.a"]);
+        assert!(layout[0].xtype == SignalType::Input && !layout[0].is_public);
+        assert!(build_circuit(parameterized(&[1]), leaf(vec![])).is_err());
+        assert!(validate_circuit(&parameterized(&[1]), &leaf(vec![5])).is_ok());
+    }
+
+    #[test]
+    fn panics_under_bisect_are_reported() {
+        // Without the type analysis the array reaches a single signal and the execution panics
+        let source = "
+            pragma circom 2.0.0;
+            template Main() {
+                signal input a[2];
+                signal b;
+                b <== a;
+            }
+            component main = Main();
+        ";
+        let parsed = parser::run_parser_from_source("<source>".to_string(), source.to_string());
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let received = Rc::clone(&events);
+        let reporter: Reporter = Box::new(move |event| {
+            let line = match event {
+                ReportEvent::Reports(reports) => reports[0].get_message().clone(),
+                ReportEvent::Line { text, .. } => text,
+                ReportEvent::Info { title, message } => format!("{}: {}", title, message),
+            };
+            received.borrow_mut().push(line);
+        });
+        let reporter = Option::Some(reporter);
+        let config = BuildConfig { reporter, flag_bisect: true, ..config() };
+        let panic_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        let built = build_circuit(parsed.ok().unwrap().0, config);
+        std::panic::set_hook(panic_hook);
+        assert!(built.is_err());
+        let events = events.borrow();
+        assert_eq!(events[0], "bisecting the failed instantiation...");
+        assert_eq!(events[1], "the failure is reproduced by instantiating Main() on its own");
+        assert!(events[2].starts_with("The instantiation panicked: "));
+        assert_eq!(events.len(), 3);
+    }
+
+    #[test]
+    fn hashed_headers_do_not_depend_on_the_instantiation_order() {
+        use compiler::hir::very_concrete_program::TemplateInstance;
+        let headers = |values: &[i64], header_naming| {
+            let config = BuildConfig { header_naming, ..config() };
+            let (_, vcp) = build_circuit(parameterized(values), config).ok().unwrap();
+            // (arguments, header) of each instance
+            let arguments =
+                |t: &TemplateInstance| t.header.iter().map(|a| a.values.clone()).collect();
+            let mut headers: Vec<(Vec<_>, String)> =
+                vcp.templates.iter().map(|t| (arguments(t), t.template_header.clone())).collect();
+            headers.sort();
+            headers
+        };
+        let hashed = headers(&[1, 2], HeaderNaming::ParameterHash);
+        assert_eq!(hashed, headers(&[2, 1], HeaderNaming::ParameterHash));
+        assert_ne!(hashed[1].1, hashed[2].1);
+        // The headers do not change between builds of the compiler
+        assert_eq!(hashed[1].1, "Param_f89f139a");
+        let sequential = headers(&[1, 2], HeaderNaming::Sequential);
+        assert_ne!(sequential, headers(&[2, 1], HeaderNaming::Sequential));
+    }
+
+    #[test]
+    fn template_costs_rank_the_heaviest_first() {
+        let costs = template_costs(&two_templates(), &config()).ok().unwrap();
+        let expected = vec![
+            ("Big()".to_string(), 1, 3, 3),
+            ("Small()".to_string(), 2, 1, 2),
+            ("Main()".to_string(), 1, 0, 0),
+        ];
+        assert_eq!(costs, expected);
+    }
+
+    #[test]
+    fn public_inputs_follow_the_signal_ids() {
+        let program = product_with_public(vec!["a", "b"]);
+        let (exporter, vcp, _) = build_circuit_with_reports(program, config()).ok().unwrap();
+        // The declaration order decides, not the order of the public list
+        assert_eq!(vcp.public_inputs(), ["b".to_string(), "a".to_string()]);
+        assert_eq!(exporter.r1cs_header().public_inputs, 2);
+        let (_, vcp, _) = build_circuit_with_reports(product(), config()).ok().unwrap();
+        assert!(vcp.public_inputs().is_empty());
+        let arrays = "
+            pragma circom 2.0.0;
+            template Main() {
+                signal input a[2][2];
+                signal input b;
+                signal output c;
+                c <== a[0][0] * a[1][1] + b;
+            }
+            component main {public [a]} = Main();
+        ";
+        let (exporter, vcp) = build_from_source(arrays, config()).ok().unwrap();
+        let expected = ["a[0][0]", "a[0][1]", "a[1][0]", "a[1][1]"];
+        assert_eq!(vcp.public_inputs(), expected);
+        assert_eq!(exporter.r1cs_header().public_inputs, 4);
+    }
+
+    #[test]
+    fn ignored_options_are_reported() {
+        let ignored = BuildConfig {
+            flag_constraints_by_template: true,
+            compare_against: Option::Some(PathBuf::from("circom_missing_baseline.json")),
+            ..config()
+        };
+        let (_, _, reports) = build_circuit_with_reports(product(), ignored).ok().unwrap();
+        assert!(reports.iter().all(|r| r.is_warning()));
+        let codes: Vec<_> = reports.iter().map(|r| r.get_code().to_string()).collect();
+        assert_eq!(codes, ["O01", "O02"]);
+    }
+
+    #[test]
+    fn circuits_over_the_non_linear_limit_fail() {
+        let limited = |limit| BuildConfig { max_non_linear: Option::Some(limit), ..config() };
+        assert!(build_circuit_with_reports(product(), limited(1)).is_ok());
+        assert!(build_circuit_with_reports(product(), limited(0)).is_err());
+        let unsimplified = BuildConfig { flag_f: true, ..limited(0) };
+        assert!(build_circuit_with_reports(product(), unsimplified).is_err());
+        let counts = CircuitCounts { no_non_linear: 3, ..CircuitCounts::default() };
+        let report = check_non_linear_limit(&counts, 2).err().unwrap();
+        assert!(matches!(report.get_code(), ReportCode::NonLinearLimitExceeded));
+        let msg = "The circuit has 3 non linear constraints, the limit is 2";
+        assert_eq!(report.get_message(), msg);
+    }
+
+    #[test]
+    fn simplification_errors_are_reports() {
+        let field = UsefulConstants::new().get_p().clone();
+        let c = vec![(0, BigInt::from(0)), (3, BigInt::from(-1))];
+        let coefficients: HashMap<_, _> = c.into_iter().collect();
+        let expression = ArithmeticExpression::Linear { coefficients };
+        let constraint =
+            ArithmeticExpression::transform_expression_to_constraint_form(expression, &field);
+        let constraint = constraint.unwrap();
+        let error = ArithmeticError::DivisionByZero;
+        let error = SimplificationError { error, signal: 2, constraint };
+        let report = simplification_report(error, &field);
+        assert!(matches!(report.get_code(), ReportCode::SimplificationArithmeticError));
+        let msg = "Division by zero in the simplification: \
+                   the signal #2 can not be cleared from -#3 = 0";
+        assert_eq!(report.get_message(), msg);
+    }
+
+    // Main(n) has the inputs in[8] and the output out <== in[index] * in[6]
+    fn indexed_product(index: &str) -> String {
+        format!(
+            "
+            pragma circom 2.0.0;
+            template Main(n) {{
+                signal input in[8];
+                signal output out;
+                out <== in[{}] * in[6];
+            }}
+            component main = Main(3);
+            ",
+            index
+        )
+    }
+
+    // Index of the first signal read by a substitution
+    fn first_index(stmt: &Statement) -> Option<BigInt> {
+        if let Statement::Substitution { rhe: Expression::InfixOp { lhe, .. }, .. } = stmt {
+            if let Expression::Variable { access, .. } = lhe.as_ref() {
+                if let Access::ArrayAccess(Expression::Number(_, v)) = &access[0] {
+                    return Option::Some(v.clone());
+                }
+            }
+        }
+        Option::None
+    }
+
+    #[test]
+    fn constant_indexes_are_folded() {
+        let build = |index| build_from_source(&indexed_product(index), config()).ok().unwrap();
+        let (folded, vcp) = build("2 * n");
+        let (direct, _) = build("6");
+        let constraints = |e: &ConstraintWriter| e.r1cs_header().number_of_constraints;
+        assert_eq!(constraints(&folded), constraints(&direct));
+        // the computed indexes are replaced by their value when the
+        // executed templates are filtered, the code reads in[6]
+        let index = match &vcp.get_main_instance().unwrap().code {
+            Statement::Block { stmts, .. } => stmts.iter().find_map(first_index),
+            _ => Option::None,
+        };
+        assert_eq!(index, Option::Some(BigInt::from(6)));
+    }
+
+    #[test]
+    fn one_warning_for_the_unused_parameter() {
+        // Main(n, m) only reads n, in the size of its input
+        let source = "
+            pragma circom 2.0.0;
+            template Main(n, m) {
+                signal input in[n];
+            }
+            component main = Main(2, 3);
+        ";
+        let reports = Rc::new(RefCell::new(Vec::new()));
+        let received = Rc::clone(&reports);
+        let reporter: Reporter = Box::new(move |event| {
+            if let ReportEvent::Reports(reports) = event {
+                received.borrow_mut().extend(reports);
+            }
+        });
+        let suggest =
+            BuildConfig { reporter: Option::Some(reporter), flag_suggest: true, ..config() };
+        assert!(build_from_source(source, suggest).is_ok());
+        let reports = reports.borrow();
+        let unused: Vec<_> = reports
+            .iter()
+            .filter(|r| matches!(r.get_code(), ReportCode::UnusedTemplateParameter))
+            .collect();
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].get_message(), "Parameter m of template Main is never used");
+    }
+
+    #[test]
+    fn stats_of_every_phase() {
+        let stats = Rc::new(RefCell::new(Vec::new()));
+        let phase_stats = Option::Some(Rc::clone(&stats));
+        // The phases are measured without flag_alloc_stats
+        let measured = BuildConfig { phase_stats, ..config() };
+        assert!(build_circuit_with_reports(product(), measured).is_ok());
+        let phases: Vec<_> = stats.borrow().iter().map(|s| s.phase).collect();
+        assert_eq!(phases, vec!["instantiation", "export", "simplification"]);
+        // flag_f writes the dag, there is no simplification
+        let phase_stats = Option::Some(Rc::clone(&stats));
+        let unsimplified = BuildConfig { phase_stats, flag_f: true, ..config() };
+        assert!(build_circuit_with_reports(product(), unsimplified).is_ok());
+        let phases: Vec<_> = stats.borrow().iter().map(|s| s.phase).collect();
+        assert_eq!(phases, vec!["instantiation", "export"]);
+    }
+
+    #[test]
+    fn no_timing_output_is_identical_between_runs() {
+        let output = |flag_no_timing: bool| {
+            let lines = Rc::new(RefCell::new(Vec::new()));
+            let received = Rc::clone(&lines);
+            let reporter: Reporter = Box::new(move |event| {
+                if let ReportEvent::Line { text, .. } = event {
+                    received.borrow_mut().push(text);
+                }
+            });
+            let config = BuildConfig {
+                reporter: Option::Some(reporter),
+                flag_alloc_stats: true,
+                flag_no_timing,
+                flag_trigger_order: true,
+                flag_r1cs_info: true,
+                ..config()
+            };
+            assert!(build_circuit(product(), config).is_ok());
+            let lines = lines.borrow().clone();
+            lines
+        };
+        let timed = output(false);
+        assert!(timed.iter().any(|line| line.ends_with(" ms")));
+        let untimed = output(true);
+        assert!(untimed.iter().all(|line| !line.ends_with(" ms")));
+        assert_eq!(untimed, output(true));
+    }
+
+    #[test]
+    fn visitors_see_every_constraint_of_the_dag() {
+        let visited = Rc::new(RefCell::new(Vec::new()));
+        let seen = Rc::clone(&visited);
+        let visitor: ConstraintVisitor =
+            Box::new(move |template, c| seen.borrow_mut().push((template.to_string(), c.clone())));
+        let unsimplified = BuildConfig {
+            flag_no_simplification: true,
+            constraint_visitor: Option::Some(visitor),
+            ..config()
+        };
+        let (exporter, _, _) = build_circuit_with_reports(product(), unsimplified).ok().unwrap();
+        let number_of_constraints = exporter.r1cs_header().number_of_constraints;
+        assert_eq!(visited.borrow().len(), number_of_constraints);
+        assert_eq!(visited.borrow()[0].0, "Main");
+        // Once per template instance, Small is used twice but has one node
+        visited.borrow_mut().clear();
+        let seen = Rc::clone(&visited);
+        let visitor: ConstraintVisitor =
+            Box::new(move |template, c| seen.borrow_mut().push((template.to_string(), c.clone())));
+        let visiting = BuildConfig { constraint_visitor: Option::Some(visitor), ..config() };
+        let mut program = two_templates();
+        type_analysis::check_types::check_types(&mut program).ok().unwrap();
+        assert!(build_circuit_with_reports(program, visiting).is_ok());
+        let templates: Vec<_> = visited.borrow().iter().map(|(t, _)| t.clone()).collect();
+        assert_eq!(templates, ["Small", "Big", "Big", "Big"]);
+    }
+
+    // Main has the inputs a and d and the output c <== a * a, d is never used.
+    // The subcomponent s of Loose has the input x and nothing else
+    fn under_constrained() -> ProgramArchive {
+        use program_structure::expression_builders::{build_infix, build_variable};
+        let signal = |xtype, name: &str| {
+            let xtype = VariableType::Signal(xtype, SignalElementType::FieldElement);
+            let declaration = build_declaration(Meta::new(0, 0), xtype, name.to_string(), vec![]);
+            build_initialization_block(Meta::new(0, 0), xtype, vec![declaration])
+        };
+        let var = |name: &str| build_variable(Meta::new(0, 0), name.to_string(), vec![]);
+        let square = build_infix(Meta::new(0, 0), var("a"), ExpressionInfixOpcode::Mul, var("a"));
+        let xtype = VariableType::Component;
+        let s = build_declaration(Meta::new(0, 0), xtype, "s".to_string(), vec![]);
+        let loose = build_call(Meta::new(0, 0), "Loose".to_string(), vec![]);
+        let op = AssignOp::AssignVar;
+        let body = vec![
+            signal(SignalType::Input, "a"),
+            signal(SignalType::Input, "d"),
+            signal(SignalType::Output, "c"),
+            build_initialization_block(Meta::new(0, 0), xtype, vec![s]),
+            build_substitution(Meta::new(0, 0), "s".to_string(), vec![], op, loose),
+            build_substitution(
+                Meta::new(0, 0),
+                "c".to_string(),
+                vec![],
+                AssignOp::AssignConstraintSignal,
+                square,
+            ),
+        ];
+        let template = |name: &str, body| {
+            let body = build_block(Meta::new(0, 0), body);
+            build_template(Meta::new(0, 0), name.to_string(), vec![], 0..0, body, false)
+        };
+        let definitions =
+            vec![template("Loose", vec![signal(SignalType::Input, "x")]), template("Main", body)];
+        let call = build_call(Meta::new(0, 0), "Main".to_string(), vec![]);
+        typed(archive(definitions, call))
+    }
+
+    #[test]
+    fn unconstrained_signals_of_the_whole_circuit() {
+        let notes_of = |simplify: bool| {
+            let reports = Rc::new(RefCell::new(Vec::new()));
+            let received = Rc::clone(&reports);
+            let reporter: Reporter = Box::new(move |event| {
+                if let ReportEvent::Reports(reports) = event {
+                    received.borrow_mut().extend(reports);
+                }
+            });
+            let config = BuildConfig {
+                reporter: Option::Some(reporter),
+                flag_unconstrained_report: true,
+                flag_no_simplification: !simplify,
+                ..config()
+            };
+            assert!(build_circuit(under_constrained(), config).is_ok());
+            let reports = reports.borrow();
+            let code = |r: &&Report| matches!(r.get_code(), ReportCode::UnconstrainedInCircuit);
+            let report = reports.iter().find(code).unwrap();
+            (report.get_message().clone(), report.get_notes().clone())
+        };
+        // The simplification removes s.x, only the inputs of main stay
+        let (message, notes) = notes_of(true);
+        assert_eq!(message, "1 signals of the simplified circuit appear in no constraint");
+        assert_eq!(notes, ["main: d"]);
+        let (message, notes) = notes_of(false);
+        assert_eq!(message, "2 signals of the simplified circuit appear in no constraint");
+        assert_eq!(notes, ["main: d", "main.s: x"]);
+    }
+
+    #[test]
+    fn reporters_receive_the_build_messages() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let received = Rc::clone(&events);
+        let reporter: Reporter = Box::new(move |event| {
+            let line = match event {
+                ReportEvent::Info { title, message } => format!("{}: {}", title, message),
+                ReportEvent::Line { style, text } => format!("{:?} {}", style, text),
+                ReportEvent::Reports(reports) => format!("{} reports", reports.len()),
+            };
+            received.borrow_mut().push(line);
+        });
+        let name = format!("circom_reported_counts_{}.json", std::process::id());
+        let counts = std::env::temp_dir().join(name);
+        let config = BuildConfig {
+            reporter: Option::Some(reporter),
+            inspect_constraints: true,
+            flag_r1cs_info: true,
+            flag_trigger_order: true,
+            flag_template_ranges: true,
+            flag_mult_depth: true,
+            flag_dof: true,
+            flag_verify_parallel: true,
+            flag_alloc_stats: true,
+            flag_emit_counts_json: true,
+            counts_json: counts.to_str().unwrap().to_string(),
+            ..config()
+        };
+        assert!(build_circuit(unconstrained_input(), config).is_ok());
+        std::fs::remove_file(&counts).unwrap();
+        let written = format!("Written successfully: {}", counts.display());
+        // Every listing asked by the flags reaches the reporter and nothing else is printed
+        let expected = vec![
+            "template instances: 1",
+            // The warnings of the unconstrained input of main
+            "2 reports",
+            "Heading component trigger order:",
+            "Plain   main",
+            "Heading signals per template instance:",
+            "Plain   Main: min 1, max 1 (1)",
+            "Heading parallel and sequential simplification agree",
+            &written,
+            "Plain multiplicative depth: 0",
+            "Plain degrees of freedom: 1 (inputs: 1)",
+            "Plain Curve: bn-128",
+            "Plain # of Wires: 2",
+            "Plain # of Constraints: 0",
+            "Plain # of Private Inputs: 1",
+            "Plain # of Public Inputs: 0",
+            "Plain # of Labels: 2",
+            "Plain # of Outputs: 0",
+        ];
+        let events = events.borrow();
+        assert_eq!(events[..expected.len()], expected[..]);
+        // The allocation stats, or the notice that they are unavailable, come last
+        assert!(events[expected.len()].contains("allocation stats"));
+    }
+
+    #[test]
+    fn witness_layout_matches_the_sym_file() {
+        use compiler::hir::witness_layout::WitnessEntry;
+        let entry = |id, name: &str, xtype, is_public| WitnessEntry {
+            id,
+            name: name.to_string(),
+            xtype,
+            is_public,
+            witness: Option::Some(id),
+        };
+        let (_, vcp) = build_circuit(product_with_public(vec!["a"]), config()).ok().unwrap();
+        let expected = vec![
+            entry(1, "main.c", SignalType::Output, true),
+            entry(2, "main.a", SignalType::Input, true),
+            entry(3, "main.b", SignalType::Input, false),
+        ];
+        assert_eq!(vcp.witness_layout(), expected);
+        let mut program = two_templates();
+        type_analysis::check_types::check_types(&mut program).ok().unwrap();
+        let (exporter, vcp, _) = build_circuit_with_reports(program, config()).ok().unwrap();
+        let layout = vcp.witness_layout();
+        let names: Vec<_> = layout.iter().map(|e| e.name.as_str()).collect();
+        // The subcomponents are numbered in the order of their names
+        assert_eq!(names, ["main.b.a", "main.s0.a", "main.s1.a"]);
+        assert!(layout.iter().all(|e| e.xtype == SignalType::Input && !e.is_public));
+        // Lines of the sym file are id,witness,node,name
+        let name = format!("circom_witness_layout_{}.sym", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        exporter.sym(file.to_str().unwrap()).unwrap();
+        let symbols = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        for line in symbols.lines() {
+            let fields: Vec<_> = line.split(',').collect();
+            let id: usize = fields[0].parse().unwrap();
+            assert!(layout.iter().any(|e| e.id == id && e.name == fields[3]));
+        }
+        assert_eq!(symbols.lines().count(), layout.len());
+    }
+
+    #[test]
+    fn interned_signal_names_write_the_same_sym() {
+        use program_structure::expression_builders::build_number;
+        let dimension = |n: u32| build_number(Meta::new(0, 0), BigInt::from(n));
+        let mut body = vec![];
+        for (xtype, name, dimensions) in [
+            (SignalType::Input, "a", vec![dimension(2), dimension(3)]),
+            (SignalType::Output, "b", vec![dimension(12)]),
+            (SignalType::Intermediate, "c", vec![]),
+        ] {
+            let xtype = VariableType::Signal(xtype, SignalElementType::FieldElement);
+            let declaration =
+                build_declaration(Meta::new(0, 0), xtype, name.to_string(), dimensions);
+            body.push(build_initialization_block(Meta::new(0, 0), xtype, vec![declaration]));
+        }
+        let body = build_block(Meta::new(0, 0), body);
+        let main = build_template(Meta::new(0, 0), "Main".to_string(), vec![], 0..0, body, false);
+        let call = build_call(Meta::new(0, 0), "Main".to_string(), vec![]);
+        let program = typed(archive(vec![main], call));
+        let sym = |interned: bool| {
+            let config = BuildConfig { flag_intern_signal_names: interned, ..config() };
+            let (exporter, _, _) = build_circuit_with_reports(program.clone(), config).unwrap();
+            let name = format!("circom_interned_{}_{}.sym", interned, std::process::id());
+            let file = std::env::temp_dir().join(name);
+            exporter.sym(file.to_str().unwrap()).unwrap();
+            let symbols = std::fs::read_to_string(&file).unwrap();
+            std::fs::remove_file(file).unwrap();
+            symbols
+        };
+        let symbols = sym(true);
+        assert_eq!(symbols, sym(false));
+        assert!(symbols.lines().any(|l| l.ends_with(",main.a[1][2]")));
+        assert!(symbols.lines().any(|l| l.ends_with(",main.b[11]")));
+    }
+
+    #[test]
+    fn normalized_builds_write_the_same_r1cs() {
+        // The multiplier with its constraint scaled by 3
+        let scaled = "
+            pragma circom 2.0.0;
+            template Multiplier() {
+                signal input a;
+                signal input b;
+                signal output c;
+                c <-- a*b;
+                3*c === (3*a)*b;
+            }
+            component main = Multiplier();
+        ";
+        let written = |src: &str, flag_f: bool| {
+            let config = BuildConfig { flag_normalize: true, flag_f, ..config() };
+            let (exporter, _) = build_from_source(src, config).ok().unwrap();
+            let name = format!("circom_normalized_{}_{}.r1cs", flag_f, std::process::id());
+            let file = std::env::temp_dir().join(name);
+            exporter.r1cs(file.to_str().unwrap()).unwrap();
+            let r1cs = std::fs::read(&file).unwrap();
+            std::fs::remove_file(file).unwrap();
+            r1cs
+        };
+        for flag_f in [false, true] {
+            assert!(written(MULTIPLIER, flag_f) == written(scaled, flag_f));
+        }
+    }
+
+    #[test]
+    fn intermediates_keep_the_substitutions() {
+        let name = format!("circom_intermediates_{}", std::process::id());
+        let folder = std::env::temp_dir().join(name);
+        let intermediates = folder.to_str().unwrap().to_string();
+        let config = BuildConfig { flag_keep_intermediates: true, intermediates, ..config() };
+        assert!(build_circuit_with_reports(product(), config).is_ok());
+        let substitutions = std::fs::read_to_string(folder.join("substitutions.json")).unwrap();
+        let rounds = folder.join("round_0.json").exists();
+        std::fs::remove_dir_all(&folder).unwrap();
+        assert!(substitutions.starts_with("{\n\"substitution\": {"));
+        assert!(substitutions.ends_with("}\n}"));
+        assert!(rounds);
+    }
+
+    #[test]
+    fn deterministic_builds_are_byte_identical() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        let build = |run: &str| {
+            let deterministic = BuildConfig { flag_deterministic: true, ..config() };
+            let (exporter, _, _) = build_circuit_with_reports(product(), deterministic).unwrap();
+            let dir = std::env::temp_dir();
+            let stem = format!("circom_deterministic_{}_{}", run, std::process::id());
+            let r1cs = dir.join(format!("{}.r1cs", stem));
+            let sym = dir.join(format!("{}.sym", stem));
+            exporter.r1cs(r1cs.to_str().unwrap()).unwrap();
+            exporter.sym(sym.to_str().unwrap()).unwrap();
+            let mut state = DefaultHasher::new();
+            std::fs::read(&r1cs).unwrap().hash(&mut state);
+            let symbols = std::fs::read_to_string(&sym).unwrap();
+            symbols.hash(&mut state);
+            std::fs::remove_file(r1cs).unwrap();
+            std::fs::remove_file(sym).unwrap();
+            (state.finish(), symbols)
+        };
+        let (first, sym) = build("first");
+        let (second, _) = build("second");
+        assert_eq!(first, second);
+        // The inputs of main keep their declaration order
+        let symbols: Vec<_> = sym.lines().map(|l| l.rsplit(',').next().unwrap()).collect();
+        assert_eq!(symbols, ["main.c", "main.b", "main.a"]);
+    }
+
+    // Main has the inputs a and b and the constraint a === b, at its place
+    // in the source
+    fn spanned(source: &str) -> ProgramArchive {
+        use program_structure::expression_builders::build_variable;
+        let mut file_library = FileLibrary::new();
+        let file_id = file_library.add_file("main.circom".to_string(), source.to_string());
+        let start = source.find("a === b").unwrap();
+        let mut meta = Meta::new(start, start + "a === b".len());
+        meta.set_file_id(file_id);
+        let input = VariableType::Signal(SignalType::Input, SignalElementType::FieldElement);
+        let declare = |name: &str| {
+            let declaration = build_declaration(Meta::new(0, 0), input, name.to_string(), vec![]);
+            build_initialization_block(Meta::new(0, 0), input, vec![declaration])
+        };
+        let var = |name: &str| build_variable(Meta::new(0, 0), name.to_string(), vec![]);
+        let equality = build_constraint_equality(meta, var("a"), var("b"));
+        let body = build_block(Meta::new(0, 0), vec![declare("a"), declare("b"), equality]);
+        let main = build_template(Meta::new(0, 0), "Main".to_string(), vec![], 0..0, body, false);
+        let call = build_call(Meta::new(0, 0), "Main".to_string(), vec![]);
+        let contents = vec![(file_id, vec![main])];
+        let mut program =
+            ProgramArchive::new(file_library, file_id, (vec![], call), contents).ok().unwrap();
+        type_analysis::check_types::check_types(&mut program).ok().unwrap();
+        program
+    }
+
+    // File, start line and column and end line and column of each
+    // constraint in the r1cs written with spans
+    fn written_spans(program: ProgramArchive, config: BuildConfig, name: &str) -> Vec<Vec<u32>> {
+        use std::convert::TryInto;
+        let spans = BuildConfig { flag_r1cs_spans: true, ..config };
+        let (exporter, _, _) = build_circuit_with_reports(program, spans).unwrap();
+        let file = std::env::temp_dir().join(format!("{}_{}.r1cs", name, std::process::id()));
+        let file = file.to_str().unwrap();
+        exporter.r1cs(file).unwrap();
+        let bytes = std::fs::read(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        let read_u32 = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let read_u64 = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        assert_eq!(read_u32(8), 4);
+        // The spans are the last section: type, size and five values per constraint
+        let mut section = 12;
+        while read_u32(section) != 0x1001 {
+            section += 12 + read_u64(section + 4) as usize;
+        }
+        let values = read_u64(section + 4) as usize / 4;
+        let values: Vec<_> = (0..values).map(|i| read_u32(section + 12 + 4 * i)).collect();
+        values.chunks(5).map(|span| span.to_vec()).collect()
+    }
+
+    #[test]
+    fn constraint_spans_point_to_the_statement() {
+        let source = "template Main() { signal input a; signal input b; a === b; }";
+        let column = source.find("a === b").unwrap() as u32 + 1;
+        let unsimplified = BuildConfig { flag_f: true, ..config() };
+        let spans = written_spans(spanned(source), unsimplified, "circom_constraint_spans");
+        assert_eq!(spans, vec![vec![0, 1, column, 1, column + 7]]);
+        // Without simplification the list has the constraints of the dag
+        let listed = BuildConfig { flag_no_simplification: true, ..config() };
+        let spans = written_spans(spanned(source), listed, "circom_listed_spans");
+        assert_eq!(spans, vec![vec![0, 1, column, 1, column + 7]]);
+    }
+
+    #[test]
+    fn derived_constraints_have_the_unknown_span() {
+        let source = "
+            pragma circom 2.0.0;
+            template Main() {
+                signal input a;
+                signal input b;
+                signal input c;
+                signal x;
+                x <== a + b;
+                a * b === c;
+                x * c === a;
+            }
+            component main = Main();
+        ";
+        let parsed = parser::run_parser_from_source("<source>".to_string(), source.to_string());
+        let program = typed(parsed.ok().unwrap().0);
+        let mut spans = written_spans(program, config(), "circom_simplified_spans");
+        spans.sort();
+        // x is replaced in the last constraint, the product of a and b is untouched
+        let start = source.find("a * b === c").unwrap();
+        let line = source[..start].lines().count() as u32;
+        let column = (start - source[..start].rfind('\n').unwrap()) as u32;
+        let unknown = vec![u32::MAX; 5];
+        // The parsed statement ends after its semicolon
+        assert_eq!(spans, vec![vec![0, line, column, line, column + 12], unknown]);
+    }
+
+    #[test]
+    fn cached_nodes_take_the_spans_of_the_build() {
+        let cache = Rc::new(RefCell::new(InstanceCache::new()));
+        let cached = || BuildConfig {
+            flag_f: true,
+            instance_cache: Option::Some(Rc::clone(&cache)),
+            ..config()
+        };
+        let source = "template Main() { signal input a; signal input b; a === b; }";
+        written_spans(spanned(source), cached(), "circom_cached_spans");
+        // The same code two lines below
+        let moved = format!("\n\n{}", source);
+        let spans = written_spans(spanned(&moved), cached(), "circom_cached_spans");
+        assert_eq!(cache.borrow().hits(), ["Main()".to_string()]);
+        let column = source.find("a === b").unwrap() as u32 + 1;
+        assert_eq!(spans, vec![vec![0, 3, column, 3, column + 7]]);
+    }
+
+    #[test]
+    fn constraint_templates_are_only_written_without_simplification() {
+        let name = format!("circom_constraint_templates_{}.sym", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        let written = |config: BuildConfig| {
+            let templates = BuildConfig {
+                flag_sym_constraints: true,
+                sym_constraints: file.to_str().unwrap().to_string(),
+                ..config
+            };
+            let (exporter, _, _) = build_circuit_with_reports(product(), templates).unwrap();
+            let contents = std::fs::read_to_string(&file).ok();
+            let _ = std::fs::remove_file(&file);
+            (contents, exporter.r1cs_header().number_of_constraints)
+        };
+        assert_eq!(written(config()).0, Option::None);
+        let unsimplified = BuildConfig { flag_no_simplification: true, ..config() };
+        let (contents, number_of_constraints) = written(unsimplified);
+        assert_eq!(number_of_constraints, 1);
+        assert_eq!(contents.unwrap(), "0,Main()\n");
+    }
+
+    #[test]
+    fn warnings_are_returned_to_the_caller() {
+        let built = build_circuit_with_reports(unconstrained_input(), config());
+        let (_, _, warnings) = built.ok().unwrap();
+        assert!(!warnings.is_empty());
+        assert!(warnings.iter().all(Report::is_warning));
+        let messages: Vec<_> = warnings.iter().map(|r| r.get_message().as_str()).collect();
+        assert!(messages.iter().any(|m| m.ends_with("Unconstrained signal. \"a\"")));
+    }
+
+    #[test]
+    fn validation_reports_the_instantiation_errors() {
+        use program_structure::expression_builders::build_number;
+        assert!(validate_circuit(&unconstrained_input(), &config()).is_ok());
+        let zero = build_number(Meta::new(0, 0), BigInt::from(0));
+        let body = build_block(Meta::new(0, 0), vec![build_assert(Meta::new(0, 0), zero)]);
+        let main = build_template(Meta::new(0, 0), "Main".to_string(), vec![], 0..0, body, false);
+        let call = build_call(Meta::new(0, 0), "Main".to_string(), vec![]);
+        let program = archive(vec![main], call);
+        let reports = validate_circuit(&program, &config()).unwrap_err();
+        assert_eq!(reports.len(), 1);
+        assert!(matches!(reports[0].get_code(), ReportCode::RuntimeError));
+        assert_eq!(reports[0].get_message(), "False assert reached");
+    }
+
+    #[test]
+    fn unchanged_instances_hit_the_cache() {
+        let cache = Rc::new(RefCell::new(InstanceCache::new()));
+        let cached = || BuildConfig { instance_cache: Option::Some(Rc::clone(&cache)), ..config() };
+        build_circuit_with_reports(unconstrained_input(), cached()).unwrap();
+        assert!(cache.borrow().hits().is_empty());
+        assert!(cache.borrow().hashes.contains_key("Main()"));
+        build_circuit_with_reports(unconstrained_input(), cached()).unwrap();
+        assert_eq!(cache.borrow().hits(), ["Main()".to_string()]);
+        // Sorting by name lays out the node in another way
+        let deterministic = BuildConfig { flag_deterministic: true, ..cached() };
+        build_circuit_with_reports(unconstrained_input(), deterministic).unwrap();
+        assert!(cache.borrow().hits().is_empty());
+        let other_field = BuildConfig { custom_prime: Option::Some(BigInt::from(257)), ..cached() };
+        build_circuit_with_reports(unconstrained_input(), other_field).unwrap();
+        assert!(cache.borrow().hits().is_empty());
+    }
+
+    #[test]
+    fn promoted_asserts_miss_the_cache() {
+        let source = "
+            pragma circom 2.0.0;
+            template Main() {
+                signal input a;
+                signal input b;
+                assert(a == b);
+            }
+            component main = Main();
+        ";
+        let cache = Rc::new(RefCell::new(InstanceCache::new()));
+        let constraints = |promoted: bool| {
+            let config = BuildConfig {
+                flag_assert_as_constraint: promoted,
+                flag_no_simplification: true,
+                instance_cache: Option::Some(Rc::clone(&cache)),
+                ..config()
+            };
+            let (exporter, _) = build_from_source(source, config).ok().unwrap();
+            exporter.r1cs_header().number_of_constraints
+        };
+        assert_eq!(constraints(false), 0);
+        assert_eq!(constraints(true), 1);
+        assert!(cache.borrow().hits().is_empty());
+        assert_eq!(constraints(false), 0);
+        assert!(cache.borrow().hits().is_empty());
+        assert_eq!(constraints(false), 0);
+        assert_eq!(cache.borrow().hits(), ["Main()".to_string()]);
+    }
+
+    fn assert_warnings(assertion: &str) -> Vec<String> {
+        let source = format!(
+            "
+            pragma circom 2.0.0;
+            template Main() {{
+                signal input a;
+                signal input b;
+                signal input c;
+                signal input d;
+                {}
+            }}
+            component main = Main();
+            ",
+            assertion
+        );
+        let parsed = parser::run_parser_from_source("<source>".to_string(), source);
+        let program = typed(parsed.ok().unwrap().0);
+        let config = BuildConfig { flag_assert_as_constraint: true, ..config() };
+        let (_, _, warnings) = build_circuit_with_reports(program, config).ok().unwrap();
+        let not_promoted =
+            warnings.iter().filter(|r| matches!(r.get_code(), ReportCode::AssertNotPromoted));
+        not_promoted.map(|r| r.get_message().clone()).collect()
+    }
+
+    #[test]
+    fn unpromoted_asserts_are_reported() {
+        assert!(assert_warnings("assert(a == b);").is_empty());
+        assert_eq!(
+            assert_warnings("assert(a * b * c == d);"),
+            ["Assertion not promoted: non quadratic"]
+        );
+        assert_eq!(assert_warnings("assert(a != b);"), ["Assertion not promoted: not an equality"]);
+        assert_eq!(
+            assert_warnings("if (a == 0) { assert(b == c); }"),
+            ["Assertion not promoted: inside an unknown block"]
+        );
+        // Asserts over the parameters are checked while building
+        assert!(assert_warnings("var n = 2; assert(n > 1);").is_empty());
+    }
+
+    #[test]
+    fn custom_primes_reach_the_r1cs_header() {
+        use std::convert::TryInto;
+        let small = BuildConfig { custom_prime: Option::Some(BigInt::from(257)), ..config() };
+        let (exporter, _, _) = build_circuit_with_reports(unconstrained_input(), small).unwrap();
+        assert_eq!(exporter.r1cs_header().field, BigInt::from(257));
+        let name = format!("circom_custom_prime_{}.r1cs", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        let file = file.to_str().unwrap();
+        exporter.r1cs(file).unwrap();
+        let bytes = std::fs::read(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        // The header section follows the constraints: type, size, field size and prime
+        let read_u64 = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        let header = 24 + read_u64(16) as usize;
+        assert_eq!(bytes[header..header + 4], 1u32.to_le_bytes());
+        assert_eq!(bytes[header + 12..header + 16], 8u32.to_le_bytes());
+        assert_eq!(read_u64(header + 16), 257);
+        let composite = BuildConfig { custom_prime: Option::Some(BigInt::from(255)), ..config() };
+        assert!(build_circuit_with_reports(unconstrained_input(), composite).is_err());
+    }
+
+    #[test]
+    fn inline_sources_are_compiled() {
+        let (_, vcp) = build_from_source(MULTIPLIER, config()).ok().unwrap();
+        assert_eq!(vcp.templates[vcp.main_id].template_name, "Multiplier");
+        assert_eq!(vcp.witness_list.len(), 4);
+        let unparsable = MULTIPLIER.replace("c <== a*b;", "c <== a*;");
+        assert!(build_from_source(&unparsable, config()).is_err());
+    }
+
+    #[test]
+    fn inline_sources_resolve_generator_powers() {
+        let scaled = MULTIPLIER.replace("c <== a*b;", "c <== a*b*(generator ** 2);");
+        assert!(build_from_source(&scaled, config()).is_ok());
+        // No generator is known for a custom prime
+        let small = BuildConfig { custom_prime: Option::Some(BigInt::from(257)), ..config() };
+        assert!(build_from_source(&scaled, small).is_err());
+    }
+
+    #[test]
+    fn witness_layout_follows_the_simplification() {
+        // m === a is removed by the simplification, m is replaced by a
+        let source = MULTIPLIER.replace("c <== a*b;", "signal m; m <== a; c <== m*b;");
+        let (_, vcp) = build_from_source(&source, config()).ok().unwrap();
+        let layout = vcp.witness_layout();
+        let witness: Vec<_> = layout.iter().map(|e| (e.name.as_str(), e.witness)).collect();
+        let expected = [
+            ("main.c", Option::Some(1)),
+            ("main.a", Option::Some(2)),
+            ("main.b", Option::Some(3)),
+            ("main.m", Option::None),
+        ];
+        assert_eq!(witness, expected);
+        let positions: Vec<_> = layout.iter().filter_map(|e| e.witness).collect();
+        assert_eq!(positions.len() + 1, vcp.witness_list.len());
+    }
 }
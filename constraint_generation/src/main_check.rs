@@ -0,0 +1,102 @@
+use num_bigint::BigInt;
+use program_structure::ast::{Expression, FillMeta, Meta};
+use program_structure::expression_builders::{build_call, build_number};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::Report;
+use program_structure::program_archive::ProgramArchive;
+
+fn at_main(mut report: Report, call: &Expression, msg: String) -> Report {
+    let meta = call.get_meta();
+    report.add_primary(meta.file_location(), meta.get_file_id(), msg);
+    report
+}
+
+// Cheap checks on `component main` so the most common top level mistakes are
+// reported before the instantiation starts
+pub fn validate_main(program: &ProgramArchive) -> Result<(), Report> {
+    let call = &program.initial_template_call;
+    let (id, args) = if let Expression::Call { id, args, .. } = call {
+        (id, args)
+    } else {
+        let report = Report::error(
+            "No main specified in the project structure".to_string(),
+            ReportCode::NoMainFoundInProject,
+        );
+        return Result::Err(at_main(report, call, "main must be a template call".to_string()));
+    };
+    if !program.contains_template(id) {
+        let msg = if program.contains_function(id) {
+            format!("The main component calls {}, which is a function and not a template", id)
+        } else {
+            format!("The main component calls the unknown template {}", id)
+        };
+        let report = Report::error(msg, ReportCode::UndefinedTemplate);
+        return Result::Err(at_main(report, call, "main is declared here".to_string()));
+    }
+    let expected = program.get_template_data(id).get_num_of_params();
+    if args.len() != expected {
+        let msg = format!(
+            "The main component calls {} with {} arguments but the template takes {}",
+            id,
+            args.len(),
+            expected
+        );
+        let report = Report::error(msg, ReportCode::TemplateWrongNumberOfArguments);
+        return Result::Err(at_main(report, call, "main is declared here".to_string()));
+    }
+    Result::Ok(())
+}
+
+// The declared main is replaced by template(params), every input of the
+// new main is private. validate_main checks the template and the params
+pub fn override_main(program: &mut ProgramArchive, template: &str, params: &[BigInt]) {
+    let args = params.iter().map(|p| build_number(Meta::new(0, 0), p.clone())).collect();
+    let mut call = build_call(Meta::new(0, 0), template.to_string(), args);
+    call.fill(program.file_id_main, &mut program.id_max);
+    program.initial_template_call = call;
+    program.public_inputs.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use circom_algebra::num_bigint::BigInt;
+    use program_structure::ast::{build_template, Meta};
+    use program_structure::expression_builders::{build_call, build_number, build_variable};
+    use program_structure::file_definition::FileLibrary;
+    use program_structure::statement_builders::build_block;
+
+    fn meta() -> Meta {
+        Meta::new(0, 0)
+    }
+
+    // A single template Num(n) with an empty body
+    fn program(main: Expression) -> ProgramArchive {
+        let body = build_block(meta(), vec![]);
+        let num =
+            build_template(meta(), "Num".to_string(), vec!["n".to_string()], 0..0, body, false);
+        let mut file_library = FileLibrary::new();
+        let file_id = file_library.add_file("main.circom".to_string(), String::new());
+        let contents = vec![(file_id, vec![num])];
+        ProgramArchive::new(file_library, file_id, (vec![], main), contents).ok().unwrap()
+    }
+
+    fn main_call(template: &str, no_args: u32) -> Expression {
+        let args = (0..no_args).map(|a| build_number(meta(), BigInt::from(a))).collect();
+        build_call(meta(), template.to_string(), args)
+    }
+
+    #[test]
+    fn missing_main_is_reported() {
+        let not_a_call = build_variable(meta(), "Num".to_string(), vec![]);
+        assert!(validate_main(&program(not_a_call)).is_err());
+        assert!(validate_main(&program(main_call("Num", 1))).is_ok());
+    }
+
+    #[test]
+    fn main_must_reference_an_existing_template() {
+        let report = validate_main(&program(main_call("Nun", 1))).err().unwrap();
+        assert_eq!(report.get_message(), "The main component calls the unknown template Nun");
+        assert!(validate_main(&program(main_call("Num", 2))).is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use regex::Regex;
+
+fn naming_report(signal: &str, pattern: &str, werror: bool) -> Report {
+    let msg = format!(
+        "Public signal \"{}\" of the main component does not match the pattern \"{}\"",
+        signal, pattern
+    );
+    if werror {
+        Report::error(msg, ReportCode::PublicSignalName)
+    } else {
+        Report::warning(msg, ReportCode::PublicSignalName)
+    }
+}
+
+// The whole name of the signal must match the pattern
+pub fn check_public_signal_names(
+    signals: &[String],
+    pattern: &str,
+    werror: bool,
+) -> Result<ReportCollection, ReportCollection> {
+    let regex = match Regex::new(&format!("^(?:{})$", pattern)) {
+        Result::Ok(regex) => regex,
+        Result::Err(_) => {
+            let msg = format!("Invalid public signal pattern \"{}\"", pattern);
+            return Result::Err(vec![Report::error(msg, ReportCode::PublicSignalName)]);
+        }
+    };
+    let reports: ReportCollection = signals
+        .iter()
+        .filter(|signal| !regex.is_match(signal))
+        .map(|signal| naming_report(signal, pattern, werror))
+        .collect();
+    if werror && !reports.is_empty() {
+        Result::Err(reports)
+    } else {
+        Result::Ok(reports)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signals() -> Vec<String> {
+        vec!["out_hash".to_string(), "root".to_string(), "out_sum".to_string()]
+    }
+
+    #[test]
+    fn violations_are_warnings_by_default() {
+        let reports = check_public_signal_names(&signals(), "out_.*", false).ok().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].is_warning());
+        assert!(reports[0].get_message().contains("\"root\""));
+        assert!(reports[0].get_message().contains("\"out_.*\""));
+    }
+
+    #[test]
+    fn violations_fail_under_werror() {
+        let reports = check_public_signal_names(&signals(), "out_.*", true).err().unwrap();
+        assert!(reports[0].is_error());
+        // the pattern must match the whole name
+        assert_eq!(check_public_signal_names(&signals(), "out", false).ok().unwrap().len(), 3);
+        assert!(check_public_signal_names(&signals(), "(", false).is_err());
+    }
+}
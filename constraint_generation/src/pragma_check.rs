@@ -0,0 +1,67 @@
+use program_structure::ast::Pragma;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum PragmaPolicy {
+    #[default]
+    Warn,
+    Error,
+}
+
+fn unknown_pragma_report(pragma: &Pragma, policy: PragmaPolicy) -> Report {
+    let msg = format!("Unknown pragma: {}", pragma.text);
+    let mut report = match policy {
+        PragmaPolicy::Warn => Report::warning(msg, ReportCode::UnknownPragma),
+        PragmaPolicy::Error => Report::error(msg, ReportCode::UnknownPragma),
+    };
+    report.add_primary(
+        pragma.meta.file_location(),
+        pragma.meta.get_file_id(),
+        "this pragma is not supported by the compiler".to_string(),
+    );
+    report
+}
+
+pub fn check_unknown_pragmas(
+    pragmas: &[Pragma],
+    policy: PragmaPolicy,
+) -> Result<ReportCollection, ReportCollection> {
+    let reports: ReportCollection =
+        pragmas.iter().map(|pragma| unknown_pragma_report(pragma, policy)).collect();
+    if policy == PragmaPolicy::Error && !reports.is_empty() {
+        Result::Err(reports)
+    } else {
+        Result::Ok(reports)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use program_structure::ast::{build_pragma, Meta};
+
+    fn custom_templates() -> Vec<Pragma> {
+        let mut meta = Meta::new(20, 45);
+        meta.set_file_id(0);
+        vec![build_pragma(meta, "custom_templates".to_string(), vec![])]
+    }
+
+    #[test]
+    fn unknown_pragmas_warn_by_default() {
+        let reports =
+            check_unknown_pragmas(&custom_templates(), PragmaPolicy::default()).ok().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].is_warning());
+        assert_eq!(reports[0].get_message(), "Unknown pragma: pragma custom_templates;");
+        assert!(check_unknown_pragmas(&[], PragmaPolicy::Error).is_ok());
+    }
+
+    #[test]
+    fn unknown_pragmas_fail_with_error_policy() {
+        let reports = check_unknown_pragmas(&custom_templates(), PragmaPolicy::Error);
+        let reports = reports.err().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].is_error());
+    }
+}
@@ -0,0 +1,94 @@
+use program_structure::ast::{Expression, Statement};
+use program_structure::ast_walker::walk_expressions;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::program_archive::ProgramArchive;
+use std::collections::{BTreeSet, HashSet};
+
+// Asserts, logs and the dimensions of declarations count as reads
+fn statement_reads(stmt: &Statement) -> HashSet<String> {
+    let mut reads = HashSet::new();
+    walk_expressions(stmt, &mut |expr| {
+        if let Expression::Variable { name, .. } = expr {
+            reads.insert(name.clone());
+        }
+    });
+    reads
+}
+
+// Parameters of the instantiated templates that are never read in the body,
+// one warning per template and parameter
+pub fn unused_parameters(
+    program: &ProgramArchive,
+    instantiated: &BTreeSet<String>,
+) -> ReportCollection {
+    let mut reports = ReportCollection::new();
+    for name in instantiated {
+        let template = program.get_template_data(name);
+        let reads = statement_reads(template.get_body());
+        for param in template.get_name_of_params() {
+            if reads.contains(param) {
+                continue;
+            }
+            let msg = format!("Parameter {} of template {} is never used", param, name);
+            let mut report = Report::warning(msg, ReportCode::UnusedTemplateParameter);
+            report.add_primary(
+                template.get_param_location(),
+                template.get_file_id(),
+                "declared here".to_string(),
+            );
+            report.add_note("The parameter can be removed from the template".to_string());
+            reports.push(report);
+        }
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use circom_algebra::num_bigint::BigInt;
+    use program_structure::ast::{build_template, Meta, SignalElementType, SignalType, VariableType};
+    use program_structure::expression_builders::{build_call, build_number, build_variable};
+    use program_structure::file_definition::FileLibrary;
+    use program_structure::statement_builders::{build_assert, build_block, build_declaration};
+
+    fn meta() -> Meta {
+        Meta::new(0, 0)
+    }
+
+    fn variable(name: &str) -> Expression {
+        build_variable(meta(), name.to_string(), vec![])
+    }
+
+    // Params(a, b, c, d): a sizes a signal array, b is only asserted and the
+    // other two are never read
+    fn program() -> ProgramArchive {
+        let signal = VariableType::Signal(SignalType::Input, SignalElementType::FieldElement);
+        let body = build_block(
+            meta(),
+            vec![
+                build_declaration(meta(), signal, "in".to_string(), vec![variable("a")]),
+                build_assert(meta(), variable("b")),
+            ],
+        );
+        let params = ["a", "b", "c", "d"].iter().map(|p| p.to_string()).collect();
+        let template = build_template(meta(), "Params".to_string(), params, 0..0, body, false);
+        let mut file_library = FileLibrary::new();
+        let file_id = file_library.add_file("main.circom".to_string(), String::new());
+        let args = (0..4).map(|a| build_number(meta(), BigInt::from(a))).collect();
+        let main = build_call(meta(), "Params".to_string(), args);
+        let contents = vec![(file_id, vec![template])];
+        ProgramArchive::new(file_library, file_id, (vec![], main), contents).ok().unwrap()
+    }
+
+    #[test]
+    fn parameters_read_anywhere_are_used() {
+        let instantiated = vec!["Params".to_string()].into_iter().collect();
+        let reports = unused_parameters(&program(), &instantiated);
+        assert_eq!(reports.len(), 2);
+        assert!(reports.iter().all(|r| r.is_warning()));
+        assert!(reports[0].get_message().contains("Parameter c "));
+        assert!(reports[1].get_message().contains("Parameter d "));
+    }
+}
@@ -0,0 +1,71 @@
+use super::Matrices;
+use ark_ff::{BigInteger, PrimeField};
+use ark_relations::r1cs::{ConstraintMatrices, Matrix};
+use circom_algebra::num_bigint::{BigInt, Sign};
+
+fn modulus<F: PrimeField>() -> BigInt {
+    BigInt::from_bytes_le(Sign::Plus, &F::MODULUS.to_bytes_le())
+}
+
+// The coefficients are already reduced, they are below the modulus of F
+// once it is checked to be the field of the circuit
+fn to_field<F: PrimeField>(value: &BigInt) -> F {
+    let (_, bytes) = value.to_bytes_le();
+    F::from_le_bytes_mod_order(&bytes)
+}
+
+// Arkworks rows are (coefficient, variable) pairs
+fn to_matrix<F: PrimeField>(rows: &super::Matrix) -> Matrix<F> {
+    rows.iter().map(|row| row.iter().map(|(wire, v)| (to_field(v), *wire)).collect()).collect()
+}
+
+fn non_zero(rows: &super::Matrix) -> usize {
+    rows.iter().map(|row| row.len()).sum()
+}
+
+// The wires keep their indexes: arkworks numbers the instance variables
+// (the constant one and the public signals) first, then the witness ones
+pub fn constraint_matrices<F: PrimeField>(matrices: &Matrices) -> ConstraintMatrices<F> {
+    // Reducing into another field would silently change the constraints
+    assert!(modulus::<F>() == matrices.field, "the modulus of F is not the prime of the circuit");
+    let num_instance_variables = matrices.no_public();
+    ConstraintMatrices {
+        num_instance_variables,
+        num_witness_variables: matrices.no_wires - num_instance_variables,
+        num_constraints: matrices.no_constraints(),
+        a_num_non_zero: non_zero(&matrices.a),
+        b_num_non_zero: non_zero(&matrices.b),
+        c_num_non_zero: non_zero(&matrices.c),
+        a: to_matrix(&matrices.a),
+        b: to_matrix(&matrices.b),
+        c: to_matrix(&matrices.c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::modulus;
+    use crate::witness::tests::{multiplier, multiplier_over};
+    use ark_bn254::Fr;
+    use ark_ff::One;
+
+    #[test]
+    fn multiplier_matrices_in_arkworks_layout() {
+        let matrices = multiplier_over(modulus::<Fr>()).to_arkworks::<Fr>();
+        // one and out are the instance, a and b the witness
+        assert_eq!(matrices.num_instance_variables, 2);
+        assert_eq!(matrices.num_witness_variables, 2);
+        assert_eq!(matrices.num_constraints, 1);
+        assert_eq!(matrices.a, vec![vec![(Fr::one(), 2)]]);
+        assert_eq!(matrices.b, vec![vec![(Fr::one(), 3)]]);
+        assert_eq!(matrices.c, vec![vec![(Fr::one(), 1)]]);
+        assert_eq!(matrices.a_num_non_zero + matrices.b_num_non_zero + matrices.c_num_non_zero, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "the modulus of F is not the prime of the circuit")]
+    fn other_fields_are_rejected() {
+        // The multiplier is over 257
+        multiplier().to_arkworks::<Fr>();
+    }
+}
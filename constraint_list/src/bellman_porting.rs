@@ -0,0 +1,90 @@
+use super::Matrices;
+use constraint_writers::bellman_writer::{self, BellmanHeader};
+use constraint_writers::debug_writer::DebugWriter;
+use constraint_writers::r1cs_writer::HeaderData;
+use constraint_writers::ConstraintExporter;
+
+fn header(m: &Matrices) -> BellmanHeader {
+    BellmanHeader {
+        field: m.field.clone(),
+        no_wires: m.no_wires,
+        no_public: m.no_public(),
+        no_constraints: m.no_constraints(),
+    }
+}
+
+// Writes the constraints as (a, b, c) triplets of little endian field
+// elements in place of the r1cs file, for the loaders that build a bellman
+// circuit out of them. Every other output comes from the wrapped exporter
+pub struct BellmanExporter<E> {
+    inner: E,
+    // The matrices are only built when the file is written
+    matrices: fn(&E) -> Matrices,
+}
+
+impl<E: ConstraintExporter> BellmanExporter<E> {
+    pub fn new(inner: E, matrices: fn(&E) -> Matrices) -> BellmanExporter<E> {
+        BellmanExporter { inner, matrices }
+    }
+
+    pub fn header(&self) -> BellmanHeader {
+        header(&(self.matrices)(&self.inner))
+    }
+}
+
+impl<E: ConstraintExporter> ConstraintExporter for BellmanExporter<E> {
+    fn r1cs(&self, out: &str) -> Result<(), ()> {
+        let m = (self.matrices)(&self.inner);
+        let constraints: Vec<_> =
+            (0..m.no_constraints()).map(|i| [&m.a[i], &m.b[i], &m.c[i]]).collect();
+        bellman_writer::write(out, &header(&m), &constraints)
+    }
+
+    fn json_constraints(&self, writer: &DebugWriter) -> Result<(), ()> {
+        self.inner.json_constraints(writer)
+    }
+
+    fn sym(&self, out: &str) -> Result<(), ()> {
+        self.inner.sym(out)
+    }
+
+    fn sql_constraints(&self, out: &str) -> Result<(), ()> {
+        self.inner.sql_constraints(out)
+    }
+
+    fn manifest(&self, out: &str) -> Result<(), ()> {
+        self.inner.manifest(out)
+    }
+
+    fn r1cs_header(&self) -> HeaderData {
+        self.inner.r1cs_header()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::witness::tests::multiplier;
+    use crate::ConstraintList;
+    use circom_algebra::num_bigint::BigInt;
+
+    #[test]
+    fn bellman_files_read_back() {
+        let list = multiplier();
+        let no_constraints = list.get_no_constraints();
+        let exporter = BellmanExporter::new(list, ConstraintList::matrices);
+        let name = format!("circom_bellman_read_back_{}.bin", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        let file = file.to_str().unwrap();
+        exporter.r1cs(file).unwrap();
+        let (header, constraints) = bellman_writer::read(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        assert_eq!(header, exporter.header());
+        assert_eq!(header.no_constraints, no_constraints);
+        assert_eq!(constraints.len(), no_constraints);
+        let one = BigInt::from(1);
+        assert_eq!(constraints[0][0], vec![(2, one.clone())]);
+        assert_eq!(constraints[0][1], vec![(3, one.clone())]);
+        assert_eq!(constraints[0][2], vec![(1, one)]);
+    }
+}
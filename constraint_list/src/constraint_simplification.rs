@@ -1,16 +1,39 @@
 // Uncomment lines 163, 165, 336 and 338 to print cluster information
+use super::provenance::Provenance;
 use super::{ConstraintStorage, EncodingIterator, SEncoded, Simplifier, A, C, S};
 use crate::SignalMap;
 use circom_algebra::num_bigint::BigInt;
 use constraint_writers::json_writer::SubstitutionJSON;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet, LinkedList};
 use std::sync::Arc;
 
 const SUB_LOG: &str = "./log_substitution.json";
 
-fn log_substitutions(substitutions: &LinkedList<S>, writer: &mut Option<SubstitutionJSON>) {
+// Constraints left after a round of simplification, numbered with the
+// signals of the DAG as the witness is not rebuilt yet
+fn write_snapshot(
+    dir: &str,
+    round: usize,
+    storage: &ConstraintStorage,
+    linear: &LinkedList<C>,
+) -> Result<(), ()> {
+    use super::json_porting::transform_constraint_to_json;
+    use constraint_writers::json_writer::ConstraintJSON;
+    let mut writer = ConstraintJSON::new(&format!("{}/round_{}.json", dir, round))?;
+    for c_id in storage.get_ids() {
+        let constraint = storage.read_constraint(c_id).unwrap();
+        writer.write_constraint(&transform_constraint_to_json(&constraint).to_string())?;
+    }
+    for constraint in linear {
+        writer.write_constraint(&transform_constraint_to_json(constraint).to_string())?;
+    }
+    writer.end()
+}
+
+fn log_substitutions(substitutions: &LinkedList<S>, writers: &mut [SubstitutionJSON]) {
     use super::json_porting::port_substitution;
-    if let Some(w) = writer {
+    for w in writers {
         for s in substitutions {
             let (from, to) = port_substitution(s);
             w.write_substitution(&from, &to).unwrap();
@@ -188,7 +211,8 @@ fn eq_simplification(
     forbidden: Arc<HashSet<usize>>,
     no_vars: usize,
     field: &BigInt,
-    substitution_log: &mut Option<SubstitutionJSON>,
+    substitution_log: &mut [SubstitutionJSON],
+    threads: Option<usize>,
 ) -> (LinkedList<S>, LinkedList<C>) {
     use std::sync::mpsc;
     use threadpool::ThreadPool;
@@ -198,16 +222,16 @@ fn eq_simplification(
     let mut substitutions = LinkedList::new();
     let clusters = build_clusters(equalities, no_vars);
     let (cluster_tx, simplified_rx) = mpsc::channel();
-    let pool = ThreadPool::new(num_cpus::get());
+    let pool = ThreadPool::new(pool_size(threads));
     let no_clusters = Vec::len(&clusters);
     // println!("Clusters: {}", no_clusters);
+    // The results are collected in cluster order, whatever the size of the pool
+    let mut results: Vec<Option<(LinkedList<S>, LinkedList<C>)>> =
+        (0..no_clusters).map(|_| None).collect();
     let mut single_clusters = 0;
-    let mut id = 0;
-    for cluster in clusters {
+    for (id, cluster) in clusters.into_iter().enumerate() {
         if Cluster::size(&cluster) == 1 {
-            let (mut subs, mut cons) = eq_cluster_simplification(cluster, &forbidden, &field);
-            LinkedList::append(&mut substitutions, &mut subs);
-            LinkedList::append(&mut constraints, &mut cons);
+            results[id] = Some(eq_cluster_simplification(cluster, &forbidden, &field));
             single_clusters += 1;
         } else {
             let cluster_tx = cluster_tx.clone();
@@ -217,17 +241,18 @@ fn eq_simplification(
                 //println!("Cluster: {}", id);
                 let result = eq_cluster_simplification(cluster, &forbidden, &field);
                 //println!("End of cluster: {}", id);
-                cluster_tx.send(result).unwrap();
+                cluster_tx.send((id, result)).unwrap();
             };
             ThreadPool::execute(&pool, job);
         }
-        let _ = id;
-        id += 1;
     }
     // println!("{} clusters were of size 1", single_clusters);
     ThreadPool::join(&pool);
     for _ in 0..(no_clusters - single_clusters) {
-        let (mut subs, mut cons) = simplified_rx.recv().unwrap();
+        let (id, result) = simplified_rx.recv().unwrap();
+        results[id] = Some(result);
+    }
+    for (mut subs, mut cons) in results.into_iter().map(Option::unwrap) {
         LinkedList::append(&mut substitutions, &mut subs);
         LinkedList::append(&mut constraints, &mut cons);
     }
@@ -239,7 +264,7 @@ fn constant_eq_simplification(
     c_eq: LinkedList<C>,
     forbidden: &HashSet<usize>,
     field: &BigInt,
-    substitution_log: &mut Option<SubstitutionJSON>,
+    substitution_log: &mut [SubstitutionJSON],
 ) -> (LinkedList<S>, LinkedList<C>) {
     let mut cons = LinkedList::new();
     let mut subs = LinkedList::new();
@@ -257,48 +282,75 @@ fn constant_eq_simplification(
     (subs, cons)
 }
 
-fn linear_simplification(
-    log: &mut Option<SubstitutionJSON>,
-    linear: LinkedList<C>,
-    forbidden: Arc<HashSet<usize>>,
-    no_labels: usize,
-    field: &BigInt,
-) -> (LinkedList<S>, LinkedList<C>) {
+// All the cores unless a thread budget is given, the cli rejects a budget of 0
+fn pool_size(threads: Option<usize>) -> usize {
+    threads.map_or_else(num_cpus::get, |threads| std::cmp::max(threads, 1))
+}
+
+type ClusterConfig = circom_algebra::simplification_utils::Config<Arc<HashSet<usize>>>;
+type Simplified = circom_algebra::simplification_utils::Simplified;
+
+// The clusters are the connected components of the linear constraints (two
+// constraints are connected when they share a signal, public ones included),
+// so they can be simplified independently. The results are collected in
+// cluster order, the output does not depend on the scheduling of the pool.
+fn parallel_cluster_simplification(
+    configs: Vec<ClusterConfig>,
+    threads: Option<usize>,
+) -> Vec<Simplified> {
     use circom_algebra::simplification_utils::full_simplification;
-    use circom_algebra::simplification_utils::Config;
     use std::sync::mpsc;
     use threadpool::ThreadPool;
 
-    // println!("Cluster simplification");
-    let mut cons = LinkedList::new();
-    let mut substitutions = LinkedList::new();
-    let clusters = build_clusters(linear, no_labels);
     let (cluster_tx, simplified_rx) = mpsc::channel();
-    let pool = ThreadPool::new(num_cpus::get());
-    let no_clusters = Vec::len(&clusters);
-    // println!("Clusters: {}", no_clusters);
-    let mut id = 0;
-    for cluster in clusters {
+    let pool = ThreadPool::new(pool_size(threads));
+    let no_clusters = Vec::len(&configs);
+    for (id, config) in configs.into_iter().enumerate() {
         let cluster_tx = cluster_tx.clone();
-        let config = Config {
-            field: field.clone(),
-            constraints: cluster.constraints,
-            forbidden: Arc::clone(&forbidden),
-        };
         let job = move || {
-            // println!("cluster: {}", id);
             let result = full_simplification(config);
-            // println!("End of cluster: {}", id);
-            cluster_tx.send(result).unwrap();
+            cluster_tx.send((id, result)).unwrap();
         };
         ThreadPool::execute(&pool, job);
-        let _ = id;
-        id += 1;
     }
     ThreadPool::join(&pool);
 
+    let mut results: Vec<Option<Simplified>> = (0..no_clusters).map(|_| None).collect();
     for _ in 0..no_clusters {
-        let mut result = simplified_rx.recv().unwrap();
+        let (id, result) = simplified_rx.recv().unwrap();
+        results[id] = Some(result);
+    }
+    results.into_iter().map(Option::unwrap).collect()
+}
+
+fn linear_simplification(
+    log: &mut [SubstitutionJSON],
+    linear: LinkedList<C>,
+    forbidden: Arc<HashSet<usize>>,
+    no_labels: usize,
+    field: &BigInt,
+    threads: Option<usize>,
+) -> (LinkedList<S>, LinkedList<C>) {
+    // println!("Cluster simplification");
+    let mut cons = LinkedList::new();
+    let mut substitutions = LinkedList::new();
+    let clusters = build_clusters(linear, no_labels);
+    // println!("Clusters: {}", clusters.len());
+    let configs: Vec<_> = clusters
+        .into_iter()
+        .map(|cluster| ClusterConfig {
+            field: field.clone(),
+            constraints: cluster.constraints,
+            forbidden: Arc::clone(&forbidden),
+        })
+        .collect();
+    let results = parallel_cluster_simplification(configs, threads);
+
+    for mut result in results {
+        // the order inside a cluster depends on hashing, not on the run
+        let mut sorted: Vec<_> = std::mem::take(&mut result.substitutions).into_iter().collect();
+        sorted.sort_by_key(|s| *s.from());
+        result.substitutions = sorted.into_iter().collect();
         log_substitutions(&result.substitutions, log);
         LinkedList::append(&mut cons, &mut result.constraints);
         LinkedList::append(&mut substitutions, &mut result.substitutions);
@@ -418,15 +470,34 @@ fn remove_not_relevant(substitutions: &mut SEncoded, relevant: &HashSet<usize>)
     }
 }
 
-pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
+// Keeps the first constraint of every canonical form, a===b and 2*a===2*b
+// are the same constraint
+fn remove_duplicates(storage: &mut ConstraintStorage, field: &BigInt) -> LinkedList<C> {
+    let seen = RefCell::new(HashSet::new());
+    storage.extract_with(&|c| !seen.borrow_mut().insert(c.canonical_form(field)))
+}
+
+// Substitutions of the run, only kept to trace the provenance
+fn record(applied: &mut Option<Vec<S>>, substitutions: &LinkedList<S>) {
+    if let Some(applied) = applied {
+        applied.extend(substitutions.iter().cloned());
+    }
+}
+
+pub fn simplification(
+    smp: &mut Simplifier,
+) -> (ConstraintStorage, SignalMap, Option<Vec<Provenance>>) {
     use super::non_linear_utils::obtain_and_simplify_non_linear;
     use circom_algebra::simplification_utils::build_encoded_fast_substitutions;
     use circom_algebra::simplification_utils::fast_encoded_constraint_substitution;
     use std::time::SystemTime;
 
-    let mut substitution_log =
-        if smp.port_substitution { Some(SubstitutionJSON::new(SUB_LOG).unwrap()) } else { None };
+    let mut substitution_log: Vec<_> = smp.substitution_log.take().into_iter().collect();
+    if smp.port_substitution {
+        substitution_log.push(SubstitutionJSON::new(SUB_LOG).unwrap());
+    }
     let apply_linear = !smp.flag_s;
+    let threads = smp.threads;
     let field = smp.field.clone();
     let forbidden = Arc::new(std::mem::replace(&mut smp.forbidden, HashSet::with_capacity(0)));
     let no_labels = Simplifier::no_labels(smp);
@@ -437,6 +508,7 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
     let mut deleted = HashSet::new();
     let mut lconst = LinkedList::new();
     let mut no_rounds = smp.no_rounds;
+    let mut applied = smp.originals.as_ref().map(|_| Vec::new());
 
     let relevant_signals = {
         // println!("Creating first relevant set");
@@ -460,9 +532,11 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
             no_labels,
             &field,
             &mut substitution_log,
+            threads,
         );
 
         LinkedList::append(&mut lconst, &mut cons);
+        record(&mut applied, &subs);
         let mut substitutions = build_encoded_fast_substitutions(subs);
         for constraint in &mut linear {
             fast_encoded_constraint_substitution(constraint, &substitutions, &field);
@@ -485,6 +559,7 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
         let (subs, mut cons) =
             constant_eq_simplification(cons_equalities, &forbidden, &field, &mut substitution_log);
         LinkedList::append(&mut lconst, &mut cons);
+        record(&mut applied, &subs);
         let substitutions = build_encoded_fast_substitutions(subs);
         for constraint in &mut linear {
             fast_encoded_constraint_substitution(constraint, &substitutions, &field);
@@ -516,9 +591,11 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
             Arc::clone(&forbidden),
             no_labels,
             &field,
+            threads,
         );
         // println!("Building substitution map");
         let now0 = SystemTime::now();
+        record(&mut applied, &subs);
         let mut only_relevant = LinkedList::new();
         for substitution in subs {
             deleted.insert(*substitution.from());
@@ -559,8 +636,14 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
     };
 
     let mut round_id = 0;
-    let _ = round_id;
     let mut linear = with_linear;
+    if let Some(progress) = &smp.progress {
+        progress(round_id, linear.len());
+    }
+    // Snapshots are only a debugging aid, failing to write one does not stop the simplification
+    if let Some(dir) = &smp.intermediates {
+        let _ = write_snapshot(dir, round_id, &constraint_storage, &linear);
+    }
     let mut apply_round = apply_linear && no_rounds > 0 && !linear.is_empty();
     let mut non_linear_map = if apply_round {
         // println!("Building non-linear map");
@@ -581,8 +664,10 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
             Arc::clone(&forbidden),
             no_labels,
             &field,
+            threads,
         );
 
+        record(&mut applied, &substitutions);
         for sub in &substitutions {
             deleted.insert(*sub.from());
         }
@@ -600,6 +685,12 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
         );
         round_id += 1;
         no_rounds -= 1;
+        if let Some(progress) = &smp.progress {
+            progress(round_id, linear.len());
+        }
+        if let Some(dir) = &smp.intermediates {
+            let _ = write_snapshot(dir, round_id, &constraint_storage, &linear);
+        }
         apply_round = !linear.is_empty() && no_rounds > 0;
         let _dur = now.elapsed().unwrap().as_millis();
         // println!("Iteration no {} took {} ms", round_id, dur);
@@ -612,17 +703,46 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
         constraint_storage.add_constraint(constraint);
     }
 
+    let traced = match (&smp.originals, applied) {
+        (Some(originals), Some(applied)) => Some(crate::provenance::trace(
+            originals,
+            &applied,
+            &constraint_storage,
+            &field,
+        )),
+        _ => None,
+    };
     let erased = crate::non_linear_simplification::simplify(
         &mut constraint_storage,
         &forbidden,
         &field
     );
 
+    let _trash = constraint_storage.extract_with(&|c| C::is_empty(c));
+    if smp.dedup_constraints {
+        let _duplicates = remove_duplicates(&mut constraint_storage, &field);
+    }
+    let provenance = traced.map(|traced| {
+        crate::provenance::rewritten(traced, &erased, &constraint_storage, &field)
+    });
+
     for signal in erased {
         deleted.insert(signal);
     }
 
-    let _trash = constraint_storage.extract_with(&|c| C::is_empty(c));
+    // Signals in no constraint are left out of the witness, the inputs and
+    // outputs of main keep their positions
+    let no_io = smp.no_public_inputs + smp.no_public_outputs + smp.no_private_inputs;
+    let mut used = HashSet::new();
+    for c_id in constraint_storage.get_ids() {
+        let constraint = constraint_storage.read_constraint(c_id).unwrap();
+        used.extend(constraint.take_cloned_signals());
+    }
+    for signal in (no_io + 1)..max_signal {
+        if !used.contains(&signal) && !forbidden.contains(&signal) {
+            deleted.insert(signal);
+        }
+    }
 
     let signal_map = {
         // println!("Rebuild witness");
@@ -633,9 +753,95 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
         signal_map
     };
 
-    if let Some(w) = substitution_log {
+    for w in substitution_log {
         w.end().unwrap();
     }
     // println!("NO CONSTANTS: {}", constraint_storage.no_constants());
-    (constraint_storage, signal_map)
+    (constraint_storage, signal_map, provenance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linear(signals: &[usize], field: &BigInt) -> C {
+        let mut expr = A::Number { value: BigInt::from(1) };
+        for (i, signal) in signals.iter().enumerate() {
+            let coefficient = A::Number { value: BigInt::from(i + 2) };
+            let term = A::mul(&coefficient, &A::Signal { symbol: *signal }, field);
+            expr = A::add(&expr, &term, field);
+        }
+        let mut constraint = A::transform_expression_to_constraint_form(expr, field).unwrap();
+        C::remove_zero_value_coefficients(&mut constraint);
+        constraint
+    }
+
+    fn as_strings(substitutions: &LinkedList<S>, constraints: &LinkedList<C>) -> Vec<String> {
+        let mut terms = Vec::new();
+        for s in substitutions {
+            let mut to: Vec<_> = s.to().iter().map(|(k, v)| format!("{}:{}", k, v)).collect();
+            to.sort();
+            terms.push(format!("{} -> {}", s.from(), to.join(" ")));
+        }
+        let mut remaining = Vec::new();
+        for c in constraints {
+            let mut lc: Vec<_> = c.c().iter().map(|(k, v)| format!("{}:{}", k, v)).collect();
+            lc.sort();
+            remaining.push(lc.join(" "));
+        }
+        remaining.sort();
+        terms.append(&mut remaining);
+        terms
+    }
+
+    // 50 independent subcircuits of 3 signals, the first one of each is
+    // public. Returns the number of labels, the public signals and the
+    // constraints, 49 clusters
+    fn subcircuits(field: &BigInt) -> (usize, Arc<HashSet<usize>>, LinkedList<C>) {
+        let no_labels = 1 + 50 * 3;
+        let mut forbidden: HashSet<_> = (0..50).map(|i| 1 + 3 * i).collect();
+        forbidden.insert(0);
+        let mut constraints = LinkedList::new();
+        for i in 0..50 {
+            let (x, y, z) = (1 + 3 * i, 2 + 3 * i, 3 + 3 * i);
+            constraints.push_back(linear(&[x, y], field));
+            constraints.push_back(linear(&[y, z], field));
+        }
+        // a public signal joins the first two subcircuits
+        constraints.push_back(linear(&[1, 6], field));
+        (no_labels, Arc::new(forbidden), constraints)
+    }
+
+    fn simplify_subcircuits(field: &BigInt, threads: Option<usize>) -> Vec<String> {
+        let (no_labels, forbidden, constraints) = subcircuits(field);
+        let (subs, cons) =
+            linear_simplification(&mut [], constraints, forbidden, no_labels, field, threads);
+        as_strings(&subs, &cons)
+    }
+
+    #[test]
+    fn parallel_clusters_match_the_sequential_run() {
+        let field = BigInt::from(257);
+        let (no_labels, forbidden, constraints) = subcircuits(&field);
+        assert_eq!(build_clusters(constraints.clone(), no_labels).len(), 49);
+        // A pool of one thread simplifies the clusters one after the other
+        let sequential = simplify_subcircuits(&field, Some(1));
+        let forbidden_set = Arc::clone(&forbidden);
+        let (subs, cons) =
+            linear_simplification(&mut [], constraints, forbidden_set, no_labels, &field, None);
+        assert_eq!(as_strings(&subs, &cons), sequential);
+        assert_eq!(subs.len(), 100);
+        assert!(subs.iter().all(|s| !forbidden.contains(s.from())));
+    }
+
+    #[test]
+    fn thread_budget_does_not_change_the_output() {
+        let field = BigInt::from(257);
+        let sequential = simplify_subcircuits(&field, Some(1));
+        for threads in [2, 3, 8].iter() {
+            assert_eq!(simplify_subcircuits(&field, Some(*threads)), sequential);
+        }
+        assert_eq!(pool_size(Some(3)), 3);
+        assert_eq!(pool_size(None), num_cpus::get());
+    }
 }
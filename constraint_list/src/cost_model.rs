@@ -0,0 +1,53 @@
+use super::ConstraintList;
+
+// Weights of a linear estimate of the proving cost. Linear constraints are
+// not weighted, most backends fold them into the non linear ones
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CostModel {
+    pub per_non_linear: f64,
+    pub per_witness: f64,
+    pub per_public: f64,
+}
+
+impl Default for CostModel {
+    // Roughly a groth16 prover: the multiexponentiations grow with the
+    // constraints and the witness, the verifier inputs with the public signals
+    fn default() -> Self {
+        CostModel { per_non_linear: 4.0, per_witness: 1.0, per_public: 1.0 }
+    }
+}
+
+pub fn proving_cost(list: &ConstraintList, model: &CostModel) -> f64 {
+    let counts = list.circuit_counts();
+    let public = counts.no_public_inputs + counts.no_public_outputs;
+    model.per_non_linear * counts.no_non_linear as f64
+        + model.per_witness * counts.no_witness as f64
+        + model.per_public * public as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::witness::tests::multiplier;
+    use circom_algebra::algebra::ArithmeticExpression;
+    type A = ArithmeticExpression<usize>;
+
+    #[test]
+    fn adding_constraints_increases_the_cost() {
+        let model = CostModel::default();
+        let mut list = multiplier();
+        let mut last = proving_cost(&list, &model);
+        for symbol in 1..4 {
+            let s = A::Signal { symbol };
+            let expr = A::sub(&A::mul(&s, &s, &list.field), &s, &list.field);
+            let constraint = A::transform_expression_to_constraint_form(expr, &list.field).unwrap();
+            list.constraints.add_constraint(constraint);
+            let cost = proving_cost(&list, &model);
+            assert!(cost > last);
+            last = cost;
+        }
+        // Four wires and one public output
+        let free = CostModel { per_non_linear: 0.0, ..model };
+        assert_eq!(proving_cost(&list, &free), 5.0);
+    }
+}
@@ -0,0 +1,24 @@
+use super::{ConstraintList, C};
+use constraint_writers::counts_writer::CircuitCounts;
+
+pub fn count(list: &ConstraintList) -> CircuitCounts {
+    let mut counts = CircuitCounts {
+        no_public_inputs: list.no_public_inputs,
+        no_public_outputs: list.no_public_outputs,
+        no_private_inputs: list.no_private_inputs,
+        no_template_instances: list.dag_encoding.nodes.len(),
+        no_witness: list.no_wires(),
+        ..CircuitCounts::default()
+    };
+    for c_id in list.constraints.get_ids() {
+        let c = list.constraints.read_constraint(c_id).unwrap();
+        if C::is_linear(&c) {
+            counts.no_linear += 1;
+        } else {
+            counts.no_non_linear += 1;
+        }
+    }
+    let io = counts.no_public_inputs + counts.no_public_outputs + counts.no_private_inputs;
+    counts.no_intermediates = counts.no_witness - 1 - io;
+    counts
+}
@@ -0,0 +1,128 @@
+use super::Matrices;
+use circom_algebra::modular_arithmetic;
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::Zero;
+use std::collections::{BTreeMap, HashMap};
+
+type Row = BTreeMap<usize, BigInt>;
+
+// k * row, added to the given row
+fn add_scaled(into: &mut Row, row: &[(usize, BigInt)], k: &BigInt, field: &BigInt) {
+    for (wire, coefficient) in row {
+        let term = modular_arithmetic::mul(k, coefficient, field);
+        let entry = into.entry(*wire).or_insert_with(BigInt::zero);
+        *entry = modular_arithmetic::add(entry, &term, field);
+    }
+    into.retain(|_, v| !v.is_zero());
+}
+
+// The constraint as a linear combination equal to zero, None when it is not linear
+fn linear_row(matrices: &Matrices, index: usize) -> Option<Row> {
+    let field = &matrices.field;
+    let (a, b, c) = (&matrices.a[index], &matrices.b[index], &matrices.c[index]);
+    let constant = |row: &[(usize, BigInt)]| match row {
+        [(0, k)] => Option::Some(k.clone()),
+        _ => Option::None,
+    };
+    let mut linear = Row::new();
+    let minus_one = modular_arithmetic::prefix_sub(&BigInt::from(1), field);
+    add_scaled(&mut linear, c, &minus_one, field);
+    if a.is_empty() || b.is_empty() {
+        Option::Some(linear)
+    } else if let Option::Some(k) = constant(a) {
+        add_scaled(&mut linear, b, &k, field);
+        Option::Some(linear)
+    } else if let Option::Some(k) = constant(b) {
+        add_scaled(&mut linear, a, &k, field);
+        Option::Some(linear)
+    } else {
+        Option::None
+    }
+}
+
+// Rank of the rows over the wires that are not the constant one. Every pivot
+// row is normalized on its largest wire, so reducing a row by a pivot only
+// leaves smaller wires behind
+fn rank(rows: Vec<Row>, field: &BigInt) -> usize {
+    let mut pivots: HashMap<usize, Vec<(usize, BigInt)>> = HashMap::new();
+    for mut row in rows {
+        row.remove(&0);
+        while let Option::Some((wire, coefficient)) = row.iter().next_back() {
+            let (wire, coefficient) = (*wire, coefficient.clone());
+            if let Option::Some(pivot) = pivots.get(&wire) {
+                let k = modular_arithmetic::prefix_sub(&coefficient, field);
+                add_scaled(&mut row, pivot, &k, field);
+                row.remove(&0);
+            } else {
+                let inverse = modular_arithmetic::div(&BigInt::from(1), &coefficient, field)
+                    .unwrap_or_else(|_| BigInt::from(1));
+                let normalized = row
+                    .iter()
+                    .map(|(w, v)| (*w, modular_arithmetic::mul(v, &inverse, field)))
+                    .collect();
+                pivots.insert(wire, normalized);
+                break;
+            }
+        }
+    }
+    pivots.len()
+}
+
+// Witness signals (every wire but the constant one) minus the independent
+// constraints. The linear constraints are counted by their rank, the non
+// linear ones are all taken as independent, so the result is a lower bound
+pub fn degrees_of_freedom(matrices: &Matrices) -> usize {
+    let mut linear = Vec::new();
+    let mut no_non_linear = 0;
+    for index in 0..matrices.no_constraints() {
+        match linear_row(matrices, index) {
+            Option::Some(row) => linear.push(row),
+            Option::None => no_non_linear += 1,
+        }
+    }
+    let independent = rank(linear, &matrices.field) + no_non_linear;
+    matrices.no_wires.saturating_sub(1).saturating_sub(independent)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::witness::tests::multiplier;
+    use crate::C;
+    use circom_algebra::algebra::ArithmeticExpression;
+    use circom_algebra::constraint_storage::ConstraintStorage;
+    use circom_algebra::num_bigint::BigInt;
+    type A = ArithmeticExpression<usize>;
+
+    fn signal(symbol: usize) -> A {
+        A::Signal { symbol }
+    }
+
+    fn constraint(expr: A, field: &BigInt) -> C {
+        A::transform_expression_to_constraint_form(expr, field).unwrap()
+    }
+
+    #[test]
+    fn determined_circuits_only_leave_the_inputs_free() {
+        // out = a*b
+        assert_eq!(multiplier().degrees_of_freedom(), 2);
+        // t = a*b; out = t + b, and the same linear constraint scaled by 3
+        let mut list = multiplier();
+        let field = list.field.clone();
+        let product = A::sub(&A::mul(&signal(2), &signal(3), &field), &signal(4), &field);
+        let sum = A::sub(&A::add(&signal(4), &signal(3), &field), &signal(1), &field);
+        let three = A::Number { value: BigInt::from(3) };
+        let scaled = A::mul(&three, &sum, &field);
+        let mut storage = ConstraintStorage::new();
+        for expr in [product, sum, scaled] {
+            storage.add_constraint(constraint(expr, &field));
+        }
+        list.constraints = storage;
+        list.signal_map = (0..5).map(|s| (s, s)).collect();
+        assert_eq!(list.degrees_of_freedom(), 2);
+        // Without the sum out is free too
+        list.constraints = ConstraintStorage::new();
+        let product = A::sub(&A::mul(&signal(2), &signal(3), &field), &signal(4), &field);
+        list.constraints.add_constraint(constraint(product, &field));
+        assert_eq!(list.degrees_of_freedom(), 3);
+    }
+}
@@ -0,0 +1,257 @@
+use super::json_porting::transform_constraint_to_json;
+use super::{ConstraintList, C};
+use circom_algebra::algebra::CanonicalConstraint;
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::Zero;
+use std::collections::HashMap;
+
+// Structural comparison of two constraint systems. The wires of the public
+// boundary (the constant one, outputs and inputs) must match position by
+// position, the rest of the wires may be renamed. The renaming is found by
+// refining a coloring of the wires with the constraints they appear in, so
+// the check is sound (a positive answer comes with a renaming under which
+// both systems have the same constraints up to order and scaling) but it is
+// not complete: systems that are only equivalent after simplifying, or
+// whose internal wires can not be told apart by the refinement, are
+// reported as different. It is not a proof of equal satisfying witnesses.
+pub fn is_equivalent(left: &ConstraintList, right: &ConstraintList) -> bool {
+    let same_shape = left.field == right.field
+        && left.no_wires() == right.no_wires()
+        && left.no_public_outputs == right.no_public_outputs
+        && left.no_public_inputs == right.no_public_inputs
+        && left.no_private_inputs == right.no_private_inputs
+        && left.constraints.get_ids().len() == right.constraints.get_ids().len();
+    same_shape && canonical_system(left) == canonical_system(right)
+}
+
+// Exact comparison, constraint by constraint in storage order, with a
+// description of the first difference found
+pub fn first_difference(left: &ConstraintList, right: &ConstraintList) -> Option<String> {
+    let boundary = |list: &ConstraintList| {
+        (list.no_wires(), list.no_public_outputs, list.no_public_inputs, list.no_private_inputs)
+    };
+    if boundary(left) != boundary(right) {
+        let msg = format!(
+            "(wires, outputs, public inputs, private inputs) are {:?} against {:?}",
+            boundary(left),
+            boundary(right)
+        );
+        return Option::Some(msg);
+    }
+    let (left, right) = (wire_constraints(left), wire_constraints(right));
+    for (position, (l, r)) in left.iter().zip(&right).enumerate() {
+        let l = transform_constraint_to_json(l).to_string();
+        let r = transform_constraint_to_json(r).to_string();
+        if l != r {
+            return Option::Some(format!("constraint {} is {} against {}", position, l, r));
+        }
+    }
+    if left.len() != right.len() {
+        return Option::Some(format!("{} constraints against {}", left.len(), right.len()));
+    }
+    Option::None
+}
+
+fn no_boundary(list: &ConstraintList) -> usize {
+    1 + list.no_public_outputs + list.no_public_inputs + list.no_private_inputs
+}
+
+fn wire_constraints(list: &ConstraintList) -> Vec<C> {
+    let mut constraints = Vec::new();
+    for c_id in list.constraints.get_ids() {
+        let constraint = list.constraints.read_constraint(c_id).unwrap();
+        constraints.push(C::apply_correspondence(&constraint, &list.signal_map));
+    }
+    constraints
+}
+
+// Sorted wires (with a non zero coefficient) of each side of a constraint, the product sides are dropped
+// when one of them is empty as canonical_form does
+fn sides(constraint: &C) -> [Vec<usize>; 3] {
+    let wires = |lc: &HashMap<usize, BigInt>| {
+        let mut wires: Vec<usize> =
+            lc.iter().filter(|(_, v)| !v.is_zero()).map(|(k, _)| *k).collect();
+        wires.sort_unstable();
+        wires
+    };
+    let (a, b) = (wires(constraint.a()), wires(constraint.b()));
+    if a.is_empty() || b.is_empty() {
+        [Vec::new(), Vec::new(), wires(constraint.c())]
+    } else {
+        [a, b, wires(constraint.c())]
+    }
+}
+
+// Replaces every signature by its rank among the distinct ones, so the new
+// colors only depend on the contents of the signatures
+fn rank<T: Ord + Clone>(signatures: &[T]) -> Vec<usize> {
+    let mut distinct = signatures.to_vec();
+    distinct.sort();
+    distinct.dedup();
+    signatures.iter().map(|s| distinct.binary_search(s).unwrap()).collect()
+}
+
+fn refine_colors(
+    no_wires: usize,
+    no_boundary: usize,
+    constraints: &[[Vec<usize>; 3]],
+) -> Vec<usize> {
+    let mut colors: Vec<usize> = (0..no_wires).map(|w| usize::min(w, no_boundary)).collect();
+    let mut no_colors = usize::min(no_wires, no_boundary + 1);
+    loop {
+        let mut constraint_colors = Vec::with_capacity(constraints.len());
+        for sides in constraints {
+            let mut sides: Vec<Vec<usize>> = sides
+                .iter()
+                .map(|wires| {
+                    let mut side: Vec<_> = wires.iter().map(|w| colors[*w]).collect();
+                    side.sort_unstable();
+                    side
+                })
+                .collect();
+            if sides[1] < sides[0] {
+                sides.swap(0, 1);
+            }
+            constraint_colors.push(sides);
+        }
+        let constraint_colors = rank(&constraint_colors);
+        let mut appearances = vec![Vec::new(); no_wires];
+        for (position, sides) in constraints.iter().enumerate() {
+            for (side, wires) in sides.iter().enumerate() {
+                // A and B are interchangeable
+                let side = usize::min(side, 1);
+                for wire in wires {
+                    appearances[*wire].push((constraint_colors[position], side));
+                }
+            }
+        }
+        let signatures: Vec<_> = appearances
+            .into_iter()
+            .enumerate()
+            .map(|(wire, mut appearances)| {
+                appearances.sort_unstable();
+                (colors[wire], appearances)
+            })
+            .collect();
+        colors = rank(&signatures);
+        // The ranks are dense, the old color is part of the signature so
+        // the number of colors never decreases
+        let refined = colors.iter().max().map_or(0, |max| max + 1);
+        if refined == no_colors {
+            return colors;
+        }
+        no_colors = refined;
+    }
+}
+
+fn canonical_system(list: &ConstraintList) -> Vec<CanonicalConstraint> {
+    let no_wires = list.no_wires();
+    let no_boundary = usize::min(no_boundary(list), no_wires);
+    let constraints = wire_constraints(list);
+    let wire_sides: Vec<_> = constraints.iter().map(sides).collect();
+    let colors = refine_colors(no_wires, no_boundary, &wire_sides);
+    let mut internal: Vec<usize> = (no_boundary..no_wires).collect();
+    internal.sort_by_key(|w| (colors[*w], *w));
+    let mut renaming: HashMap<usize, usize> = (0..no_boundary).map(|w| (w, w)).collect();
+    for (position, wire) in internal.into_iter().enumerate() {
+        renaming.insert(wire, no_boundary + position);
+    }
+    let mut system: Vec<_> = constraints
+        .iter()
+        .map(|c| C::apply_correspondence(c, &renaming).canonical_form(&list.field))
+        .collect();
+    system.sort();
+    system
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::witness::tests::multiplier;
+    use circom_algebra::algebra::ArithmeticExpression;
+    use circom_algebra::constraint_storage::ConstraintStorage;
+    type A = ArithmeticExpression<usize>;
+
+    fn signal(symbol: usize) -> A {
+        A::Signal { symbol }
+    }
+
+    fn number(value: i32) -> A {
+        A::Number { value: BigInt::from(value) }
+    }
+
+    // a*b - c = 0
+    fn product(a: A, b: A, c: A, field: &BigInt) -> C {
+        let expr = A::sub(&A::mul(&a, &b, field), &c, field);
+        A::transform_expression_to_constraint_form(expr, field).unwrap()
+    }
+
+    // Boundary of the multiplier (1 = out, 2 = a, 3 = b) plus two internal wires
+    fn with_constraints(constraints: Vec<C>) -> ConstraintList {
+        let mut list = multiplier();
+        let mut storage = ConstraintStorage::new();
+        for constraint in constraints {
+            storage.add_constraint(constraint);
+        }
+        list.constraints = storage;
+        list.signal_map = (0..6).map(|s| (s, s)).collect();
+        list
+    }
+
+    // t = a*b; u = t*a; out = u + b
+    fn original() -> ConstraintList {
+        let field = multiplier().field;
+        with_constraints(vec![
+            product(signal(2), signal(3), signal(4), &field),
+            product(signal(4), signal(2), signal(5), &field),
+            product(number(1), A::add(&signal(5), &signal(3), &field), signal(1), &field),
+        ])
+    }
+
+    #[test]
+    fn reordered_circuits_are_equivalent() {
+        let field = multiplier().field;
+        // Internal wires swapped, constraints reordered, scaled and with A and B swapped
+        let refactored = with_constraints(vec![
+            product(
+                number(2),
+                A::add(&signal(4), &signal(3), &field),
+                A::mul(&number(2), &signal(1), &field),
+                &field,
+            ),
+            product(signal(3), signal(2), signal(5), &field),
+            product(signal(2), signal(5), signal(4), &field),
+        ]);
+        assert!(original().is_equivalent(&refactored));
+        assert!(refactored.is_equivalent(&original()));
+        assert!(original().is_equivalent(&original()));
+    }
+
+    #[test]
+    fn boundary_changes_are_detected() {
+        let field = multiplier().field;
+        // u = t*b instead of t*a
+        let changed = with_constraints(vec![
+            product(signal(2), signal(3), signal(4), &field),
+            product(signal(4), signal(3), signal(5), &field),
+            product(number(1), A::add(&signal(5), &signal(3), &field), signal(1), &field),
+        ]);
+        assert!(!original().is_equivalent(&changed));
+        assert!(!original().is_equivalent(&multiplier()));
+    }
+
+    #[test]
+    fn first_difference_is_exact() {
+        assert_eq!(original().first_difference(&original()), Option::None);
+        let field = multiplier().field;
+        // Same constraints with the last two swapped
+        let reordered = with_constraints(vec![
+            product(signal(2), signal(3), signal(4), &field),
+            product(number(1), A::add(&signal(5), &signal(3), &field), signal(1), &field),
+            product(signal(4), signal(2), signal(5), &field),
+        ]);
+        let difference = original().first_difference(&reordered).unwrap();
+        assert!(difference.starts_with("constraint 1 is "));
+        assert!(original().first_difference(&multiplier()).unwrap().starts_with("(wires"));
+    }
+}
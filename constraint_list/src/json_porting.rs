@@ -46,3 +46,40 @@ pub fn port_constraints(
     }
     writer.end()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::witness::tests::multiplier;
+    use constraint_writers::ConstraintExporter;
+
+    #[test]
+    fn json_constraints_read_back() {
+        let list = multiplier();
+        let no_constraints = list.get_no_constraints();
+        let name = format!("circom_json_constraints_{}.json", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        let file = file.to_str().unwrap();
+        let writer = DebugWriter::new(file.to_string(), 0).unwrap();
+        list.json_constraints(&writer).unwrap();
+        let contents = std::fs::read_to_string(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        let parsed = json::parse(&contents).unwrap();
+        let constraints = &parsed["constraints"];
+        assert_eq!(constraints.len(), no_constraints);
+        for constraint in constraints.members() {
+            assert_eq!(constraint.len(), 3);
+            for map in constraint.members() {
+                assert!(map.is_object());
+                for (signal, coefficient) in map.entries() {
+                    assert!(signal.parse::<usize>().is_ok());
+                    let coefficient = coefficient.as_str().unwrap();
+                    assert!(BigInt::parse_bytes(coefficient.as_bytes(), 10).is_some());
+                }
+            }
+        }
+        // out = a * b over the wires 1, 2 and 3, the constant term is kept
+        let expected = r#"[{"0":"0","2":"1"},{"0":"0","3":"1"},{"0":"0","1":"1"}]"#;
+        assert_eq!(constraints[0].dump(), expected);
+    }
+}
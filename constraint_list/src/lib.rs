@@ -1,17 +1,46 @@
 use std::collections::{HashMap, HashSet, LinkedList};
+use std::io::Write;
 
+use circom_algebra::algebra::ArithmeticError;
 use circom_algebra::constraint_storage::ConstraintStorage;
 use circom_algebra::num_bigint::BigInt;
+use constraint_writers::counts_writer::CircuitCounts;
 use constraint_writers::debug_writer::DebugWriter;
+use constraint_writers::json_writer::SubstitutionJSON;
+use constraint_writers::manifest_writer::SignalManifest;
+use constraint_writers::r1cs_writer::{HeaderData, SourceSpan};
+use constraint_writers::sym_writer::SymElem;
 use constraint_writers::ConstraintExporter;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
 
+#[cfg(feature = "arkworks")]
+mod arkworks;
+mod bellman_porting;
 mod constraint_simplification;
+mod cost_model;
+mod counts_porting;
+mod degrees_of_freedom;
+mod equivalence;
 mod json_porting;
+mod matrices;
+mod multiplicative_depth;
 mod non_linear_utils;
 mod r1cs_porting;
+mod sql_porting;
 mod state_utils;
 mod sym_porting;
 mod non_linear_simplification;
+mod provenance;
+mod unconstrained;
+mod validation;
+mod witness;
+
+pub use bellman_porting::BellmanExporter;
+pub use cost_model::CostModel;
+pub use matrices::{Matrices, Matrix};
+pub use provenance::Provenance;
+pub use witness::Witness;
 
 type C = circom_algebra::algebra::Constraint<usize>;
 type S = circom_algebra::algebra::Substitution<usize>;
@@ -118,12 +147,57 @@ pub struct Simplifier {
     // Flags
     pub no_rounds: usize,
     pub parallel_flag: bool,
+    // Size of the simplification pools, all the cores when None
+    pub threads: Option<usize>,
     pub flag_s: bool,
     pub port_substitution: bool,
+    // Directory that receives the per round snapshots
+    pub intermediates: Option<String>,
+    // Also receives the substitutions, next to the log of port_substitution
+    pub substitution_log: Option<SubstitutionJSON>,
+    // Called after every round with the round and the linear constraints left
+    pub progress: Option<Box<dyn Fn(usize, usize)>>,
+    pub dedup_constraints: bool,
+    // The constraints before the simplification, in the order of the
+    // unsimplified export. When given, the list records the provenance
+    pub originals: Option<Vec<C>>,
+    // Source locations of the originals, they need the originals as well
+    pub original_spans: Option<Vec<Option<SourceSpan>>>,
+}
+// A linear constraint where a signal can not be cleared, its coefficient
+// has no inverse in the field
+pub struct SimplificationError {
+    pub error: ArithmeticError,
+    pub signal: usize,
+    pub constraint: C,
 }
+
 impl Simplifier {
+    // Checks the coefficients before simplifying, the substitutions of the
+    // rounds can not fail once the constraints they start from are valid
+    pub fn try_simplify_constraints(self) -> Result<ConstraintList, SimplificationError> {
+        let equalities = self.equalities.iter().chain(self.cons_equalities.iter());
+        for constraint in self.linear.iter().chain(equalities) {
+            constraint.check_coefficients(&self.field).map_err(|(signal, error)| {
+                SimplificationError { error, signal, constraint: constraint.clone() }
+            })?;
+        }
+        Result::Ok(self.simplify_constraints())
+    }
+
     pub fn simplify_constraints(mut self) -> ConstraintList {
-        let (portable, map) = constraint_simplification::simplification(&mut self);
+        let (portable, map, provenance) = constraint_simplification::simplification(&mut self);
+        // A constraint keeps the location of its original, the derived ones have none
+        let spans = match (&provenance, self.original_spans.take()) {
+            (Option::Some(provenance), Option::Some(spans)) => {
+                let span = |p: &Provenance| match p.sources.first() {
+                    Option::Some(source) if !p.derived => spans[*source].clone(),
+                    _ => Option::None,
+                };
+                Option::Some(provenance.iter().map(span).collect())
+            }
+            _ => Option::None,
+        };
         ConstraintList {
             field: self.field,
             dag_encoding: self.dag_encoding,
@@ -133,6 +207,8 @@ impl Simplifier {
             no_labels: self.max_signal,
             constraints: portable,
             signal_map: map,
+            provenance,
+            spans,
         }
     }
 
@@ -155,6 +231,12 @@ pub struct ConstraintList {
     pub no_labels: usize,
     //  Signals in [witness_len, Vec::len(&signal_map)) are the ones deleted
     pub signal_map: SignalMap,
+    // Provenance of each constraint in the order of the export, only when
+    // the simplifier was given the originals
+    pub provenance: Option<Vec<Provenance>>,
+    // Source location of each constraint in the order of the export, the
+    // r1cs gets a section with them when they are given
+    pub spans: Option<Vec<Option<SourceSpan>>>,
 }
 
 impl ConstraintExporter for ConstraintList {
@@ -162,6 +244,14 @@ impl ConstraintExporter for ConstraintList {
         r1cs_porting::port_r1cs(self, out)
     }
 
+    fn r1cs_header(&self) -> HeaderData {
+        r1cs_porting::header(self)
+    }
+
+    fn r1cs_shards(&self, out: &str, shard_size: usize) -> Result<(), ()> {
+        r1cs_porting::port_r1cs_shards(self, out, shard_size)
+    }
+
     fn json_constraints(&self, writer: &DebugWriter) -> Result<(), ()> {
         json_porting::port_constraints(&self.constraints, &self.signal_map, writer)
     }
@@ -169,6 +259,14 @@ impl ConstraintExporter for ConstraintList {
     fn sym(&self, out: &str) -> Result<(), ()> {
         sym_porting::port_sym(self, out)
     }
+
+    fn sql_constraints(&self, out: &str) -> Result<(), ()> {
+        sql_porting::port_constraints(self, out)
+    }
+
+    fn manifest(&self, out: &str) -> Result<(), ()> {
+        self.signal_manifest().write(out)
+    }
 }
 
 impl ConstraintList {
@@ -188,7 +286,236 @@ impl ConstraintList {
         self.no_labels
     }
 
+    // Witness position of every signal numbered before the simplification,
+    // None for the ones that were eliminated
+    pub fn signal_remap(&self) -> HashMap<usize, Option<usize>> {
+        (0..self.no_labels).map(|signal| (signal, self.signal_map.get(&signal).cloned())).collect()
+    }
+
+    pub fn get_no_constraints(&self) -> usize {
+        self.constraints.get_ids().len()
+    }
+
+    // The constraints over the wires, in the order of the r1cs export
+    pub fn constraints_iter(&self) -> impl Iterator<Item = C> + '_ {
+        self.constraints.get_ids().into_iter().map(move |c_id| {
+            let constraint = self.constraints.read_constraint(c_id).unwrap();
+            C::apply_correspondence(&constraint, &self.signal_map)
+        })
+    }
+
+    // The a, b and c rows of each constraint, as in the matrices
+    pub fn linear_combinations(&self) -> impl Iterator<Item = [Vec<(usize, BigInt)>; 3]> + '_ {
+        self.constraints_iter().map(|c| matrices::rows(&c))
+    }
+
     pub fn no_wires(&self) -> usize {
         self.signal_map.len()
     }
+
+    pub fn circuit_counts(&self) -> CircuitCounts {
+        counts_porting::count(self)
+    }
+
+    // Estimate to compare circuit designs, see cost_model.rs
+    pub fn proving_cost(&self, model: &CostModel) -> f64 {
+        cost_model::proving_cost(self, model)
+    }
+
+    // The constraints with the signal in A, B or C, named as in the sym file.
+    // None when there is no such signal or the simplification removed it
+    pub fn constraints_with_signal(&self, name: &str) -> Option<Vec<C>> {
+        let witness = sym_porting::witness_of(self, name)?;
+        let touching = self.constraints_iter().filter(|c| c.take_signals().contains(&witness));
+        Option::Some(touching.collect())
+    }
+
+    // The constraints with both signals, to follow an equality through the
+    // intermediate signals. None when one of them is missing or removed
+    pub fn constraints_linking(&self, first: &str, second: &str) -> Option<Vec<C>> {
+        let first = sym_porting::witness_of(self, first)?;
+        let second = sym_porting::witness_of(self, second)?;
+        let linking = self.constraints_iter().filter(|c| {
+            let signals = c.take_signals();
+            signals.contains(&first) && signals.contains(&second)
+        });
+        Option::Some(linking.collect())
+    }
+
+    pub fn signal_manifest(&self) -> SignalManifest {
+        sym_porting::manifest(self)
+    }
+
+    // Instance paths with the names of their signals in no constraint
+    pub fn unconstrained_signals(&self) -> Vec<(String, Vec<String>)> {
+        unconstrained::unconstrained_signals(self)
+    }
+
+    // Same lines as the .sym file, without keeping them in memory
+    pub fn stream_sym(&self, writer: &mut dyn Write) -> Result<(), ()> {
+        sym_porting::stream_sym(self, writer)
+    }
+
+    // Each signal in the order of the .sym file, removed signals have witness -1
+    pub fn visit_symbols(
+        &self,
+        visit: &mut dyn FnMut(SymElem) -> Result<(), ()>,
+    ) -> Result<(), ()> {
+        sym_porting::visit_symbols(self, visit)
+    }
+
+    pub fn check_witness(&self, witness: &Witness) -> Result<(), Report> {
+        witness::check_witness(self, witness)
+    }
+
+    // A*B - C mod p for each constraint the witness violates
+    pub fn check_witness_verbose(&self, witness: &Witness) -> Result<Vec<(usize, BigInt)>, Report> {
+        witness::violated_constraints(self, witness)
+    }
+
+    pub fn matrices(&self) -> Matrices {
+        matrices::build_matrices(self)
+    }
+
+    pub fn validate_r1cs(&self) -> ReportCollection {
+        validation::validate_r1cs(&self.matrices())
+    }
+
+    // Equal up to constraint order, scaling and renaming of the non public
+    // wires, see equivalence.rs for the limitations of the check
+    pub fn is_equivalent(&self, other: &ConstraintList) -> bool {
+        equivalence::is_equivalent(self, other)
+    }
+
+    // The wire w is written as permutation[w]: the constraints, the signal
+    // section of the r1cs and the sym file move together. The constant one
+    // keeps the wire 0 and every wire stays among the outputs, the public
+    // inputs, the private inputs or the rest, as the r1cs header counts them
+    pub fn permute_wires(&mut self, permutation: &[usize]) -> Result<(), Report> {
+        let no_wires = self.no_wires();
+        let mut seen = vec![false; no_wires];
+        for &to in permutation {
+            if to >= no_wires || seen[to] {
+                break;
+            }
+            seen[to] = true;
+        }
+        let valid = permutation.len() == no_wires && seen.iter().all(|s| *s);
+        if !valid || permutation.first() != Option::Some(&0) {
+            let msg = format!(
+                "The wire permutation must reorder the {} wires and keep the wire 0 in place",
+                no_wires
+            );
+            return Result::Err(Report::error(msg, ReportCode::InvalidWirePermutation));
+        }
+        let ends = [
+            1,
+            1 + self.no_public_outputs,
+            1 + self.no_public_outputs + self.no_public_inputs,
+            1 + self.no_public_outputs + self.no_public_inputs + self.no_private_inputs,
+        ];
+        let range = |wire: usize| ends.iter().filter(|end| wire >= **end).count();
+        let moved = (0..no_wires).find(|w| range(*w) != range(permutation[*w]));
+        if let Option::Some(wire) = moved {
+            let msg = format!(
+                "The wire permutation moves the wire {} to {}, wires can not leave the \
+                 outputs, the public inputs or the private inputs of main",
+                wire, permutation[wire]
+            );
+            return Result::Err(Report::error(msg, ReportCode::InvalidWirePermutation));
+        }
+        self.apply_permutation(permutation);
+        Result::Ok(())
+    }
+
+    fn apply_permutation(&mut self, permutation: &[usize]) {
+        for wire in self.signal_map.values_mut() {
+            *wire = permutation[*wire];
+        }
+    }
+
+    // The signals become public outputs: their wires go right after the
+    // outputs of main and the rest keep their order. A signal in no
+    // constraint would be an output the circuit does not constrain
+    pub fn promote_to_outputs(&mut self, signals: &[usize]) -> Result<(), Report> {
+        let no_io = self.no_public_outputs + self.no_public_inputs + self.no_private_inputs;
+        let mut constrained = HashSet::new();
+        for c_id in self.constraints.get_ids() {
+            let constraint = self.constraints.read_constraint(c_id).unwrap();
+            constrained.extend(constraint.take_cloned_signals());
+        }
+        let no_wires = self.no_wires();
+        let mut promoted = Vec::new();
+        for signal in signals {
+            match self.signal_map.get(signal) {
+                Option::Some(wire) if *wire > no_io && *wire < no_wires => {
+                    if !constrained.contains(signal) {
+                        let msg = format!(
+                            "The signal {} can not be promoted, it is in no constraint",
+                            signal
+                        );
+                        return Result::Err(Report::error(msg, ReportCode::InvalidPromotedSignal));
+                    }
+                    if !promoted.contains(wire) {
+                        promoted.push(*wire);
+                    }
+                }
+                _ => {
+                    let msg = format!(
+                        "The signal {} can not be promoted, it was removed or it is already \
+                         an input or an output of main",
+                        signal
+                    );
+                    return Result::Err(Report::error(msg, ReportCode::InvalidPromotedSignal));
+                }
+            }
+        }
+        let outputs = 1..=self.no_public_outputs;
+        let rest = (self.no_public_outputs + 1..self.no_wires()).filter(|w| !promoted.contains(w));
+        let order: Vec<_> =
+            std::iter::once(0).chain(outputs).chain(promoted.iter().cloned()).chain(rest).collect();
+        let mut permutation = vec![0; order.len()];
+        for (to, from) in order.into_iter().enumerate() {
+            permutation[from] = to;
+        }
+        // The promoted wires join the outputs, the ranges are not checked
+        self.apply_permutation(&permutation);
+        self.no_public_outputs += promoted.len();
+        Result::Ok(())
+    }
+
+    // Every constraint is replaced by its normalized form, see algebra::normalize
+    pub fn normalize_constraints(&mut self) {
+        for id in self.constraints.get_ids() {
+            let constraint = self.constraints.read_constraint(id).unwrap();
+            let normalized = circom_algebra::algebra::normalize(constraint, &self.field);
+            self.constraints.replace(id, normalized);
+        }
+    }
+
+    // None when both lists have exactly the same constraints in the same order
+    pub fn first_difference(&self, other: &ConstraintList) -> Option<String> {
+        equivalence::first_difference(self, other)
+    }
+
+    // Longest chain of dependent non linear constraints, see
+    // multiplicative_depth.rs for how the dependencies are oriented
+    pub fn multiplicative_depth(&self) -> Result<usize, Report> {
+        multiplicative_depth::multiplicative_depth(&self.matrices())
+    }
+
+    // The matrices in the layout of arkworks, F must be the field of the
+    // circuit. Panics when its modulus is not the prime of the list
+    #[cfg(feature = "arkworks")]
+    pub fn to_arkworks<F: ark_ff::PrimeField>(&self) -> ark_relations::r1cs::ConstraintMatrices<F> {
+        arkworks::constraint_matrices(&self.matrices())
+    }
+
+    // Lower bound of the witness signals left free by the constraints, see
+    // degrees_of_freedom.rs. The rank of the non linear constraints is
+    // approximated by their number. In a well constrained circuit only the
+    // inputs (public and private) are free
+    pub fn degrees_of_freedom(&self) -> usize {
+        degrees_of_freedom::degrees_of_freedom(&self.matrices())
+    }
 }
@@ -0,0 +1,83 @@
+use super::{ConstraintList, C};
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::Zero;
+use std::collections::HashMap;
+
+// Every row holds the (wire, coefficient) pairs of one constraint sorted by wire
+pub type Matrix = Vec<Vec<(usize, BigInt)>>;
+
+pub struct Matrices {
+    pub field: BigInt,
+    pub a: Matrix,
+    pub b: Matrix,
+    pub c: Matrix,
+    pub no_wires: usize,
+    pub no_public_outputs: usize,
+    pub no_public_inputs: usize,
+    pub no_private_inputs: usize,
+}
+
+impl Matrices {
+    pub fn no_constraints(&self) -> usize {
+        self.a.len()
+    }
+
+    // Wire 0 is the constant one, public outputs and inputs come next
+    pub fn no_public(&self) -> usize {
+        1 + self.no_public_outputs + self.no_public_inputs
+    }
+
+    // The constraint has to be expressed over wires already
+    pub fn push_constraint(&mut self, constraint: &C) {
+        let [a, b, c] = rows(constraint);
+        self.a.push(a);
+        self.b.push(b);
+        self.c.push(c);
+    }
+}
+
+fn into_row(lc: &HashMap<usize, BigInt>) -> Vec<(usize, BigInt)> {
+    let mut row: Vec<_> =
+        lc.iter().filter(|(_, v)| !v.is_zero()).map(|(k, v)| (*k, v.clone())).collect();
+    row.sort_by_key(|(k, _)| *k);
+    row
+}
+
+pub fn rows(constraint: &C) -> [Vec<(usize, BigInt)>; 3] {
+    [into_row(constraint.a()), into_row(constraint.b()), into_row(constraint.c())]
+}
+
+pub fn build_matrices(list: &ConstraintList) -> Matrices {
+    let mut matrices = Matrices {
+        field: list.field.clone(),
+        a: Vec::new(),
+        b: Vec::new(),
+        c: Vec::new(),
+        no_wires: list.no_wires(),
+        no_public_outputs: list.no_public_outputs,
+        no_public_inputs: list.no_public_inputs,
+        no_private_inputs: list.no_private_inputs,
+    };
+    for constraint in list.constraints_iter() {
+        matrices.push_constraint(&constraint);
+    }
+    matrices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::witness::tests::multiplier;
+
+    #[test]
+    fn multiplier_matrices() {
+        let matrices = multiplier().matrices();
+        let one = BigInt::from(1);
+        assert_eq!(matrices.no_constraints(), 1);
+        assert_eq!(matrices.no_wires, 4);
+        assert_eq!(matrices.no_public(), 2);
+        assert_eq!(matrices.a[0], vec![(2, one.clone())]);
+        assert_eq!(matrices.b[0], vec![(3, one.clone())]);
+        assert_eq!(matrices.c[0], vec![(1, one)]);
+    }
+}
@@ -0,0 +1,141 @@
+use super::Matrices;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::Report;
+use std::collections::{BTreeSet, VecDeque};
+
+// R1CS constraints have no direction, so they are oriented by solving them:
+// starting from the constant one and the inputs, a constraint with a single
+// unknown wire determines it from the rest. The depth of a wire is the number
+// of non linear constraints in the chain that determines it, and constraints
+// with no unknown wires left (checks) still count for the circuit depth.
+// When no constraint has a single unknown wire the remaining wires depend on
+// each other and there is no order to measure, this is reported as an error.
+pub fn multiplicative_depth(matrices: &Matrices) -> Result<usize, Report> {
+    let inputs =
+        (1 + matrices.no_public_outputs)..(matrices.no_public() + matrices.no_private_inputs);
+    let wires: Vec<BTreeSet<usize>> = (0..matrices.no_constraints())
+        .map(|index| {
+            let rows = [&matrices.a[index], &matrices.b[index], &matrices.c[index]];
+            rows.iter().flat_map(|row| row.iter().map(|(wire, _)| *wire)).collect()
+        })
+        .collect();
+    let non_linear: Vec<bool> = (0..matrices.no_constraints())
+        .map(|index| !matrices.a[index].is_empty() && !matrices.b[index].is_empty())
+        .collect();
+    let mut depth: Vec<Option<usize>> = (0..matrices.no_wires)
+        .map(
+            |wire| if wire == 0 || inputs.contains(&wire) { Option::Some(0) } else { Option::None },
+        )
+        .collect();
+    let mut appears_in = vec![Vec::new(); matrices.no_wires];
+    let mut unknown = vec![0; wires.len()];
+    for (index, constraint) in wires.iter().enumerate() {
+        for wire in constraint {
+            appears_in[*wire].push(index);
+        }
+        unknown[index] = constraint.iter().filter(|w| depth[**w].is_none()).count();
+    }
+    let mut pending: VecDeque<usize> = (0..wires.len()).filter(|i| unknown[*i] <= 1).collect();
+    let mut solved = vec![false; wires.len()];
+    let mut circuit_depth = 0;
+    while let Option::Some(index) = pending.pop_front() {
+        if solved[index] {
+            continue;
+        }
+        solved[index] = true;
+        let known_depth = wires[index].iter().filter_map(|w| depth[*w]).max().unwrap_or(0);
+        let constraint_depth = known_depth + if non_linear[index] { 1 } else { 0 };
+        circuit_depth = usize::max(circuit_depth, constraint_depth);
+        let determined = wires[index].iter().find(|w| depth[**w].is_none()).cloned();
+        if let Option::Some(wire) = determined {
+            depth[wire] = Option::Some(constraint_depth);
+            for other in &appears_in[wire] {
+                unknown[*other] -= 1;
+                if unknown[*other] <= 1 && !solved[*other] {
+                    pending.push_back(*other);
+                }
+            }
+        }
+    }
+    let unsolved = solved.iter().filter(|s| !**s).count();
+    if unsolved == 0 {
+        Result::Ok(circuit_depth)
+    } else {
+        let undetermined = depth.iter().filter(|d| d.is_none()).count();
+        let msg = format!(
+            "The multiplicative depth is undefined: {} constraints over {} wires depend on each other",
+            unsolved, undetermined
+        );
+        let mut report = Report::error(msg, ReportCode::CyclicConstraintDependency);
+        report.add_note(
+            "None of these constraints can be solved for a single wire from the rest".to_string(),
+        );
+        Result::Err(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::witness::tests::multiplier;
+    use crate::C;
+    use circom_algebra::algebra::ArithmeticExpression;
+    use circom_algebra::constraint_storage::ConstraintStorage;
+    use circom_algebra::num_bigint::BigInt;
+    type A = ArithmeticExpression<usize>;
+
+    fn signal(symbol: usize) -> A {
+        A::Signal { symbol }
+    }
+
+    // lhs - rhs = 0
+    fn equality(lhs: A, rhs: A, field: &BigInt) -> C {
+        let expr = A::sub(&lhs, &rhs, field);
+        let mut constraint = A::transform_expression_to_constraint_form(expr, field).unwrap();
+        C::remove_zero_value_coefficients(&mut constraint);
+        constraint
+    }
+
+    // Boundary of the multiplier (1 = out, 2 = a, 3 = b) plus wires 4 and 5
+    fn with_constraints(constraints: Vec<C>) -> crate::ConstraintList {
+        let mut list = multiplier();
+        let mut storage = ConstraintStorage::new();
+        for constraint in constraints {
+            storage.add_constraint(constraint);
+        }
+        list.constraints = storage;
+        list.signal_map = (0..6).map(|s| (s, s)).collect();
+        list
+    }
+
+    #[test]
+    fn chained_multiplications_add_up() {
+        let field = multiplier().field;
+        assert_eq!(multiplier().multiplicative_depth().ok().unwrap(), 1);
+        // out = u*b with u = t*a and t = a*b, listed backwards
+        let chain = with_constraints(vec![
+            equality(A::mul(&signal(5), &signal(3), &field), signal(1), &field),
+            equality(A::mul(&signal(4), &signal(2), &field), signal(5), &field),
+            equality(A::mul(&signal(2), &signal(3), &field), signal(4), &field),
+        ]);
+        assert_eq!(chain.multiplicative_depth().ok().unwrap(), 3);
+        // Linear steps do not add depth: t = a*b, u = t + a, out = u*u
+        let mixed = with_constraints(vec![
+            equality(A::mul(&signal(2), &signal(3), &field), signal(4), &field),
+            equality(A::add(&signal(4), &signal(2), &field), signal(5), &field),
+            equality(A::mul(&signal(5), &signal(5), &field), signal(1), &field),
+        ]);
+        assert_eq!(mixed.multiplicative_depth().ok().unwrap(), 2);
+    }
+
+    #[test]
+    fn mutually_dependent_wires_are_reported() {
+        let field = multiplier().field;
+        // t*u = a and t + u = b, neither t nor u can be solved first
+        let cyclic = with_constraints(vec![
+            equality(A::mul(&signal(4), &signal(5), &field), signal(2), &field),
+            equality(A::add(&signal(4), &signal(5), &field), signal(3), &field),
+            equality(signal(4), signal(1), &field),
+        ]);
+        assert!(cyclic.multiplicative_depth().is_err());
+    }
+}
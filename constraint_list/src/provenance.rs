@@ -0,0 +1,176 @@
+use super::{C, S};
+use circom_algebra::constraint_storage::ConstraintStorage;
+use circom_algebra::num_bigint::BigInt;
+use std::collections::{BTreeSet, HashMap, HashSet, LinkedList};
+
+// Where a constraint of the simplified list comes from. The sources are
+// ids of the constraints without simplification, in the order of
+// DAG::map_to_list_unsimplified
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Provenance {
+    // false when the constraint is one of the original ones, untouched
+    pub derived: bool,
+    pub sources: Vec<usize>,
+}
+
+// The originals are rewritten with every substitution of the run. A final
+// constraint matches the originals that end up equal to it up to a factor,
+// and it is original when one of them had no substituted signal. Otherwise
+// the sources also hold the originals consumed by the substitutions (they
+// end up empty) reachable through the substituted signals. Returns every
+// constraint of the storage with its provenance
+pub fn trace(
+    originals: &[C],
+    substitutions: &[S],
+    storage: &ConstraintStorage,
+    field: &BigInt,
+) -> Vec<(C, Provenance)> {
+    let by_signal: HashMap<_, _> = substitutions.iter().map(|s| (*s.from(), s)).collect();
+    let mut matches = HashMap::new();
+    let mut consumed_with: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut substituted = Vec::with_capacity(originals.len());
+    for (id, original) in originals.iter().enumerate() {
+        let signals: Vec<_> = original
+            .take_cloned_signals()
+            .into_iter()
+            .filter(|signal| by_signal.contains_key(signal))
+            .collect();
+        let reduced = reduce(original, &by_signal, field);
+        if reduced.is_empty() {
+            for signal in &signals {
+                consumed_with.entry(*signal).or_default().push(id);
+            }
+        } else {
+            matches.entry(reduced.canonical_form(field)).or_insert_with(Vec::new).push(id);
+        }
+        substituted.push(signals);
+    }
+    let mut provenance = Vec::new();
+    for c_id in storage.get_ids() {
+        let constraint = storage.read_constraint(c_id).unwrap();
+        let found = matches.get(&constraint.canonical_form(field)).cloned().unwrap_or_default();
+        if let Option::Some(id) = found.iter().find(|id| substituted[**id].is_empty()) {
+            provenance.push((constraint, Provenance { derived: false, sources: vec![*id] }));
+            continue;
+        }
+        let mut sources: BTreeSet<_> = found.into_iter().collect();
+        let mut pending: Vec<_> = sources.iter().flat_map(|id| substituted[*id].clone()).collect();
+        let mut visited = HashSet::new();
+        while let Option::Some(signal) = pending.pop() {
+            if !visited.insert(signal) {
+                continue;
+            }
+            for id in consumed_with.get(&signal).into_iter().flatten() {
+                if sources.insert(*id) {
+                    pending.extend(substituted[*id].iter().cloned());
+                }
+            }
+        }
+        let sources = sources.into_iter().collect();
+        provenance.push((constraint, Provenance { derived: true, sources }));
+    }
+    provenance
+}
+
+// The non-linear pass rewrites constraints of the storage and erases
+// signals. A constraint it left alone keeps the provenance traced before
+// the pass, a rewritten one comes from the constraints the pass removed
+// that share a signal with it or hold an erased one
+pub fn rewritten(
+    traced: Vec<(C, Provenance)>,
+    erased: &LinkedList<usize>,
+    storage: &ConstraintStorage,
+    field: &BigInt,
+) -> Vec<Provenance> {
+    let mut kept = HashSet::new();
+    for c_id in storage.get_ids() {
+        kept.insert(storage.read_constraint(c_id).unwrap().canonical_form(field));
+    }
+    let mut by_form = HashMap::new();
+    let mut removed = Vec::new();
+    for (constraint, provenance) in traced {
+        let form = constraint.canonical_form(field);
+        if kept.contains(&form) {
+            by_form.entry(form).or_insert(provenance);
+        } else if !constraint.is_empty() {
+            removed.push((constraint.take_cloned_signals(), provenance));
+        }
+    }
+    let mut provenance = Vec::new();
+    for c_id in storage.get_ids() {
+        let constraint = storage.read_constraint(c_id).unwrap();
+        if let Option::Some(found) = by_form.get(&constraint.canonical_form(field)) {
+            provenance.push(found.clone());
+            continue;
+        }
+        let mut signals = constraint.take_cloned_signals();
+        signals.extend(erased.iter().cloned());
+        let mut sources = BTreeSet::new();
+        for (removed_signals, removed_provenance) in &removed {
+            if !removed_signals.is_disjoint(&signals) {
+                sources.extend(removed_provenance.sources.iter().cloned());
+            }
+        }
+        provenance.push(Provenance { derived: true, sources: sources.into_iter().collect() });
+    }
+    provenance
+}
+
+// The substitutions of later rounds may introduce signals substituted
+// before, they are applied until none is left
+fn reduce(original: &C, by_signal: &HashMap<usize, &S>, field: &BigInt) -> C {
+    let mut constraint = original.clone();
+    for _ in 0..=by_signal.len() {
+        let pending: Vec<_> = constraint
+            .take_cloned_signals()
+            .into_iter()
+            .filter_map(|signal| by_signal.get(&signal))
+            .collect();
+        if pending.is_empty() {
+            break;
+        }
+        for substitution in pending {
+            C::apply_substitution(&mut constraint, substitution, field);
+        }
+    }
+    constraint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::A;
+
+    // left - right === 0
+    fn constraint(left: A, right: A, field: &BigInt) -> C {
+        let expr = A::sub(&left, &right, field);
+        let mut constraint = A::transform_expression_to_constraint_form(expr, field).unwrap();
+        C::remove_zero_value_coefficients(&mut constraint);
+        constraint
+    }
+
+    #[test]
+    fn rewritten_constraints_come_from_the_removed_ones() {
+        let field = BigInt::from(257);
+        let signal = |symbol| A::Signal { symbol };
+        let number = |value: i32| A::Number { value: BigInt::from(value) };
+        let (x, y, z, w, q) = (1, 2, 3, 4, 5);
+        let product = A::mul(&signal(x), &signal(y), &field);
+        let square = A::mul(&signal(q), &signal(q), &field);
+        let original = |id| Provenance { derived: false, sources: vec![id] };
+        let derived = Provenance { derived: true, sources: vec![2, 3] };
+        let traced = vec![
+            (constraint(product.clone(), signal(z), &field), original(0)),
+            (constraint(signal(z), signal(w), &field), original(1)),
+            (constraint(square.clone(), signal(q), &field), derived.clone()),
+        ];
+        // The pass turns the first two into x*y = 3 and erases z
+        let mut storage = ConstraintStorage::new();
+        storage.add_constraint(constraint(product, number(3), &field));
+        storage.add_constraint(constraint(square, signal(q), &field));
+        let erased: LinkedList<_> = vec![z].into_iter().collect();
+        let provenance = rewritten(traced, &erased, &storage, &field);
+        let expected = vec![Provenance { derived: true, sources: vec![0, 1] }, derived];
+        assert_eq!(provenance, expected);
+    }
+}
@@ -1,5 +1,18 @@
 use super::{ConstraintList, C};
 use constraint_writers::r1cs_writer::{ConstraintSection, HeaderData, R1CSWriter, SignalSection};
+use constraint_writers::shard_writer::ShardWriter;
+
+pub fn header(list: &ConstraintList) -> HeaderData {
+    HeaderData {
+        field: list.field.clone(),
+        public_outputs: list.no_public_outputs,
+        public_inputs: list.no_public_inputs,
+        private_inputs: list.no_private_inputs,
+        total_wires: ConstraintList::no_wires(list),
+        number_of_labels: ConstraintList::no_labels(list),
+        number_of_constraints: list.get_no_constraints(),
+    }
+}
 
 pub fn port_r1cs(list: &ConstraintList, output: &str) -> Result<(), ()> {
     use constraint_writers::log_writer::Log;
@@ -12,12 +25,10 @@ pub fn port_r1cs(list: &ConstraintList, output: &str) -> Result<(), ()> {
     log.no_public_outputs = list.no_public_outputs;
 
     let r1cs = R1CSWriter::new(output.to_string(), field_size)?;
-    let mut constraint_section = R1CSWriter::start_constraints_section(r1cs)?;
+    let mut constraint_section = R1CSWriter::start_constraints_section(r1cs, &list.field)?;
     let mut written = 0;
 
-    for c_id in list.constraints.get_ids() {
-        let c = list.constraints.read_constraint(c_id).unwrap();
-        let c = C::apply_correspondence(&c, &list.signal_map);
+    for c in list.constraints_iter() {
         ConstraintSection::write_constraint_usize(&mut constraint_section, c.a(), c.b(), c.c())?;
         if C::is_linear(&c) {
             log.no_linear += 1;
@@ -26,18 +37,12 @@ pub fn port_r1cs(list: &ConstraintList, output: &str) -> Result<(), ()> {
         }
         written += 1;
     }
+    log.no_reduced_coefficients = constraint_section.reduced_coefficients();
 
     let r1cs = constraint_section.end_section()?;
     let mut header_section = R1CSWriter::start_header_section(r1cs)?;
-    let header_data = HeaderData {
-        field: list.field.clone(),
-        public_outputs: list.no_public_outputs,
-        public_inputs: list.no_public_inputs,
-        private_inputs: list.no_private_inputs,
-        total_wires: ConstraintList::no_wires(list),
-        number_of_labels: ConstraintList::no_labels(list),
-        number_of_constraints: written,
-    };
+    let header_data = header(list);
+    debug_assert_eq!(header_data.number_of_constraints, written);
     header_section.write_section(header_data)?;
     let r1cs = header_section.end_section()?;
     let mut signal_section = R1CSWriter::start_signal_section(r1cs)?;
@@ -45,7 +50,145 @@ pub fn port_r1cs(list: &ConstraintList, output: &str) -> Result<(), ()> {
     for id in list.get_witness_as_vec() {
         SignalSection::write_signal_usize(&mut signal_section, id)?;
     }
-    let _r1cs = signal_section.end_section()?;
+    let r1cs = signal_section.end_section()?;
+    if let Option::Some(spans) = &list.spans {
+        let mut spans_section = R1CSWriter::start_spans_section(r1cs)?;
+        for span in spans {
+            spans_section.write_span(span.as_ref())?;
+        }
+        let _r1cs = spans_section.end_section()?;
+    }
     Log::print(&log);
     Ok(())
 }
+
+pub fn port_r1cs_shards(list: &ConstraintList, output: &str, shard_size: usize) -> Result<(), ()> {
+    let signals = list.get_witness_as_vec();
+    let mut shards = ShardWriter::new(output.to_string(), shard_size, header(list), signals)?;
+    for c in list.constraints_iter() {
+        shards.write_constraint_usize(c.a(), c.b(), c.c())?;
+    }
+    shards.finish()?;
+    Result::Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::witness::tests::multiplier;
+    use crate::A;
+    use circom_algebra::num_bigint::{BigInt, Sign};
+    use circom_algebra::num_traits::Zero;
+    use std::convert::TryInto;
+
+    fn read_u32(bytes: &[u8], at: &mut usize) -> usize {
+        let value = u32::from_le_bytes(bytes[*at..*at + 4].try_into().unwrap());
+        *at += 4;
+        value as usize
+    }
+
+    // The rows of the constraint section, the first one in the file
+    fn written_rows(bytes: &[u8], field_size: usize) -> Vec<[Vec<(usize, BigInt)>; 3]> {
+        // magic, version, number of sections, section type
+        let mut at = 16;
+        let end = at + 8 + u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap()) as usize;
+        at += 8;
+        let mut constraints = Vec::new();
+        while at < end {
+            let mut rows: [Vec<(usize, BigInt)>; 3] = Default::default();
+            for row in rows.iter_mut() {
+                for _ in 0..read_u32(bytes, &mut at) {
+                    let wire = read_u32(bytes, &mut at);
+                    let value = BigInt::from_bytes_le(Sign::Plus, &bytes[at..at + field_size]);
+                    at += field_size;
+                    if !value.is_zero() {
+                        row.push((wire, value));
+                    }
+                }
+                row.sort_by_key(|(wire, _)| *wire);
+            }
+            constraints.push(rows);
+        }
+        constraints
+    }
+
+    #[test]
+    fn iterator_matches_the_written_constraints() {
+        let list = multiplier();
+        let name = format!("circom_constraints_iter_{}.r1cs", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        let file = file.to_str().unwrap();
+        port_r1cs(&list, file).unwrap();
+        let bytes = std::fs::read(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        let field_size = (list.field.bits() / 64 + 1) * 8;
+        let iterated: Vec<_> = list.linear_combinations().collect();
+        assert_eq!(iterated, written_rows(&bytes, field_size));
+        assert_eq!(list.constraints_iter().count(), list.get_no_constraints());
+    }
+
+    #[test]
+    fn shards_split_the_constraints_without_overlap() {
+        let mut list = multiplier();
+        // a*b - k*out = 0 for k = 2 and 3
+        let s = |symbol| A::Signal { symbol };
+        for k in 2..4 {
+            let k = A::Number { value: BigInt::from(k) };
+            let k_out = A::mul(&k, &s(1), &list.field);
+            let expr = A::sub(&A::mul(&s(2), &s(3), &list.field), &k_out, &list.field);
+            let constraint = A::transform_expression_to_constraint_form(expr, &list.field);
+            list.constraints.add_constraint(constraint.unwrap());
+        }
+        let out = std::env::temp_dir().join(format!("circom_shards_{}", std::process::id()));
+        let out = out.to_str().unwrap();
+        port_r1cs_shards(&list, out, 2).unwrap();
+        let manifest = std::fs::read_to_string(format!("{}.json", out)).unwrap();
+        let manifest = json::parse(&manifest).unwrap();
+        std::fs::remove_file(format!("{}.json", out)).unwrap();
+        assert_eq!(manifest["number_of_constraints"], 3);
+        assert_eq!(manifest["total_wires"], 4);
+        let field_size = (list.field.bits() / 64 + 1) * 8;
+        let mut rows = Vec::new();
+        let mut next = 0;
+        for (index, shard) in manifest["shards"].members().enumerate() {
+            assert_eq!(shard["index"], index);
+            assert_eq!(shard["first_constraint"], next);
+            next = shard["end_constraint"].as_usize().unwrap();
+            let file = shard["file"].as_str().unwrap();
+            let bytes = std::fs::read(file).unwrap();
+            std::fs::remove_file(file).unwrap();
+            let shard_rows = written_rows(&bytes, field_size);
+            assert_eq!(shard_rows.len(), next - shard["first_constraint"].as_usize().unwrap());
+            rows.extend(shard_rows);
+        }
+        assert_eq!(manifest["shards"].len(), 2);
+        assert_eq!(rows, list.linear_combinations().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn out_of_range_coefficients_are_written_reduced() {
+        let mut list = multiplier();
+        let lc = |signal: usize, value: i64| {
+            let mut lc = std::collections::HashMap::new();
+            lc.insert(signal, BigInt::from(value));
+            lc
+        };
+        // The quadratic form keeps a and b as they are, c is negated
+        let mut c = lc(1, 5);
+        c.insert(crate::C::constant_coefficient(), BigInt::from(0));
+        let expression = crate::A::Quadratic { a: lc(2, 300), b: lc(3, 1), c };
+        let field = list.field.clone();
+        let constraint = crate::A::transform_expression_to_constraint_form(expression, &field);
+        list.constraints.add_constraint(constraint.unwrap());
+        let name = format!("circom_reduced_coefficients_{}.r1cs", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        let file = file.to_str().unwrap();
+        port_r1cs(&list, file).unwrap();
+        let bytes = std::fs::read(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        let field_size = (list.field.bits() / 64 + 1) * 8;
+        let rows = written_rows(&bytes, field_size);
+        let expected = |signal: usize, value: i64| vec![(signal, BigInt::from(value))];
+        assert_eq!(rows.last().unwrap(), &[expected(2, 43), expected(3, 1), expected(1, 252)]);
+    }
+}
@@ -0,0 +1,37 @@
+use super::{ConstraintList, C};
+use constraint_writers::sql_writer::ConstraintsSQL;
+
+pub fn port_constraints(list: &ConstraintList, out: &str) -> Result<(), ()> {
+    let mut writer = ConstraintsSQL::new(out)?;
+    for (index, c_id) in list.constraints.get_ids().into_iter().enumerate() {
+        let constraint = list.constraints.read_constraint(c_id).unwrap();
+        let constraint = C::apply_correspondence(&constraint, &list.signal_map);
+        writer.write_constraint(index, constraint.a(), constraint.b(), constraint.c())?;
+    }
+    writer.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::witness::tests::multiplier;
+
+    #[test]
+    fn one_row_per_non_zero() {
+        let list = multiplier();
+        let matrices = list.matrices();
+        let non_zeros: usize =
+            matrices.a.iter().chain(matrices.b.iter()).chain(matrices.c.iter()).map(Vec::len).sum();
+        let name = format!("circom_sql_porting_test_{}.sql", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        let file = file.to_str().unwrap();
+        port_constraints(&list, file).unwrap();
+        let content = std::fs::read_to_string(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        assert!(content.starts_with("CREATE TABLE constraints ("));
+        assert!(content.ends_with("COMMIT;\n"));
+        let rows: Vec<_> = content.lines().filter(|l| l.starts_with("INSERT INTO")).collect();
+        assert_eq!(rows.len(), non_zeros);
+        assert_eq!(rows[0], "INSERT INTO constraints VALUES (0, 'A', 2, '1');");
+    }
+}
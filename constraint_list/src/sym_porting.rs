@@ -1,19 +1,34 @@
 use super::{ConstraintList, EncodingIterator, IteratorSignal, SignalMap};
 use circom_algebra::num_traits::AsPrimitive;
+use constraint_writers::manifest_writer::SignalManifest;
 use constraint_writers::sym_writer::*;
+use std::io::Write;
 
 pub fn port_sym(list: &ConstraintList, file_name: &str) -> Result<(), ()> {
-    let iter = EncodingIterator::new(&list.dag_encoding);
     let mut dot_sym = SymFile::new(file_name)?;
-    signal_iteration(iter, &list.signal_map, &mut dot_sym)?;
+    visit_symbols(list, &mut |elem| SymFile::write_sym_elem(&mut dot_sym, elem))?;
     SymFile::close(dot_sym);
     Ok(())
 }
 
-pub fn signal_iteration(
+// The lines of the .sym file, written one by one
+pub fn stream_sym(list: &ConstraintList, writer: &mut dyn Write) -> Result<(), ()> {
+    visit_symbols(list, &mut |elem| write_sym_line(writer, &elem))?;
+    writer.flush().map_err(|_err| {})
+}
+
+pub fn visit_symbols(
+    list: &ConstraintList,
+    visit: &mut dyn FnMut(SymElem) -> Result<(), ()>,
+) -> Result<(), ()> {
+    let iter = EncodingIterator::new(&list.dag_encoding);
+    signal_iteration(iter, &list.signal_map, visit)
+}
+
+fn signal_iteration(
     mut iter: EncodingIterator,
     map: &SignalMap,
-    dot_sym: &mut SymFile,
+    visit: &mut dyn FnMut(SymElem) -> Result<(), ()>,
 ) -> Result<(), ()> {
     let (signals, _) = EncodingIterator::take(&mut iter);
 
@@ -25,12 +40,63 @@ pub fn signal_iteration(
             node_id: iter.node_id.as_(),
             symbol: signal.name.clone(),
         };
-        SymFile::write_sym_elem(dot_sym, sym_elem)?;
+        visit(sym_elem)?;
     }
 
     for edge in EncodingIterator::edges(&iter) {
         let next = EncodingIterator::next(&iter, edge);
-        signal_iteration(next, map, dot_sym)?;
+        signal_iteration(next, map, visit)?;
     }
     Ok(())
 }
+
+// The positions are the ones of the simplified witness, removed signals are not listed
+pub fn manifest(list: &ConstraintList) -> SignalManifest {
+    let mut manifest = SignalManifest::new(
+        list.no_public_outputs,
+        list.no_public_inputs,
+        list.no_private_inputs,
+    );
+    let iter = EncodingIterator::new(&list.dag_encoding);
+    manifest_iteration(iter, &list.signal_map, &mut manifest);
+    manifest
+}
+
+fn manifest_iteration(mut iter: EncodingIterator, map: &SignalMap, manifest: &mut SignalManifest) {
+    let (signals, _) = EncodingIterator::take(&mut iter);
+    for signal in signals {
+        let signal = IteratorSignal::new(signal, map);
+        if signal.witness != map.len() {
+            manifest.add_symbol(signal.witness, signal.name);
+        }
+    }
+    for edge in EncodingIterator::edges(&iter) {
+        let next = EncodingIterator::next(&iter, edge);
+        manifest_iteration(next, map, manifest);
+    }
+}
+
+// Witness position of the signal with the given name, None when there is
+// no such signal or the simplification removed it
+pub fn witness_of(list: &ConstraintList, name: &str) -> Option<usize> {
+    let iter = EncodingIterator::new(&list.dag_encoding);
+    find_witness(iter, &list.signal_map, name)
+}
+
+fn find_witness(mut iter: EncodingIterator, map: &SignalMap, name: &str) -> Option<usize> {
+    let (signals, _) = EncodingIterator::take(&mut iter);
+    for signal in signals {
+        let signal = IteratorSignal::new(signal, map);
+        if signal.name == name {
+            let removed = signal.witness == map.len();
+            return if removed { Option::None } else { Option::Some(signal.witness) };
+        }
+    }
+    for edge in EncodingIterator::edges(&iter) {
+        let next = EncodingIterator::next(&iter, edge);
+        if let Option::Some(witness) = find_witness(next, map, name) {
+            return Option::Some(witness);
+        }
+    }
+    Option::None
+}
@@ -0,0 +1,43 @@
+use super::ConstraintList;
+use std::collections::{BTreeMap, HashSet};
+
+// Path of the instance and name of the signal, main.m.in[1] is ("main.m", "in[1]")
+fn split_path(symbol: &str) -> (&str, &str) {
+    match symbol.rfind('.') {
+        Option::Some(dot) => (&symbol[..dot], &symbol[dot + 1..]),
+        Option::None => ("", symbol),
+    }
+}
+
+// Signals of the final circuit, the ones with a wire, that appear in no
+// constraint. They are grouped by the instance that declares them, sorted
+// by its path, and listed in the order of the sym file
+pub fn unconstrained_signals(list: &ConstraintList) -> Vec<(String, Vec<String>)> {
+    let mut constrained = HashSet::new();
+    for c in list.constraints_iter() {
+        constrained.extend(c.a().keys().chain(c.b().keys()).chain(c.c().keys()).cloned());
+    }
+    let mut by_instance: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut seen = HashSet::new();
+    let _ = list.visit_symbols(&mut |elem| {
+        let unconstrained = elem.witness > 0 && !constrained.contains(&(elem.witness as usize));
+        // Only the first name of a wire is listed
+        if unconstrained && seen.insert(elem.witness) {
+            let (path, name) = split_path(&elem.symbol);
+            by_instance.entry(path.to_string()).or_default().push(name.to_string());
+        }
+        Result::Ok(())
+    });
+    by_instance.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbols_are_split_at_the_last_dot() {
+        assert_eq!(split_path("main.m.in[1]"), ("main.m", "in[1]"));
+        assert_eq!(split_path("one"), ("", "one"));
+    }
+}
@@ -0,0 +1,72 @@
+use super::{Matrices, Matrix};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+
+// C is a linear combination by construction, so a product that was misplaced
+// by a faulty normalization shows up as a half product: one of A or B empty
+// while the other is not, which silently turns A*B into 0
+fn check_product(matrices: &Matrices, index: usize, reports: &mut ReportCollection) {
+    let a_empty = matrices.a[index].is_empty();
+    let b_empty = matrices.b[index].is_empty();
+    if a_empty != b_empty {
+        let side = if a_empty { "B" } else { "A" };
+        let msg = format!(
+            "Constraint {} is not in R1CS form: its product only has the {} side",
+            index, side
+        );
+        let mut report = Report::error(msg, ReportCode::MisplacedProduct);
+        report
+            .add_note("Linear terms must be moved to C, products must have both sides".to_string());
+        reports.push(report);
+    }
+}
+
+fn check_wires(matrix: &Matrix, name: &str, no_wires: usize, reports: &mut ReportCollection) {
+    for (index, row) in matrix.iter().enumerate() {
+        for (wire, _) in row.iter().filter(|(wire, _)| *wire >= no_wires) {
+            let msg = format!(
+                "Constraint {} references wire {} in {} but there are only {} wires",
+                index, wire, name, no_wires
+            );
+            reports.push(Report::error(msg, ReportCode::WireOutOfRange));
+        }
+    }
+}
+
+pub fn validate_r1cs(matrices: &Matrices) -> ReportCollection {
+    let mut reports = ReportCollection::new();
+    check_wires(&matrices.a, "A", matrices.no_wires, &mut reports);
+    check_wires(&matrices.b, "B", matrices.no_wires, &mut reports);
+    check_wires(&matrices.c, "C", matrices.no_wires, &mut reports);
+    for index in 0..matrices.no_constraints() {
+        check_product(matrices, index, &mut reports);
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::witness::tests::multiplier;
+    use circom_algebra::num_bigint::BigInt;
+
+    #[test]
+    fn compiled_constraints_are_valid() {
+        assert!(validate_r1cs(&multiplier().matrices()).is_empty());
+    }
+
+    #[test]
+    fn misplaced_product_is_detected() {
+        let mut matrices = multiplier().matrices();
+        // out = a*b with the product half moved into C
+        let one = BigInt::from(1);
+        matrices.a.push(vec![(2, one.clone())]);
+        matrices.b.push(vec![]);
+        matrices.c.push(vec![(1, one.clone()), (3, one.clone()), (7, one)]);
+        let reports = validate_r1cs(&matrices);
+        assert_eq!(reports.len(), 2);
+        assert!(reports.iter().all(|r| r.is_error()));
+        assert!(reports[0].get_message().contains("Constraint 1 references wire 7 in C"));
+        assert!(reports[1].get_message().contains("Constraint 1 is not in R1CS form"));
+    }
+}
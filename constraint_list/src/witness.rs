@@ -0,0 +1,223 @@
+use super::{ConstraintList, C};
+use circom_algebra::modular_arithmetic;
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_traits::Zero;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::Report;
+use std::collections::HashMap;
+
+#[derive(Clone)]
+pub struct Witness {
+    values: Vec<BigInt>,
+}
+
+fn invalid_entry(index: usize, entry: &str, representation: &str) -> Report {
+    let msg =
+        format!("Witness entry {} (\"{}\") is not a valid {} value", index, entry, representation);
+    Report::error(msg, ReportCode::InvalidWitnessValue)
+}
+
+fn parse_entries<T: AsRef<str>>(
+    entries: &[T],
+    radix: u32,
+    representation: &str,
+) -> Result<Witness, Report> {
+    let mut values = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        let entry = entry.as_ref().trim();
+        let (negative, digits) = match entry.strip_prefix('-') {
+            Option::Some(digits) => (true, digits),
+            Option::None => (false, entry),
+        };
+        let digits = if radix == 16 {
+            digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")).unwrap_or(digits)
+        } else {
+            digits
+        };
+        let value = BigInt::parse_bytes(digits.as_bytes(), radix)
+            .filter(|_| !digits.starts_with('-') && !digits.starts_with('+'))
+            .ok_or_else(|| invalid_entry(index, entry, representation))?;
+        values.push(if negative { -value } else { value });
+    }
+    Result::Ok(Witness { values })
+}
+
+impl Witness {
+    pub fn from_decimal<T: AsRef<str>>(entries: &[T]) -> Result<Witness, Report> {
+        parse_entries(entries, 10, "decimal")
+    }
+
+    pub fn from_hex<T: AsRef<str>>(entries: &[T]) -> Result<Witness, Report> {
+        parse_entries(entries, 16, "hexadecimal")
+    }
+
+    pub fn from_bigints(values: Vec<BigInt>) -> Witness {
+        Witness { values }
+    }
+
+    // Maps every value (negative ones included) into [0, field)
+    pub fn normalize(&mut self, field: &BigInt) {
+        for value in &mut self.values {
+            *value = modular_arithmetic::add(value, &BigInt::from(0), field);
+        }
+    }
+
+    pub fn values(&self) -> &[BigInt] {
+        &self.values
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+fn evaluate(lc: &HashMap<usize, BigInt>, witness: &[BigInt], field: &BigInt) -> BigInt {
+    let constant = C::constant_coefficient();
+    let mut result = BigInt::from(0);
+    for (signal, coefficient) in lc {
+        let term = if *signal == constant {
+            coefficient.clone()
+        } else {
+            modular_arithmetic::mul(coefficient, &witness[*signal], field)
+        };
+        result = modular_arithmetic::add(&result, &term, field);
+    }
+    result
+}
+
+// A*B - C mod p, with the signals already expressed as witness positions
+fn residual(constraint: &C, witness: &[BigInt], field: &BigInt) -> BigInt {
+    let a = evaluate(constraint.a(), witness, field);
+    let b = evaluate(constraint.b(), witness, field);
+    let c = evaluate(constraint.c(), witness, field);
+    modular_arithmetic::sub(&modular_arithmetic::mul(&a, &b, field), &c, field)
+}
+
+fn check_length(list: &ConstraintList, witness: &Witness) -> Result<(), Report> {
+    if witness.len() != list.no_wires() {
+        let msg = format!(
+            "The witness has {} values but the circuit has {} wires",
+            witness.len(),
+            list.no_wires()
+        );
+        return Result::Err(Report::error(msg, ReportCode::UnsatisfiedWitness));
+    }
+    Result::Ok(())
+}
+
+pub fn check_witness(list: &ConstraintList, witness: &Witness) -> Result<(), Report> {
+    if let Option::Some((position, _)) = violated_constraints(list, witness)?.first() {
+        let msg = format!("Constraint {} is not satisfied by the witness", position);
+        return Result::Err(Report::error(msg, ReportCode::UnsatisfiedWitness));
+    }
+    Result::Ok(())
+}
+
+// Position and residual of every constraint the witness does not satisfy,
+// an error when the witness does not have a value per wire
+pub fn violated_constraints(
+    list: &ConstraintList,
+    witness: &Witness,
+) -> Result<Vec<(usize, BigInt)>, Report> {
+    check_length(list, witness)?;
+    let mut witness = witness.clone();
+    witness.normalize(&list.field);
+    let mut violated = Vec::new();
+    for (position, c_id) in list.constraints.get_ids().into_iter().enumerate() {
+        let constraint = list.constraints.read_constraint(c_id).unwrap();
+        let constraint = C::apply_correspondence(&constraint, &list.signal_map);
+        let residual = residual(&constraint, witness.values(), &list.field);
+        if !residual.is_zero() {
+            violated.push((position, residual));
+        }
+    }
+    Result::Ok(violated)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::DAGEncoding;
+    use circom_algebra::algebra::ArithmeticExpression;
+    use circom_algebra::constraint_storage::ConstraintStorage;
+    type A = ArithmeticExpression<usize>;
+    const FIELD: &str = "257";
+
+    fn field() -> BigInt {
+        BigInt::parse_bytes(FIELD.as_bytes(), 10).unwrap()
+    }
+
+    // Signals: 1 = out, 2 = a, 3 = b with the constraint a*b - out = 0
+    pub fn multiplier() -> ConstraintList {
+        multiplier_over(field())
+    }
+
+    pub fn multiplier_over(field: BigInt) -> ConstraintList {
+        let a = A::Signal { symbol: 2 };
+        let b = A::Signal { symbol: 3 };
+        let out = A::Signal { symbol: 1 };
+        let expr = A::sub(&A::mul(&a, &b, &field), &out, &field);
+        let constraint = A::transform_expression_to_constraint_form(expr, &field).unwrap();
+        let mut constraints = ConstraintStorage::new();
+        constraints.add_constraint(constraint);
+        ConstraintList {
+            field,
+            dag_encoding: DAGEncoding {
+                init: 0,
+                no_constraints: 1,
+                nodes: vec![],
+                adjacency: vec![],
+            },
+            no_public_inputs: 0,
+            no_public_outputs: 1,
+            no_private_inputs: 2,
+            constraints,
+            no_labels: 4,
+            signal_map: (0..4).map(|s| (s, s)).collect(),
+            provenance: Option::None,
+            spans: Option::None,
+        }
+    }
+
+    #[test]
+    fn witness_representations_agree() {
+        let decimal = Witness::from_decimal(&["1", "6", "-2", "-3"]).ok().unwrap();
+        let hex = Witness::from_hex(&["0x1", "0x6", "-0x2", "-3"]).ok().unwrap();
+        let big =
+            Witness::from_bigints(vec![1, 6, 255, 254].into_iter().map(BigInt::from).collect());
+        let list = multiplier();
+        for witness in [decimal, hex, big].iter() {
+            assert!(check_witness(&list, witness).is_ok());
+        }
+        let mut wrong = Witness::from_decimal(&["1", "7", "2", "3"]).ok().unwrap();
+        assert!(check_witness(&list, &wrong).is_err());
+        wrong.normalize(&field());
+        assert_eq!(wrong.values()[1], BigInt::from(7));
+    }
+
+    #[test]
+    fn residuals_of_the_violated_constraints() {
+        let list = multiplier();
+        let right = Witness::from_decimal(&["1", "6", "2", "3"]).ok().unwrap();
+        assert!(list.check_witness_verbose(&right).ok().unwrap().is_empty());
+        // 2*3 - 7 = -1
+        let wrong = Witness::from_decimal(&["1", "7", "2", "3"]).ok().unwrap();
+        let violated = list.check_witness_verbose(&wrong).ok().unwrap();
+        assert_eq!(violated, vec![(0, BigInt::from(256))]);
+        // A short witness is an error, not an out of bounds access
+        let short = Witness::from_decimal(&["1", "6"]).ok().unwrap();
+        assert!(list.check_witness_verbose(&short).is_err());
+    }
+
+    #[test]
+    fn invalid_entries_are_rejected() {
+        assert!(Witness::from_decimal(&["1", "0x2"]).is_err());
+        assert!(Witness::from_hex(&["1", "zz"]).is_err());
+        assert!(Witness::from_decimal(&["1", "--2"]).is_err());
+        assert!(check_witness(&multiplier(), &Witness::from_decimal(&["1"]).ok().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use circom_algebra::num_bigint::{BigInt, Sign};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+// Layout of the file, every integer and field element is little endian:
+//   field size n (u32), prime (n bytes)
+//   wires, public wires including the constant one, constraints (u64 each)
+//   per constraint the a, b and c linear combinations, each one as the
+//   number of terms (u32) followed by the (wire (u64), coefficient (n bytes)) terms
+pub type Row = Vec<(usize, BigInt)>;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BellmanHeader {
+    pub field: BigInt,
+    pub no_wires: usize,
+    pub no_public: usize,
+    pub no_constraints: usize,
+}
+
+// Same element size as the r1cs format: whole 64 bit words with room for the prime
+pub fn field_size(field: &BigInt) -> usize {
+    (field.bits() / 64 + 1) * 8
+}
+
+fn element_bytes(value: &BigInt, size: usize) -> Vec<u8> {
+    let (_, mut bytes) = value.to_bytes_le();
+    bytes.resize(size, 0);
+    bytes
+}
+
+fn write_row(writer: &mut BufWriter<File>, row: &Row, size: usize) -> Result<(), ()> {
+    writer.write_all(&(row.len() as u32).to_le_bytes()).map_err(|_| {})?;
+    for (wire, coefficient) in row {
+        writer.write_all(&(*wire as u64).to_le_bytes()).map_err(|_| {})?;
+        writer.write_all(&element_bytes(coefficient, size)).map_err(|_| {})?;
+    }
+    Result::Ok(())
+}
+
+pub fn write(file: &str, header: &BellmanHeader, constraints: &[[&Row; 3]]) -> Result<(), ()> {
+    let size = field_size(&header.field);
+    let mut writer = BufWriter::new(File::create(file).map_err(|_| {})?);
+    writer.write_all(&(size as u32).to_le_bytes()).map_err(|_| {})?;
+    writer.write_all(&element_bytes(&header.field, size)).map_err(|_| {})?;
+    for count in [header.no_wires, header.no_public, header.no_constraints] {
+        writer.write_all(&(count as u64).to_le_bytes()).map_err(|_| {})?;
+    }
+    for constraint in constraints {
+        for row in constraint {
+            write_row(&mut writer, row, size)?;
+        }
+    }
+    writer.flush().map_err(|_| {})
+}
+
+fn read_bytes(reader: &mut BufReader<File>, size: usize) -> Result<Vec<u8>, ()> {
+    let mut bytes = vec![0; size];
+    reader.read_exact(&mut bytes).map_err(|_| {})?;
+    Result::Ok(bytes)
+}
+
+fn read_u32(reader: &mut BufReader<File>) -> Result<usize, ()> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes).map_err(|_| {})?;
+    Result::Ok(u32::from_le_bytes(bytes) as usize)
+}
+
+fn read_u64(reader: &mut BufReader<File>) -> Result<usize, ()> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes).map_err(|_| {})?;
+    Result::Ok(u64::from_le_bytes(bytes) as usize)
+}
+
+pub fn read(file: &str) -> Result<(BellmanHeader, Vec<[Row; 3]>), ()> {
+    let mut reader = BufReader::new(File::open(file).map_err(|_| {})?);
+    let size = read_u32(&mut reader)?;
+    let field = BigInt::from_bytes_le(Sign::Plus, &read_bytes(&mut reader, size)?);
+    let header = BellmanHeader {
+        field,
+        no_wires: read_u64(&mut reader)?,
+        no_public: read_u64(&mut reader)?,
+        no_constraints: read_u64(&mut reader)?,
+    };
+    let mut constraints = Vec::with_capacity(header.no_constraints);
+    for _ in 0..header.no_constraints {
+        let mut rows: [Row; 3] = Default::default();
+        for row in rows.iter_mut() {
+            for _ in 0..read_u32(&mut reader)? {
+                let wire = read_u64(&mut reader)?;
+                let coefficient =
+                    BigInt::from_bytes_le(Sign::Plus, &read_bytes(&mut reader, size)?);
+                row.push((wire, coefficient));
+            }
+        }
+        constraints.push(rows);
+    }
+    Result::Ok((header, constraints))
+}
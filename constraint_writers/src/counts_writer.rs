@@ -0,0 +1,132 @@
+use json::JsonValue;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct CircuitCounts {
+    pub no_linear: usize,
+    pub no_non_linear: usize,
+    pub no_public_inputs: usize,
+    pub no_public_outputs: usize,
+    pub no_private_inputs: usize,
+    pub no_intermediates: usize,
+    pub no_witness: usize,
+    pub no_template_instances: usize,
+}
+
+// Change of one metric with respect to a baseline
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CountDelta {
+    pub metric: &'static str,
+    pub baseline: usize,
+    pub current: usize,
+}
+
+impl CountDelta {
+    pub fn difference(&self) -> i64 {
+        self.current as i64 - self.baseline as i64
+    }
+
+    // Every metric measures size, so growing is a regression
+    pub fn is_regression(&self) -> bool {
+        self.current > self.baseline
+    }
+}
+
+impl CircuitCounts {
+    fn metrics(&self) -> [(&'static str, usize); 8] {
+        [
+            ("linear_constraints", self.no_linear),
+            ("non_linear_constraints", self.no_non_linear),
+            ("public_inputs", self.no_public_inputs),
+            ("public_outputs", self.no_public_outputs),
+            ("private_inputs", self.no_private_inputs),
+            ("intermediates", self.no_intermediates),
+            ("witness_length", self.no_witness),
+            ("template_instances", self.no_template_instances),
+        ]
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let mut json = json::object! {};
+        for (metric, value) in self.metrics().iter() {
+            json[*metric] = (*value).into();
+        }
+        json
+    }
+
+    // None if some metric is missing or is not a number
+    pub fn from_json(json: &JsonValue) -> Option<CircuitCounts> {
+        let metric = |name: &str| json[name].as_usize();
+        Option::Some(CircuitCounts {
+            no_linear: metric("linear_constraints")?,
+            no_non_linear: metric("non_linear_constraints")?,
+            no_public_inputs: metric("public_inputs")?,
+            no_public_outputs: metric("public_outputs")?,
+            no_private_inputs: metric("private_inputs")?,
+            no_intermediates: metric("intermediates")?,
+            no_witness: metric("witness_length")?,
+            no_template_instances: metric("template_instances")?,
+        })
+    }
+
+    pub fn read(file: &str) -> Result<CircuitCounts, ()> {
+        let content = std::fs::read_to_string(file).map_err(|_err| {})?;
+        let json = json::parse(&content).map_err(|_err| {})?;
+        CircuitCounts::from_json(&json).ok_or(())
+    }
+
+    // One entry per metric, in the order of the counts file
+    pub fn diff(&self, baseline: &CircuitCounts) -> Vec<CountDelta> {
+        let current = self.metrics();
+        let baseline = baseline.metrics();
+        current
+            .iter()
+            .zip(baseline.iter())
+            .map(|((metric, current), (_, baseline))| CountDelta {
+                metric,
+                baseline: *baseline,
+                current: *current,
+            })
+            .collect()
+    }
+
+    pub fn write(&self, file: &str) -> Result<(), ()> {
+        let file = File::create(file).map_err(|_err| {})?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(self.to_json().pretty(4).as_bytes()).map_err(|_err| {})?;
+        writer.flush().map_err(|_err| {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(no_linear: usize, no_non_linear: usize, no_witness: usize) -> CircuitCounts {
+        CircuitCounts { no_linear, no_non_linear, no_witness, ..CircuitCounts::default() }
+    }
+
+    #[test]
+    fn diff_against_a_stored_baseline() {
+        let name = format!("circom_counts_writer_test_{}.json", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        let file = file.to_str().unwrap();
+        counts(10, 20, 31).write(file).unwrap();
+        let baseline = CircuitCounts::read(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        assert_eq!(baseline, counts(10, 20, 31));
+        let deltas = counts(7, 25, 31).diff(&baseline);
+        assert_eq!(deltas.len(), 8);
+        assert_eq!((deltas[0].metric, deltas[0].difference()), ("linear_constraints", -3));
+        assert_eq!((deltas[1].metric, deltas[1].difference()), ("non_linear_constraints", 5));
+        assert!(!deltas[0].is_regression() && deltas[1].is_regression());
+        assert_eq!(deltas[6].difference(), 0);
+    }
+
+    #[test]
+    fn malformed_baselines_are_rejected() {
+        assert!(CircuitCounts::from_json(&json::object! { "linear_constraints": 1 }).is_none());
+        assert!(CircuitCounts::read("/this/file/does/not/exist.json").is_err());
+    }
+}
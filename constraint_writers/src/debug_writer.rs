@@ -3,13 +3,15 @@ use super::json_writer::ConstraintJSON;
 #[derive(Clone)]
 pub struct DebugWriter {
     pub json_constraints: String,
+    // Constraints flushed at once, 0 writes the whole file before flushing
+    pub chunk_size: usize,
 }
 impl DebugWriter {
-    pub fn new(c: String) -> Result<DebugWriter, ()> {
-        Result::Ok(DebugWriter { json_constraints: c })
+    pub fn new(c: String, chunk_size: usize) -> Result<DebugWriter, ()> {
+        Result::Ok(DebugWriter { json_constraints: c, chunk_size })
     }
 
     pub fn build_constraints_file(&self) -> Result<ConstraintJSON, ()> {
-        ConstraintJSON::new(&self.json_constraints)
+        ConstraintJSON::with_chunk_size(&self.json_constraints, self.chunk_size)
     }
 }
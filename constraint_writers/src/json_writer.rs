@@ -4,10 +4,16 @@ use std::io::{BufWriter, Write};
 pub struct ConstraintJSON {
     writer_constraints: BufWriter<File>,
     constraints_flag: bool,
+    // Constraints written between two flushes, 0 only flushes at the end
+    chunk_size: usize,
+    pending: usize,
 }
 
 impl ConstraintJSON {
     pub fn new(file: &str) -> Result<ConstraintJSON, ()> {
+        ConstraintJSON::with_chunk_size(file, 1)
+    }
+    pub fn with_chunk_size(file: &str, chunk_size: usize) -> Result<ConstraintJSON, ()> {
         let file_constraints = File::create(file).map_err(|_err| {})?;
         let mut writer_constraints = BufWriter::new(file_constraints);
 
@@ -16,19 +22,26 @@ impl ConstraintJSON {
         writer_constraints.write_all(b"\n\"constraints\": [").map_err(|_err| {})?;
         writer_constraints.flush().map_err(|_err| {})?;
 
-        Result::Ok(ConstraintJSON { writer_constraints, constraints_flag: false })
+        Result::Ok(ConstraintJSON {
+            writer_constraints,
+            constraints_flag: false,
+            chunk_size,
+            pending: 0,
+        })
     }
     pub fn write_constraint(&mut self, constraint: &str) -> Result<(), ()> {
         if !self.constraints_flag {
             self.constraints_flag = true;
             self.writer_constraints.write_all(b"\n").map_err(|_err| {})?;
-            self.writer_constraints.flush().map_err(|_err| {})?;
         } else {
             self.writer_constraints.write_all(b",\n").map_err(|_err| {})?;
-            self.writer_constraints.flush().map_err(|_err| {})?;
         }
         self.writer_constraints.write_all(constraint.as_bytes()).map_err(|_err| {})?;
-        self.writer_constraints.flush().map_err(|_err| {})?;
+        self.pending += 1;
+        if self.pending == self.chunk_size {
+            self.pending = 0;
+            self.writer_constraints.flush().map_err(|_err| {})?;
+        }
         Result::Ok(())
     }
     pub fn end(mut self) -> Result<(), ()> {
@@ -98,3 +111,31 @@ impl SubstitutionJSON {
         self.writer_substitutions.flush().map_err(|_err| {})
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_chunked(chunk_size: usize) -> String {
+        let name = format!("circom_json_writer_test_{}_{}.json", chunk_size, std::process::id());
+        let file = std::env::temp_dir().join(name);
+        let file = file.to_str().unwrap();
+        let mut writer = ConstraintJSON::with_chunk_size(file, chunk_size).unwrap();
+        for i in 0..10 {
+            writer.write_constraint(&format!("[{{}},{{}},{{\"{}\":\"1\"}}]", i)).unwrap();
+        }
+        writer.end().unwrap();
+        let written = std::fs::read_to_string(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        written
+    }
+
+    #[test]
+    fn chunks_do_not_change_the_output() {
+        let unchunked = write_chunked(0);
+        assert!(unchunked.starts_with("{\n\"constraints\": [\n[{},{},{\"0\":\"1\"}],\n"));
+        for chunk_size in [1, 3, 10, 64].iter() {
+            assert_eq!(write_chunked(*chunk_size), unchunked);
+        }
+    }
+}
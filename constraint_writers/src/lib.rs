@@ -1,11 +1,26 @@
+pub mod bellman_writer;
+pub mod counts_writer;
 pub mod debug_writer;
 pub mod json_writer;
 pub mod log_writer;
+pub mod manifest_writer;
 pub mod r1cs_writer;
+pub mod shard_writer;
+pub mod sql_writer;
+pub mod summary_writer;
 pub mod sym_writer;
 
 pub trait ConstraintExporter {
     fn r1cs(&self, out: &str) -> Result<(), ()>;
     fn json_constraints(&self, writer: &debug_writer::DebugWriter) -> Result<(), ()>;
     fn sym(&self, out: &str) -> Result<(), ()>;
+    fn sql_constraints(&self, out: &str) -> Result<(), ()>;
+    fn manifest(&self, out: &str) -> Result<(), ()>;
+    // The header the r1cs file is written with
+    fn r1cs_header(&self) -> r1cs_writer::HeaderData;
+    // The r1cs split in files of at most shard_size constraints, see
+    // shard_writer. Only the circom layout can be sharded
+    fn r1cs_shards(&self, _out: &str, _shard_size: usize) -> Result<(), ()> {
+        Result::Err(())
+    }
 }
@@ -7,6 +7,7 @@ pub struct Log {
     pub no_private_inputs: usize,
     pub no_public_outputs: usize,
     pub no_private_outputs: usize,
+    pub no_reduced_coefficients: usize,
 }
 
 impl Log {
@@ -20,6 +21,7 @@ impl Log {
             no_private_outputs: 0,
             no_wires: 0,
             no_labels: 0,
+            no_reduced_coefficients: 0,
         }
     }
 
@@ -32,5 +34,11 @@ impl Log {
         println!("private outputs: {}", log.no_private_outputs);
         println!("wires: {}", log.no_wires);
         println!("labels: {}", log.no_labels);
+        if log.no_reduced_coefficients > 0 {
+            println!(
+                "warning: {} coefficients were out of the field and were written reduced",
+                log.no_reduced_coefficients
+            );
+        }
     }
 }
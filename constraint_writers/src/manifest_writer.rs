@@ -0,0 +1,126 @@
+use json::JsonValue;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// Name of every witness signal: entry i describes the signal at position i.
+// The kind is the role of the wire in the circuit (0 is the constant one,
+// then the outputs and the public and private inputs of main), the signals of
+// the subcomponents are intermediate wires whatever their kind in the
+// template that declares them
+pub struct SignalManifest {
+    symbols: Vec<Option<String>>,
+    no_public_outputs: usize,
+    no_public_inputs: usize,
+    no_private_inputs: usize,
+}
+
+// main.c.in[1][0] is ("main.c", "in", [1, 0])
+fn split_symbol(symbol: &str) -> (&str, &str, Vec<usize>) {
+    let (path, last) = match symbol.rfind('.') {
+        Option::Some(dot) => (&symbol[..dot], &symbol[dot + 1..]),
+        Option::None => ("", symbol),
+    };
+    let name_end = last.find('[').unwrap_or(last.len());
+    let indices = last[name_end..]
+        .split(['[', ']'])
+        .filter(|index| !index.is_empty())
+        .filter_map(|index| index.parse().ok())
+        .collect();
+    (path, &last[..name_end], indices)
+}
+
+impl SignalManifest {
+    pub fn new(
+        no_public_outputs: usize,
+        no_public_inputs: usize,
+        no_private_inputs: usize,
+    ) -> Self {
+        SignalManifest {
+            symbols: vec![Option::Some("one".to_string())],
+            no_public_outputs,
+            no_public_inputs,
+            no_private_inputs,
+        }
+    }
+
+    // A signal that shares its position with an earlier one keeps the first name
+    pub fn add_symbol(&mut self, witness: usize, symbol: String) {
+        if witness >= self.symbols.len() {
+            self.symbols.resize(witness + 1, Option::None);
+        }
+        if self.symbols[witness].is_none() {
+            self.symbols[witness] = Option::Some(symbol);
+        }
+    }
+
+    pub fn kind(&self, witness: usize) -> &'static str {
+        let outputs_end = 1 + self.no_public_outputs;
+        let public_end = outputs_end + self.no_public_inputs;
+        if witness == 0 {
+            "constant"
+        } else if witness < outputs_end {
+            "output"
+        } else if witness < public_end {
+            "public_input"
+        } else if witness < public_end + self.no_private_inputs {
+            "private_input"
+        } else {
+            "intermediate"
+        }
+    }
+
+    // Outputs and public inputs of main, as they are listed in the r1cs
+    pub fn public_signals(&self) -> Vec<String> {
+        let public_end = 1 + self.no_public_outputs + self.no_public_inputs;
+        (1..public_end)
+            .map(|witness| self.symbols.get(witness).cloned().flatten().unwrap_or_default())
+            .collect()
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let mut entries = JsonValue::new_array();
+        for (witness, symbol) in self.symbols.iter().enumerate() {
+            let symbol = symbol.as_deref().unwrap_or("");
+            let (path, name, indices) = split_symbol(symbol);
+            let entry = json::object! {
+                "name" => name,
+                "type" => self.kind(witness),
+                "component" => path,
+                "indices" => indices,
+            };
+            entries.push(entry).unwrap();
+        }
+        entries
+    }
+
+    pub fn write(&self, file: &str) -> Result<(), ()> {
+        let file = File::create(file).map_err(|_err| {})?;
+        let mut writer = BufWriter::new(file);
+        self.to_json().write_pretty(&mut writer, 4).map_err(|_err| {})?;
+        writer.flush().map_err(|_err| {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbols_are_split_into_path_name_and_indices() {
+        assert_eq!(split_symbol("main.c.in[1][0]"), ("main.c", "in", vec![1, 0]));
+        assert_eq!(split_symbol("main.out"), ("main", "out", vec![]));
+        let mut manifest = SignalManifest::new(1, 1, 0);
+        manifest.add_symbol(3, "main.c.in[2]".to_string());
+        manifest.add_symbol(1, "main.out".to_string());
+        manifest.add_symbol(1, "main.c.out".to_string());
+        let json = manifest.to_json();
+        assert_eq!(json.len(), 4);
+        assert_eq!(json[0]["type"], "constant");
+        assert_eq!(json[1]["name"], "out");
+        assert_eq!(json[1]["type"], "output");
+        // position 2 has no name
+        assert_eq!(json[2]["name"], "");
+        assert_eq!(json[3]["indices"][0], 2);
+        assert_eq!(json[3]["type"], "intermediate");
+    }
+}
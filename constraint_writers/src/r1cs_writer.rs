@@ -1,4 +1,4 @@
-use circom_algebra::num_bigint::BigInt;
+use circom_algebra::num_bigint::{BigInt, Sign};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufWriter, Seek, SeekFrom, Write};
@@ -9,6 +9,11 @@ const NUMBER_OF_SECTIONS: &[u8] = &[3, 0, 0, 0];
 const HEADER_TYPE: &[u8] = &[1, 0, 0, 0];
 const CONSTRAINT_TYPE: &[u8] = &[2, 0, 0, 0];
 const WIRE2LABEL_TYPE: &[u8] = &[3, 0, 0, 0];
+// Not part of the r1cs format, readers skip the sections they do not know
+const SPANS_TYPE: &[u8] = &[1, 16, 0, 0];
+const SECTIONS_WITH_SPANS: &[u8] = &[4, 0, 0, 0];
+const NUMBER_OF_SECTIONS_POSITION: u64 = 8;
+const UNKNOWN_SPAN: u32 = u32::MAX;
 const PLACE_HOLDER: &[u8] = &[3, 3, 3, 3, 3, 3, 3, 3];
 
 fn into_format(number: &[u8], with_bytes: usize) -> (Vec<u8>, usize) {
@@ -43,7 +48,7 @@ fn end_section(writer: &mut BufWriter<File>, go_back: u64, size: usize) -> Resul
 }
 
 fn obtain_linear_combination_block<T>(
-    linear_combination: &HashMap<T, BigInt>,
+    linear_combination: &[(T, BigInt)],
     field_size: usize,
 ) -> (Vec<u8>, usize)
 where
@@ -69,9 +74,9 @@ where
 
 fn write_constraint<T>(
     file: &mut BufWriter<File>,
-    a: &HashMap<T, BigInt>,
-    b: &HashMap<T, BigInt>,
-    c: &HashMap<T, BigInt>,
+    a: &[(T, BigInt)],
+    b: &[(T, BigInt)],
+    c: &[(T, BigInt)],
     field_size: usize,
 ) -> Result<usize, ()>
 where
@@ -102,7 +107,7 @@ fn initialize_file(writer: &mut BufWriter<File>) -> Result<(), ()> {
 pub struct R1CSWriter {
     field_size: usize,
     writer: BufWriter<File>,
-    sections: [bool; 3],
+    sections: [bool; 4],
 }
 pub struct HeaderSection {
     writer: BufWriter<File>,
@@ -110,16 +115,19 @@ pub struct HeaderSection {
     size: usize,
     index: usize,
     field_size: usize,
-    sections: [bool; 3],
+    sections: [bool; 4],
 }
 pub struct ConstraintSection {
     writer: BufWriter<File>,
     number_of_constraints: usize,
+    // Coefficients are written in [0, field)
+    field: BigInt,
+    reduced_coefficients: usize,
     go_back: u64,
     size: usize,
     index: usize,
     field_size: usize,
-    sections: [bool; 3],
+    sections: [bool; 4],
 }
 pub struct SignalSection {
     writer: BufWriter<File>,
@@ -127,12 +135,12 @@ pub struct SignalSection {
     size: usize,
     index: usize,
     field_size: usize,
-    sections: [bool; 3],
+    sections: [bool; 4],
 }
 
 impl R1CSWriter {
     pub fn new(output_file: String, field_size: usize) -> Result<R1CSWriter, ()> {
-        let sections = [false; 3];
+        let sections = [false; 4];
         let mut writer =
             File::create(&output_file).map_err(|_err| {}).map(|f| BufWriter::new(f))?;
         initialize_file(&mut writer)?;
@@ -149,10 +157,15 @@ impl R1CSWriter {
             sections: r1cs.sections,
         })
     }
-    pub fn start_constraints_section(mut r1cs: R1CSWriter) -> Result<ConstraintSection, ()> {
+    pub fn start_constraints_section(
+        mut r1cs: R1CSWriter,
+        field: &BigInt,
+    ) -> Result<ConstraintSection, ()> {
         let start = initialize_section(&mut r1cs.writer, CONSTRAINT_TYPE)?;
         Result::Ok(ConstraintSection {
             number_of_constraints: 0,
+            field: field.clone(),
+            reduced_coefficients: 0,
             writer: r1cs.writer,
             go_back: start,
             size: 0,
@@ -161,6 +174,17 @@ impl R1CSWriter {
             sections: r1cs.sections,
         })
     }
+    pub fn start_spans_section(mut r1cs: R1CSWriter) -> Result<SpansSection, ()> {
+        let start = initialize_section(&mut r1cs.writer, SPANS_TYPE)?;
+        Result::Ok(SpansSection {
+            writer: r1cs.writer,
+            go_back: start,
+            size: 0,
+            index: 3,
+            field_size: r1cs.field_size,
+            sections: r1cs.sections,
+        })
+    }
     pub fn start_signal_section(mut r1cs: R1CSWriter) -> Result<SignalSection, ()> {
         let start = initialize_section(&mut r1cs.writer, WIRE2LABEL_TYPE)?;
         Result::Ok(SignalSection {
@@ -174,6 +198,55 @@ impl R1CSWriter {
     }
 }
 
+// Source location of a constraint, lines and columns start at 1
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SourceSpan {
+    pub file_id: usize,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+// file id, start line, start column, end line and end column as u32 for
+// each constraint in the order of the constraint section, all of them
+// u32::MAX when the location is unknown
+pub struct SpansSection {
+    writer: BufWriter<File>,
+    go_back: u64,
+    size: usize,
+    index: usize,
+    field_size: usize,
+    sections: [bool; 4],
+}
+
+impl SpansSection {
+    pub fn write_span(&mut self, span: Option<&SourceSpan>) -> Result<(), ()> {
+        let values = match span {
+            Option::Some(s) => {
+                [s.file_id, s.start.0, s.start.1, s.end.0, s.end.1].map(|v| v as u32)
+            }
+            Option::None => [UNKNOWN_SPAN; 5],
+        };
+        for value in values.iter() {
+            self.writer.write_all(&value.to_le_bytes()).map_err(|_err| {})?;
+        }
+        self.size += 4 * values.len();
+        Result::Ok(())
+    }
+
+    pub fn end_section(mut self) -> Result<R1CSWriter, ()> {
+        end_section(&mut self.writer, self.go_back, self.size)?;
+        let end = self.writer.seek(SeekFrom::Current(0)).map_err(|_err| {})?;
+        self.writer.seek(SeekFrom::Start(NUMBER_OF_SECTIONS_POSITION)).map_err(|_err| {})?;
+        self.writer.write_all(SECTIONS_WITH_SPANS).map_err(|_err| {})?;
+        self.writer.seek(SeekFrom::Start(end)).map_err(|_err| {})?;
+        self.writer.flush().map_err(|_err| {})?;
+        let mut sections = self.sections;
+        sections[self.index] = true;
+        Result::Ok(R1CSWriter { writer: self.writer, field_size: self.field_size, sections })
+    }
+}
+
+#[derive(Clone)]
 pub struct HeaderData {
     pub field: BigInt,
     pub total_wires: usize,
@@ -183,6 +256,31 @@ pub struct HeaderData {
     pub number_of_labels: usize,
     pub number_of_constraints: usize,
 }
+
+const BN128_PRIME: &str =
+    "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+const BLS12381_PRIME: &str =
+    "52435875175126190479447740508185965837690552500527637822603658699938581184513";
+
+impl HeaderData {
+    // The lines of `snarkjs r1cs info` for the file written with this header
+    pub fn info_lines(&self) -> Vec<String> {
+        let curve = match self.field.to_str_radix(10).as_str() {
+            BN128_PRIME => "Curve: bn-128".to_string(),
+            BLS12381_PRIME => "Curve: bls12-381".to_string(),
+            prime => format!("Unknown Curve. Prime: {}", prime),
+        };
+        vec![
+            curve,
+            format!("# of Wires: {}", self.total_wires),
+            format!("# of Constraints: {}", self.number_of_constraints),
+            format!("# of Private Inputs: {}", self.private_inputs),
+            format!("# of Public Inputs: {}", self.public_inputs),
+            format!("# of Labels: {}", self.number_of_labels),
+            format!("# of Outputs: {}", self.public_outputs),
+        ]
+    }
+}
 impl HeaderSection {
     pub fn write_section(&mut self, data: HeaderData) -> Result<(), ()> {
         let (field_stream, bytes_field) = bigint_as_bytes(&data.field, self.field_size);
@@ -226,22 +324,33 @@ impl ConstraintSection {
         b: &Constraint,
         c: &Constraint,
     ) -> Result<(), ()> {
-        let field_size = self.field_size;
-        let mut r1cs_a = HashMap::new();
-        for (k, v) in a {
-            let (_, bytes) = BigInt::from(*k).to_bytes_le();
-            r1cs_a.insert(bytes, v.clone());
-        }
-        let mut r1cs_b = HashMap::new();
-        for (k, v) in b {
-            let (_, bytes) = BigInt::from(*k).to_bytes_le();
-            r1cs_b.insert(bytes, v.clone());
-        }
-        let mut r1cs_c = HashMap::new();
-        for (k, v) in c {
-            let (_, bytes) = BigInt::from(*k).to_bytes_le();
-            r1cs_c.insert(bytes, v.clone());
+        // Terms sorted by signal, the hash map order would change between runs.
+        // A coefficient out of [0, field) would make the file invalid, it is
+        // reduced and counted
+        fn as_terms(
+            lc: &Constraint,
+            field: &BigInt,
+            reduced: &mut usize,
+        ) -> Vec<(Vec<u8>, BigInt)> {
+            let mut sorted: Vec<_> = lc.iter().collect();
+            sorted.sort_by_key(|(k, _)| **k);
+            let mut terms = Vec::with_capacity(sorted.len());
+            for (k, v) in sorted {
+                let v = if v.sign() == Sign::Minus || v >= field {
+                    *reduced += 1;
+                    ((v % field) + field) % field
+                } else {
+                    v.clone()
+                };
+                terms.push((BigInt::from(*k).to_bytes_le().1, v));
+            }
+            terms
         }
+        let field_size = self.field_size;
+        let reduced = &mut self.reduced_coefficients;
+        let r1cs_a = as_terms(a, &self.field, reduced);
+        let r1cs_b = as_terms(b, &self.field, reduced);
+        let r1cs_c = as_terms(c, &self.field, reduced);
         let size = write_constraint(&mut self.writer, &r1cs_a, &r1cs_b, &r1cs_c, field_size)?;
         self.size += size;
         self.number_of_constraints += 1;
@@ -258,6 +367,11 @@ impl ConstraintSection {
     pub fn constraints_written(&self) -> usize {
         self.number_of_constraints
     }
+
+    // Coefficients that were not reduced modulo the field when written
+    pub fn reduced_coefficients(&self) -> usize {
+        self.reduced_coefficients
+    }
 }
 
 impl SignalSection {
@@ -282,3 +396,33 @@ impl SignalSection {
         Result::Ok(R1CSWriter { writer: self.writer, field_size: self.field_size, sections })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn info_lines_follow_snarkjs() {
+        let header = HeaderData {
+            field: BigInt::parse_bytes(BN128_PRIME.as_bytes(), 10).unwrap(),
+            total_wires: 4,
+            public_outputs: 1,
+            public_inputs: 0,
+            private_inputs: 2,
+            number_of_labels: 5,
+            number_of_constraints: 1,
+        };
+        let expected = [
+            "Curve: bn-128",
+            "# of Wires: 4",
+            "# of Constraints: 1",
+            "# of Private Inputs: 2",
+            "# of Public Inputs: 0",
+            "# of Labels: 5",
+            "# of Outputs: 1",
+        ];
+        assert_eq!(header.info_lines(), expected);
+        let header = HeaderData { field: BigInt::from(257), ..header };
+        assert_eq!(header.info_lines()[0], "Unknown Curve. Prime: 257");
+    }
+}
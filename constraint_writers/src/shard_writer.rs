@@ -0,0 +1,125 @@
+use super::r1cs_writer::{ConstraintSection, HeaderData, R1CSWriter};
+use circom_algebra::num_bigint::BigInt;
+use json::JsonValue;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+
+type Constraint = HashMap<usize, BigInt>;
+
+// Writes the constraints in r1cs files <out>_<i>.r1cs of at most shard_size
+// constraints each. Every shard has the header and the signal section of the
+// whole circuit, only the constraints are split. <out>.json maps each shard
+// to the range of constraint ids it holds
+pub struct ShardWriter {
+    out: String,
+    shard_size: usize,
+    field_size: usize,
+    header: HeaderData,
+    signals: Vec<usize>,
+    current: Option<ConstraintSection>,
+    shards: Vec<Range<usize>>,
+    written: usize,
+}
+
+impl ShardWriter {
+    pub fn new(
+        out: String,
+        shard_size: usize,
+        header: HeaderData,
+        signals: Vec<usize>,
+    ) -> Result<ShardWriter, ()> {
+        if shard_size == 0 {
+            return Result::Err(());
+        }
+        let field_size = (header.field.bits() / 64 + 1) * 8;
+        Result::Ok(ShardWriter {
+            out,
+            shard_size,
+            field_size,
+            header,
+            signals,
+            current: Option::None,
+            shards: Vec::new(),
+            written: 0,
+        })
+    }
+
+    pub fn shard_file(&self, index: usize) -> String {
+        format!("{}_{}.r1cs", self.out, index)
+    }
+
+    pub fn write_constraint_usize(
+        &mut self,
+        a: &Constraint,
+        b: &Constraint,
+        c: &Constraint,
+    ) -> Result<(), ()> {
+        if self.current.is_none() {
+            let r1cs = R1CSWriter::new(self.shard_file(self.shards.len()), self.field_size)?;
+            let section = R1CSWriter::start_constraints_section(r1cs, &self.header.field)?;
+            self.current = Option::Some(section);
+            self.shards.push(self.written..self.written);
+        }
+        let section = self.current.as_mut().unwrap();
+        section.write_constraint_usize(a, b, c)?;
+        self.written += 1;
+        self.shards.last_mut().unwrap().end = self.written;
+        if section.constraints_written() == self.shard_size {
+            self.close_shard()?;
+        }
+        Result::Ok(())
+    }
+
+    fn close_shard(&mut self) -> Result<(), ()> {
+        let section = match self.current.take() {
+            Option::Some(section) => section,
+            Option::None => return Result::Ok(()),
+        };
+        let number_of_constraints = section.constraints_written();
+        let r1cs = section.end_section()?;
+        let mut header_section = R1CSWriter::start_header_section(r1cs)?;
+        header_section.write_section(HeaderData { number_of_constraints, ..self.header.clone() })?;
+        let r1cs = header_section.end_section()?;
+        let mut signal_section = R1CSWriter::start_signal_section(r1cs)?;
+        for signal in &self.signals {
+            signal_section.write_signal_usize(*signal)?;
+        }
+        signal_section.end_section()?;
+        Result::Ok(())
+    }
+
+    pub fn manifest(&self) -> JsonValue {
+        let mut shards = JsonValue::new_array();
+        for (index, range) in self.shards.iter().enumerate() {
+            let shard = json::object! {
+                "index" => index,
+                "file" => self.shard_file(index),
+                "first_constraint" => range.start,
+                "end_constraint" => range.end,
+            };
+            shards.push(shard).unwrap();
+        }
+        json::object! {
+            "shard_size" => self.shard_size,
+            "number_of_constraints" => self.written,
+            "total_wires" => self.header.total_wires,
+            "public_outputs" => self.header.public_outputs,
+            "public_inputs" => self.header.public_inputs,
+            "private_inputs" => self.header.private_inputs,
+            "number_of_labels" => self.header.number_of_labels,
+            "shards" => shards,
+        }
+    }
+
+    // Closes the last shard and writes the manifest, the ranges of the shards
+    pub fn finish(mut self) -> Result<Vec<Range<usize>>, ()> {
+        self.close_shard()?;
+        let file = File::create(format!("{}.json", self.out)).map_err(|_err| {})?;
+        let mut writer = BufWriter::new(file);
+        self.manifest().write_pretty(&mut writer, 4).map_err(|_err| {})?;
+        writer.flush().map_err(|_err| {})?;
+        Result::Ok(self.shards)
+    }
+}
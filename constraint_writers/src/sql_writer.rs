@@ -0,0 +1,49 @@
+use circom_algebra::num_bigint::BigInt;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// The coefficients are field elements, too large for an integer column
+const HEADER: &str = "CREATE TABLE constraints (constraint_index INTEGER, matrix TEXT, \
+                      signal_index INTEGER, coefficient TEXT);\nBEGIN;\n";
+
+// One row per non zero coefficient of the A, B and C matrices
+pub struct ConstraintsSQL {
+    writer: BufWriter<File>,
+}
+
+impl ConstraintsSQL {
+    pub fn new(file: &str) -> Result<ConstraintsSQL, ()> {
+        let file = File::create(file).map_err(|_err| {})?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(HEADER.as_bytes()).map_err(|_err| {})?;
+        Result::Ok(ConstraintsSQL { writer })
+    }
+
+    pub fn write_constraint(
+        &mut self,
+        index: usize,
+        a: &HashMap<usize, BigInt>,
+        b: &HashMap<usize, BigInt>,
+        c: &HashMap<usize, BigInt>,
+    ) -> Result<(), ()> {
+        for (matrix, lc) in [("A", a), ("B", b), ("C", c)].iter() {
+            let mut signals: Vec<_> =
+                lc.iter().filter(|(_, v)| **v != BigInt::from(0)).map(|(k, _)| *k).collect();
+            signals.sort_unstable();
+            for signal in signals {
+                let row = format!(
+                    "INSERT INTO constraints VALUES ({}, '{}', {}, '{}');\n",
+                    index, matrix, signal, lc[&signal]
+                );
+                self.writer.write_all(row.as_bytes()).map_err(|_err| {})?;
+            }
+        }
+        Result::Ok(())
+    }
+
+    pub fn end(mut self) -> Result<(), ()> {
+        self.writer.write_all(b"COMMIT;\n").map_err(|_err| {})?;
+        self.writer.flush().map_err(|_err| {})
+    }
+}
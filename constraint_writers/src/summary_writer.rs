@@ -0,0 +1,118 @@
+use super::counts_writer::CircuitCounts;
+use super::manifest_writer::SignalManifest;
+use json::JsonValue;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// Contents of circuit_summary.json, the keys are part of the format:
+//   template_instances      number of template instances
+//   non_linear_constraints  number of non linear constraints
+//   linear_constraints      number of linear constraints
+//   public_inputs           number of public inputs of main
+//   public_outputs          number of outputs of main
+//   private_inputs          number of private inputs of main
+//   public_signals          names of the public signals, in the order of the r1cs
+//                           (the outputs first, then the public inputs)
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct CircuitSummary {
+    pub no_template_instances: usize,
+    pub no_non_linear: usize,
+    pub no_linear: usize,
+    pub no_public_inputs: usize,
+    pub no_public_outputs: usize,
+    pub no_private_inputs: usize,
+    pub public_signals: Vec<String>,
+}
+
+impl CircuitSummary {
+    pub fn new(counts: &CircuitCounts, manifest: &SignalManifest) -> CircuitSummary {
+        CircuitSummary {
+            no_template_instances: counts.no_template_instances,
+            no_non_linear: counts.no_non_linear,
+            no_linear: counts.no_linear,
+            no_public_inputs: counts.no_public_inputs,
+            no_public_outputs: counts.no_public_outputs,
+            no_private_inputs: counts.no_private_inputs,
+            public_signals: manifest.public_signals(),
+        }
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        json::object! {
+            "template_instances" => self.no_template_instances,
+            "non_linear_constraints" => self.no_non_linear,
+            "linear_constraints" => self.no_linear,
+            "public_inputs" => self.no_public_inputs,
+            "public_outputs" => self.no_public_outputs,
+            "private_inputs" => self.no_private_inputs,
+            "public_signals" => self.public_signals.clone(),
+        }
+    }
+
+    // None if some entry is missing or has the wrong type
+    pub fn from_json(json: &JsonValue) -> Option<CircuitSummary> {
+        let number = |name: &str| json[name].as_usize();
+        let signals = &json["public_signals"];
+        if !signals.is_array() {
+            return Option::None;
+        }
+        let public_signals = signals
+            .members()
+            .map(|signal| signal.as_str().map(|signal| signal.to_string()))
+            .collect::<Option<Vec<_>>>()?;
+        Option::Some(CircuitSummary {
+            no_template_instances: number("template_instances")?,
+            no_non_linear: number("non_linear_constraints")?,
+            no_linear: number("linear_constraints")?,
+            no_public_inputs: number("public_inputs")?,
+            no_public_outputs: number("public_outputs")?,
+            no_private_inputs: number("private_inputs")?,
+            public_signals,
+        })
+    }
+
+    pub fn read(file: &str) -> Result<CircuitSummary, ()> {
+        let content = std::fs::read_to_string(file).map_err(|_err| {})?;
+        let json = json::parse(&content).map_err(|_err| {})?;
+        CircuitSummary::from_json(&json).ok_or(())
+    }
+
+    pub fn write(&self, file: &str) -> Result<(), ()> {
+        let file = File::create(file).map_err(|_err| {})?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(self.to_json().pretty(4).as_bytes()).map_err(|_err| {})?;
+        writer.flush().map_err(|_err| {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summaries_read_back() {
+        let counts = CircuitCounts {
+            no_linear: 2,
+            no_non_linear: 3,
+            no_public_inputs: 1,
+            no_public_outputs: 1,
+            no_private_inputs: 1,
+            no_template_instances: 2,
+            ..CircuitCounts::default()
+        };
+        let mut manifest = SignalManifest::new(1, 1, 1);
+        manifest.add_symbol(1, "main.out".to_string());
+        manifest.add_symbol(2, "main.x".to_string());
+        manifest.add_symbol(3, "main.y".to_string());
+        let summary = CircuitSummary::new(&counts, &manifest);
+        assert_eq!(summary.public_signals, vec!["main.out", "main.x"]);
+        let name = format!("circom_summary_writer_test_{}.json", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        let file = file.to_str().unwrap();
+        summary.write(file).unwrap();
+        let read = CircuitSummary::read(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        assert_eq!(read, summary);
+        assert!(CircuitSummary::from_json(&json::object! { "linear_constraints": 1 }).is_none());
+    }
+}
@@ -13,6 +13,17 @@ impl ToString for SymElem {
     }
 }
 
+// Template instance that produced a constraint, listed by constraint index
+pub struct ConstraintSymElem {
+    pub constraint: usize,
+    pub template: String,
+}
+impl ToString for ConstraintSymElem {
+    fn to_string(&self) -> String {
+        format!("{},{}", self.constraint, self.template)
+    }
+}
+
 pub struct SymFile {
     writer: BufWriter<File>,
 }
@@ -25,6 +36,11 @@ impl SymFile {
     }
 
     pub fn write_sym_elem(sym: &mut SymFile, elem: SymElem) -> Result<(), ()> {
+        write_sym_line(&mut sym.writer, &elem)?;
+        sym.writer.flush().map_err(|_err| {})
+    }
+
+    pub fn write_constraint_elem(sym: &mut SymFile, elem: ConstraintSymElem) -> Result<(), ()> {
         sym.writer.write_all(elem.to_string().as_bytes()).map_err(|_err| {})?;
         sym.writer.write_all(b"\n").map_err(|_err| {})?;
         sym.writer.flush().map_err(|_err| {})
@@ -32,3 +48,9 @@ impl SymFile {
 
     pub fn close(_sym: SymFile) {}
 }
+
+// The line of the element in the .sym file, for writers other than SymFile
+pub fn write_sym_line(writer: &mut dyn Write, elem: &SymElem) -> Result<(), ()> {
+    writer.write_all(elem.to_string().as_bytes()).map_err(|_err| {})?;
+    writer.write_all(b"\n").map_err(|_err| {})
+}
@@ -86,7 +86,8 @@ fn visit_node(node: &mut Node) -> Analysis {
     }
     let length_bound = Vec::len(&node.constraints);
     let work = std::mem::replace(&mut node.constraints, Vec::with_capacity(length_bound));
-    for mut constraint in work {
+    let spans = std::mem::replace(&mut node.spans, Vec::with_capacity(length_bound));
+    for (mut constraint, span) in work.into_iter().zip(spans) {
         let signals = constraint.take_cloned_signals();
         for signal in signals {
             let prev = constraint_counter.remove(&signal).unwrap();
@@ -95,6 +96,7 @@ fn visit_node(node: &mut Node) -> Analysis {
         C::remove_zero_value_coefficients(&mut constraint);
         if !C::is_empty(&constraint) {
             Vec::push(&mut node.constraints, constraint);
+            Vec::push(&mut node.spans, span);
         }
     }
 
@@ -0,0 +1,129 @@
+use super::Constraint;
+use circom_algebra::modular_arithmetic;
+use circom_algebra::num_bigint::BigInt;
+use std::collections::HashMap;
+
+type Terms = Vec<(usize, BigInt)>;
+
+// Coefficients reduced modulo the field, without zeros and sorted by signal
+fn reduce(lc: &HashMap<usize, BigInt>, field: &BigInt, negate: bool) -> Terms {
+    let zero = BigInt::from(0);
+    let mut terms: Terms = lc
+        .iter()
+        .map(|(k, v)| {
+            let reduced = if negate {
+                modular_arithmetic::sub(&zero, v, field)
+            } else {
+                modular_arithmetic::add(v, &zero, field)
+            };
+            (*k, reduced)
+        })
+        .filter(|(_, v)| *v != zero)
+        .collect();
+    terms.sort_by_key(|(k, _)| *k);
+    terms
+}
+
+// Coefficients above field / 2 are shown as their negative representative,
+// the constant term goes last
+pub(crate) fn terms_to_string(
+    terms: &[(usize, BigInt)],
+    name_of: &dyn Fn(usize) -> String,
+    field: &BigInt,
+) -> String {
+    let constant = Constraint::constant_coefficient();
+    let half = field / 2;
+    let mut rendered = Vec::new();
+    let signals = terms.iter().filter(|(k, _)| *k != constant);
+    for (k, v) in signals.chain(terms.iter().filter(|(k, _)| *k == constant)) {
+        let (negative, abs) = if *v > half { (true, field - v) } else { (false, v.clone()) };
+        let term = if *k == constant {
+            abs.to_str_radix(10)
+        } else if abs == BigInt::from(1) {
+            name_of(*k)
+        } else {
+            format!("{}*{}", abs, name_of(*k))
+        };
+        let sign = match (rendered.is_empty(), negative) {
+            (true, true) => "-",
+            (true, false) => "",
+            (false, true) => " - ",
+            (false, false) => " + ",
+        };
+        rendered.push(format!("{}{}", sign, term));
+    }
+    if rendered.is_empty() {
+        "0".to_string()
+    } else {
+        rendered.concat()
+    }
+}
+
+fn factor_to_string(terms: &Terms, name_of: &dyn Fn(usize) -> String, field: &BigInt) -> String {
+    let factor = terms_to_string(terms, name_of, field);
+    if terms.len() > 1 {
+        format!("({})", factor)
+    } else {
+        factor
+    }
+}
+
+// Renders A*B - C = 0 with the names of the correspondence, signals
+// without a name are shown as their index
+pub fn pretty_constraint(
+    constraint: &Constraint,
+    correspondence: &HashMap<String, usize>,
+    field: &BigInt,
+) -> String {
+    let names: HashMap<_, _> = correspondence.iter().map(|(name, id)| (*id, name)).collect();
+    let name_of = |id: usize| names.get(&id).map_or_else(|| format!("#{}", id), |n| n.to_string());
+    let a = reduce(constraint.a(), field, false);
+    let b = reduce(constraint.b(), field, false);
+    let minus_c = reduce(constraint.c(), field, true);
+    if a.is_empty() || b.is_empty() {
+        return format!("{} = 0", terms_to_string(&minus_c, &name_of, field));
+    }
+    let a = factor_to_string(&a, &name_of, field);
+    let b = factor_to_string(&b, &name_of, field);
+    if minus_c.is_empty() {
+        return format!("{}*{} = 0", a, b);
+    }
+    let linear = terms_to_string(&minus_c, &name_of, field);
+    match linear.strip_prefix('-') {
+        Option::Some(rest) => format!("{}*{} - {} = 0", a, b, rest),
+        Option::None => format!("{}*{} + {} = 0", a, b, linear),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use circom_algebra::algebra::ArithmeticExpression;
+    use program_structure::constants::UsefulConstants;
+
+    type A = ArithmeticExpression<usize>;
+
+    fn names() -> HashMap<String, usize> {
+        let names = [("a", 1), ("b", 2), ("c", 3)];
+        names.iter().map(|(n, id)| (n.to_string(), *id)).collect()
+    }
+
+    #[test]
+    fn multiplier_constraint_is_readable() {
+        let field = UsefulConstants::new().get_p().clone();
+        let s = |symbol| A::Signal { symbol };
+        let expr = A::sub(&A::mul(&s(1), &s(2), &field), &s(3), &field);
+        let constraint = A::transform_expression_to_constraint_form(expr, &field).unwrap();
+        assert_eq!(pretty_constraint(&constraint, &names(), &field), "a*b - c = 0");
+    }
+
+    #[test]
+    fn linear_constraints_omit_the_product() {
+        let field = UsefulConstants::new().get_p().clone();
+        let s = |symbol| A::Signal { symbol };
+        let two = A::Number { value: BigInt::from(2) };
+        let expr = A::sub(&A::sub(&s(3), &A::mul(&two, &s(1), &field), &field), &s(4), &field);
+        let constraint = A::transform_expression_to_constraint_form(expr, &field).unwrap();
+        assert_eq!(pretty_constraint(&constraint, &names(), &field), "-2*a + c - #4 = 0");
+    }
+}
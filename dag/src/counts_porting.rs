@@ -0,0 +1,110 @@
+use super::{Constraint, Tree, DAG};
+use constraint_writers::counts_writer::CircuitCounts;
+
+fn visit_tree(tree: &Tree, counts: &mut CircuitCounts) {
+    counts.no_witness += tree.signals.len();
+    for c in &tree.constraints {
+        if Constraint::is_linear(c) {
+            counts.no_linear += 1;
+        } else {
+            counts.no_non_linear += 1;
+        }
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        visit_tree(&subtree, counts);
+    }
+}
+
+pub fn count(dag: &DAG) -> CircuitCounts {
+    let mut counts = CircuitCounts {
+        no_public_inputs: dag.public_inputs(),
+        no_public_outputs: dag.public_outputs(),
+        no_private_inputs: dag.private_inputs(),
+        no_template_instances: dag.number_of_nodes(),
+        // the signal one
+        no_witness: 1,
+        ..CircuitCounts::default()
+    };
+    visit_tree(&Tree::new(dag), &mut counts);
+    let io = counts.no_public_inputs + counts.no_public_outputs + counts.no_private_inputs;
+    counts.no_intermediates = counts.no_witness - 1 - io;
+    counts
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::SimplificationFlags;
+    use circom_algebra::algebra::ArithmeticExpression;
+    use circom_algebra::num_bigint::BigInt;
+    use program_structure::constants::UsefulConstants;
+
+    type A = ArithmeticExpression<usize>;
+
+    fn constraint(expr: A, field: &BigInt) -> Constraint {
+        A::transform_expression_to_constraint_form(expr, field).unwrap()
+    }
+
+    // Main(x public, y) -> out, wired through a Mul subcomponent m
+    pub fn multiplier_dag() -> DAG {
+        let field = UsefulConstants::new().get_p().clone();
+        let s = |symbol| A::Signal { symbol };
+        let mut dag = DAG::new();
+        dag.add_node("Mul".to_string(), false);
+        dag.add_input("a".to_string(), false);
+        dag.add_input("b".to_string(), false);
+        dag.add_output("c".to_string());
+        dag.add_constraint(constraint(
+            A::sub(&A::mul(&s(1), &s(2), &field), &s(3), &field),
+            &field,
+        ));
+        dag.add_node("Main".to_string(), false);
+        dag.add_output("out".to_string());
+        dag.add_input("x".to_string(), true);
+        dag.add_input("y".to_string(), false);
+        dag.add_edge(0, "m");
+        // m.a = 4, m.b = 5, m.c = 6
+        dag.add_constraint(constraint(A::sub(&s(2), &s(4), &field), &field));
+        dag.add_constraint(constraint(A::sub(&s(3), &s(5), &field), &field));
+        dag.add_constraint(constraint(A::sub(&s(1), &s(6), &field), &field));
+        // cleans the constraints as the export does
+        let _warnings = dag.constraint_analysis();
+        dag
+    }
+
+    #[test]
+    fn counts_without_simplification() {
+        let counts = count(&multiplier_dag());
+        let expected = CircuitCounts {
+            no_linear: 3,
+            no_non_linear: 1,
+            no_public_inputs: 1,
+            no_public_outputs: 1,
+            no_private_inputs: 1,
+            no_intermediates: 3,
+            no_witness: 7,
+            no_template_instances: 2,
+        };
+        assert_eq!(counts, expected);
+        assert_eq!(counts.to_json()["witness_length"], 7);
+    }
+
+    #[test]
+    fn counts_after_simplification() {
+        let flags = SimplificationFlags::default();
+        let list = multiplier_dag().map_to_list(flags);
+        let expected = CircuitCounts {
+            no_linear: 0,
+            no_non_linear: 1,
+            no_public_inputs: 1,
+            no_public_outputs: 1,
+            no_private_inputs: 1,
+            no_intermediates: 0,
+            no_witness: 4,
+            no_template_instances: 2,
+        };
+        assert_eq!(list.circuit_counts(), expected);
+        assert_eq!(list.circuit_counts().to_json()["linear_constraints"], 0);
+    }
+}
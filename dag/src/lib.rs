@@ -1,15 +1,27 @@
 mod constraint_correctness_analysis;
+mod constraint_printer;
+mod counts_porting;
 mod json_porting;
 mod map_to_constraint_list;
+mod path_resolver;
 mod r1cs_porting;
+mod redundancy_analysis;
+mod sql_porting;
+mod structure_porting;
 mod sym_porting;
+mod template_constraints;
 mod witness_producer;
 use circom_algebra::num_bigint::BigInt;
-use constraint_list::ConstraintList;
+pub use constraint_printer::pretty_constraint;
+use constraint_list::{ConstraintList, Matrices, SimplificationError};
+use constraint_writers::counts_writer::CircuitCounts;
 use constraint_writers::debug_writer::DebugWriter;
+use constraint_writers::json_writer::SubstitutionJSON;
+use constraint_writers::manifest_writer::SignalManifest;
+use constraint_writers::r1cs_writer::{HeaderData, SourceSpan};
 use constraint_writers::ConstraintExporter;
 use program_structure::constants::UsefulConstants;
-use program_structure::error_definition::ReportCollection;
+use program_structure::error_definition::{Report, ReportCollection};
 use std::collections::{HashMap, HashSet};
 
 type Signal = usize;
@@ -29,17 +41,19 @@ pub struct Tree<'a> {
     pub forbidden: HashSet<usize>,
     pub id_to_name: HashMap<usize, String>,
     pub constraints: Vec<Constraint>,
+    // Source location of each constraint, when it is known
+    pub spans: Vec<Option<SourceSpan>>,
 }
 
 impl<'a> Tree<'a> {
     pub fn new(dag: &DAG) -> Tree {
-        let constants = UsefulConstants::new();
-        let field = constants.get_p().clone();
+        let field = dag.constants.get_p().clone();
         let root = dag.get_main().unwrap();
         let node_id = dag.main_id();
         let offset = dag.get_entry().unwrap().in_number;
         let path = dag.get_entry().unwrap().label.clone();
         let constraints = root.constraints.clone();
+        let spans = root.spans.clone();
         let mut id_to_name = HashMap::new();
         let mut signals: Vec<_> = Vec::new();
         let forbidden: HashSet<_> =
@@ -51,12 +65,22 @@ impl<'a> Tree<'a> {
             }
         }
         signals.sort();
-        Tree { field, dag, path, offset, node_id, signals, forbidden, id_to_name, constraints }
+        Tree {
+            field,
+            dag,
+            path,
+            offset,
+            node_id,
+            signals,
+            forbidden,
+            id_to_name,
+            constraints,
+            spans,
+        }
     }
 
     pub fn go_to_subtree(current: &'a Tree, edge: &Edge) -> Tree<'a> {
-        let constants = UsefulConstants::new();
-        let field = constants.get_p().clone();
+        let field = current.field.clone();
         let dag = current.dag;
         let node_id = edge.goes_to;
         let node = &current.dag.nodes[node_id];
@@ -72,13 +96,25 @@ impl<'a> Tree<'a> {
             }
         }
         signals.sort();
-        let constraints: Vec<_> = node
+        let (constraints, spans): (Vec<_>, Vec<_>) = node
             .constraints
             .iter()
-            .filter(|c| !c.is_empty())
-            .map(|c| Constraint::apply_offset(c, offset))
-            .collect();
-        Tree { field, dag, path, offset, node_id, signals, forbidden, id_to_name, constraints }
+            .zip(&node.spans)
+            .filter(|(c, _)| !c.is_empty())
+            .map(|(c, span)| (Constraint::apply_offset(c, offset), span.clone()))
+            .unzip();
+        Tree {
+            field,
+            dag,
+            path,
+            offset,
+            node_id,
+            signals,
+            forbidden,
+            id_to_name,
+            constraints,
+            spans,
+        }
     }
 
     pub fn get_edges(tree: &'a Tree) -> &'a Vec<Edge> {
@@ -86,7 +122,7 @@ impl<'a> Tree<'a> {
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Edge {
     label: String,
     goes_to: usize,
@@ -133,7 +169,7 @@ impl Edge {
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Node {
     entry: Edge,
     template_name: String,
@@ -148,6 +184,7 @@ pub struct Node {
     forbidden_if_main: HashSet<usize>,
     io_signals: Vec<usize>,
     constraints: Vec<Constraint>,
+    spans: Vec<Option<SourceSpan>>,
     is_parallel: bool,
     has_parallel_sub_cmp: bool,
 }
@@ -197,8 +234,9 @@ impl Node {
         self.intermediates_length += 1;
     }
 
-    fn add_constraint(&mut self, constraint: Constraint) {
-        self.constraints.push(constraint)
+    fn add_constraint(&mut self, constraint: Constraint, span: Option<SourceSpan>) {
+        self.constraints.push(constraint);
+        self.spans.push(span);
     }
 
     fn is_local_signal(&self, s: usize) -> bool {
@@ -250,11 +288,15 @@ impl Node {
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct DAG {
     pub one_signal: usize,
     pub nodes: Vec<Node>,
     pub adjacency: Vec<Vec<Edge>>,
+    // The r1cs output gets a section with the source location of each constraint
+    pub r1cs_spans: bool,
+    // Field of the constraints, bn128 unless the build sets another prime
+    pub constants: UsefulConstants,
 }
 
 impl ConstraintExporter for DAG {
@@ -262,6 +304,14 @@ impl ConstraintExporter for DAG {
         DAG::generate_r1cs_output(self, out)
     }
 
+    fn r1cs_header(&self) -> HeaderData {
+        r1cs_porting::header(self)
+    }
+
+    fn r1cs_shards(&self, out: &str, shard_size: usize) -> Result<(), ()> {
+        r1cs_porting::write_shards(self, out, shard_size)
+    }
+
     fn json_constraints(&self, writer: &DebugWriter) -> Result<(), ()> {
         DAG::generate_json_constraints(self, writer)
     }
@@ -269,6 +319,14 @@ impl ConstraintExporter for DAG {
     fn sym(&self, out: &str) -> Result<(), ()> {
         DAG::generate_sym_output(self, out)
     }
+
+    fn sql_constraints(&self, out: &str) -> Result<(), ()> {
+        sql_porting::port_constraints(self, out)
+    }
+
+    fn manifest(&self, out: &str) -> Result<(), ()> {
+        self.signal_manifest().write(out)
+    }
 }
 
 impl DAG {
@@ -322,6 +380,23 @@ impl DAG {
         id
     }
 
+    // Adds a node built by a previous compilation with its edges. The spans
+    // come from the current compilation, the code may have moved since
+    pub fn add_cached_node(
+        &mut self,
+        mut node: Node,
+        edges: Vec<Edge>,
+        spans: Vec<Option<SourceSpan>>,
+    ) -> usize {
+        let id = self.nodes.len();
+        debug_assert_eq!(node.entry.goes_to, id);
+        debug_assert_eq!(node.spans.len(), spans.len());
+        node.spans = spans;
+        self.nodes.push(node);
+        self.adjacency.push(edges);
+        id
+    }
+
     pub fn add_input(&mut self, name: String, is_public: bool) {
         if let Option::Some(node) = self.get_mut_main() {
             node.add_input(name, is_public);
@@ -341,8 +416,12 @@ impl DAG {
     }
 
     pub fn add_constraint(&mut self, constraint: Constraint) {
+        self.add_constraint_with_span(constraint, Option::None);
+    }
+
+    pub fn add_constraint_with_span(&mut self, constraint: Constraint, span: Option<SourceSpan>) {
         if let Option::Some(node) = self.get_mut_main() {
-            node.add_constraint(constraint);
+            node.add_constraint(constraint, span);
         }
     }
 
@@ -393,6 +472,10 @@ impl DAG {
         }
     }
 
+    pub fn redundant_range_checks(&self) -> ReportCollection {
+        redundancy_analysis::analyse(self)
+    }
+
     pub fn generate_r1cs_output(&self, output_file: &str) -> Result<(), ()> {
         r1cs_porting::write(self, output_file)
     }
@@ -405,6 +488,62 @@ impl DAG {
         json_porting::port_constraints(self, debug)
     }
 
+    // Constraint indices follow the order of the non simplified r1cs
+    pub fn constraints_by_template(&self) -> HashMap<String, Vec<usize>> {
+        template_constraints::constraints_by_template(self)
+    }
+
+    // Nodes and edges of the dag, for tools that draw the component graph
+    pub fn export_json(&self) -> json::JsonValue {
+        structure_porting::structure(self)
+    }
+
+    // The same graph in the Graphviz format
+    pub fn to_dot(&self) -> String {
+        structure_porting::dot(self)
+    }
+
+    // Template that produced each constraint, in export order
+    pub fn constraint_templates(&self) -> Vec<String> {
+        template_constraints::constraint_templates(self)
+    }
+
+    pub fn write_constraint_templates(&self, out: &str) -> Result<(), ()> {
+        sym_porting::write_constraint_templates(self, out)
+    }
+
+    // Number of constraints of the templates with the same key
+    pub fn constraints_grouped_by<K, F>(&self, key: F) -> HashMap<K, usize>
+    where
+        K: Eq + std::hash::Hash,
+        F: Fn(&str) -> Option<K>,
+    {
+        template_constraints::constraints_grouped_by(self, key)
+    }
+
+    pub fn matrices(&self) -> Matrices {
+        r1cs_porting::matrices(self)
+    }
+
+    pub fn circuit_counts(&self) -> CircuitCounts {
+        counts_porting::count(self)
+    }
+
+    // Every constraint is replaced by its normalized form, see algebra::normalize
+    pub fn normalize_constraints(&mut self) {
+        let field = self.constants.get_p().clone();
+        for node in &mut self.nodes {
+            for constraint in &mut node.constraints {
+                let normalized = circom_algebra::algebra::normalize(constraint.clone(), &field);
+                *constraint = normalized;
+            }
+        }
+    }
+
+    pub fn signal_manifest(&self) -> SignalManifest {
+        sym_porting::manifest(self)
+    }
+
     pub fn produce_witness(&self) -> Vec<usize> {
         witness_producer::produce_witness(self)
     }
@@ -429,6 +568,20 @@ impl DAG {
         self.get_main().map(|v| v.get_entry())
     }
 
+    // Id of a signal given its full path, as main.sub.out[3]. The ids are the
+    // ones of the dag (the labels of the sym file), the wire after the
+    // simplification comes from the signal map of the list
+    pub fn signal_id(&self, path: &str) -> Result<usize, Report> {
+        path_resolver::signal_id(self, path)
+    }
+
+    // The simplification can not remove the signal, as with the public
+    // inputs of main
+    pub fn keep_signal(&mut self, signal: usize) {
+        let main_id = self.main_id();
+        self.nodes[main_id].forbidden_if_main.insert(signal);
+    }
+
     pub fn public_inputs(&self) -> usize {
         if let Option::Some(main) = self.get_main() {
             main.number_of_public_inputs()
@@ -456,11 +609,76 @@ impl DAG {
     pub fn map_to_list(self, flags: SimplificationFlags) -> ConstraintList {
         map_to_constraint_list::map(self, flags)
     }
+
+    // Same as map_to_list, the constraints that can not be simplified in
+    // the field are returned as an error instead of panicking
+    pub fn try_map_to_list(
+        self,
+        flags: SimplificationFlags,
+    ) -> Result<ConstraintList, SimplificationError> {
+        map_to_constraint_list::try_map(self, flags)
+    }
+
+    // Same constraints and wires as the dag, without any simplification
+    pub fn map_to_list_unsimplified(self) -> ConstraintList {
+        map_to_constraint_list::map_without_simplification(self)
+    }
+
+    // Simplifies a copy of the dag with and without the parallel flag, None
+    // when both produce exactly the same constraints
+    pub fn parallel_simplification_difference(
+        &self,
+        flag_s: bool,
+        no_rounds: usize,
+    ) -> Option<String> {
+        let simplify = |parallel_flag| {
+            let flags = SimplificationFlags {
+                no_rounds,
+                flag_s,
+                parallel_flag,
+                ..Default::default()
+            };
+            self.clone().map_to_list(flags)
+        };
+        let (parallel, sequential) = (simplify(true), simplify(false));
+        parallel.first_difference(&sequential)
+    }
 }
 
 pub struct SimplificationFlags {
     pub no_rounds: usize,
     pub flag_s: bool,
     pub parallel_flag: bool,
+    // Caps the threads of the simplification, None uses every core
+    pub threads: Option<usize>,
     pub port_substitution: bool,
+    pub intermediates: Option<String>,
+    // Opened by the caller, so a path that can not be written is reported
+    // before the simplification starts
+    pub substitution_log: Option<SubstitutionJSON>,
+    // Called after every round with the round and the linear constraints left
+    pub progress: Option<Box<dyn Fn(usize, usize)>>,
+    // Constraints equal up to a factor are kept once
+    pub dedup_constraints: bool,
+    // The list records where each of its constraints comes from, it keeps
+    // a copy of the constraints during the simplification
+    pub provenance: bool,
+}
+
+// Rounds until no linear constraint is left, everything else off
+impl Default for SimplificationFlags {
+    fn default() -> Self {
+        SimplificationFlags {
+            no_rounds: usize::MAX,
+            flag_s: false,
+            parallel_flag: false,
+            threads: Option::None,
+            port_substitution: false,
+            intermediates: Option::None,
+            substitution_log: Option::None,
+            progress: Option::None,
+            dedup_constraints: false,
+            provenance: false,
+        }
+    }
 }
@@ -1,6 +1,10 @@
 use super::{Constraint, Edge, Node, SimplificationFlags, Tree, DAG};
-use constraint_list::{ConstraintList, DAGEncoding, EncodingEdge, EncodingNode, SignalInfo, Simplifier};
-use program_structure::utils::constants::UsefulConstants;
+use circom_algebra::constraint_storage::ConstraintStorage;
+use constraint_list::{
+    ConstraintList, DAGEncoding, EncodingEdge, EncodingNode, SignalInfo, SimplificationError,
+    Simplifier,
+};
+use constraint_writers::r1cs_writer::SourceSpan;
 use std::collections::{HashSet, LinkedList};
 #[derive(Default)]
 struct CHolder {
@@ -85,16 +89,30 @@ fn map_edge_to_encoding(edge: Edge) -> EncodingEdge {
 }
 
 pub fn map(dag: DAG, flags: SimplificationFlags) -> ConstraintList {
+    simplifier(dag, flags).simplify_constraints()
+}
+
+pub fn try_map(
+    dag: DAG,
+    flags: SimplificationFlags,
+) -> Result<ConstraintList, SimplificationError> {
+    simplifier(dag, flags).try_simplify_constraints()
+}
+
+fn simplifier(dag: DAG, flags: SimplificationFlags) -> Simplifier {
     use std::time::SystemTime;
     // println!("Start of dag to list mapping");
     let now = SystemTime::now();
-    let constants = UsefulConstants::new();
-    let field = constants.get_p().clone();
+    let field = dag.constants.get_p().clone();
     let init_id = dag.main_id();
     let no_public_inputs = dag.public_inputs();
     let no_public_outputs = dag.public_outputs();
     let no_private_inputs = dag.private_inputs();
     let forbidden = dag.get_main().unwrap().forbidden_if_main.clone();
+    // The spans of the simplified constraints are found through their provenance
+    let traced = flags.provenance || dag.r1cs_spans;
+    let originals = if traced { Option::Some(originals(&dag)) } else { Option::None };
+    let original_spans = if dag.r1cs_spans { Option::Some(spans(&dag)) } else { Option::None };
     let mut c_holder = CHolder::default();
     let mut signal_map = vec![0];
     let no_constraints = map_tree(&Tree::new(&dag), &mut signal_map, &mut c_holder);
@@ -116,7 +134,422 @@ pub fn map(dag: DAG, flags: SimplificationFlags) -> ConstraintList {
         no_rounds: flags.no_rounds,
         flag_s: flags.flag_s,
         parallel_flag: flags.parallel_flag,
+        threads: flags.threads,
         port_substitution: flags.port_substitution,
+        intermediates: flags.intermediates,
+        substitution_log: flags.substitution_log,
+        progress: flags.progress,
+        dedup_constraints: flags.dedup_constraints,
+        originals,
+        original_spans,
+    }
+}
+
+// Every constraint in the order of the r1cs export, the signals are the wires
+fn store_tree(tree: &Tree, witness: &mut Vec<usize>, storage: &mut ConstraintStorage) -> usize {
+    let mut no_non_linear = 0;
+    witness.extend(&tree.signals);
+    for constraint in &tree.constraints {
+        if !Constraint::is_linear(constraint) {
+            no_non_linear += 1;
+        }
+        storage.add_constraint(constraint.clone());
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        no_non_linear += store_tree(&subtree, witness, storage);
+    }
+    no_non_linear
+}
+
+// The constraints of map_without_simplification, in the same order
+fn originals(dag: &DAG) -> Vec<Constraint> {
+    let mut storage = ConstraintStorage::new();
+    store_tree(&Tree::new(dag), &mut Vec::new(), &mut storage);
+    storage.get_ids().into_iter().map(|id| storage.read_constraint(id).unwrap()).collect()
+}
+
+// The spans of the constraints of map_without_simplification, in the same order
+fn store_spans(tree: &Tree, spans: &mut Vec<Option<SourceSpan>>) {
+    spans.extend(tree.spans.iter().cloned());
+    for edge in Tree::get_edges(tree) {
+        store_spans(&Tree::go_to_subtree(tree, edge), spans);
+    }
+}
+
+fn spans(dag: &DAG) -> Vec<Option<SourceSpan>> {
+    let mut spans = Vec::new();
+    store_spans(&Tree::new(dag), &mut spans);
+    spans
+}
+
+pub fn map_without_simplification(dag: DAG) -> ConstraintList {
+    let field = dag.constants.get_p().clone();
+    let spans = if dag.r1cs_spans { Option::Some(spans(&dag)) } else { Option::None };
+    let mut constraints = ConstraintStorage::new();
+    let mut witness = vec![0];
+    let no_constraints = store_tree(&Tree::new(&dag), &mut witness, &mut constraints);
+    let (no_public_inputs, no_public_outputs) = (dag.public_inputs(), dag.public_outputs());
+    let no_private_inputs = dag.private_inputs();
+    let no_labels = witness.len();
+    let init_id = dag.main_id();
+    ConstraintList {
+        field,
+        dag_encoding: produce_encoding(no_constraints, init_id, dag.nodes, dag.adjacency),
+        no_public_inputs,
+        no_public_outputs,
+        no_private_inputs,
+        constraints,
+        no_labels,
+        signal_map: witness.into_iter().map(|signal| (signal, signal)).collect(),
+        provenance: Option::None,
+        spans,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConstraintList;
+    use crate::counts_porting::tests::multiplier_dag;
+    use crate::SimplificationFlags;
+    use circom_algebra::num_bigint::BigInt;
+    use program_structure::constants::UsefulConstants;
+
+    #[test]
+    fn remap_of_the_eliminated_signals() {
+        let flags = SimplificationFlags::default();
+        let list = multiplier_dag().map_to_list(flags);
+        let remap = list.signal_remap();
+        assert_eq!(remap.len(), 7);
+        // one, out, x and y are kept, m.a, m.b and m.c are replaced by them
+        for signal in 0..4 {
+            assert_eq!(remap[&signal], Option::Some(signal));
+        }
+        for signal in 4..7 {
+            assert_eq!(remap[&signal], Option::None);
+        }
+    }
+
+    #[test]
+    fn manifest_follows_the_r1cs_public_signals() {
+        let flags = SimplificationFlags::default();
+        let dag = multiplier_dag();
+        let unsimplified = dag.signal_manifest().to_json();
+        let list = multiplier_dag().map_to_list(flags);
+        let simplified = list.signal_manifest().to_json();
+        assert_eq!(unsimplified.len(), 7);
+        assert_eq!(simplified.len(), list.no_wires());
+        // The r1cs header lists the outputs first, then the public and the private inputs
+        let public = [("out", "output"), ("x", "public_input"), ("y", "private_input")];
+        for manifest in [&unsimplified, &simplified].iter() {
+            assert_eq!(manifest[0]["type"], "constant");
+            for (position, (name, kind)) in public.iter().enumerate() {
+                assert_eq!(manifest[position + 1]["name"], *name);
+                assert_eq!(manifest[position + 1]["component"], "main");
+                assert_eq!(manifest[position + 1]["type"], *kind);
+            }
+        }
+        assert_eq!(list.no_public_outputs + list.no_public_inputs + list.no_private_inputs, 3);
+        assert_eq!(unsimplified[4]["component"], "main.m");
+        assert_eq!(unsimplified[4]["type"], "intermediate");
+    }
+
+    #[test]
+    fn lists_without_simplification_keep_every_constraint() {
+        let flags = SimplificationFlags::default();
+        let simplified = multiplier_dag().map_to_list(flags);
+        let raw = multiplier_dag().map_to_list_unsimplified();
+        // The three linear constraints that wire m to main are not removed,
+        // and neither are m.a, m.b and m.c
+        assert_eq!(simplified.get_no_constraints(), 1);
+        assert_eq!(raw.get_no_constraints(), 4);
+        assert_eq!(simplified.no_wires(), 4);
+        assert_eq!(raw.no_wires(), 7);
+        assert_eq!(raw.get_witness_as_vec(), (0..7).collect::<Vec<_>>());
+        assert_eq!(raw.circuit_counts(), multiplier_dag().circuit_counts());
+    }
+
+    #[test]
+    fn constraints_are_filtered_by_signal() {
+        let flags = SimplificationFlags::default();
+        let signals_of = |c: &crate::Constraint| {
+            let mut signals: Vec<_> = c.take_signals().into_iter().cloned().collect();
+            signals.sort_unstable();
+            signals
+        };
+        let found = |list: &ConstraintList, name| {
+            let constraints = list.constraints_with_signal(name).unwrap();
+            constraints.iter().map(signals_of).collect::<Vec<_>>()
+        };
+        let raw = multiplier_dag().map_to_list_unsimplified();
+        assert_eq!(found(&raw, "main.out"), vec![vec![1, 6]]);
+        assert_eq!(found(&raw, "main.m.c"), vec![vec![1, 6], vec![4, 5, 6]]);
+        // Only out = x*y is left, m.c is replaced by out
+        let simplified = multiplier_dag().map_to_list(flags);
+        assert_eq!(found(&simplified, "main.out"), vec![vec![1, 2, 3]]);
+        assert!(simplified.constraints_with_signal("main.m.c").is_none());
+        assert!(simplified.constraints_with_signal("main.missing").is_none());
+    }
+
+    #[test]
+    fn substituted_constraints_point_at_their_sources() {
+        let flags = |provenance| SimplificationFlags { provenance, ..Default::default() };
+        assert!(multiplier_dag().map_to_list(flags(false)).provenance.is_none());
+        let list = multiplier_dag().map_to_list(flags(true));
+        let provenance = list.provenance.unwrap();
+        // out = x*y is m.a*m.b = m.c (id 3) after the substitutions of
+        // x === m.a, y === m.b and out === m.c (ids 0, 1 and 2)
+        let expected = constraint_list::Provenance { derived: true, sources: vec![0, 1, 2, 3] };
+        assert_eq!(provenance, vec![expected]);
+        let raw = multiplier_dag().map_to_list_unsimplified();
+        let product = raw.constraints_iter().nth(3).unwrap();
+        assert!(!crate::Constraint::is_linear(&product));
+    }
+
+    #[test]
+    fn constraints_are_filtered_by_pairs_of_signals() {
+        let raw = multiplier_dag().map_to_list_unsimplified();
+        // x === m.a is the constraint x - m.a = 0
+        let linking = raw.constraints_linking("main.x", "main.m.a").unwrap();
+        assert_eq!(linking.len(), 1);
+        let mut signals: Vec<_> = linking[0].take_signals().into_iter().cloned().collect();
+        signals.sort_unstable();
+        assert_eq!(signals, vec![2, 4]);
+        assert_eq!(raw.constraints_linking("main.m.a", "main.x").unwrap().len(), 1);
+        assert!(raw.constraints_linking("main.x", "main.y").unwrap().is_empty());
+        assert!(raw.constraints_linking("main.x", "main.missing").is_none());
+    }
+
+    // Main(x) -> out with k = 3, k*a = 6, a*x = e and e*x = out. Each
+    // substitution turns the next non linear constraint into a linear one
+    fn chained_dag() -> crate::DAG {
+        use circom_algebra::algebra::ArithmeticExpression;
+        type A = ArithmeticExpression<usize>;
+        let field = UsefulConstants::new().get_p().clone();
+        let s = |symbol| A::Signal { symbol };
+        let n = |value: u64| A::Number { value: BigInt::from(value) };
+        let constraint = |expr| A::transform_expression_to_constraint_form(expr, &field).unwrap();
+        let mut dag = crate::DAG::new();
+        dag.add_node("Main".to_string(), false);
+        dag.add_output("out".to_string());
+        dag.add_input("x".to_string(), false);
+        for name in ["k", "a", "e"].iter() {
+            dag.add_intermediate(name.to_string());
+        }
+        // out = 1, x = 2, k = 3, a = 4, e = 5
+        dag.add_constraint(constraint(A::sub(&s(3), &n(3), &field)));
+        dag.add_constraint(constraint(A::sub(&A::mul(&s(3), &s(4), &field), &n(6), &field)));
+        dag.add_constraint(constraint(A::sub(&A::mul(&s(4), &s(2), &field), &s(5), &field)));
+        dag.add_constraint(constraint(A::sub(&A::mul(&s(5), &s(2), &field), &s(1), &field)));
+        let _warnings = dag.constraint_analysis();
+        dag
+    }
+
+    #[test]
+    fn progress_is_reported_after_every_round() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        let simplify = |dag: crate::DAG, no_rounds| {
+            let rounds = Rc::new(RefCell::new(Vec::new()));
+            let recorded = Rc::clone(&rounds);
+            let flags = SimplificationFlags {
+                no_rounds,
+                progress: Option::Some(Box::new(move |round, left| {
+                    recorded.borrow_mut().push((round, left));
+                })),
+                ..Default::default()
+            };
+            dag.map_to_list(flags);
+            let rounds = rounds.borrow().clone();
+            rounds
+        };
+        // The first round already removes every linear constraint
+        assert_eq!(simplify(multiplier_dag(), usize::MAX), vec![(0, 0)]);
+        assert_eq!(simplify(multiplier_dag(), 1), vec![(0, 0)]);
+        // Every round leaves a new linear constraint until the last one
+        let rounds = simplify(chained_dag(), usize::MAX);
+        assert!(rounds.len() > 2);
+        assert!(rounds.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(rounds, vec![(0, 1), (1, 1), (2, 1), (3, 0)]);
+        // The rounds stop at the limit, the first one is spent before them
+        assert_eq!(simplify(chained_dag(), 3), vec![(0, 1), (1, 1), (2, 1)]);
     }
-    .simplify_constraints()
+
+    #[test]
+    fn permuted_wires_move_the_constraints_and_the_symbols() {
+        let symbols = |list: &ConstraintList| {
+            let mut symbols = Vec::new();
+            list.visit_symbols(&mut |elem| {
+                symbols.push((elem.symbol, elem.witness));
+                Result::Ok(())
+            })
+            .unwrap();
+            symbols
+        };
+        let original = multiplier_dag().map_to_list_unsimplified();
+        let mut permuted = multiplier_dag().map_to_list_unsimplified();
+        // m.a and m.b swap their wires, the rest stays
+        let permutation = vec![0, 1, 2, 3, 5, 4, 6];
+        permuted.permute_wires(&permutation).ok().unwrap();
+        let wire = |w: i64| permutation[w as usize] as i64;
+        let expected: Vec<_> = symbols(&original).into_iter().map(|(n, w)| (n, wire(w))).collect();
+        assert_eq!(symbols(&permuted), expected);
+        let moved: Vec<_> = original
+            .linear_combinations()
+            .map(|rows| {
+                rows.map(|row| {
+                    let mut row: Vec<_> =
+                        row.into_iter().map(|(w, v)| (permutation[w], v)).collect();
+                    row.sort_by_key(|(w, _)| *w);
+                    row
+                })
+            })
+            .collect();
+        assert_eq!(permuted.linear_combinations().collect::<Vec<_>>(), moved);
+        assert_eq!(permuted.get_witness_as_vec(), vec![0, 1, 2, 3, 5, 4, 6]);
+        assert!(permuted.permute_wires(&[1, 0, 2, 3, 4, 5, 6]).is_err());
+        // The public input x and the private input y, the output and m.a
+        assert!(permuted.permute_wires(&[0, 1, 3, 2, 4, 5, 6]).is_err());
+        assert!(permuted.permute_wires(&[0, 4, 2, 3, 1, 5, 6]).is_err());
+        assert!(permuted.permute_wires(&[0, 1, 1, 3, 4, 5, 6]).is_err());
+        assert!(permuted.permute_wires(&[0, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn promoted_signals_are_public_outputs() {
+        let flags = SimplificationFlags::default();
+        let mut dag = multiplier_dag();
+        let a = dag.signal_id("main.m.a").ok().unwrap();
+        dag.keep_signal(a);
+        let mut list = dag.map_to_list(flags);
+        let wire = *list.get_witness().get(&a).unwrap();
+        assert!(wire > 3);
+        let constrained = |list: &ConstraintList, wire| {
+            list.linear_combinations().flatten().any(|row| row.iter().any(|(w, _)| *w == wire))
+        };
+        assert!(constrained(&list, wire));
+        list.promote_to_outputs(&[a]).ok().unwrap();
+        // out is the wire 1, the promoted signal goes right after it
+        assert_eq!(list.no_public_outputs, 2);
+        assert_eq!(list.get_witness_as_vec()[2], a);
+        assert!(constrained(&list, 2));
+        let out = list.get_witness_as_vec()[1];
+        assert!(list.promote_to_outputs(&[out]).is_err());
+        // An intermediate of main that no constraint uses keeps its wire
+        let mut dag = multiplier_dag();
+        dag.add_intermediate("free".to_string());
+        let free = dag.signal_id("main.free").ok().unwrap();
+        let mut list = dag.map_to_list_unsimplified();
+        assert!(list.get_witness().get(&free).is_some());
+        let error = list.promote_to_outputs(&[free]).err().unwrap();
+        assert!(error.get_message().ends_with("it is in no constraint"));
+        assert_eq!(list.no_public_outputs, 1);
+    }
+
+    #[test]
+    fn streamed_symbols_match_the_sym_file() {
+        use constraint_writers::ConstraintExporter;
+        let flags = SimplificationFlags::default();
+        let list = multiplier_dag().map_to_list(flags);
+        let file = std::env::temp_dir().join(format!("circom_streamed_{}.sym", std::process::id()));
+        list.sym(file.to_str().unwrap()).unwrap();
+        let written = std::fs::read(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        let mut streamed = Vec::new();
+        list.stream_sym(&mut streamed).unwrap();
+        assert_eq!(streamed, written);
+        let mut symbols = Vec::new();
+        list.visit_symbols(&mut |elem| {
+            symbols.push((elem.symbol, elem.witness));
+            Result::Ok(())
+        })
+        .unwrap();
+        // Every signal but the constant one
+        assert_eq!(symbols.len(), 6);
+        assert_eq!(symbols[0], ("main.out".to_string(), 1));
+        assert!(symbols.iter().any(|(name, witness)| name == "main.m.c" && *witness == -1));
+    }
+
+    #[test]
+    fn intermediates_without_constraints_leave_the_witness() {
+        let flags = SimplificationFlags { flag_s: true, ..Default::default() };
+        let mut dag = multiplier_dag();
+        dag.add_intermediate("dead".to_string());
+        assert_eq!(dag.clone().map_to_list_unsimplified().no_wires(), 8);
+        let list = dag.map_to_list(flags);
+        // Only m.a, m.b and m.c were replaced before, now the dead signal goes too
+        assert_eq!(list.no_wires(), 4);
+        assert_eq!(list.no_public_outputs + list.no_public_inputs + list.no_private_inputs, 3);
+        assert_eq!(list.signal_remap()[&7], Option::None);
+    }
+
+    #[test]
+    fn repeated_constraints_are_kept_once() {
+        use circom_algebra::algebra::ArithmeticExpression as A;
+        let field = UsefulConstants::new().get_p().clone();
+        let s = |symbol| A::Signal { symbol };
+        let simplify = |dedup_constraints| {
+            let mut dag = multiplier_dag();
+            // out <== x * y, three times and once scaled by two
+            let product = A::sub(&A::mul(&s(2), &s(3), &field), &s(1), &field);
+            let constraint = |e| A::transform_expression_to_constraint_form(e, &field).unwrap();
+            for _ in 0..3 {
+                dag.add_constraint(constraint(product.clone()));
+            }
+            let two = A::Number { value: BigInt::from(2) };
+            let scaled = A::mul(&two, &product, &field);
+            dag.add_constraint(constraint(scaled));
+            let flags = SimplificationFlags { dedup_constraints, ..Default::default() };
+            dag.map_to_list(flags)
+        };
+        // m.c = out, m.a = x and m.b = y make the multiplier the same constraint
+        assert_eq!(simplify(false).circuit_counts().no_non_linear, 5);
+        assert_eq!(simplify(true).circuit_counts().no_non_linear, 1);
+    }
+
+    #[test]
+    fn coefficients_without_inverse_are_errors() {
+        use circom_algebra::algebra::{ArithmeticError, ArithmeticExpression as A};
+        use std::collections::HashMap;
+        let field = UsefulConstants::new().get_p().clone();
+        let mut dag = multiplier_dag();
+        // x * p - y = 0, p is zero in the field. The quadratic form keeps
+        // the coefficients as they are
+        let c = vec![(0, BigInt::from(0)), (2, field.clone()), (3, -BigInt::from(1))];
+        let c: HashMap<_, _> = c.into_iter().collect();
+        let expression = A::Quadratic { a: HashMap::new(), b: HashMap::new(), c };
+        let constraint = A::transform_expression_to_constraint_form(expression, &field);
+        dag.add_constraint(constraint.unwrap());
+        let flags = SimplificationFlags::default();
+        let error = dag.try_map_to_list(flags).err().unwrap();
+        assert!(matches!(error.error, ArithmeticError::DivisionByZero));
+        assert_eq!(error.signal, 2);
+    }
+
+    #[test]
+    fn parallel_and_sequential_simplification_agree() {
+        let dag = multiplier_dag();
+        assert_eq!(dag.parallel_simplification_difference(false, usize::MAX), Option::None);
+        assert_eq!(dag.parallel_simplification_difference(true, usize::MAX), Option::None);
+    }
+
+    #[test]
+    fn summaries_follow_the_list() {
+        use constraint_writers::summary_writer::CircuitSummary;
+        let flags = SimplificationFlags::default();
+        let list = multiplier_dag().map_to_list(flags);
+        let counts = list.circuit_counts();
+        let name = format!("circom_circuit_summary_{}.json", std::process::id());
+        let file = std::env::temp_dir().join(name);
+        let file = file.to_str().unwrap();
+        CircuitSummary::new(&counts, &list.signal_manifest()).write(file).unwrap();
+        let summary = CircuitSummary::read(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        assert_eq!(summary.no_non_linear, counts.no_non_linear);
+        assert_eq!(summary.no_linear, counts.no_linear);
+        assert_eq!(summary.no_non_linear + summary.no_linear, list.get_no_constraints());
+        assert_eq!(summary.no_template_instances, 2);
+        assert_eq!(summary.public_signals, vec!["main.out", "main.x"]);
+    }
+
 }
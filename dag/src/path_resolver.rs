@@ -0,0 +1,84 @@
+use super::{Node, DAG};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::Report;
+
+// The correspondence of a node also has the signals of its direct
+// subcomponents, deeper paths go down the edge of their first component
+fn resolve_in_node(
+    dag: &DAG,
+    node: &Node,
+    node_id: usize,
+    path: &str,
+    offset: usize,
+) -> Option<usize> {
+    if let Option::Some(id) = node.correspondence().get(path) {
+        return Option::Some(offset + id);
+    }
+    for edge in &dag.adjacency[node_id] {
+        let rest = path.strip_prefix(edge.label.as_str()).and_then(|r| r.strip_prefix('.'));
+        if let Option::Some(rest) = rest {
+            let sub = &dag.nodes[edge.goes_to];
+            return resolve_in_node(dag, sub, edge.goes_to, rest, offset + edge.in_number);
+        }
+    }
+    Option::None
+}
+
+pub fn signal_id(dag: &DAG, path: &str) -> Result<usize, Report> {
+    let resolved = dag.get_entry().and_then(|entry| {
+        let rest = path.strip_prefix(entry.label.as_str())?.strip_prefix('.')?;
+        resolve_in_node(dag, dag.get_main()?, dag.main_id(), rest, entry.in_number)
+    });
+    resolved.ok_or_else(|| {
+        let msg = format!("Unknown signal path {}", path);
+        Report::error(msg, ReportCode::UnknownSignalPath)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Main has the output out, the input x and the component p: Pair, every
+    // Pair has the output out, the input in and the components bits[0] and
+    // bits[1]: Bit, with the output out and the input in
+    fn nested_dag() -> DAG {
+        let mut dag = DAG::new();
+        dag.add_node("Bit".to_string(), false);
+        dag.add_output("out".to_string());
+        dag.add_input("in".to_string(), false);
+        dag.add_node("Pair".to_string(), false);
+        dag.add_output("out".to_string());
+        dag.add_input("in".to_string(), false);
+        dag.add_edge(0, "bits[0]");
+        dag.add_edge(0, "bits[1]");
+        dag.add_node("Main".to_string(), false);
+        dag.add_output("out".to_string());
+        dag.add_input("x".to_string(), true);
+        dag.add_edge(1, "p");
+        dag
+    }
+
+    #[test]
+    fn nested_paths_resolve_to_their_ids() {
+        let dag = nested_dag();
+        let id = |path| signal_id(&dag, path).ok().unwrap();
+        assert_eq!(id("main.x"), 2);
+        assert_eq!(id("main.p.out"), 3);
+        // p starts after the 2 signals of main, bits[1] after the 4 first of p
+        assert_eq!(id("main.p.bits[0].out"), 5);
+        assert_eq!(id("main.p.bits[1].out"), 7);
+        assert_eq!(id("main.p.bits[1].in"), 8);
+    }
+
+    #[test]
+    fn unknown_paths_are_errors() {
+        let dag = nested_dag();
+        for path in ["main.y", "main.p.bits[2].out", "main.p.bits", "p.out", ""] {
+            let report = signal_id(&dag, path).err().unwrap();
+            assert!(matches!(report.get_code(), ReportCode::UnknownSignalPath));
+        }
+        let report = signal_id(&dag, "main.p.bits[2].out").err().unwrap();
+        assert_eq!(report.get_message(), "Unknown signal path main.p.bits[2].out");
+    }
+}
@@ -1,6 +1,58 @@
 use super::{Constraint, Tree, DAG};
+use constraint_list::Matrices;
 use constraint_writers::log_writer::Log;
-use constraint_writers::r1cs_writer::{ConstraintSection, HeaderData, R1CSWriter};
+use constraint_writers::r1cs_writer::{ConstraintSection, HeaderData, R1CSWriter, SpansSection};
+use constraint_writers::shard_writer::ShardWriter;
+
+fn count_tree(tree: &Tree, wires: &mut usize, constraints: &mut usize) {
+    *wires += tree.signals.len();
+    *constraints += tree.constraints.len();
+    for edge in Tree::get_edges(tree) {
+        count_tree(&Tree::go_to_subtree(tree, edge), wires, constraints);
+    }
+}
+
+// Without simplification every signal is a wire and labels and wires agree
+pub fn header(dag: &DAG) -> HeaderData {
+    let tree = Tree::new(dag);
+    let (mut wires, mut constraints) = (0, 0);
+    count_tree(&tree, &mut wires, &mut constraints);
+    HeaderData {
+        field: tree.field.clone(),
+        total_wires: wires,
+        number_of_labels: wires,
+        public_outputs: dag.public_outputs(),
+        public_inputs: dag.public_inputs(),
+        private_inputs: dag.private_inputs(),
+        number_of_constraints: constraints,
+    }
+}
+
+fn push_tree_constraints(tree: &Tree, matrices: &mut Matrices) {
+    for c in &tree.constraints {
+        matrices.push_constraint(c);
+    }
+    for edge in Tree::get_edges(tree) {
+        push_tree_constraints(&Tree::go_to_subtree(tree, edge), matrices);
+    }
+}
+
+// In the order of the r1cs export, the signals are the wires
+pub fn matrices(dag: &DAG) -> Matrices {
+    let tree = Tree::new(dag);
+    let mut matrices = Matrices {
+        field: tree.field.clone(),
+        a: Vec::new(),
+        b: Vec::new(),
+        c: Vec::new(),
+        no_wires: dag.circuit_counts().no_witness,
+        no_public_outputs: dag.public_outputs(),
+        no_public_inputs: dag.public_inputs(),
+        no_private_inputs: dag.private_inputs(),
+    };
+    push_tree_constraints(&tree, &mut matrices);
+    matrices
+}
 
 pub fn write(dag: &DAG, output: &str) -> Result<(), ()> {
     let tree = Tree::new(dag);
@@ -8,21 +60,16 @@ pub fn write(dag: &DAG, output: &str) -> Result<(), ()> {
     let mut log = Log::new();
     let r1cs = R1CSWriter::new(output.to_string(), field_size)?;
 
-    let mut constraint_section = R1CSWriter::start_constraints_section(r1cs)?;
+    let mut constraint_section = R1CSWriter::start_constraints_section(r1cs, &tree.field)?;
     let wires = write_constraint_section(&mut constraint_section, &mut log, &tree)?;
+    log.no_reduced_coefficients = constraint_section.reduced_coefficients();
     let labels = wires;
     let constraint_counter = constraint_section.constraints_written();
     let r1cs = constraint_section.end_section()?;
 
-    let header_data = HeaderData {
-        field: tree.field.clone(),
-        total_wires: wires,
-        number_of_labels: labels,
-        public_outputs: dag.public_outputs(),
-        public_inputs: dag.public_inputs(),
-        private_inputs: dag.private_inputs(),
-        number_of_constraints: constraint_counter,
-    };
+    let header_data = header(dag);
+    debug_assert_eq!(header_data.total_wires, wires);
+    debug_assert_eq!(header_data.number_of_constraints, constraint_counter);
 
     log.no_public_inputs = dag.public_inputs();
     log.no_public_outputs = dag.public_outputs();
@@ -37,11 +84,46 @@ pub fn write(dag: &DAG, output: &str) -> Result<(), ()> {
     for signal in 0..labels {
         signal_section.write_signal_usize(signal)?;
     }
-    let _r1cs = signal_section.end_section()?;
+    let r1cs = signal_section.end_section()?;
+    if dag.r1cs_spans {
+        let mut spans_section = R1CSWriter::start_spans_section(r1cs)?;
+        write_spans_section(&mut spans_section, &tree)?;
+        let _r1cs = spans_section.end_section()?;
+    }
     Log::print(&log);
     Result::Ok(())
 }
 
+fn write_tree_shards(shards: &mut ShardWriter, tree: &Tree) -> Result<(), ()> {
+    for c in &tree.constraints {
+        shards.write_constraint_usize(c.a(), c.b(), c.c())?;
+    }
+    for edge in Tree::get_edges(tree) {
+        write_tree_shards(shards, &Tree::go_to_subtree(tree, edge))?;
+    }
+    Result::Ok(())
+}
+
+// The constraints in the order of write, the signals are the wires
+pub fn write_shards(dag: &DAG, output: &str, shard_size: usize) -> Result<(), ()> {
+    let header_data = header(dag);
+    let signals = (0..header_data.number_of_labels).collect();
+    let mut shards = ShardWriter::new(output.to_string(), shard_size, header_data, signals)?;
+    write_tree_shards(&mut shards, &Tree::new(dag))?;
+    shards.finish()?;
+    Result::Ok(())
+}
+
+fn write_spans_section(spans_section: &mut SpansSection, tree: &Tree) -> Result<(), ()> {
+    for span in &tree.spans {
+        spans_section.write_span(span.as_ref())?;
+    }
+    for edge in Tree::get_edges(tree) {
+        write_spans_section(spans_section, &Tree::go_to_subtree(tree, edge))?;
+    }
+    Result::Ok(())
+}
+
 fn write_constraint_section(
     constraint_section: &mut ConstraintSection,
     log: &mut Log,
@@ -63,3 +145,41 @@ fn write_constraint_section(
     }
     Result::Ok(no_signals)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::counts_porting::tests::multiplier_dag;
+    use crate::SimplificationFlags;
+    use constraint_writers::ConstraintExporter;
+
+    #[test]
+    fn r1cs_info_of_the_multiplier() {
+        let dag = multiplier_dag();
+        let info = dag.r1cs_header().info_lines();
+        assert_eq!(info[0], "Curve: bn-128");
+        assert_eq!(info[1..3], ["# of Wires: 6", "# of Constraints: 4"]);
+        let flags = SimplificationFlags::default();
+        let info = dag.map_to_list(flags).r1cs_header().info_lines();
+        let expected = [
+            "Curve: bn-128",
+            "# of Wires: 4",
+            "# of Constraints: 1",
+            "# of Private Inputs: 1",
+            "# of Public Inputs: 1",
+            "# of Labels: 7",
+            "# of Outputs: 1",
+        ];
+        assert_eq!(info, expected);
+    }
+
+    #[test]
+    fn matrices_follow_the_export_order() {
+        let matrices = multiplier_dag().matrices();
+        assert_eq!(matrices.no_wires, 7);
+        assert_eq!(matrices.no_constraints(), 4);
+        // The product of Mul comes last, over m.a, m.b and m.c
+        assert_eq!(matrices.a[3].len(), 1);
+        assert_eq!(matrices.a[3][0].0, 4);
+        assert_eq!(matrices.b[3][0].0, 5);
+    }
+}
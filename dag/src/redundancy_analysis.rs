@@ -0,0 +1,136 @@
+use super::constraint_printer::terms_to_string;
+use super::{Tree, DAG};
+use circom_algebra::algebra::{CanonicalConstraint, CanonicalTerms, Constraint};
+use circom_algebra::num_bigint::BigInt;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use std::collections::HashMap;
+
+type C = Constraint<usize>;
+
+const REDUNDANT_CHECK: &str = "is checked more than once with the same constraint:";
+const REDUNDANT_CHECK_CODE: ReportCode = ReportCode::RedundantRangeCheck;
+
+struct RedundantCheck;
+impl RedundantCheck {
+    pub fn new(signal: &str, pattern: &str, times: usize) -> Report {
+        let msg = format!("Signal \"{}\" {} {}", signal, REDUNDANT_CHECK, pattern);
+        let hint = format!("The constraint appears {} times, one of them is enough", times);
+        let mut report = Report::warning(msg, REDUNDANT_CHECK_CODE);
+        report.add_note(hint);
+        report
+    }
+}
+
+struct Occurrences {
+    first_found: usize,
+    times: usize,
+}
+
+fn single_signal(constraint: &C) -> Option<usize> {
+    let constant = C::constant_coefficient();
+    let mut signals = constraint.take_signals().into_iter().filter(|s| **s != constant);
+    match (signals.next(), signals.next()) {
+        (Option::Some(signal), Option::None) => Option::Some(*signal),
+        _ => Option::None,
+    }
+}
+
+fn lc_to_string(lc: &CanonicalTerms, name: &str, field: &BigInt) -> String {
+    terms_to_string(lc, &|_| name.to_string(), field)
+}
+
+fn key_to_string(key: &CanonicalConstraint, name: &str, field: &BigInt) -> String {
+    let c = lc_to_string(&key.c, name, field);
+    if key.is_linear() {
+        format!("{} = 0", c)
+    } else {
+        let a = lc_to_string(&key.a, name, field);
+        let b = lc_to_string(&key.b, name, field);
+        format!("({}) * ({}) = {}", a, b, c)
+    }
+}
+
+fn visit_tree(
+    tree: &Tree,
+    names: &mut HashMap<usize, String>,
+    checks: &mut HashMap<(usize, CanonicalConstraint), Occurrences>,
+    order: &mut usize,
+) {
+    for (id, name) in &tree.id_to_name {
+        names.insert(*id, format!("{}.{}", tree.path, name));
+    }
+    for constraint in &tree.constraints {
+        if let Option::Some(signal) = single_signal(constraint) {
+            let key = constraint.canonical_form(&tree.field);
+            let entry = checks
+                .entry((signal, key))
+                .or_insert(Occurrences { first_found: *order, times: 0 });
+            entry.times += 1;
+            *order += 1;
+        }
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        visit_tree(&subtree, names, checks, order);
+    }
+}
+
+pub fn analyse(dag: &DAG) -> ReportCollection {
+    let tree = Tree::new(dag);
+    let field = tree.field.clone();
+    let mut names = HashMap::new();
+    let mut checks = HashMap::new();
+    visit_tree(&tree, &mut names, &mut checks, &mut 0);
+    let mut redundant: Vec<_> = checks.into_iter().filter(|(_, o)| o.times > 1).collect();
+    redundant.sort_by_key(|(_, o)| o.first_found);
+    let mut reports = ReportCollection::new();
+    for ((signal, key), occurrences) in redundant {
+        let name = names.get(&signal).cloned().unwrap_or_else(|| signal.to_string());
+        let local_name = name.rsplit('.').next().unwrap_or(&name).to_string();
+        let pattern = key_to_string(&key, &local_name, &field);
+        reports.push(RedundantCheck::new(&name, &pattern, occurrences.times));
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use circom_algebra::algebra::ArithmeticExpression;
+    use program_structure::constants::UsefulConstants;
+
+    type A = ArithmeticExpression<usize>;
+
+    fn boolean_check(signal: usize, factor: u32, field: &BigInt) -> C {
+        let s = A::Signal { symbol: signal };
+        let k = A::Number { value: BigInt::from(factor) };
+        let one = A::Number { value: BigInt::from(1) };
+        let scaled = A::mul(&k, &s, field);
+        let expr = A::mul(&scaled, &A::sub(&s, &one, field), field);
+        A::transform_expression_to_constraint_form(expr, field).unwrap()
+    }
+
+    #[test]
+    fn repeated_boolean_check_is_reported() {
+        let field = UsefulConstants::new().get_p().clone();
+        let mut dag = DAG::new();
+        dag.add_node("Main".to_string(), false);
+        dag.add_input("x".to_string(), false);
+        dag.add_input("y".to_string(), false);
+        dag.add_output("out".to_string());
+        dag.add_constraint(boolean_check(1, 1, &field));
+        dag.add_constraint(boolean_check(1, 3, &field));
+        dag.add_constraint(boolean_check(2, 1, &field));
+        let reports = analyse(&dag);
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].is_warning());
+    }
+
+    #[test]
+    fn canonical_pattern_is_readable() {
+        let field = UsefulConstants::new().get_p().clone();
+        let key = boolean_check(1, 5, &field).canonical_form(&field);
+        assert_eq!(key_to_string(&key, "x", &field), "(x - 1) * (x) = 0");
+    }
+}
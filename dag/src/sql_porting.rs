@@ -0,0 +1,20 @@
+use super::{Tree, DAG};
+use constraint_writers::sql_writer::ConstraintsSQL;
+
+fn visit_tree(tree: &Tree, writer: &mut ConstraintsSQL, index: &mut usize) -> Result<(), ()> {
+    for constraint in &tree.constraints {
+        writer.write_constraint(*index, constraint.a(), constraint.b(), constraint.c())?;
+        *index += 1;
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        visit_tree(&subtree, writer, index)?;
+    }
+    Result::Ok(())
+}
+
+pub fn port_constraints(dag: &DAG, out: &str) -> Result<(), ()> {
+    let mut writer = ConstraintsSQL::new(out)?;
+    visit_tree(&Tree::new(dag), &mut writer, &mut 0)?;
+    writer.end()
+}
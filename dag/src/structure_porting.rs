@@ -0,0 +1,102 @@
+use super::{Edge, Node, DAG};
+use json::JsonValue;
+
+// The signal counts of a node include the signals of its subcomponents
+fn node_json(id: usize, node: &Node) -> JsonValue {
+    json::object! {
+        "id" => id,
+        "template" => node.template_name.clone(),
+        "parallel" => node.is_parallel,
+        "signals" => node.number_of_signals,
+        "inputs" => node.number_of_inputs(),
+        "public_inputs" => node.number_of_public_inputs(),
+        "outputs" => node.number_of_outputs(),
+        "intermediates" => node.number_of_intermediates(),
+        "components" => node.number_of_components,
+        "constraints" => node.constraints.len(),
+    }
+}
+
+// The signals and components of the subcomponent are the ranges [from, to)
+// of the local ids of the parent
+fn edge_json(from: usize, edge: &Edge) -> JsonValue {
+    json::object! {
+        "source" => from,
+        "target" => edge.goes_to,
+        "label" => edge.label.clone(),
+        "signals" => json::array![edge.in_number, edge.out_number],
+        "components" => json::array![edge.in_component_number, edge.out_component_number],
+    }
+}
+
+pub fn structure(dag: &DAG) -> JsonValue {
+    let mut nodes = JsonValue::new_array();
+    for (id, node) in dag.nodes.iter().enumerate() {
+        nodes.push(node_json(id, node)).unwrap();
+    }
+    let mut edges = JsonValue::new_array();
+    for (from, adjacency) in dag.adjacency.iter().enumerate() {
+        for edge in adjacency {
+            edges.push(edge_json(from, edge)).unwrap();
+        }
+    }
+    json::object! {
+        "main" => dag.main_id(),
+        "nodes" => nodes,
+        "edges" => edges,
+    }
+}
+
+// Instances are labeled with their name and edges with the component,
+// parallel instances are drawn dashed
+pub fn dot(dag: &DAG) -> String {
+    let mut dot = String::from("digraph components {\n");
+    for (id, node) in dag.nodes.iter().enumerate() {
+        let style = if node.is_parallel { ", style=dashed" } else { "" };
+        let name = &node.template_name;
+        dot.push_str(&format!("    {} [label=\"{}\", shape=box{}];\n", id, name, style));
+    }
+    for (from, adjacency) in dag.adjacency.iter().enumerate() {
+        for edge in adjacency {
+            let line = format!("    {} -> {} [label=\"{}\"];\n", from, edge.goes_to, edge.label);
+            dot.push_str(&line);
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counts_porting::tests::multiplier_dag;
+
+    #[test]
+    fn nodes_and_edges_of_the_multiplier() {
+        let json = structure(&multiplier_dag());
+        assert_eq!(json["main"], 1);
+        assert_eq!(json["nodes"].len(), 2);
+        assert_eq!(json["nodes"][0]["template"], "Mul");
+        assert_eq!(json["nodes"][1]["template"], "Main");
+        assert_eq!(json["nodes"][1]["signals"], 6);
+        assert_eq!(json["nodes"][1]["constraints"], 3);
+        assert_eq!(json["edges"].len(), 1);
+        let edge = &json["edges"][0];
+        assert_eq!((edge["source"].as_usize(), edge["target"].as_usize()), (Some(1), Some(0)));
+        assert_eq!(edge["label"], "m");
+        // m.a, m.b and m.c follow out, x and y
+        assert_eq!(edge["signals"], json::array![3, 6]);
+    }
+
+    #[test]
+    fn dot_graph_of_the_multiplier() {
+        let mut dag = multiplier_dag();
+        let dot = dag.to_dot();
+        assert!(dot.starts_with("digraph components {"));
+        assert!(dot.contains("    0 [label=\"Mul\", shape=box];"));
+        assert!(dot.contains("    1 [label=\"Main\", shape=box];"));
+        assert!(dot.contains("    1 -> 0 [label=\"m\"];"));
+        dag.nodes[0].is_parallel = true;
+        assert!(dag.to_dot().contains("    0 [label=\"Mul\", shape=box, style=dashed];"));
+    }
+}
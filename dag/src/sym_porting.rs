@@ -1,5 +1,6 @@
 use super::{Tree, DAG};
 use circom_algebra::num_traits::AsPrimitive;
+use constraint_writers::manifest_writer::SignalManifest;
 use constraint_writers::sym_writer::*;
 use std::collections::HashMap;
 
@@ -27,3 +28,31 @@ fn visit_tree(tree: &Tree, dot_sym: &mut SymFile) -> Result<(), ()> {
     }
     Ok(())
 }
+
+pub fn write_constraint_templates(dag: &DAG, file_name: &str) -> Result<(), ()> {
+    let mut dot_sym = SymFile::new(file_name)?;
+    for (constraint, template) in dag.constraint_templates().into_iter().enumerate() {
+        SymFile::write_constraint_elem(&mut dot_sym, ConstraintSymElem { constraint, template })?;
+    }
+    SymFile::close(dot_sym);
+    Ok(())
+}
+
+// Without simplification every signal keeps its own position
+pub fn manifest(dag: &DAG) -> SignalManifest {
+    let mut manifest =
+        SignalManifest::new(dag.public_outputs(), dag.public_inputs(), dag.private_inputs());
+    manifest_visit(&Tree::new(dag), &mut manifest);
+    manifest
+}
+
+fn manifest_visit(tree: &Tree, manifest: &mut SignalManifest) {
+    for signal in &tree.signals {
+        let name = HashMap::get(&tree.id_to_name, signal).unwrap();
+        manifest.add_symbol(*signal, format!("{}.{}", tree.path, name));
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        manifest_visit(&subtree, manifest);
+    }
+}
@@ -0,0 +1,91 @@
+use super::{Tree, DAG};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Same traversal as the r1cs export, so the indices match the written file
+// when the constraints are not simplified (--O0 or --no_simplification)
+fn visit_tree(tree: &Tree, next_index: &mut usize, by_template: &mut HashMap<String, Vec<usize>>) {
+    let template_name = &tree.dag.nodes[tree.node_id].template_name;
+    let indices = by_template.entry(template_name.clone()).or_default();
+    for _ in &tree.constraints {
+        indices.push(*next_index);
+        *next_index += 1;
+    }
+    for edge in Tree::get_edges(tree) {
+        let subtree = Tree::go_to_subtree(tree, edge);
+        visit_tree(&subtree, next_index, by_template);
+    }
+}
+
+pub fn constraints_by_template(dag: &DAG) -> HashMap<String, Vec<usize>> {
+    let mut by_template = HashMap::new();
+    visit_tree(&Tree::new(dag), &mut 0, &mut by_template);
+    by_template
+}
+
+// Owner of each constraint, indexed like the constraints of the r1cs export
+pub fn constraint_templates(dag: &DAG) -> Vec<String> {
+    let by_template = constraints_by_template(dag);
+    let no_constraints = by_template.values().map(|indices| indices.len()).sum();
+    let mut templates = vec![String::new(); no_constraints];
+    for (template_name, indices) in by_template {
+        for index in indices {
+            templates[index] = template_name.clone();
+        }
+    }
+    templates
+}
+
+// Number of constraints of the templates with the same key, the templates
+// without a key are left out
+pub fn constraints_grouped_by<K, F>(dag: &DAG, key: F) -> HashMap<K, usize>
+where
+    K: Eq + Hash,
+    F: Fn(&str) -> Option<K>,
+{
+    let mut grouped = HashMap::new();
+    for (template_name, indices) in constraints_by_template(dag) {
+        if let Option::Some(group) = key(&template_name) {
+            *grouped.entry(group).or_insert(0) += indices.len();
+        }
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counts_porting::tests::multiplier_dag;
+
+    #[test]
+    fn indices_follow_the_export_order() {
+        let by_template = constraints_by_template(&multiplier_dag());
+        assert_eq!(by_template.len(), 2);
+        assert_eq!(by_template["Main"], vec![0, 1, 2]);
+        assert_eq!(by_template["Mul"], vec![3]);
+    }
+
+    #[test]
+    fn constraints_are_attributed_to_their_template() {
+        let templates = constraint_templates(&multiplier_dag());
+        assert_eq!(templates, vec!["Main", "Main", "Main", "Mul"]);
+    }
+
+    #[test]
+    fn groups_add_up_their_templates() {
+        // Mul comes from an included file
+        let by_file = constraints_grouped_by(&multiplier_dag(), |template| {
+            Option::Some(if template == "Mul" { 1 } else { 0 })
+        });
+        assert_eq!(by_file.len(), 2);
+        assert_eq!(by_file[&0], 3);
+        assert_eq!(by_file[&1], 1);
+        let single_group = constraints_grouped_by(&multiplier_dag(), |_| Option::Some(0));
+        assert_eq!(single_group[&0], 4);
+        let only_mul = constraints_grouped_by(&multiplier_dag(), |template| {
+            if template == "Mul" { Option::Some(0) } else { Option::None }
+        });
+        assert_eq!(only_mul.len(), 1);
+        assert_eq!(only_mul[&0], 1);
+    }
+}
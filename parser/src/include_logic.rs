@@ -16,6 +16,11 @@ impl FileStack {
         FileStack { current_location: location, black_paths: HashSet::new(), stack: vec![src] }
     }
 
+    // No file to start with, the includes are looked up from the given location
+    pub fn at_location(location: PathBuf) -> FileStack {
+        FileStack { current_location: location, black_paths: HashSet::new(), stack: vec![] }
+    }
+
     pub fn add_include(f_stack: &mut FileStack, path: String) -> Result<(), Report> {
         let mut crr = f_stack.current_location.clone();
         crr.push(path.clone());
@@ -19,15 +19,33 @@ use std::path::PathBuf;
 pub type Version = (usize, usize, usize);
 
 
-pub fn run_parser(file: String) -> Result<(ProgramArchive, ReportCollection), (FileLibrary, ReportCollection)> {
+type ParserResult = Result<(ProgramArchive, ReportCollection), (FileLibrary, ReportCollection)>;
+
+pub fn run_parser(file: String) -> ParserResult {
+    parse_program(FileStack::new(PathBuf::from(file)), None)
+}
+
+// The source is parsed as a file with the given name, its includes are
+// looked up from the current directory
+pub fn run_parser_from_source(name: String, src: String) -> ParserResult {
+    parse_program(FileStack::at_location(PathBuf::new()), Some((name, src)))
+}
+
+fn parse_program(mut file_stack: FileStack, mut in_memory: Option<(String, String)>) -> ParserResult {
     let mut file_library = FileLibrary::new();
     let mut definitions = Vec::new();
     let mut main_components = Vec::new();
-    let mut file_stack = FileStack::new(PathBuf::from(file));
     let mut warnings = Vec::new();
+    let mut unknown_pragmas = Vec::new();
 
-    while let Some(crr_file) = FileStack::take_next(&mut file_stack) {
-        let (path, src) = open_file(crr_file).map_err(|e| (file_library.clone(), vec![e]))?;
+    loop {
+        let (path, src) = if let Some(source) = in_memory.take() {
+            source
+        } else if let Some(crr_file) = FileStack::take_next(&mut file_stack) {
+            open_file(crr_file).map_err(|e| (file_library.clone(), vec![e]))?
+        } else {
+            break;
+        };
         let file_id = file_library.add_file(path.clone(), src.clone());
         let program = 
             parser_logic::parse_file(&src, file_id).map_err(|e| (file_library.clone(), vec![e]))?;
@@ -35,6 +53,10 @@ pub fn run_parser(file: String) -> Result<(ProgramArchive, ReportCollection), (F
         if let Some(main) = program.main_component {
             main_components.push((file_id, main));
         }
+        for mut pragma in program.pragmas {
+            pragma.meta.set_file_id(file_id);
+            unknown_pragmas.push(pragma);
+        }
         let includes = program.includes;
         definitions.push((file_id, program.definitions));
         for include in includes {
@@ -59,7 +81,8 @@ pub fn run_parser(file: String) -> Result<(ProgramArchive, ReportCollection), (F
             Err((lib, rep)) => {
                 Err((lib, rep))
             }
-            Ok(program_archive) => {
+            Ok(mut program_archive) => {
+                program_archive.unknown_pragmas = unknown_pragmas;
                 Ok((program_archive, warnings))
             }
         }
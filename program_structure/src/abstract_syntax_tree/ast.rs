@@ -15,7 +15,7 @@ pub fn build_main_component(public: Vec<String>, call: Expression) -> MainCompon
 pub type Version = (usize, usize, usize);
 
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Meta {
     pub elem_id: usize,
     pub start: usize,
@@ -80,6 +80,7 @@ impl Meta {
 pub struct AST {
     pub meta: Meta,
     pub compiler_version: Option<Version>,
+    pub pragmas: Vec<Pragma>,
     pub includes: Vec<String>,
     pub definitions: Vec<Definition>,
     pub main_component: Option<MainComponent>,
@@ -88,14 +89,31 @@ impl AST {
     pub fn new(
         meta: Meta,
         compiler_version: Option<Version>,
+        pragmas: Vec<Pragma>,
         includes: Vec<String>,
         definitions: Vec<Definition>,
         main_component: Option<MainComponent>,
     ) -> AST {
-        AST { meta, compiler_version, includes, definitions, main_component }
+        AST { meta, compiler_version, pragmas, includes, definitions, main_component }
     }
 }
 
+// Pragmas other than the compiler version, the compiler does not know them
+#[derive(Clone)]
+pub struct Pragma {
+    pub meta: Meta,
+    pub text: String,
+}
+pub fn build_pragma(meta: Meta, name: String, arguments: Vec<String>) -> Pragma {
+    let mut text = format!("pragma {}", name);
+    for argument in arguments {
+        text.push(' ');
+        text.push_str(&argument);
+    }
+    text.push(';');
+    Pragma { meta, text }
+}
+
 #[derive(Clone)]
 pub enum Definition {
     Template {
@@ -142,7 +160,7 @@ pub fn build_function(
     Definition::Function { meta, name, args, arg_location, body }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Statement {
     IfThenElse {
         meta: Meta,
@@ -204,21 +222,21 @@ pub enum SignalElementType {
     FieldElement,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
 pub enum SignalType {
     Output,
     Input,
     Intermediate,
 }
 
-#[derive(Copy, Clone, PartialEq, Ord, PartialOrd, Eq)]
+#[derive(Copy, Clone, PartialEq, Ord, PartialOrd, Eq, Serialize, Deserialize)]
 pub enum VariableType {
     Var,
     Signal(SignalType, SignalElementType),
     Component,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Expression {
     InfixOp {
         meta: Meta,
@@ -254,7 +272,7 @@ pub enum Expression {
     },
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Access {
     ComponentAccess(String),
     ArrayAccess(Expression),
@@ -266,14 +284,14 @@ pub fn build_array_access(expr: Expression) -> Access {
     Access::ArrayAccess(expr)
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum AssignOp {
     AssignVar,
     AssignSignal,
     AssignConstraintSignal,
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ExpressionInfixOpcode {
     Mul,
     Div,
@@ -297,7 +315,7 @@ pub enum ExpressionInfixOpcode {
     BitXor,
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ExpressionPrefixOpcode {
     Sub,
     BoolNot,
@@ -306,14 +324,14 @@ pub enum ExpressionPrefixOpcode {
 
 // Knowledge buckets
 
-#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq)]
+#[derive(Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Serialize, Deserialize)]
 pub enum TypeReduction {
     Variable,
     Component,
     Signal,
 }
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct TypeKnowledge {
     reduces_to: Option<TypeReduction>,
 }
@@ -342,7 +360,7 @@ impl TypeKnowledge {
     }
 }
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct MemoryKnowledge {
     concrete_dimensions: Option<Vec<usize>>,
     full_length: Option<usize>,
@@ -0,0 +1,168 @@
+use super::ast::{Access, Expression, Statement};
+
+// Calls visit on the statement and on every statement inside it, outer
+// statements first
+pub fn walk_statements(stmt: &Statement, visit: &mut dyn FnMut(&Statement)) {
+    use Statement::*;
+    visit(stmt);
+    match stmt {
+        IfThenElse { if_case, else_case, .. } => {
+            walk_statements(if_case, visit);
+            if let Option::Some(else_case) = else_case {
+                walk_statements(else_case, visit);
+            }
+        }
+        While { stmt, .. } => walk_statements(stmt, visit),
+        InitializationBlock { initializations: stmts, .. } | Block { stmts, .. } => {
+            for stmt in stmts {
+                walk_statements(stmt, visit);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Calls visit on every expression of the statement and of the statements
+// inside it, including the indices of the accesses. An expression is
+// visited before the ones inside it
+pub fn walk_expressions(stmt: &Statement, visit: &mut dyn FnMut(&Expression)) {
+    use Statement::*;
+    match stmt {
+        IfThenElse { cond, if_case, else_case, .. } => {
+            walk_expression(cond, visit);
+            walk_expressions(if_case, visit);
+            if let Option::Some(else_case) = else_case {
+                walk_expressions(else_case, visit);
+            }
+        }
+        While { cond, stmt, .. } => {
+            walk_expression(cond, visit);
+            walk_expressions(stmt, visit);
+        }
+        Return { value: arg, .. } | LogCall { arg, .. } | Assert { arg, .. } => {
+            walk_expression(arg, visit)
+        }
+        InitializationBlock { initializations: stmts, .. } | Block { stmts, .. } => {
+            for stmt in stmts {
+                walk_expressions(stmt, visit);
+            }
+        }
+        Declaration { dimensions, .. } => {
+            for dimension in dimensions {
+                walk_expression(dimension, visit);
+            }
+        }
+        Substitution { access, rhe, .. } => {
+            walk_access(access, visit);
+            walk_expression(rhe, visit);
+        }
+        ConstraintEquality { lhe, rhe, .. } => {
+            walk_expression(lhe, visit);
+            walk_expression(rhe, visit);
+        }
+    }
+}
+
+pub fn walk_expression(expr: &Expression, visit: &mut dyn FnMut(&Expression)) {
+    use Expression::*;
+    visit(expr);
+    match expr {
+        InfixOp { lhe, rhe, .. } => {
+            walk_expression(lhe, visit);
+            walk_expression(rhe, visit);
+        }
+        PrefixOp { rhe, .. } => walk_expression(rhe, visit),
+        InlineSwitchOp { cond, if_true, if_false, .. } => {
+            walk_expression(cond, visit);
+            walk_expression(if_true, visit);
+            walk_expression(if_false, visit);
+        }
+        Variable { access, .. } => walk_access(access, visit),
+        Call { args: values, .. } | ArrayInLine { values, .. } => {
+            for value in values {
+                walk_expression(value, visit);
+            }
+        }
+        Number(..) => {}
+    }
+}
+
+fn walk_access(access: &[Access], visit: &mut dyn FnMut(&Expression)) {
+    for acc in access {
+        if let Access::ArrayAccess(index) = acc {
+            walk_expression(index, visit);
+        }
+    }
+}
+
+// The expressions of walk_expressions, visit may change them. The ones
+// inside an expression are walked after visit returns, as it left them
+pub fn walk_expressions_mut(stmt: &mut Statement, visit: &mut dyn FnMut(&mut Expression)) {
+    use Statement::*;
+    match stmt {
+        IfThenElse { cond, if_case, else_case, .. } => {
+            walk_expression_mut(cond, visit);
+            walk_expressions_mut(if_case, visit);
+            if let Option::Some(else_case) = else_case {
+                walk_expressions_mut(else_case, visit);
+            }
+        }
+        While { cond, stmt, .. } => {
+            walk_expression_mut(cond, visit);
+            walk_expressions_mut(stmt, visit);
+        }
+        Return { value: arg, .. } | LogCall { arg, .. } | Assert { arg, .. } => {
+            walk_expression_mut(arg, visit)
+        }
+        InitializationBlock { initializations: stmts, .. } | Block { stmts, .. } => {
+            for stmt in stmts {
+                walk_expressions_mut(stmt, visit);
+            }
+        }
+        Declaration { dimensions, .. } => {
+            for dimension in dimensions {
+                walk_expression_mut(dimension, visit);
+            }
+        }
+        Substitution { access, rhe, .. } => {
+            walk_access_mut(access, visit);
+            walk_expression_mut(rhe, visit);
+        }
+        ConstraintEquality { lhe, rhe, .. } => {
+            walk_expression_mut(lhe, visit);
+            walk_expression_mut(rhe, visit);
+        }
+    }
+}
+
+pub fn walk_expression_mut(expr: &mut Expression, visit: &mut dyn FnMut(&mut Expression)) {
+    use Expression::*;
+    visit(expr);
+    match expr {
+        InfixOp { lhe, rhe, .. } => {
+            walk_expression_mut(lhe, visit);
+            walk_expression_mut(rhe, visit);
+        }
+        PrefixOp { rhe, .. } => walk_expression_mut(rhe, visit),
+        InlineSwitchOp { cond, if_true, if_false, .. } => {
+            walk_expression_mut(cond, visit);
+            walk_expression_mut(if_true, visit);
+            walk_expression_mut(if_false, visit);
+        }
+        Variable { access, .. } => walk_access_mut(access, visit),
+        Call { args: values, .. } | ArrayInLine { values, .. } => {
+            for value in values {
+                walk_expression_mut(value, visit);
+            }
+        }
+        Number(..) => {}
+    }
+}
+
+fn walk_access_mut(access: &mut [Access], visit: &mut dyn FnMut(&mut Expression)) {
+    for acc in access {
+        if let Access::ArrayAccess(index) = acc {
+            walk_expression_mut(index, visit);
+        }
+    }
+}
@@ -2,6 +2,7 @@ mod assign_op_impl;
 pub mod ast;
 mod ast_impl;
 pub mod ast_shortcuts;
+pub mod ast_walker;
 pub mod expression_builders;
 mod expression_impl;
 pub mod statement_builders;
@@ -1,7 +1,8 @@
 use core::fmt;
+use serde_derive::{Deserialize, Serialize};
 use std::fmt::Formatter;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Serialize, Deserialize)]
 pub enum ReportCode {
     AssertWrongType,
     ParseFail,
@@ -33,6 +34,8 @@ pub enum ReportCode {
     NonExistentSymbol,
     NoMainFoundInProject,
     NoCompilerVersionWarning,
+    UnknownPragma,
+    InvalidCustomPrime,
     MultipleMainInComponent,
     TemplateCallAsArgument,
     TemplateWrongNumberOfArguments,
@@ -59,14 +62,45 @@ pub enum ReportCode {
     MustBeSingleArithmetic,
     ExpectedDimDiffGotDim(usize, usize),
     RuntimeError,
+    AssertPromotedToConstraint,
+    NonExactConstantDivision,
+    UnconnectedInput,
+    InstanceLimitExceeded,
+    UnconstrainedAssignment,
+    NonQuadraticConstraint,
+    CyclicInstantiation,
+    AssertNotPromoted,
     UnknownTemplate,
     NonQuadratic,
     NonConstantArrayLength,
     NonComputableExpression,
+    UnknownFieldGenerator,
     // Constraint analysis codes
     UnconstrainedSignal,
     OneConstraintIntermediate,
     NoOutputInInstance,
+    RedundantRangeCheck,
+    PossibleDivisionByZero,
+    UnusedTemplateParameter,
+    CyclicSignalDependency,
+    UnconstrainedInCircuit,
+    // Witness check codes
+    InvalidWitnessValue,
+    UnsatisfiedWitness,
+    // R1CS validation codes
+    WireOutOfRange,
+    MisplacedProduct,
+    CyclicConstraintDependency,
+    ParallelSimplificationMismatch,
+    NonLinearLimitExceeded,
+    SimplificationArithmeticError,
+    UnknownSignalPath,
+    InvalidWirePermutation,
+    InvalidPromotedSignal,
+    InternalCompilerError,
+    PublicSignalName,
+    IgnoredBuildOption,
+    UnreadableCountsBaseline,
 }
 impl fmt::Display for ReportCode {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -77,6 +111,8 @@ impl fmt::Display for ReportCode {
             MultipleMainInComponent => "P1002",
             CompilerVersionError => "P1003",
             NoCompilerVersionWarning => "P1004",
+            UnknownPragma => "P1005",
+            InvalidCustomPrime => "P1006",
             WrongTypesInAssignOperation => "T2000",
             UndefinedFunction => "T2001",
             UndefinedTemplate => "T2002",
@@ -126,16 +162,50 @@ impl fmt::Display for ReportCode {
             MustBeSingleArithmetic => "T2044",
             ExpectedDimDiffGotDim(..) => "T2045",
             RuntimeError => "T3001",
+            AssertPromotedToConstraint => "T3002",
+            NonExactConstantDivision => "T3003",
+            UnconnectedInput => "T3004",
+            InstanceLimitExceeded => "T3005",
+            UnconstrainedAssignment => "T3006",
+            NonQuadraticConstraint => "T3007",
+            CyclicInstantiation => "T3008",
+            AssertNotPromoted => "T3009",
             UnknownDimension => "T20460",
             UnknownTemplate => "T20461",
             NonQuadratic => "T20462",
             NonConstantArrayLength => "T20463",
             NonComputableExpression => "T20464",
             WrongNumberOfArguments(..) => "T20465",
+            UnknownFieldGenerator => "T20466",
             // Constraint analysis codes
             UnconstrainedSignal => "CA01",
             OneConstraintIntermediate => "CA02",
             NoOutputInInstance => "CA03",
+            RedundantRangeCheck => "CA04",
+            PossibleDivisionByZero => "CA05",
+            UnusedTemplateParameter => "CA06",
+            CyclicSignalDependency => "CA07",
+            UnconstrainedInCircuit => "CA08",
+            // Witness check codes
+            InvalidWitnessValue => "W01",
+            UnsatisfiedWitness => "W02",
+            UnknownSignalPath => "W03",
+            // R1CS validation codes
+            WireOutOfRange => "R01",
+            MisplacedProduct => "R02",
+            CyclicConstraintDependency => "R03",
+            ParallelSimplificationMismatch => "R04",
+            NonLinearLimitExceeded => "R05",
+            SimplificationArithmeticError => "R06",
+            InvalidWirePermutation => "R07",
+            InvalidPromotedSignal => "R08",
+            // Internal consistency checks
+            InternalCompilerError => "I01",
+            // Naming convention codes
+            PublicSignalName => "N01",
+            // Build option codes
+            IgnoredBuildOption => "O01",
+            UnreadableCountsBaseline => "O02",
         };
         f.write_str(string_format)
     }
@@ -2,16 +2,18 @@ use super::error_code::ReportCode;
 use super::file_definition::{FileID, FileLibrary, FileLocation};
 use codespan_reporting::diagnostic::{Diagnostic, Label};
 use codespan_reporting::term;
+use serde_derive::{Deserialize, Serialize};
 
 pub type ReportCollection = Vec<Report>;
 pub type DiagnosticCode = String;
 type ReportLabel = Label<FileID>;
 type ReportNote = String;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Serialize, Deserialize)]
 enum MessageCategory {
     Error,
     Warning,
+    Note,
 }
 impl MessageCategory {
     fn is_error(&self) -> bool {
@@ -28,9 +30,12 @@ impl MessageCategory {
             _ => false,
         }
     }
+    fn is_note(&self) -> bool {
+        matches!(self, MessageCategory::Note)
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Report {
     category: MessageCategory,
     error_message: String,
@@ -75,6 +80,9 @@ impl Report {
     pub fn warning(error_message: String, code: ReportCode) -> Report {
         Report::new(MessageCategory::Warning, error_message, code)
     }
+    pub fn note(error_message: String, code: ReportCode) -> Report {
+        Report::new(MessageCategory::Note, error_message, code)
+    }
     pub fn add_primary(
         &mut self,
         location: FileLocation,
@@ -108,7 +116,14 @@ impl Report {
         let mut secondary = self.get_secondary().clone();
         labels.append(&mut secondary);
 
-        if self.is_warning() { Diagnostic::warning() } else { Diagnostic::error() }
+        let diagnostic = if self.is_warning() {
+            Diagnostic::warning()
+        } else if self.is_note() {
+            Diagnostic::note()
+        } else {
+            Diagnostic::error()
+        };
+        diagnostic
             .with_message(self.get_message())
             .with_code(Report::error_code_to_diagnostic_code(self.get_code()))
             .with_labels(labels)
@@ -121,13 +136,16 @@ impl Report {
     pub fn is_warning(&self) -> bool {
         self.get_category().is_warning()
     }
+    pub fn is_note(&self) -> bool {
+        self.get_category().is_note()
+    }
     fn get_category(&self) -> &MessageCategory {
         &self.category
     }
-    fn get_message(&self) -> &String {
+    pub fn get_message(&self) -> &String {
         &self.error_message
     }
-    fn get_code(&self) -> &ReportCode {
+    pub fn get_code(&self) -> &ReportCode {
         &self.error_code
     }
     fn get_primary(&self) -> &Vec<ReportLabel> {
@@ -142,10 +160,55 @@ impl Report {
     fn get_mut_secondary(&mut self) -> &mut Vec<ReportLabel> {
         &mut self.secondary
     }
-    fn get_notes(&self) -> &Vec<ReportNote> {
+    pub fn get_notes(&self) -> &Vec<ReportNote> {
         &self.notes
     }
     fn get_mut_notes(&mut self) -> &mut Vec<ReportNote> {
         &mut self.notes
     }
 }
+
+// ReportCollection is a plain Vec, so the grouping by severity lives in a trait
+pub trait ReportPartition {
+    // (errors, warnings, notes), each one in the original order
+    fn partition(&self) -> (Vec<&Report>, Vec<&Report>, Vec<&Report>);
+}
+impl ReportPartition for [Report] {
+    fn partition(&self) -> (Vec<&Report>, Vec<&Report>, Vec<&Report>) {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        let mut notes = Vec::new();
+        for report in self {
+            match report.get_category() {
+                MessageCategory::Error => errors.push(report),
+                MessageCategory::Warning => warnings.push(report),
+                MessageCategory::Note => notes.push(report),
+            }
+        }
+        (errors, warnings, notes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_are_partitioned_by_severity() {
+        let reports: ReportCollection = vec![
+            Report::warning("first warning".to_string(), ReportCode::UnconstrainedSignal),
+            Report::error("first error".to_string(), ReportCode::RuntimeError),
+            Report::note("a note".to_string(), ReportCode::AssertPromotedToConstraint),
+            Report::error("second error".to_string(), ReportCode::NonQuadratic),
+            Report::warning("second warning".to_string(), ReportCode::RedundantRangeCheck),
+        ];
+        let (errors, warnings, notes) = reports.partition();
+        let messages = |reports: Vec<&Report>| -> Vec<String> {
+            reports.into_iter().map(|r| r.get_message().clone()).collect()
+        };
+        assert_eq!(messages(errors), vec!["first error", "second error"]);
+        assert_eq!(messages(warnings), vec!["first warning", "second warning"]);
+        assert_eq!(messages(notes), vec!["a note"]);
+        assert!(ReportCollection::new().partition().0.is_empty());
+    }
+}
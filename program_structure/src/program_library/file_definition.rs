@@ -31,6 +31,15 @@ impl FileLibrary {
             None => None,
         }
     }
+    // Line and column of the position, both starting at 1
+    pub fn get_line_column(&self, position: usize, file_id: FileID) -> Option<(usize, usize)> {
+        let line = self.files.line_index(file_id, position)?;
+        let line_start = self.files.line_range(file_id, line)?.start;
+        Some((line + 1, position - line_start + 1))
+    }
+    pub fn get_file_name(&self, file_id: FileID) -> Option<&str> {
+        self.files.get(file_id).map(|file| file.name().as_str())
+    }
     pub fn to_storage(&self) -> &FileStorage {
         &self.get_files()
     }
@@ -5,4 +5,5 @@ pub mod file_definition;
 pub mod function_data;
 pub mod program_archive;
 pub mod program_merger;
+pub mod template_call_graph;
 pub mod template_data;
@@ -1,7 +1,8 @@
-use super::ast::{Definition, Expression, MainComponent};
+use super::ast::{Definition, Expression, MainComponent, Pragma};
 use super::file_definition::{FileID, FileLibrary};
 use super::function_data::{FunctionData, FunctionInfo};
 use super::program_merger::Merger;
+use super::template_call_graph::TemplateCallGraph;
 use super::template_data::{TemplateData, TemplateInfo};
 use crate::abstract_syntax_tree::ast::FillMeta;
 use std::collections::HashSet;
@@ -20,6 +21,7 @@ pub struct ProgramArchive {
     pub template_keys: HashSet<String>,
     pub public_inputs: Vec<String>,
     pub initial_template_call: Expression,
+    pub unknown_pragmas: Vec<Pragma>,
 }
 impl ProgramArchive {
     pub fn new(
@@ -57,6 +59,7 @@ impl ProgramArchive {
                 initial_template_call,
                 function_keys,
                 template_keys,
+                unknown_pragmas: Vec::new(),
             })
         } else {
             Err((file_library, reports))
@@ -82,6 +85,10 @@ impl ProgramArchive {
     pub fn get_template_names(&self) -> &HashSet<String> {
         &self.template_keys
     }
+    pub fn template_call_graph(&self) -> TemplateCallGraph {
+        let bodies = self.templates.iter().map(|(name, data)| (name.as_str(), data.get_body()));
+        super::template_call_graph::build(bodies)
+    }
     pub fn get_templates(&self) -> &TemplateInfo {
         &self.templates
     }
@@ -0,0 +1,115 @@
+use super::ast::{Expression, Statement};
+use crate::ast_walker::walk_expressions;
+use json::JsonValue;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+// Template definitions (not instances): an edge goes from a template to every
+// template it instantiates, weighted with the number of instantiation sites
+#[derive(Default)]
+pub struct TemplateCallGraph {
+    pub templates: BTreeSet<String>,
+    pub edges: BTreeMap<(String, String), usize>,
+}
+
+impl TemplateCallGraph {
+    pub fn sites(&self, from: &str, to: &str) -> usize {
+        self.edges.get(&(from.to_string(), to.to_string())).cloned().unwrap_or(0)
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph templates {\n");
+        for template in &self.templates {
+            dot.push_str(&format!("    \"{}\";\n", template));
+        }
+        for ((from, to), sites) in &self.edges {
+            dot.push_str(&format!("    \"{}\" -> \"{}\" [label=\"{}\"];\n", from, to, sites));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let templates: Vec<_> = self.templates.iter().cloned().map(JsonValue::from).collect();
+        let edges: Vec<_> = self
+            .edges
+            .iter()
+            .map(|((from, to), sites)| {
+                json::object! { "from" => from.clone(), "to" => to.clone(), "sites" => *sites }
+            })
+            .collect();
+        json::object! { "templates" => templates, "edges" => edges }
+    }
+}
+
+// Components are instantiated by the calls to templates
+fn statement_sites(stmt: &Statement, templates: &HashSet<String>, sites: &mut Vec<String>) {
+    walk_expressions(stmt, &mut |expr| {
+        if let Expression::Call { id, .. } = expr {
+            if templates.contains(id) {
+                sites.push(id.clone());
+            }
+        }
+    });
+}
+
+pub fn build<'a, I>(bodies: I) -> TemplateCallGraph
+where
+    I: Iterator<Item = (&'a str, &'a Statement)> + Clone,
+{
+    let templates: HashSet<_> = bodies.clone().map(|(name, _)| name.to_string()).collect();
+    let mut graph = TemplateCallGraph::default();
+    for (name, body) in bodies {
+        graph.templates.insert(name.to_string());
+        let mut sites = Vec::new();
+        statement_sites(body, &templates, &mut sites);
+        for callee in sites {
+            *graph.edges.entry((name.to_string(), callee)).or_insert(0) += 1;
+        }
+    }
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{AssignOp, Meta};
+    use crate::expression_builders::build_call;
+    use crate::statement_builders::{build_block, build_substitution};
+
+    fn instantiate(component: &str, template: &str) -> Statement {
+        let call = build_call(Meta::new(0, 0), template.to_string(), vec![]);
+        build_substitution(
+            Meta::new(0, 0),
+            component.to_string(),
+            vec![],
+            AssignOp::AssignVar,
+            call,
+        )
+    }
+
+    #[test]
+    fn edges_count_the_instantiation_sites() {
+        // Main instantiates two Pairs and a Bit, Pair instantiates a Bit and
+        // calls the function inv, which is not a template
+        let main = build_block(
+            Meta::new(0, 0),
+            vec![instantiate("p0", "Pair"), instantiate("p1", "Pair"), instantiate("b", "Bit")],
+        );
+        let pair =
+            build_block(Meta::new(0, 0), vec![instantiate("x", "Bit"), instantiate("v", "inv")]);
+        let bit = build_block(Meta::new(0, 0), vec![]);
+        let bodies = vec![("Main", &main), ("Pair", &pair), ("Bit", &bit)];
+        let graph = build(bodies.into_iter());
+        assert_eq!(graph.edges.len(), 3);
+        assert_eq!(graph.sites("Main", "Pair"), 2);
+        assert_eq!(graph.sites("Main", "Bit"), 1);
+        assert_eq!(graph.sites("Pair", "Bit"), 1);
+        assert_eq!(graph.sites("Pair", "inv"), 0);
+        assert_eq!(graph.templates.len(), 3);
+        assert!(graph.to_dot().contains("\"Main\" -> \"Pair\" [label=\"2\"];"));
+        let json = graph.to_json();
+        assert_eq!(json["templates"].len(), 3);
+        let edge = json::object! { "from" => "Pair", "to" => "Bit", "sites" => 1 };
+        assert!(json["edges"].members().any(|e| *e == edge));
+    }
+}
@@ -1,7 +1,88 @@
 use num_bigint::BigInt;
+use serde_derive::{Deserialize, Serialize};
 
 const P_STR: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+const BLS12381_STR: &str =
+    "52435875175126190479447740508185965837690552500527637822603658699938581184513";
+const GOLDILOCKS_STR: &str = "18446744069414584321";
+const GRUMPKIN_STR: &str =
+    "21888242871839275222246405745257275088696311157297823662689037894645226208583";
+const PALLAS_STR: &str =
+    "28948022309329048855892746252171976963363056481941560715954676764349967630337";
+const VESTA_STR: &str =
+    "28948022309329048855892746252171976963363056481941647379679742748393362948097";
 
+const NAMED_PRIMES: &[(&str, &str)] = &[
+    ("bn128", P_STR),
+    ("bls12381", BLS12381_STR),
+    ("goldilocks", GOLDILOCKS_STR),
+    ("grumpkin", GRUMPKIN_STR),
+    ("pallas", PALLAS_STR),
+    ("vesta", VESTA_STR),
+];
+
+// Smallest generator of the multiplicative group of each named field
+const GENERATORS: &[(&str, u32)] = &[
+    ("bn128", 5),
+    ("bls12381", 7),
+    ("goldilocks", 7),
+    ("grumpkin", 3),
+    ("pallas", 5),
+    ("vesta", 5),
+];
+
+pub fn supported_primes() -> Vec<(String, BigInt)> {
+    NAMED_PRIMES
+        .iter()
+        .map(|(name, value)| {
+            let p = BigInt::parse_bytes(value.as_bytes(), 10).expect("can not parse p");
+            (name.to_string(), p)
+        })
+        .collect()
+}
+
+// Miller-Rabin with the primes up to 37 as bases, which is exact below 3.18 * 10^23
+// and a probabilistic check beyond it
+pub fn is_probable_prime(n: &BigInt) -> bool {
+    const BASES: [u32; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    let (zero, one, two) = (BigInt::from(0), BigInt::from(1), BigInt::from(2));
+    if *n < two {
+        return false;
+    }
+    for base in BASES.iter() {
+        let base = BigInt::from(*base);
+        if *n == base {
+            return true;
+        }
+        if n % &base == zero {
+            return false;
+        }
+    }
+    // n - 1 = d * 2^s with d odd
+    let n_minus_one = n - &one;
+    let mut d = n_minus_one.clone();
+    let mut s = 0;
+    while &d % &two == zero {
+        d >>= 1;
+        s += 1;
+    }
+    'bases: for base in BASES.iter() {
+        let mut x = BigInt::from(*base).modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n_minus_one {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct UsefulConstants {
     p: BigInt,
 }
@@ -21,7 +102,76 @@ impl UsefulConstants {
     pub fn new() -> UsefulConstants {
         UsefulConstants::default()
     }
+    pub fn with_prime(p: BigInt) -> UsefulConstants {
+        UsefulConstants { p }
+    }
+    pub fn from_prime_name(name: &str) -> Option<UsefulConstants> {
+        supported_primes()
+            .into_iter()
+            .find(|(prime_name, _)| prime_name == name)
+            .map(|(_, p)| UsefulConstants::with_prime(p))
+    }
     pub fn get_p(&self) -> &BigInt {
         &self.p
     }
+    // Only known for the named primes
+    pub fn get_generator(&self) -> Option<BigInt> {
+        let (name, _) = supported_primes().into_iter().find(|(_, p)| *p == self.p)?;
+        GENERATORS.iter().find(|(prime_name, _)| *prime_name == name).map(|(_, g)| BigInt::from(*g))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_prime_is_bn128() {
+        let bn128 = UsefulConstants::from_prime_name("bn128").unwrap();
+        assert_eq!(bn128.get_p(), UsefulConstants::new().get_p());
+        assert!(UsefulConstants::from_prime_name("unknown").is_none());
+    }
+
+    #[test]
+    fn supported_primes_are_usable() {
+        let two = BigInt::from(2);
+        let one = BigInt::from(1);
+        for (name, p) in supported_primes() {
+            let constants = UsefulConstants::from_prime_name(&name).unwrap();
+            assert_eq!(constants.get_p(), &p);
+            // Fermat check: 2^(p-1) = 1 mod p
+            let exp = constants.get_p() - &one;
+            assert_eq!(two.modpow(&exp, constants.get_p()), one, "{} is not prime", name);
+        }
+    }
+
+    #[test]
+    fn primes_are_told_apart_from_composites() {
+        for (name, p) in supported_primes() {
+            assert!(is_probable_prime(&p), "{} is not prime", name);
+        }
+        let primes = [2, 3, 37, 41, 257, 65537, 2147483647];
+        assert!(primes.iter().all(|p| is_probable_prime(&BigInt::from(*p))));
+        // 561 and 3215031751 are Carmichael numbers, the second one a strong pseudoprime
+        // to the bases 2, 3, 5 and 7
+        let composites = [0, 1, 4, 561, 65535, 3215031751u64];
+        assert!(composites.iter().all(|n| !is_probable_prime(&BigInt::from(*n))));
+        let bn128 = UsefulConstants::new().get_p().clone();
+        assert!(!is_probable_prime(&(&bn128 * &bn128)));
+    }
+
+    #[test]
+    fn bn128_generator_spans_the_two_adic_subgroup() {
+        let constants = UsefulConstants::new();
+        let p = constants.get_p();
+        let g = constants.get_generator().unwrap();
+        assert_eq!(g, BigInt::from(5));
+        let one = BigInt::from(1);
+        // A generator is not a square, and g^((p-1)/2^28) is a root of unity of order exactly 2^28
+        assert_eq!(g.modpow(&((p - &one) / 2), p), p - &one);
+        let root = g.modpow(&((p - &one) >> 28), p);
+        assert_eq!(root.modpow(&(BigInt::from(1) << 28), p), one);
+        assert_ne!(root.modpow(&(BigInt::from(1) << 27), p), one);
+        assert!(UsefulConstants::with_prime(BigInt::from(101)).get_generator().is_none());
+    }
 }
@@ -1,4 +1,5 @@
 use num_bigint_dig::BigInt;
+use serde_derive::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 pub enum MemoryError {
     OutOfBoundsError,
@@ -12,7 +13,7 @@ pub type SimpleSlice = MemorySlice<BigInt>;
     The attribute route stores the dimensions of the slice, used to navigate through them.
     The length of values is equal to multiplying all the values in route.
 */
-#[derive(Eq, PartialEq)]
+#[derive(Eq, PartialEq, Serialize, Deserialize)]
 pub struct MemorySlice<C> {
     route: Vec<SliceCapacity>,
     values: Vec<C>,
@@ -171,6 +172,10 @@ impl<C: Clone> MemorySlice<C> {
     pub fn route(&self) -> &[SliceCapacity] {
         &self.route
     }
+    // Cells in row-major order
+    pub fn values(&self) -> &[C] {
+        &self.values
+    }
     pub fn is_single(&self) -> bool {
         self.route.is_empty()
     }